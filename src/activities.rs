@@ -4,7 +4,7 @@
 //! perform real I/O (HTTP calls, shell commands, etc.).  Results are
 //! recorded in the workflow history for deterministic replay.
 
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
 
 use codex_core::auth::AuthCredentialsStoreMode;
@@ -17,6 +17,7 @@ use codex_core::{
     built_in_model_providers,
 };
 use codex_core::error::CodexErr;
+use codex_core::mcp::split_qualified_tool_name;
 use codex_core::tools::router::{ToolCall, ToolCallSource, ToolRouter, ToolRouterParams};
 use codex_protocol::config_types::WindowsSandboxLevel;
 use codex_otel::SessionTelemetry;
@@ -25,22 +26,89 @@ use codex_protocol::openai_models::ModelInfo;
 use codex_protocol::protocol::SessionSource;
 use codex_protocol::ThreadId;
 use futures::StreamExt;
+use temporalio_client::tonic::{self, IntoRequest};
+use temporalio_client::{Client, WorkflowService};
+use temporalio_common::protos::coresdk::AsJsonPayloadExt;
+use temporalio_common::protos::temporal::api::common::v1::{
+    Payloads, WorkflowExecution, WorkflowType,
+};
+use temporalio_common::protos::temporal::api::enums::v1::WorkflowExecutionStatus;
+use temporalio_common::protos::temporal::api::taskqueue::v1::TaskQueue;
+use temporalio_common::protos::temporal::api::workflowservice::v1::{
+    DescribeWorkflowExecutionRequest, ListWorkflowExecutionsRequest,
+    SignalWithStartWorkflowExecutionRequest, TerminateWorkflowExecutionRequest,
+};
 use temporalio_macros::activities;
 use temporalio_sdk::activities::{ActivityContext, ActivityError};
 use tokio::sync::Mutex;
 
 use temporalio_sdk::ActivityOptions;
 
+use crate::TASK_QUEUE;
 use crate::config_loader::config_from_toml;
-use crate::mcp::HarnessMcpManager;
+use crate::mcp::{HarnessMcpManager, McpCallProgress};
 use crate::sink::BufferEventSink;
 use crate::storage::InMemoryStorage;
 use crate::types::{
-    ConfigOutput, McpDiscoverInput, McpDiscoverOutput, McpToolCallInput, McpToolCallOutput,
+    CollectArtifactsInput, CollectArtifactsOutput, ConfigOutput, CrewArtifact, HarnessInput,
+    HarnessSignalInput, MAX_ARTIFACT_BYTES, MAX_ARTIFACT_FILES, MAX_TOTAL_ARTIFACT_BYTES,
+    McpDiscoverInput, McpDiscoverOutput, McpElicitationResolveInput, McpGetPromptInput,
+    McpListInput, McpReadResourceInput, McpRequestOutput, McpToolCallInput, McpToolCallOutput,
     ModelCallInput, ModelCallOutput, ProjectContextOutput, ResolveModelInfoInput,
-    ResolveRoleConfigInput, ResolveRoleConfigOutput, ToolExecInput, ToolExecOutput,
+    ResolveRoleConfigInput, ResolveRoleConfigOutput, SessionStatus, TerminateWorkflowsInput,
+    ToolExecInput, ToolExecOutput,
 };
 
+/// Register the session's MCP server configs with the manager when the
+/// worker does not know them yet (e.g. after a worker restart), so servers
+/// can be reconnected lazily.  `server` of `None` means "any server".
+fn register_session_mcp_configs(
+    manager: &mut HarnessMcpManager,
+    server: Option<&str>,
+    config_toml: Option<&str>,
+    cwd: Option<&str>,
+) {
+    let known = match server {
+        Some(server) => manager.has_server_config(server),
+        None => manager.has_server_configs(),
+    };
+    let (false, Some(toml), Some(cwd)) = (known, config_toml, cwd) else {
+        return;
+    };
+    match config_from_toml(toml, std::path::Path::new(cwd), None) {
+        Ok(config) => manager.register_configs(config.mcp_servers.get()),
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to rebuild config for MCP reconnection");
+        }
+    }
+}
+
+/// Convert MCP call progress into the activity output.
+fn call_progress_to_output(
+    call_id: String,
+    progress: anyhow::Result<McpCallProgress>,
+) -> McpToolCallOutput {
+    let (result, elicitation) = match progress {
+        Ok(McpCallProgress::Completed(Ok(call_result))) => {
+            match serde_json::to_value(&call_result) {
+                Ok(v) => (Ok(v), None),
+                Err(e) => (Err(format!("failed to serialize CallToolResult: {e}")), None),
+            }
+        }
+        Ok(McpCallProgress::Completed(Err(e))) | Err(e) => (Err(format!("{e}")), None),
+        Ok(McpCallProgress::Elicitation(captured)) => {
+            (Err("awaiting elicitation response".to_string()), Some(captured))
+        }
+    };
+    McpToolCallOutput {
+        call_id,
+        result,
+        elicitation,
+        worker_task_queue: None,
+        unavailable: None,
+    }
+}
+
 /// Build `ActivityOptions` with only a schedule-to-close timeout.
 pub(crate) fn activity_opts(timeout_secs: u64) -> ActivityOptions {
     ActivityOptions {
@@ -81,6 +149,10 @@ fn resolve_provider() -> ModelProviderInfo {
 }
 
 /// Activity implementations for the codex workflow.
+///
+/// Clones share the MCP connections, so one instance can serve both the
+/// shared task queue and the worker's own queue.
+#[derive(Clone)]
 pub struct CodexActivities {
     provider: ModelProviderInfo,
     /// Auth manager for model API calls (uses API-key from env, ephemeral store).
@@ -90,6 +162,13 @@ pub struct CodexActivities {
     models_manager: Arc<ModelsManager>,
     /// Persistent MCP server connections (initialized via `discover_mcp_tools`).
     mcp_manager: Arc<Mutex<HarnessMcpManager>>,
+    /// Temporal client and namespace for activities that talk to other
+    /// workflows (harness signal-with-start, session status checks, idle
+    /// session termination).
+    temporal: Option<(Client, String)>,
+    /// Task queue only this worker polls, where elicitation answers for
+    /// its pending MCP calls are delivered.
+    worker_task_queue: Option<String>,
 }
 
 impl Default for CodexActivities {
@@ -121,8 +200,131 @@ impl CodexActivities {
             _auth_manager: auth_manager,
             models_manager,
             mcp_manager: Arc::new(Mutex::new(HarnessMcpManager::new())),
+            temporal: None,
+            worker_task_queue: None,
+        }
+    }
+
+    /// Give activities a Temporal client for `namespace`.  Without one,
+    /// `signal_with_start_harness`, `session_statuses`, `terminate_sessions`
+    /// and `terminate_workflows` report that they are unavailable.
+    pub fn with_client(mut self, client: Client, namespace: impl Into<String>) -> Self {
+        self.temporal = Some((client, namespace.into()));
+        self
+    }
+
+    /// Set the task queue only this worker polls.  MCP calls waiting for an
+    /// elicitation answer report it, so `resolve_mcp_elicitation` reaches
+    /// the worker that holds the call.
+    pub fn with_worker_task_queue(mut self, task_queue: impl Into<String>) -> Self {
+        self.worker_task_queue = Some(task_queue.into());
+        self
+    }
+
+    /// Convert MCP call progress into the activity output, naming this
+    /// worker's task queue when the call is waiting for an elicitation.
+    fn mcp_call_output(
+        &self,
+        call_id: String,
+        progress: anyhow::Result<McpCallProgress>,
+    ) -> McpToolCallOutput {
+        let mut output = call_progress_to_output(call_id, progress);
+        if output.elicitation.is_some() {
+            output.worker_task_queue = self.worker_task_queue.clone();
         }
+        output
     }
+
+    /// Terminate the current run of each workflow in `workflow_ids`,
+    /// returning those that were terminated.  Failures are logged.
+    async fn terminate_all(&self, workflow_ids: Vec<String>, reason: &str) -> Vec<String> {
+        let Some((client, namespace)) = &self.temporal else {
+            return Vec::new();
+        };
+        let mut terminated = Vec::new();
+        for workflow_id in workflow_ids {
+            let request = TerminateWorkflowExecutionRequest {
+                namespace: namespace.clone(),
+                workflow_execution: Some(WorkflowExecution {
+                    workflow_id: workflow_id.clone(),
+                    run_id: String::new(),
+                }),
+                reason: reason.to_string(),
+                ..Default::default()
+            };
+            match WorkflowService::terminate_workflow_execution(
+                &mut client.clone(),
+                request.into_request(),
+            )
+            .await
+            {
+                Ok(_) => terminated.push(workflow_id),
+                Err(e) => {
+                    tracing::warn!(workflow_id = %workflow_id, error = %e, "terminate failed");
+                }
+            }
+        }
+        terminated
+    }
+
+    /// IDs of the running agent workflows of `session_id`, as listed by the
+    /// visibility store.  Failures are logged.
+    async fn running_session_agents(&self, session_id: &str) -> Vec<String> {
+        let Some((client, namespace)) = &self.temporal else {
+            return Vec::new();
+        };
+        let mut agents = Vec::new();
+        let mut next_page_token = Vec::new();
+        loop {
+            let request = ListWorkflowExecutionsRequest {
+                namespace: namespace.clone(),
+                query: session_agents_query(session_id),
+                next_page_token,
+                ..Default::default()
+            };
+            let response = match WorkflowService::list_workflow_executions(
+                &mut client.clone(),
+                request.into_request(),
+            )
+            .await
+            {
+                Ok(response) => response.into_inner(),
+                Err(e) => {
+                    tracing::warn!(session_id = %session_id, error = %e, "listing agents failed");
+                    return agents;
+                }
+            };
+            agents.extend(
+                response
+                    .executions
+                    .into_iter()
+                    .filter_map(|info| info.execution)
+                    .map(|execution| execution.workflow_id),
+            );
+            if response.next_page_token.is_empty() {
+                return agents;
+            }
+            next_page_token = response.next_page_token;
+        }
+    }
+
+    /// Terminate the running agents of each session in `session_ids`,
+    /// returning the agents that were terminated.
+    async fn terminate_agents_of(&self, session_ids: &[String], reason: &str) -> Vec<String> {
+        let mut terminated = Vec::new();
+        for session_id in session_ids {
+            let agents = self.running_session_agents(session_id).await;
+            terminated.extend(self.terminate_all(agents, reason).await);
+        }
+        terminated
+    }
+}
+
+/// Visibility query for the running agent workflows of `session_id`
+/// (`<session-id>/main`, `<session-id>/worker-2`, ...).
+pub fn session_agents_query(session_id: &str) -> String {
+    let prefix = format!("{session_id}/").replace('\\', "\\\\").replace('"', "\\\"");
+    format!("WorkflowId STARTS_WITH \"{prefix}\" AND ExecutionStatus = \"Running\"")
 }
 
 #[activities]
@@ -294,9 +496,10 @@ impl CodexActivities {
 
     /// Discover MCP tools from configured servers.
     ///
-    /// Connects to all enabled MCP servers from config.toml, performs the
-    /// MCP handshake, and lists available tools. Results are cached in the
-    /// `mcp_manager` for subsequent `mcp_tool_call` invocations.
+    /// Connects the session's enabled MCP servers from config.toml (reusing
+    /// the worker's shared connections), performs the MCP handshake, and
+    /// lists available tools. Connections are kept in the `mcp_manager` for
+    /// subsequent `mcp_tool_call` invocations.
     #[activity]
     pub async fn discover_mcp_tools(
         self: Arc<Self>,
@@ -309,7 +512,14 @@ impl CodexActivities {
         let config = config_from_toml(&input.config_toml, &cwd, None)
             .map_err(|e| anyhow::anyhow!("failed to build config from TOML: {e}"))?;
 
-        let mcp_servers = config.mcp_servers.get().clone();
+        let mut mcp_servers = config.mcp_servers.get().clone();
+        // Servers disabled at runtime are skipped for this session only; the
+        // shared manager keeps their connections for other sessions.
+        for server in &input.disabled_servers {
+            if mcp_servers.remove(server).is_some() {
+                tracing::info!(server = %server, "MCP server disabled for session, skipping");
+            }
+        }
         if mcp_servers.is_empty() {
             tracing::debug!("no MCP servers configured");
             return Ok(McpDiscoverOutput {
@@ -370,34 +580,180 @@ impl CodexActivities {
                         call_id: input.call_id,
                         result: Err(format!("invalid JSON arguments: {e}")),
                         elicitation: None,
+                        worker_task_queue: None,
+                        unavailable: None,
                     });
                 }
             }
         };
 
-        let manager = self.mcp_manager.lock().await;
-        // Clear any previously captured elicitation.
-        manager.take_captured_elicitation().await;
+        let server_name = split_qualified_tool_name(&input.qualified_name)
+            .map(|(server, _)| server);
 
-        let result = match manager
-            .call_tool(&input.qualified_name, arguments)
-            .await
+        let mut manager = self.mcp_manager.lock().await;
+        if let Some(server) = &server_name {
+            register_session_mcp_configs(
+                &mut manager,
+                Some(server),
+                input.config_toml.as_deref(),
+                input.cwd.as_deref(),
+            );
+        }
+
+        let progress = manager
+            .start_tool_call(&input.call_id, &input.qualified_name, arguments)
+            .await;
+        let mut output = self.mcp_call_output(input.call_id, progress);
+
+        // Report servers that could not be (re)connected.
+        if output.result.is_err()
+            && output.elicitation.is_none()
+            && let Some(server) = &server_name
         {
-            Ok(call_result) => match serde_json::to_value(&call_result) {
-                Ok(v) => Ok(v),
-                Err(e) => Err(format!("failed to serialize CallToolResult: {e}")),
-            },
-            Err(e) => Err(format!("{e}")),
-        };
+            output.unavailable = manager.server_unavailable(server);
+        }
 
-        // Check if an elicitation was captured during this call.
-        let elicitation = manager.take_captured_elicitation().await;
+        Ok(output)
+    }
 
-        Ok(McpToolCallOutput {
-            call_id: input.call_id,
-            result,
-            elicitation,
-        })
+    /// Deliver the user's answer to an MCP elicitation and resume the
+    /// original tool call.
+    ///
+    /// The call started by `mcp_tool_call` is still pending on this worker
+    /// (the activity is scheduled on its `worker_task_queue`); the answer is
+    /// sent back to the server as the elicitation result and the call is
+    /// driven until it completes or elicits again.
+    #[activity]
+    pub async fn resolve_mcp_elicitation(
+        self: Arc<Self>,
+        _ctx: ActivityContext,
+        input: McpElicitationResolveInput,
+    ) -> Result<McpToolCallOutput, ActivityError> {
+        tracing::debug!(
+            server = %input.server_name,
+            call_id = %input.call_id,
+            "resolve_mcp_elicitation activity invoked"
+        );
+
+        // Wire format of the MCP `elicitation/create` result.
+        let mut response = serde_json::json!({ "action": input.action });
+        if let Some(content) = input.content {
+            response["content"] = content;
+        }
+
+        let mut manager = self.mcp_manager.lock().await;
+        let progress = manager
+            .resolve_elicitation(&input.call_id, &input.server_name, &input.request_id, response)
+            .await;
+        Ok(self.mcp_call_output(input.call_id, progress))
+    }
+
+    /// List resources exposed by MCP servers (`list_mcp_resources` tool).
+    #[activity]
+    pub async fn mcp_list_resources(
+        self: Arc<Self>,
+        _ctx: ActivityContext,
+        input: McpListInput,
+    ) -> Result<McpRequestOutput, ActivityError> {
+        let mut manager = self.mcp_manager.lock().await;
+        register_session_mcp_configs(
+            &mut manager,
+            input.server.as_deref(),
+            input.config_toml.as_deref(),
+            input.cwd.as_deref(),
+        );
+        let result = manager
+            .list_resources(input.server.as_deref(), input.cursor)
+            .await
+            .map_err(|e| format!("{e}"));
+        Ok(McpRequestOutput { result })
+    }
+
+    /// List resource templates exposed by MCP servers
+    /// (`list_mcp_resource_templates` tool).
+    #[activity]
+    pub async fn mcp_list_resource_templates(
+        self: Arc<Self>,
+        _ctx: ActivityContext,
+        input: McpListInput,
+    ) -> Result<McpRequestOutput, ActivityError> {
+        let mut manager = self.mcp_manager.lock().await;
+        register_session_mcp_configs(
+            &mut manager,
+            input.server.as_deref(),
+            input.config_toml.as_deref(),
+            input.cwd.as_deref(),
+        );
+        let result = manager
+            .list_resource_templates(input.server.as_deref(), input.cursor)
+            .await
+            .map_err(|e| format!("{e}"));
+        Ok(McpRequestOutput { result })
+    }
+
+    /// Read an MCP resource (`read_mcp_resource` tool).  Content is capped
+    /// at `MAX_RESOURCE_CONTENT_BYTES` before it reaches the history.
+    #[activity]
+    pub async fn mcp_read_resource(
+        self: Arc<Self>,
+        _ctx: ActivityContext,
+        input: McpReadResourceInput,
+    ) -> Result<McpRequestOutput, ActivityError> {
+        let mut manager = self.mcp_manager.lock().await;
+        register_session_mcp_configs(
+            &mut manager,
+            Some(&input.server),
+            input.config_toml.as_deref(),
+            input.cwd.as_deref(),
+        );
+        let result = manager
+            .read_resource(&input.server, &input.uri)
+            .await
+            .map_err(|e| format!("{e}"));
+        Ok(McpRequestOutput { result })
+    }
+
+    /// List prompts exposed by MCP servers (`list_mcp_prompts` tool).
+    #[activity]
+    pub async fn mcp_list_prompts(
+        self: Arc<Self>,
+        _ctx: ActivityContext,
+        input: McpListInput,
+    ) -> Result<McpRequestOutput, ActivityError> {
+        let mut manager = self.mcp_manager.lock().await;
+        register_session_mcp_configs(
+            &mut manager,
+            input.server.as_deref(),
+            input.config_toml.as_deref(),
+            input.cwd.as_deref(),
+        );
+        let result = manager
+            .list_prompts(input.server.as_deref(), input.cursor)
+            .await
+            .map_err(|e| format!("{e}"));
+        Ok(McpRequestOutput { result })
+    }
+
+    /// Fetch a rendered MCP prompt (`get_mcp_prompt` tool and the
+    /// `/mcp-prompt` user command).
+    #[activity]
+    pub async fn mcp_get_prompt(
+        self: Arc<Self>,
+        _ctx: ActivityContext,
+        input: McpGetPromptInput,
+    ) -> Result<McpRequestOutput, ActivityError> {
+        let mut manager = self.mcp_manager.lock().await;
+        register_session_mcp_configs(
+            &mut manager,
+            Some(&input.server),
+            input.config_toml.as_deref(),
+            input.cwd.as_deref(),
+        );
+        let result = manager
+            .get_prompt(&input.server, &input.name, input.arguments)
+            .await
+            .map_err(|e| format!("{e}"));
+        Ok(McpRequestOutput { result })
     }
 
     /// Collect project context from the worker's environment.
@@ -452,6 +808,169 @@ impl CodexActivities {
         })
     }
 
+    /// Collect a crew session's artifacts: the listed workspace files, and
+    /// the files inside listed directories, as text with size limits.
+    #[activity]
+    pub async fn collect_artifacts(
+        _ctx: ActivityContext,
+        input: CollectArtifactsInput,
+    ) -> Result<CollectArtifactsOutput, ActivityError> {
+        tracing::debug!(cwd = %input.cwd, paths = input.paths.len(), "collecting artifacts");
+        let cwd = PathBuf::from(&input.cwd);
+        let output = tokio::task::spawn_blocking(move || collect_artifacts_in(&cwd, &input.paths))
+            .await
+            .map_err(|e| anyhow::anyhow!("artifact collection panicked: {e}"))?;
+        Ok(output)
+    }
+
+    /// Send a signal to the harness, starting it first if it is not
+    /// running.  Returns `false` when this worker has no Temporal client
+    /// (the caller then signals the harness directly).
+    #[activity]
+    pub async fn signal_with_start_harness(
+        self: Arc<Self>,
+        _ctx: ActivityContext,
+        input: HarnessSignalInput,
+    ) -> Result<bool, ActivityError> {
+        let Some((client, namespace)) = &self.temporal else {
+            return Ok(false);
+        };
+        let harness_input = HarnessInput {
+            continued_state: None,
+        };
+        let request = SignalWithStartWorkflowExecutionRequest {
+            namespace: namespace.clone(),
+            workflow_id: input.harness_id.clone(),
+            workflow_type: Some(WorkflowType {
+                name: "CodexHarness".to_string(),
+            }),
+            task_queue: Some(TaskQueue {
+                name: TASK_QUEUE.to_string(),
+                ..Default::default()
+            }),
+            input: Some(Payloads {
+                payloads: vec![
+                    harness_input
+                        .as_json_payload()
+                        .map_err(|e| anyhow::anyhow!("failed to serialize harness input: {e}"))?,
+                ],
+            }),
+            signal_name: input.signal_name.clone(),
+            signal_input: Some(Payloads {
+                payloads: vec![
+                    input
+                        .payload
+                        .as_json_payload()
+                        .map_err(|e| anyhow::anyhow!("failed to serialize signal: {e}"))?,
+                ],
+            }),
+            request_id: uuid::Uuid::new_v4().to_string(),
+            ..Default::default()
+        };
+        WorkflowService::signal_with_start_workflow_execution(
+            &mut client.clone(),
+            request.into_request(),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("signal-with-start of {} failed: {e}", input.harness_id))?;
+        Ok(true)
+    }
+
+    /// Look up sessions that are no longer running and the status they
+    /// closed with ([`SessionStatus::Missing`] for workflows that do not
+    /// exist).  Sessions still running, or whose status could not be read,
+    /// are left out; without a Temporal client the result is empty.
+    #[activity]
+    pub async fn session_statuses(
+        self: Arc<Self>,
+        _ctx: ActivityContext,
+        session_ids: Vec<String>,
+    ) -> Result<Vec<(String, SessionStatus)>, ActivityError> {
+        let Some((client, namespace)) = &self.temporal else {
+            return Ok(Vec::new());
+        };
+        let mut closed = Vec::new();
+        for session_id in session_ids {
+            let request = DescribeWorkflowExecutionRequest {
+                namespace: namespace.clone(),
+                execution: Some(WorkflowExecution {
+                    workflow_id: session_id.clone(),
+                    run_id: String::new(),
+                }),
+                ..Default::default()
+            };
+            let status = match WorkflowService::describe_workflow_execution(
+                &mut client.clone(),
+                request.into_request(),
+            )
+            .await
+            {
+                Ok(response) => response
+                    .into_inner()
+                    .workflow_execution_info
+                    .map(|info| info.status())
+                    .unwrap_or(WorkflowExecutionStatus::Unspecified),
+                // Never started, or already removed by namespace retention.
+                Err(e) if e.code() == tonic::Code::NotFound => {
+                    closed.push((session_id, SessionStatus::Missing));
+                    continue;
+                }
+                Err(e) => {
+                    tracing::debug!(session_id = %session_id, error = %e, "describe failed");
+                    continue;
+                }
+            };
+            if let Some(status) = closed_session_status(status) {
+                closed.push((session_id, status));
+            }
+        }
+        Ok(closed)
+    }
+
+    /// Terminate idle session workflows (retention `idle_action =
+    /// "terminate"`) together with their agents, which outlive a terminated
+    /// session (`Abandon` parent close policy).  Returns the sessions that
+    /// were terminated; without a Temporal client none are.
+    #[activity]
+    pub async fn terminate_sessions(
+        self: Arc<Self>,
+        _ctx: ActivityContext,
+        session_ids: Vec<String>,
+    ) -> Result<Vec<String>, ActivityError> {
+        let reason = "idle session terminated by harness retention policy";
+        let terminated = self.terminate_all(session_ids, reason).await;
+        self.terminate_agents_of(&terminated, reason).await;
+        Ok(terminated)
+    }
+
+    /// Terminate the agents still running of sessions that closed without
+    /// stopping them (terminated, timed out or failed from outside).  Agents
+    /// are started with the `Abandon` parent close policy, so they would
+    /// outlive such a session.  Returns the agents that were terminated;
+    /// without a Temporal client none are.
+    #[activity]
+    pub async fn terminate_session_agents(
+        self: Arc<Self>,
+        _ctx: ActivityContext,
+        session_ids: Vec<String>,
+    ) -> Result<Vec<String>, ActivityError> {
+        Ok(self
+            .terminate_agents_of(&session_ids, "session closed without stopping its agents")
+            .await)
+    }
+
+    /// Terminate workflows (e.g. the agents of a session that ended, or a
+    /// single agent on `control_agent terminate`).  Returns the workflows
+    /// that were terminated; without a Temporal client none are.
+    #[activity]
+    pub async fn terminate_workflows(
+        self: Arc<Self>,
+        _ctx: ActivityContext,
+        input: TerminateWorkflowsInput,
+    ) -> Result<Vec<String>, ActivityError> {
+        Ok(self.terminate_all(input.workflow_ids, &input.reason).await)
+    }
+
     /// Check if the worker has API credentials available.
     ///
     /// Returns `true` if `OPENAI_API_KEY` or `OPENAI_BEARER_TOKEN` is set
@@ -555,6 +1074,133 @@ impl CodexActivities {
     }
 }
 
+/// Harness status of a session workflow that closed with `status`; `None`
+/// while it is still running.  Cancelled and timed-out sessions count as
+/// failed.
+pub fn closed_session_status(status: WorkflowExecutionStatus) -> Option<SessionStatus> {
+    match status {
+        WorkflowExecutionStatus::Completed => Some(SessionStatus::Completed),
+        WorkflowExecutionStatus::Terminated => Some(SessionStatus::Terminated),
+        WorkflowExecutionStatus::Failed
+        | WorkflowExecutionStatus::Canceled
+        | WorkflowExecutionStatus::TimedOut => Some(SessionStatus::Failed),
+        // Running, continued-as-new (the new run is tracked) or unknown.
+        _ => None,
+    }
+}
+
+/// Read the artifacts at `paths` (relative to `cwd`).  Directories are
+/// walked recursively, skipping hidden entries and symbolic links; files are
+/// read in path order until [`MAX_ARTIFACT_FILES`] or
+/// [`MAX_TOTAL_ARTIFACT_BYTES`] is reached, never more than the remaining
+/// limit.  Paths outside `cwd` (including through symbolic links) are
+/// rejected.
+pub fn collect_artifacts_in(cwd: &Path, paths: &[String]) -> CollectArtifactsOutput {
+    let mut files = Vec::new();
+    let mut output = CollectArtifactsOutput::default();
+    let root = match cwd.canonicalize() {
+        Ok(root) => root,
+        Err(e) => {
+            output.errors.push(format!("{}: {e}", cwd.display()));
+            return output;
+        }
+    };
+    for path in paths {
+        let relative = Path::new(path);
+        if relative.is_absolute() || relative.components().any(|c| c == Component::ParentDir) {
+            output.errors.push(format!("{path}: outside the workspace"));
+            continue;
+        }
+        let full = root.join(relative);
+        let Ok(metadata) = std::fs::symlink_metadata(&full) else {
+            output.errors.push(format!("{path}: not found"));
+            continue;
+        };
+        // A symbolic link in a parent directory can still lead elsewhere.
+        if metadata.file_type().is_symlink()
+            || !full.canonicalize().is_ok_and(|c| c.starts_with(&root))
+        {
+            output.errors.push(format!("{path}: symbolic link or outside the workspace"));
+        } else if metadata.is_dir() {
+            walk_artifact_dir(&full, &mut files);
+        } else if metadata.is_file() {
+            files.push(full);
+        } else {
+            output.errors.push(format!("{path}: not a regular file"));
+        }
+    }
+
+    let mut total = 0;
+    for file in files {
+        let path = file.strip_prefix(&root).unwrap_or(&file).to_string_lossy().to_string();
+        if output.artifacts.iter().any(|a| a.path == path) {
+            continue;
+        }
+        if output.artifacts.len() == MAX_ARTIFACT_FILES {
+            output.errors.push(format!("{path}: more than {MAX_ARTIFACT_FILES} artifacts"));
+            continue;
+        }
+        let limit = MAX_ARTIFACT_BYTES.min(MAX_TOTAL_ARTIFACT_BYTES - total);
+        let (size, kept) = match read_artifact(&file, limit) {
+            Ok(read) => read,
+            Err(e) => {
+                output.errors.push(format!("{path}: {e}"));
+                continue;
+            }
+        };
+        let mut artifact = CrewArtifact {
+            path,
+            size,
+            content: String::new(),
+            truncated: size > kept.len() as u64,
+            binary: false,
+        };
+        match std::str::from_utf8(&kept) {
+            Ok(text) => artifact.content = text.to_string(),
+            // A cut may split a multi-byte character.
+            Err(e) if artifact.truncated && e.error_len().is_none() => {
+                artifact.content = String::from_utf8_lossy(&kept[..e.valid_up_to()]).to_string();
+            }
+            Err(_) => artifact.binary = true,
+        }
+        total += artifact.content.len();
+        output.artifacts.push(artifact);
+    }
+    output
+}
+
+/// Read at most `limit` bytes of `file`; returns its size and those bytes.
+fn read_artifact(file: &Path, limit: usize) -> std::io::Result<(u64, Vec<u8>)> {
+    use std::io::Read;
+
+    let file = std::fs::File::open(file)?;
+    let size = file.metadata()?.len();
+    let mut kept = Vec::with_capacity(limit.min(size as usize));
+    file.take(limit as u64).read_to_end(&mut kept)?;
+    Ok((size, kept))
+}
+
+/// Append the non-hidden files below `dir` to `files`, in path order.
+/// Symbolic links are not followed.
+fn walk_artifact_dir(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<(PathBuf, std::fs::FileType)> = entries
+        .filter_map(Result::ok)
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| Some((e.path(), e.file_type().ok()?)))
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    for (path, file_type) in entries {
+        if file_type.is_dir() {
+            walk_artifact_dir(&path, files);
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+}
+
 /// Known file tools that codex models support.
 ///
 /// The bundled `models.json` ships with `experimental_supported_tools: []`
@@ -2,35 +2,105 @@
 //!
 //! Usage:
 //!   codex-temporal-client "your prompt here"   → start new session
-//!   codex-temporal-client list                 → list sessions from harness
+//!   codex-temporal-client exec [<prompt>|-] [--session-id <id>] [--approval-policy <policy>]
+//!                         [--approval-timeout <interval>] [--timeout <interval>]
+//!                         [--max-iterations <n>] [--output-schema <file>] [--json] [--detach]
+//!                                              → run a prompt to the end of its turn (CI)
+//!   codex-temporal-client list [--archived] [--search <text>] [--tag <tag>]... [--repo <text>]
+//!                         [--status running|completed|failed|terminated|missing]
+//!                         [--since <interval>]
+//!                         [--before <interval>]
+//!                                              → list (and filter) sessions from harness
+//!   codex-temporal-client archive|unarchive|pin|unpin <session-id>
+//!                                              → archive or pin a session in the harness
+//!   codex-temporal-client rename <session-id> <title>
+//!   codex-temporal-client tag|untag <session-id> <tag>...
+//!                                              → edit a session's title and tags
+//!   codex-temporal-client retention [--max-sessions <n>] [--max-age <interval>]
+//!                         [--max-archived <n>] [--idle-after <interval>]
+//!                         [--idle-action shutdown|terminate] [--keep-pinned true|false]
+//!                                              → show or change the harness retention policy
 //!   codex-temporal-client crews                → list available crew types
 //!   codex-temporal-client start-crew <name> [--input key=value]...
+//!   codex-temporal-client prompt <server> <prompt> [--input key=value]...
+//!                                              → start a session from an MCP prompt
+//!   codex-temporal-client refresh-mcp <session-id> [--enable <server>]... [--disable <server>]...
+//!                                              → re-discover MCP tools mid-session
+//!   codex-temporal-client messages <session-id> → show inter-agent messages
+//!   codex-temporal-client pause|resume|cancel|terminate <session-id> <agent-id>
+//!                                              → control a single agent
+//!   codex-temporal-client show <session-id> [--json] → session details, agents, approvals
+//!   codex-temporal-client agents <session-id> [--json] → agents of a session
+//!   codex-temporal-client send <session-id> <message> [--agent <agent-id>] [--json]
+//!                                              → send a user message to an agent
+//!   codex-temporal-client tail <session-id> [--agent <agent-id>] [--since <n>] [--json]
+//!                                              → follow an agent's events
+//!   codex-temporal-client interrupt <session-id> [--agent <agent-id>] [--json]
+//!   codex-temporal-client shutdown <session-id> [--json]
+//!   codex-temporal-client spawn <session-id> <role> <message> [--json]
+//!                                              → start another agent in a session
+//!   codex-temporal-client approvals <session-id> [--json] → list pending approvals of all agents
+//!   codex-temporal-client approve|deny <session-id> [<agent-id>] <call-id> [--json]
+//!                                              → decide a pending approval
+//!   codex-temporal-client results <session-id> [--json] [--artifacts <dir>]
+//!                                              → outputs and artifacts of a crew run
+//!   codex-temporal-client timeline <session-id> [--follow]
+//!                                              → interleaved events of all agents
+//!   codex-temporal-client schedule create <schedule-id> <crew> (--cron <expr> | --every <interval>)...
+//!                         [--input key=value]... [--paused]
+//!   codex-temporal-client schedule list
+//!   codex-temporal-client schedule pause|unpause <schedule-id> [note]
+//!   codex-temporal-client schedule delete <schedule-id>
+//!                                              → recurring crew runs via Temporal Schedules
+//!   codex-temporal-client import <rollout.jsonl> [--name <name>] [--model <model>]
+//!                                              → move a codex CLI conversation into a session
+//!   codex-temporal-client export <session-id> [--format rollout|markdown] [--agent <agent-id>]
+//!                         [--output <path>|-]
+//!                                              → write a session as a rollout or transcript
+//!
+//! Every subcommand takes `--json` for machine-readable output.  Flags take
+//! their value as `--name value` or `--name=value`; unknown flags are errors.
 
 use std::collections::BTreeMap;
+use std::io::{IsTerminal, Read};
 use std::str::FromStr;
 
 use temporalio_client::{
     Client, ClientOptions, Connection, ConnectionOptions,
     WorkflowQueryOptions, WorkflowSignalOptions, WorkflowStartOptions,
 };
-use temporalio_common::protos::temporal::api::enums::v1::WorkflowIdConflictPolicy;
+use temporalio_common::protos::temporal::api::enums::v1::{
+    WorkflowExecutionStatus, WorkflowIdConflictPolicy,
+};
 use temporalio_common::telemetry::TelemetryOptions;
 use temporalio_sdk_core::{CoreRuntime, RuntimeOptions, Url};
+use tokio_util::sync::CancellationToken;
 
+use codex_core::AgentSession;
+use codex_core::config::find_codex_home;
+use codex_protocol::protocol::{Event, EventMsg};
+
+use codex_temporal::TASK_QUEUE;
+use codex_temporal::cli::{
+    CommandArgs, ExecOutcome, ExecTracker, agent_workflow_id, event_line, parse_approval_policy,
+};
 use codex_temporal::config_loader;
-use codex_temporal::harness::{CodexHarness, CodexHarnessRun};
-use codex_temporal::session_workflow::SessionWorkflow;
+use codex_temporal::rollout::{check_start_payload, parse_rollout_jsonl};
+use codex_temporal::schedules::{self, CrewScheduleSpec};
+use codex_temporal::harness::{CodexHarness, CodexHarnessRun, harness_workflow_id};
+use codex_temporal::workflow::{AgentWorkflow, AgentWorkflowRun};
+use codex_temporal::session::{TemporalAgentSession, user_turn_op};
+use codex_temporal::session_workflow::{SessionWorkflow, SessionWorkflowRun};
 use codex_temporal::types::{
-    CrewMode, HarnessInput, SessionEntry, SessionStatus,
+    AgentControlAction, AgentControlRequest, AgentMessage, AgentRecord, ContinueAsNewState,
+    CrewMode, CrewResults, ExportFormat, ExportPage, ExportRequest, HarnessInput,
+    HarnessRegistration, MCP_PROMPT_COMMAND, McpRefreshRequest, RetentionPolicy, SessionEdit,
+    SessionEntry, SessionFilter, SessionStatus, SessionTimeline, SpawnAgentInput, preview_text,
+    short_agent_id,
 };
+use codex_temporal::watcher::watch_session;
 
-const TASK_QUEUE: &str = "codex-temporal";
-
-/// Derive the harness workflow ID for the current user.
-fn harness_workflow_id() -> String {
-    let user = std::env::var("USER").unwrap_or_else(|_| "default".to_string());
-    format!("codex-harness-{user}")
-}
+const NAMESPACE: &str = "default";
 
 /// Ensure the harness workflow is running (start if not).
 async fn ensure_harness(client: &Client) -> Result<(), Box<dyn std::error::Error>> {
@@ -47,41 +117,96 @@ async fn ensure_harness(client: &Client) -> Result<(), Box<dyn std::error::Error
     Ok(())
 }
 
-/// Query the harness for sessions and print them.
-async fn list_sessions(client: &Client) -> Result<(), Box<dyn std::error::Error>> {
+/// Flags of `list` that take a value (the [`SessionFilter`] options).
+const LIST_OPTIONS: &[&str] = &["search", "tag", "repo", "status", "since", "before"];
+
+/// Query the harness for sessions matching the `list` flags and print
+/// them, pinned first, then most recently active (a JSON array with
+/// `--json`).
+async fn list_sessions(client: &Client, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let args = CommandArgs::parse(args, LIST_OPTIONS, &["archived", "json"])?;
+    if let Some(arg) = args.positional(0) {
+        return Err(format!("unexpected argument '{arg}'").into());
+    }
+    let now_millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    let mut filter = SessionFilter {
+        archived: args.switch("archived"),
+        ..Default::default()
+    };
+    for name in LIST_OPTIONS {
+        for value in args.values(name) {
+            filter.set_option(name, value, now_millis)?;
+        }
+    }
+
     ensure_harness(client).await?;
 
     let harness_id = harness_workflow_id();
     let handle = client.get_workflow_handle::<CodexHarnessRun>(&harness_id);
     let json: String = handle
         .query(
-            CodexHarness::list_sessions,
-            (),
+            CodexHarness::search_sessions,
+            filter,
             WorkflowQueryOptions::default(),
         )
         .await?;
 
     let sessions: Vec<SessionEntry> = serde_json::from_str(&json).unwrap_or_default();
+    if args.json() {
+        println!("{}", serde_json::to_string_pretty(&sessions)?);
+        return Ok(());
+    }
     if sessions.is_empty() {
         println!("No sessions found.");
         return Ok(());
     }
 
     println!(
-        "{:<40} {:<12} {:<16} STATUS",
-        "SESSION ID", "MODEL", "CREATED"
+        "{:<40} {:<12} {:<16} {:<16} STATUS",
+        "SESSION ID", "MODEL", "CREATED", "LAST ACTIVE"
     );
     for s in &sessions {
         let status = match s.status {
             SessionStatus::Running => "Running",
             SessionStatus::Completed => "Completed",
             SessionStatus::Failed => "Failed",
+            SessionStatus::Terminated => "Terminated",
+            SessionStatus::Missing => "Missing",
         };
         let created = format_millis_ago(s.created_at_millis);
+        let active = format_millis_ago(s.last_active_millis());
+        let schedule = s
+            .schedule_id
+            .as_ref()
+            .map(|id| format!(" (schedule {id})"))
+            .unwrap_or_default();
+        let pinned = if s.pinned { " (pinned)" } else { "" };
         println!(
-            "{:<40} {:<12} {:<16} {}",
-            s.session_id, s.model, created, status
+            "{:<40} {:<12} {:<16} {:<16} {}{}{}",
+            s.session_id, s.model, created, active, status, schedule, pinned
         );
+
+        // Second line: title/name, tags and workspace.
+        let mut details = Vec::new();
+        if let Some(name) = s.display_name() {
+            details.push(name.to_string());
+        }
+        if !s.tags.is_empty() {
+            let tags: Vec<String> = s.tags.iter().map(|t| format!("#{t}")).collect();
+            details.push(tags.join(" "));
+        }
+        let workspace = match (&s.repository, &s.branch) {
+            (Some(repo), Some(branch)) => Some(format!("{repo}@{branch}")),
+            (Some(repo), None) => Some(repo.clone()),
+            (None, _) => s.cwd.clone(),
+        };
+        details.extend(workspace);
+        if !details.is_empty() {
+            println!("    {}", details.join("  "));
+        }
     }
 
     Ok(())
@@ -106,26 +231,133 @@ fn format_millis_ago(millis: u64) -> String {
     }
 }
 
-/// Register a session with the harness.
-async fn register_with_harness(
+/// Archive, unarchive, pin, unpin, rename, tag or untag a session in the
+/// harness.
+async fn edit_session(
     client: &Client,
-    entry: SessionEntry,
+    command: &str,
+    args: &[String],
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let harness_id = harness_workflow_id();
-    let handle = client.get_workflow_handle::<CodexHarnessRun>(&harness_id);
-    handle
-        .signal(
-            CodexHarness::register_session,
-            entry,
-            WorkflowSignalOptions::default(),
+    let usage = match command {
+        "rename" => format!("usage: {command} <session-id> <title> [--json]"),
+        "tag" | "untag" => format!("usage: {command} <session-id> <tag>... [--json]"),
+        _ => format!("usage: {command} <session-id> [--json]"),
+    };
+    let args = CommandArgs::parse(args, &[], &["json"])?;
+    let session_id = args.positional(0).ok_or(usage.as_str())?.to_string();
+    let values = &args.positional[1..];
+    let takes_values = matches!(command, "rename" | "tag" | "untag");
+    if takes_values == values.is_empty() {
+        return Err(usage.into());
+    }
+    let handle = client.get_workflow_handle::<CodexHarnessRun>(&harness_workflow_id());
+    let options = WorkflowSignalOptions::default();
+    match command {
+        "archive" => {
+            handle
+                .signal(CodexHarness::archive_session, session_id.clone(), options)
+                .await?
+        }
+        "unarchive" => {
+            handle
+                .signal(CodexHarness::unarchive_session, session_id.clone(), options)
+                .await?
+        }
+        "rename" | "tag" | "untag" => {
+            let mut edit = SessionEdit {
+                session_id: session_id.clone(),
+                ..Default::default()
+            };
+            match command {
+                "rename" => edit.title = Some(values.join(" ")),
+                "tag" => edit.add_tags = values.to_vec(),
+                _ => edit.remove_tags = values.to_vec(),
+            }
+            handle.signal(CodexHarness::edit_session, edit, options).await?
+        }
+        _ => {
+            let pinned = command == "pin";
+            handle
+                .signal(CodexHarness::set_session_pinned, (session_id.clone(), pinned), options)
+                .await?
+        }
+    }
+    if args.json() {
+        println!("{}", serde_json::json!({"session_id": session_id, "action": command}));
+    } else {
+        println!("{command}: {session_id}");
+    }
+    Ok(())
+}
+
+/// Flags of `retention` (the [`RetentionPolicy`] options).
+const RETENTION_OPTIONS: &[&str] = &[
+    "max-sessions",
+    "max-age",
+    "max-archived",
+    "idle-after",
+    "idle-action",
+    "keep-pinned",
+];
+
+/// Show the harness retention policy, or change the options given as
+/// `--<option> <value>` flags (`off` clears a limit).
+async fn retention(client: &Client, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let args = CommandArgs::parse(args, RETENTION_OPTIONS, &["json"])?;
+    if let Some(arg) = args.positional(0) {
+        return Err(format!("unexpected argument '{arg}'").into());
+    }
+    ensure_harness(client).await?;
+    let handle = client.get_workflow_handle::<CodexHarnessRun>(&harness_workflow_id());
+    let json: String = handle
+        .query(
+            CodexHarness::retention_policy,
+            (),
+            WorkflowQueryOptions::default(),
         )
         .await?;
+    let mut policy: RetentionPolicy = serde_json::from_str(&json).unwrap_or_default();
+
+    let mut changed = false;
+    for name in RETENTION_OPTIONS {
+        for value in args.values(name) {
+            policy.set_option(name, value)?;
+            changed = true;
+        }
+    }
+    if changed {
+        handle
+            .signal(
+                CodexHarness::set_retention_policy,
+                policy.clone(),
+                WorkflowSignalOptions::default(),
+            )
+            .await?;
+    }
+
+    if args.json() {
+        println!("{}", serde_json::to_string_pretty(&policy)?);
+        return Ok(());
+    }
+    let limit = |value: Option<String>| value.unwrap_or_else(|| "off".to_string());
+    let secs = |value: Option<u64>| limit(value.map(|s| format!("{s}s")));
+    println!("max sessions:  {}", limit(policy.max_sessions.map(|n| n.to_string())));
+    println!("max age:       {}", secs(policy.max_age_secs));
+    println!("max archived:  {}", limit(policy.max_archived.map(|n| n.to_string())));
+    println!("idle after:    {}", secs(policy.idle_after_secs));
+    println!("idle action:   {:?}", policy.idle_action);
+    println!("keep pinned:   {}", policy.keep_pinned);
     Ok(())
 }
 
 /// List available crew types (local only, no Temporal connection needed).
-fn list_crew_types() -> Result<(), Box<dyn std::error::Error>> {
+fn list_crew_types(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let args = CommandArgs::parse(args, &[], &["json"])?;
     let crews = config_loader::discover_crew_types()?;
+    if args.json() {
+        println!("{}", serde_json::to_string_pretty(&crews)?);
+        return Ok(());
+    }
     if crews.is_empty() {
         println!("No crew types found. Place TOML files in {{CODEX_HOME}}/crews/.");
         return Ok(());
@@ -160,23 +392,1141 @@ fn list_crew_types() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-/// Parse `--input key=value` pairs from CLI arguments.
-fn parse_input_args(args: &[String]) -> BTreeMap<String, String> {
-    let mut inputs = BTreeMap::new();
-    let mut i = 0;
-    while i < args.len() {
-        if args[i] == "--input"
-            && let Some(kv) = args.get(i + 1)
-        {
-            if let Some((k, v)) = kv.split_once('=') {
-                inputs.insert(k.to_string(), v.to_string());
+/// Signal a session to re-run MCP discovery, enabling/disabling servers.
+async fn refresh_mcp(client: &Client, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: refresh-mcp <session-id> [--enable <server>]... [--disable <server>]... \
+                 [--json]";
+    let args = CommandArgs::parse(args, &["enable", "disable"], &["json"])?;
+    let session_id = args.positional(0).ok_or(usage)?;
+    let request = McpRefreshRequest {
+        enable: args.values("enable").to_vec(),
+        disable: args.values("disable").to_vec(),
+    };
+
+    let handle = client.get_workflow_handle::<SessionWorkflowRun>(session_id);
+    handle
+        .signal(
+            SessionWorkflow::refresh_mcp,
+            request.clone(),
+            WorkflowSignalOptions::default(),
+        )
+        .await?;
+
+    if args.json() {
+        println!(
+            "{}",
+            serde_json::json!({
+                "session_id": session_id,
+                "status": "refresh requested",
+                "enable": request.enable,
+                "disable": request.disable,
+            })
+        );
+    } else {
+        println!("MCP refresh requested for {session_id}");
+    }
+    Ok(())
+}
+
+/// Print a session's inter-agent message log.
+async fn list_messages(client: &Client, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let args = CommandArgs::parse(args, &[], &["json"])?;
+    let session_id = args.positional(0).ok_or("usage: messages <session-id> [--json]")?;
+    let handle = client.get_workflow_handle::<SessionWorkflowRun>(session_id);
+    let json: String = handle
+        .query(
+            SessionWorkflow::list_messages,
+            (),
+            WorkflowQueryOptions::default(),
+        )
+        .await?;
+
+    let messages: Vec<AgentMessage> = serde_json::from_str(&json).unwrap_or_default();
+    if args.json() {
+        println!("{}", serde_json::to_string_pretty(&messages)?);
+        return Ok(());
+    }
+    if messages.is_empty() {
+        println!("No messages.");
+        return Ok(());
+    }
+
+    println!("{:<6} {:<16} {:<16} {:<24} MESSAGE", "SEQ", "FROM", "TO", "DELIVERED TO");
+    for m in &messages {
+        let delivered: Vec<&str> = m.delivered_to.iter().map(|a| short_agent_id(a)).collect();
+        let delivered = if delivered.is_empty() {
+            "(nobody)".to_string()
+        } else {
+            delivered.join(",")
+        };
+        println!(
+            "{:<6} {:<16} {:<16} {:<24} {}",
+            m.seq,
+            short_agent_id(&m.from),
+            m.to,
+            delivered,
+            m.body
+        );
+    }
+
+    Ok(())
+}
+
+/// Pause, resume, cancel or terminate one agent of a session.
+async fn control_agent(
+    client: &Client,
+    action: AgentControlAction,
+    args: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: pause|resume|cancel|terminate <session-id> <agent-id> [--json]";
+    let args = CommandArgs::parse(args, &[], &["json"])?;
+    let [session_id, agent_id] = args.positional.as_slice() else {
+        return Err(usage.into());
+    };
+    let agent_id = agent_workflow_id(session_id, Some(agent_id));
+
+    let handle = client.get_workflow_handle::<SessionWorkflowRun>(session_id);
+    handle
+        .signal(
+            SessionWorkflow::control_agent,
+            AgentControlRequest {
+                agent_id: agent_id.clone(),
+                action,
+            },
+            WorkflowSignalOptions::default(),
+        )
+        .await?;
+
+    if args.json() {
+        println!(
+            "{}",
+            serde_json::json!({"session_id": session_id, "agent_id": agent_id, "action": action})
+        );
+    } else {
+        println!("{action:?} requested for {agent_id} in {session_id}");
+    }
+    Ok(())
+}
+
+/// Print the approval requests of a session's agents awaiting a decision.
+async fn list_approvals(client: &Client, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let args = CommandArgs::parse(args, &[], &["json"])?;
+    let session_id = args.positional(0).ok_or("usage: approvals <session-id> [--json]")?;
+    let approvals = attach_session(client, session_id, None)
+        .await?
+        .pending_approvals()
+        .await?;
+
+    if args.json() {
+        println!("{}", serde_json::to_string_pretty(&approvals)?);
+        return Ok(());
+    }
+    if approvals.is_empty() {
+        println!("No pending approvals.");
+        return Ok(());
+    }
+
+    println!("{:<16} {:<32} REQUEST", "AGENT", "CALL ID");
+    for a in &approvals {
+        println!(
+            "{:<16} {:<32} {}",
+            short_agent_id(&a.agent_id),
+            a.call_id,
+            a.summary()
+        );
+    }
+
+    Ok(())
+}
+
+/// Approve or deny one pending approval of a session.  The waiting agent is
+/// looked up among the session's pending approvals unless given (`--agent`,
+/// or as the middle argument of the older three-argument form).
+async fn decide_approval(
+    client: &Client,
+    approved: bool,
+    args: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let usage =
+        "usage: approve|deny <session-id> [<agent-id>] <call-id> [--agent <agent-id>] [--json]";
+    let args = CommandArgs::parse(args, &["agent"], &["json"])?;
+    let (session_id, agent_id, call_id) = match args.positional.as_slice() {
+        [session_id, call_id] => (session_id, args.value("agent"), call_id),
+        [session_id, agent_id, call_id] => (session_id, Some(agent_id.as_str()), call_id),
+        _ => return Err(usage.into()),
+    };
+
+    let session = attach_session(client, session_id, None).await?;
+    let agent_id = match agent_id {
+        Some(agent_id) => agent_workflow_id(session_id, Some(agent_id)),
+        None => session
+            .pending_approvals()
+            .await?
+            .into_iter()
+            .find(|a| &a.call_id == call_id)
+            .map(|a| a.agent_id)
+            .ok_or_else(|| {
+                format!("no pending approval {call_id} in {session_id} (see `approvals`)")
+            })?,
+    };
+    let result = session.decide_approval(agent_id.clone(), call_id.clone(), approved).await;
+
+    let decision = if approved { "approved" } else { "denied" };
+    match (&result, args.json()) {
+        (Ok(()), true) => println!(
+            "{}",
+            serde_json::json!({"agent_id": agent_id, "call_id": call_id, "decision": decision})
+        ),
+        (Err(e), true) => println!(
+            "{}",
+            serde_json::json!({
+                "agent_id": agent_id,
+                "call_id": call_id,
+                "decision": decision,
+                "error": e.to_string(),
+            })
+        ),
+        (Ok(()), false) => {
+            let verb = if approved { "Approval" } else { "Denial" };
+            println!("{verb} of {call_id} sent to {} in {session_id}", short_agent_id(&agent_id));
+        }
+        (Err(_), false) => {}
+    }
+    result.map_err(Into::into)
+}
+
+/// Attach to a session without a watcher, with ops (user turns,
+/// interrupts) sent to `agent` (short or full agent ID; default: main).
+async fn attach_session(
+    client: &Client,
+    session_id: &str,
+    agent: Option<&str>,
+) -> Result<TemporalAgentSession, Box<dyn std::error::Error>> {
+    let harness_config = config_loader::load_harness_config().await?;
+    let mut input = harness_config.base_input;
+    config_loader::apply_env_overrides(&mut input);
+    input.model_provider = Some(harness_config.model_provider);
+    Ok(TemporalAgentSession::attach(
+        client.clone(),
+        session_id.to_string(),
+        Some(agent_workflow_id(session_id, agent)),
+        input,
+        Some(harness_workflow_id()),
+    ))
+}
+
+/// Send a user message to an agent of a session (restarting the session
+/// with its conversation if it has closed).
+async fn send_message(client: &Client, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: send <session-id> <message> [--agent <agent-id>] [--json]";
+    let args = CommandArgs::parse(args, &["agent"], &["json"])?;
+    let session_id = args.positional(0).ok_or(usage)?;
+    let message = args.rest(1).ok_or(usage)?;
+
+    let session = attach_session(client, session_id, args.value("agent")).await?;
+    session
+        .submit(user_turn_op(session.base_input(), message))
+        .await?;
+    session.detach();
+
+    let agent_id = session.active_agent_id();
+    if args.json() {
+        println!(
+            "{}",
+            serde_json::json!({"session_id": session_id, "agent_id": agent_id, "status": "sent"})
+        );
+    } else {
+        println!("Sent to {} in {session_id}", short_agent_id(&agent_id));
+    }
+    Ok(())
+}
+
+/// How long one `tail` poll waits for new events.
+const TAIL_POLL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Print the events of one agent as they happen, until the agent's
+/// workflow completes.  `--json` prints one JSON object per event.
+async fn tail_events(client: &Client, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: tail <session-id> [--agent <agent-id>] [--since <n>] [--json]";
+    let args = CommandArgs::parse(args, &["agent", "since"], &["json"])?;
+    let session_id = args.positional(0).ok_or(usage)?;
+    let mut watermark: usize = match args.value("since") {
+        Some(since) => since.parse().map_err(|_| format!("invalid --since '{since}'"))?,
+        None => 0,
+    };
+
+    let session = attach_session(client, session_id, args.value("agent")).await?;
+    let agent_id = session.active_agent_id();
+    loop {
+        let update = session.events_since(watermark, TAIL_POLL).await?;
+        let first = update.watermark.saturating_sub(update.events.len());
+        for (i, json) in update.events.iter().enumerate() {
+            let Ok(event) = serde_json::from_str::<Event>(json) else {
+                continue;
+            };
+            if args.json() {
+                println!(
+                    "{}",
+                    serde_json::json!({
+                        "agent_id": agent_id,
+                        "watermark": first + i + 1,
+                        "event": event,
+                    })
+                );
+            } else if let Some(line) = event_line(&event.msg) {
+                println!("{line}");
+            }
+        }
+        watermark = update.watermark;
+        if update.completed {
+            return Ok(());
+        }
+    }
+}
+
+/// Interrupt the current turn of an agent.
+async fn interrupt_agent(
+    client: &Client,
+    args: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let args = CommandArgs::parse(args, &["agent"], &["json"])?;
+    let session_id = args
+        .positional(0)
+        .ok_or("usage: interrupt <session-id> [--agent <agent-id>] [--json]")?;
+
+    let session = attach_session(client, session_id, args.value("agent")).await?;
+    session.interrupt().await?;
+
+    let agent_id = session.active_agent_id();
+    if args.json() {
+        println!("{}", serde_json::json!({"agent_id": agent_id, "status": "interrupted"}));
+    } else {
+        println!("Interrupt sent to {} in {session_id}", short_agent_id(&agent_id));
+    }
+    Ok(())
+}
+
+/// Shut down a session: every agent finishes its current turn and exits.
+async fn shutdown_session(
+    client: &Client,
+    args: &[String],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let args = CommandArgs::parse(args, &[], &["json"])?;
+    let session_id = args
+        .positional(0)
+        .ok_or("usage: shutdown <session-id> [--json]")?;
+
+    attach_session(client, session_id, None)
+        .await?
+        .shutdown_session()
+        .await?;
+
+    if args.json() {
+        let value = serde_json::json!({"session_id": session_id, "status": "shutdown requested"});
+        println!("{value}");
+    } else {
+        println!("Shutdown requested for {session_id}");
+    }
+    Ok(())
+}
+
+/// Print the agents of a session with their role and status.
+async fn list_agents(client: &Client, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let args = CommandArgs::parse(args, &[], &["json"])?;
+    let session_id = args
+        .positional(0)
+        .ok_or("usage: agents <session-id> [--json]")?;
+    let agents = attach_session(client, session_id, None)
+        .await?
+        .list_agents()
+        .await?;
+
+    if args.json() {
+        println!("{}", serde_json::to_string_pretty(&agents)?);
+        return Ok(());
+    }
+    print_agents(&agents);
+    Ok(())
+}
+
+/// Print an agent table.
+fn print_agents(agents: &[AgentRecord]) {
+    if agents.is_empty() {
+        println!("No agents.");
+        return;
+    }
+    println!("{:<16} {:<12} {:<11} {:<16} LAST MESSAGE", "AGENT", "ROLE", "STATUS", "PARENT");
+    for a in agents {
+        let status = match &a.error {
+            Some(_) => format!("{:?}!", a.status),
+            None => format!("{:?}", a.status),
+        };
+        println!(
+            "{:<16} {:<12} {:<11} {:<16} {}",
+            short_agent_id(&a.agent_id),
+            a.role,
+            status,
+            a.parent_agent_id.as_deref().map_or("-", short_agent_id),
+            a.last_agent_message.as_deref().map(preview_text).unwrap_or_default()
+        );
+    }
+}
+
+/// Spawn a new agent with a role and an initial message in a session.
+async fn spawn_agent(client: &Client, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: spawn <session-id> <role> <message> [--json]";
+    let args = CommandArgs::parse(args, &[], &["json"])?;
+    let (Some(session_id), Some(role), Some(message)) =
+        (args.positional(0), args.positional(1), args.rest(2))
+    else {
+        return Err(usage.into());
+    };
+
+    attach_session(client, session_id, None)
+        .await?
+        .spawn_agent(SpawnAgentInput {
+            role: role.to_string(),
+            message,
+            requested_by: None,
+            request_id: None,
+        })
+        .await?;
+
+    if args.json() {
+        println!(
+            "{}",
+            serde_json::json!({"session_id": session_id, "role": role, "status": "spawn requested"})
+        );
+    } else {
+        println!("Spawn of a {role} agent requested in {session_id}");
+    }
+    Ok(())
+}
+
+/// Print a session's harness entry, its agents and pending approvals.
+async fn show_session(client: &Client, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let args = CommandArgs::parse(args, &[], &["json"])?;
+    let session_id = args
+        .positional(0)
+        .ok_or("usage: show <session-id> [--json]")?;
+
+    let session = attach_session(client, session_id, None).await?;
+    let entry = session
+        .query_sessions()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .find(|e| e.session_id == session_id);
+    let agents = match session.list_agents().await {
+        Ok(agents) => agents,
+        Err(e) if entry.is_none() => return Err(e.into()),
+        Err(_) => Vec::new(),
+    };
+    let approvals = session.pending_approvals().await.unwrap_or_default();
+
+    if args.json() {
+        let value = serde_json::json!({
+            "session": entry,
+            "agents": agents,
+            "pending_approvals": approvals,
+        });
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
+    }
+
+    println!("Session      {session_id}");
+    if let Some(entry) = &entry {
+        if let Some(name) = entry.display_name() {
+            println!("Title        {name}");
+        }
+        let pinned = if entry.pinned { " (pinned)" } else { "" };
+        let archived = if entry.is_archived() { " (archived)" } else { "" };
+        println!("Status       {:?}{pinned}{archived}", entry.status);
+        println!("Model        {}", entry.model);
+        println!("Created      {}", format_millis_ago(entry.created_at_millis));
+        println!("Last active  {}", format_millis_ago(entry.last_active_millis()));
+        if let Some(crew) = &entry.crew_type {
+            println!("Crew         {crew}");
+        }
+        let workspace = match (&entry.repository, &entry.branch) {
+            (Some(repo), Some(branch)) => Some(format!("{repo}@{branch}")),
+            (Some(repo), None) => Some(repo.clone()),
+            (None, _) => entry.cwd.clone(),
+        };
+        if let Some(workspace) = workspace {
+            println!("Workspace    {workspace}");
+        }
+        if !entry.tags.is_empty() {
+            let tags: Vec<String> = entry.tags.iter().map(|t| format!("#{t}")).collect();
+            println!("Tags         {}", tags.join(" "));
+        }
+        if let Some(preview) = &entry.last_message_preview {
+            println!("Last message {preview}");
+        }
+    }
+    println!();
+    print_agents(&agents);
+    if !approvals.is_empty() {
+        println!();
+        println!("Pending approvals:");
+        for a in &approvals {
+            println!("  {:<32} {}", a.call_id, a.summary());
+        }
+    }
+    Ok(())
+}
+
+/// How long `exec --detach` waits for a new session to start when no
+/// `--timeout` is given.
+const EXEC_START_WAIT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// How long a reattaching `exec` waits for the agent's buffered events.
+const EXEC_BACKLOG_WAIT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Run one prompt to the end of its turn, for CI: prints the final message
+/// (or, with `--json`, the agent's events and a result line) and returns how
+/// the turn ended.  With `--session-id`, a session that is already running
+/// is reattached instead of started, so a restarted job picks up the result
+/// of its latest turn (or, given a prompt, of the turn the prompt starts).
+async fn exec(client: &Client, args: &[String]) -> Result<ExecOutcome, Box<dyn std::error::Error>> {
+    let usage = "usage: exec [<prompt>|-] [--session-id <id>] [--approval-policy <policy>] \
+                 [--approval-timeout <interval>] [--timeout <interval>] [--max-iterations <n>] \
+                 [--output-schema <file>] [--json] [--detach]";
+    let args = CommandArgs::parse(
+        args,
+        &[
+            "session-id",
+            "approval-policy",
+            "approval-timeout",
+            "timeout",
+            "max-iterations",
+            "output-schema",
+        ],
+        &["json", "detach"],
+    )?;
+    let interval = |name: &str| args.value(name).map(schedules::parse_interval).transpose();
+    let timeout = interval("timeout")?;
+    let approval_timeout = interval("approval-timeout")?.unwrap_or_default();
+    let deadline = timeout.map(|t| tokio::time::Instant::now() + t);
+
+    let harness_config = config_loader::load_harness_config().await?;
+    let mut input = harness_config.base_input;
+    config_loader::apply_env_overrides(&mut input);
+    input.model_provider = Some(harness_config.model_provider);
+    if let Some(policy) = args.value("approval-policy") {
+        input.approval_policy = parse_approval_policy(policy)?;
+    }
+    if let Some(max) = args.value("max-iterations") {
+        let max = max.parse().map_err(|_| format!("invalid --max-iterations '{max}'"))?;
+        input.max_iterations = Some(max);
+    }
+    if let Some(path) = args.value("output-schema") {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {path}: {e}"))?;
+        let schema = serde_json::from_str(&text)
+            .map_err(|e| format!("{path}: invalid JSON schema: {e}"))?;
+        input.output_schema = Some(schema);
+    }
+
+    let session_id = match args.value("session-id") {
+        Some(id) if id.starts_with("codex-session-") => id.to_string(),
+        Some(id) => format!("codex-session-{id}"),
+        None => format!("codex-session-{}", uuid::Uuid::new_v4()),
+    };
+    let probe = TemporalAgentSession::attach(
+        client.clone(),
+        session_id.clone(),
+        None,
+        input.clone(),
+        Some(harness_workflow_id()),
+    );
+    // The prompt comes from the arguments, or from stdin when it is `-`
+    // (or missing, for a new session).
+    let read_prompt = |required: bool| -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let prompt = match args.rest(0) {
+            Some(prompt) if prompt != "-" => prompt,
+            None if !required => return Ok(None),
+            given => {
+                if given.is_none() && std::io::stdin().is_terminal() {
+                    return Err(usage.into());
+                }
+                let mut prompt = String::new();
+                std::io::stdin().read_to_string(&mut prompt)?;
+                prompt
             }
-            i += 2;
+        };
+        if prompt.trim().is_empty() {
+            return Err("exec: the prompt is empty".into());
+        }
+        Ok(Some(prompt))
+    };
+    let (session, started, mut tracker) = match probe.run_status().await? {
+        None => {
+            let prompt = read_prompt(true)?.unwrap_or_default();
+            // The session registers itself with this client's harness.
+            let _ = ensure_harness(client).await;
+            let session = TemporalAgentSession::new_with_harness(
+                client.clone(),
+                session_id.clone(),
+                input,
+                Some(harness_workflow_id()),
+            );
+            session
+                .submit(user_turn_op(session.base_input(), prompt))
+                .await?;
+            (session, true, ExecTracker::default())
+        }
+        Some(WorkflowExecutionStatus::Running) => {
+            // Follow the agent from its latest turn rather than from its
+            // first: that turn's outcome is picked up, or, with a prompt,
+            // the outcome of the turn queued behind it.
+            let prompt = read_prompt(false)?;
+            let backlog = probe.events_since(0, EXEC_BACKLOG_WAIT).await?;
+            let first = backlog.watermark.saturating_sub(backlog.events.len());
+            let latest_turn = backlog.events.iter().rposition(|json| {
+                serde_json::from_str::<Event>(json)
+                    .is_ok_and(|event| matches!(event.msg, EventMsg::TurnStarted(_)))
+            });
+            let session = TemporalAgentSession::resume_from(
+                client.clone(),
+                session_id.clone(),
+                input,
+                Some(harness_workflow_id()),
+                first + latest_turn.unwrap_or(0),
+            );
+            let mut turns_before = 0;
+            if let Some(prompt) = prompt {
+                session
+                    .submit(user_turn_op(session.base_input(), prompt))
+                    .await?;
+                turns_before = usize::from(latest_turn.is_some());
+            }
+            (session, false, ExecTracker::after_turns(turns_before))
+        }
+        Some(status) => {
+            return Err(format!(
+                "session {session_id} has already ended ({status:?}); \
+                 continue it with `send` or read it with `export`"
+            )
+            .into());
+        }
+    };
+    let verb = if started { "Started" } else { "Reattached to" };
+    eprintln!("{verb} {session_id}");
+
+    if args.switch("detach") {
+        // A start that failed at first is retried in the background; wait
+        // for the session to come up before leaving.
+        if started {
+            let wait = timeout.unwrap_or(EXEC_START_WAIT);
+            let configured = tokio::time::timeout(wait, async {
+                loop {
+                    match session.next_event().await {
+                        Ok(event) if matches!(event.msg, EventMsg::SessionConfigured(_)) => {
+                            return Ok(());
+                        }
+                        Ok(_) => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+            })
+            .await;
+            match configured {
+                Ok(result) => result?,
+                Err(_) => return Ok(ExecOutcome::TimedOut),
+            }
+        }
+        session.detach();
+        if args.json() {
+            println!("{}", serde_json::json!({"session_id": session_id, "status": "detached"}));
+        } else {
+            println!("{session_id}");
+        }
+        return Ok(ExecOutcome::Success);
+    }
+
+    let mut outcome = loop {
+        let awaiting_approval = !tracker.pending_approvals().is_empty();
+        let next = async {
+            if awaiting_approval {
+                tokio::time::timeout(approval_timeout, session.next_event()).await.ok()
+            } else {
+                Some(session.next_event().await)
+            }
+        };
+        let next = match deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline, next).await {
+                Ok(next) => next,
+                Err(_) => break ExecOutcome::TimedOut,
+            },
+            None => next.await,
+        };
+        let Some(event) = next else {
+            break ExecOutcome::ApprovalPending;
+        };
+        let event = event?;
+        if args.json() {
+            println!("{}", serde_json::json!({"event": event}));
+        } else if let Some(line) = event_line(&event.msg) {
+            eprintln!("{line}");
+        }
+        if let Some(outcome) = tracker.observe(&event.msg) {
+            break outcome;
+        }
+    };
+    if outcome == ExecOutcome::Success
+        && let Some(turn_id) = tracker.turn_id().map(str::to_string)
+    {
+        match session.turn_budget_exhausted(&turn_id).await {
+            Ok(true) => outcome = tracker.budget_exhausted(),
+            Ok(false) => {}
+            Err(e) => tracing::warn!(error = %e, "could not tell if the turn ran out of budget"),
+        }
+    }
+    session.detach();
+
+    if args.json() {
+        let result = serde_json::json!({
+            "session_id": session_id,
+            "status": outcome.as_str(),
+            "exit_code": outcome.exit_code(),
+            "last_agent_message": tracker.last_message(),
+            "pending_approvals": tracker.pending_approvals(),
+            "error": tracker.error(),
+        });
+        println!("{}", serde_json::json!({"result": result}));
+        return Ok(outcome);
+    }
+
+    if matches!(outcome, ExecOutcome::Success | ExecOutcome::BudgetExhausted)
+        && let Some(message) = tracker.last_message()
+    {
+        println!("{message}");
+    }
+    let reattach = format!("rerun with --session-id {session_id} to pick up the result");
+    match outcome {
+        ExecOutcome::Success => {}
+        ExecOutcome::Error => {
+            eprintln!("error: {}", tracker.error().unwrap_or("the turn failed"));
+        }
+        ExecOutcome::BudgetExhausted => {
+            eprintln!("The turn ran out of iterations; continue it with `send {session_id}`.");
+        }
+        ExecOutcome::ApprovalPending => {
+            for call_id in tracker.pending_approvals() {
+                eprintln!("Waiting for approval: approve|deny {session_id} {call_id}");
+            }
+            eprintln!("The session keeps waiting; {reattach}.");
+        }
+        ExecOutcome::TimedOut => {
+            eprintln!("Timed out; the session keeps running, {reattach}.");
+        }
+    }
+    Ok(outcome)
+}
+
+/// How long `timeline` without `--follow` waits for further events.
+const TIMELINE_IDLE: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Print the events of all agents of a session as one timeline, followed
+/// by a per-agent status panel.  With `--follow`, keeps printing new events.
+/// With `--json`, prints one event per line and no panel.
+async fn timeline(client: &Client, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let args = CommandArgs::parse(args, &[], &["follow", "json"])?;
+    let session_id = args
+        .positional(0)
+        .ok_or("usage: timeline <session-id> [--follow] [--json]")?;
+    let follow = args.switch("follow");
+
+    let cancel = CancellationToken::new();
+    let mut rx =
+        watch_session(client.clone(), session_id.to_string(), BTreeMap::new(), cancel.clone());
+    let mut timeline = SessionTimeline::new();
+    loop {
+        let event = if follow {
+            rx.recv().await
+        } else {
+            match tokio::time::timeout(TIMELINE_IDLE, rx.recv()).await {
+                Ok(event) => event,
+                Err(_) => None,
+            }
+        };
+        let Some(event) = event else {
+            break;
+        };
+        if !timeline.record(&event) {
             continue;
         }
-        i += 1;
+        if args.json() {
+            println!("{}", serde_json::to_string(&event)?);
+        } else if let Some(line) = event.timeline_line() {
+            println!("{line}");
+        }
+    }
+
+    cancel.cancel();
+
+    if !args.json() {
+        println!();
+        print!("{}", timeline.render_status_panel());
+    }
+    Ok(())
+}
+
+/// Print a crew session's outputs (or JSON with `--json`) and optionally
+/// write its artifacts below a directory.  Falls back to the outputs of a
+/// session that is still running.
+async fn show_results(client: &Client, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: results <session-id> [--json] [--artifacts <dir>]";
+    let args = CommandArgs::parse(args, &["artifacts"], &["json"])?;
+    let session_id = args.positional(0).ok_or(usage)?.to_string();
+    let json_output = args.json();
+    let artifacts_dir = args.value("artifacts");
+
+    ensure_harness(client).await?;
+    let handle = client.get_workflow_handle::<CodexHarnessRun>(&harness_workflow_id());
+    let json: String = handle
+        .query(
+            CodexHarness::get_session_results,
+            session_id.clone(),
+            WorkflowQueryOptions::default(),
+        )
+        .await?;
+    let summary: Option<CrewResults> = serde_json::from_str(&json).unwrap_or_default();
+    let finished = summary.is_some();
+    // The harness only keeps a summary: read the full results (artifact
+    // contents) from the session itself, falling back to the summary once
+    // its history is gone.
+    let session = client.get_workflow_handle::<SessionWorkflowRun>(&session_id);
+    let full = session
+        .query(SessionWorkflow::crew_results, (), WorkflowQueryOptions::default())
+        .await
+        .map(|json: String| serde_json::from_str::<Option<CrewResults>>(&json).unwrap_or_default());
+    let (results, contents) = match (full, summary) {
+        (Ok(Some(full)), _) => (Some(full), true),
+        (_, Some(summary)) => (Some(summary), false),
+        (Ok(None), None) => (None, true),
+        (Err(e), None) => return Err(e.into()),
+    };
+    let results = results.ok_or_else(|| format!("session {session_id} declares no crew results"))?;
+    if !contents && (artifacts_dir.is_some() || !results.truncated_outputs.is_empty()) {
+        eprintln!("session history unavailable: artifact contents and full outputs are missing");
+    }
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        if !finished {
+            println!("(session still running; artifacts are collected when it ends)");
+        }
+        for (name, value) in &results.outputs {
+            println!("{name}: {value}");
+        }
+        for name in &results.missing_outputs {
+            println!("{name}: (missing)");
+        }
+        for artifact in &results.artifacts {
+            let note = match (artifact.binary, artifact.truncated) {
+                (true, _) => " (binary, not stored)",
+                (false, true) => " (truncated)",
+                (false, false) => "",
+            };
+            println!("artifact {} — {} bytes{note}", artifact.path, artifact.size);
+        }
+        for error in &results.artifact_errors {
+            println!("artifact error: {error}");
+        }
+    }
+
+    if let Some(dir) = artifacts_dir.filter(|_| contents) {
+        let dir = std::path::Path::new(dir);
+        for artifact in results.artifacts.iter().filter(|a| !a.binary) {
+            let path = dir.join(&artifact.path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, &artifact.content)?;
+        }
+    }
+
+    // Missing outputs fail the command so CI pipelines notice.
+    if finished && !results.missing_outputs.is_empty() {
+        return Err(format!("missing outputs: {}", results.missing_outputs.join(", ")).into());
+    }
+    Ok(())
+}
+
+/// Import a codex CLI rollout file as a new durable session: its main
+/// agent is restored with the rollout's conversation and waits for the next
+/// message (e.g. from `codex-temporal-tui --resume <session-id>`).
+async fn import_rollout(client: &Client, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: import <rollout.jsonl> [--name <name>] [--model <model>] [--json]";
+    let args = CommandArgs::parse(args, &["name", "model"], &["json"])?;
+    let path = args.positional(0).ok_or(usage)?.to_string();
+    let name = args.value("name").map(str::to_string);
+    let model = args.value("model").map(str::to_string);
+
+    let text = std::fs::read_to_string(&path)?;
+    let rollout = parse_rollout_jsonl(&text).map_err(|e| format!("{path}: {e}"))?;
+    if rollout.skipped > 0 {
+        eprintln!("Skipped {} rollout lines of unknown types", rollout.skipped);
+    }
+
+    let harness_config = config_loader::load_harness_config().await?;
+    let mut input = harness_config.base_input;
+    config_loader::apply_env_overrides(&mut input);
+    input.model_provider = Some(harness_config.model_provider);
+    if let Some(model) = model.or_else(|| rollout.model.clone()) {
+        input.model = model;
+    }
+    input.user_message = String::new();
+
+    ensure_harness(client).await?;
+    let name = name.unwrap_or_else(|| {
+        let preview = rollout.first_user_message().map(|m| preview_text(&m));
+        format!("imported: {}", preview.unwrap_or_else(|| path.clone()))
+    });
+    input.harness_registration = Some(HarnessRegistration {
+        harness_id: harness_workflow_id(),
+        name: Some(name),
+        crew_type: None,
+        schedule_id: None,
+    });
+
+    let item_count = rollout.items.len();
+    let skipped = rollout.skipped;
+    input.main_agent_state = Some(ContinueAsNewState::from_rollout(rollout.items));
+    check_start_payload(&input).map_err(|e| format!("{path}: {e}"))?;
+
+    let workflow_id = format!("codex-session-{}", uuid::Uuid::new_v4());
+    let options = WorkflowStartOptions::new(TASK_QUEUE, &workflow_id).build();
+    client
+        .start_workflow(SessionWorkflow::run, input, options)
+        .await?;
+
+    tracing::info!(
+        workflow_id = %workflow_id,
+        source = ?rollout.source_id,
+        items = item_count,
+        "imported rollout"
+    );
+    if args.json() {
+        println!(
+            "{}",
+            serde_json::json!({
+                "session_id": workflow_id,
+                "items": item_count,
+                "skipped": skipped,
+            })
+        );
+        return Ok(());
+    }
+    println!("{workflow_id}");
+    eprintln!(
+        "Imported {item_count} items; continue with: codex-temporal-tui --resume {workflow_id}"
+    );
+    Ok(())
+}
+
+/// Export one agent of a session (the main agent by default) as a codex
+/// rollout or a Markdown transcript.  Rollouts go below
+/// `$CODEX_HOME/sessions/` unless `--output` is given, so `codex resume
+/// <id>` finds them; transcripts go to stdout.  With `--json`, prints where
+/// the export went (and its content when it went to stdout).
+async fn export_session(client: &Client, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: export <session-id> [--format rollout|markdown] [--agent <agent-id>] \
+                 [--output <path>|-] [--json]";
+    let args = CommandArgs::parse(args, &["format", "agent", "output"], &["json"])?;
+    let session_id = args.positional(0).ok_or(usage)?;
+    let format = match args.value("format") {
+        Some(value) => value.parse()?,
+        None => ExportFormat::Rollout,
+    };
+    let output = args.value("output");
+    let agent_id = agent_workflow_id(session_id, args.value("agent"));
+
+    // The main agent keeps the session's UUID; other agents get their own.
+    let conversation_id = session_id
+        .strip_prefix("codex-session-")
+        .and_then(|id| uuid::Uuid::parse_str(id).ok())
+        .filter(|_| agent_id.ends_with("/main"))
+        .unwrap_or_else(uuid::Uuid::new_v4);
+    let now = chrono::Utc::now();
+    let mut request = ExportRequest {
+        format,
+        offset: 0,
+        limit: None,
+        conversation_id: conversation_id.to_string(),
+        timestamp: now.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+    };
+
+    let handle = client.get_workflow_handle::<AgentWorkflowRun>(&agent_id);
+    let mut lines = Vec::new();
+    loop {
+        let json: String = handle
+            .query(
+                AgentWorkflow::export_session,
+                request.clone(),
+                WorkflowQueryOptions::default(),
+            )
+            .await?;
+        let page: ExportPage = serde_json::from_str(&json)?;
+        lines.extend(page.lines);
+        match page.next_offset {
+            Some(next) if next > request.offset => request.offset = next,
+            _ => break,
+        }
+    }
+    let mut text = lines.join("\n");
+    text.push('\n');
+    if format == ExportFormat::Markdown {
+        text = format!("# {agent_id}\n\n{text}");
     }
-    inputs
+
+    let path = match output {
+        Some("-") => None,
+        Some(path) => Some(std::path::PathBuf::from(path)),
+        None if format == ExportFormat::Rollout => {
+            let dir = find_codex_home()?
+                .join("sessions")
+                .join(now.format("%Y/%m/%d").to_string());
+            let file = format!(
+                "rollout-{}-{conversation_id}.jsonl",
+                now.format("%Y-%m-%dT%H-%M-%S")
+            );
+            Some(dir.join(file))
+        }
+        None => None,
+    };
+    if let Some(path) = &path {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, &text)?;
+    }
+    if args.json() {
+        let mut report = serde_json::json!({
+            "agent_id": agent_id,
+            "format": format,
+            "path": path.as_ref().map(|p| p.display().to_string()),
+            "lines": lines.len(),
+        });
+        if path.is_none() {
+            report["content"] = text.into();
+        }
+        println!("{report}");
+        return Ok(());
+    }
+    match path {
+        Some(path) => {
+            println!("{}", path.display());
+            if format == ExportFormat::Rollout {
+                eprintln!(
+                    "Exported {} lines; open with: codex resume {conversation_id}",
+                    lines.len()
+                );
+            }
+        }
+        None => print!("{text}"),
+    }
+    Ok(())
+}
+
+/// Manage Temporal Schedules that start crew sessions.
+async fn schedule(client: &Client, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: schedule create <schedule-id> <crew> (--cron <expr> | --every <interval>)... \
+                 [--input key=value]... [--paused] [--json]\n       schedule list [--json]\n       \
+                 schedule pause|unpause <schedule-id> [note] [--json]\n       \
+                 schedule delete <schedule-id> [--json]";
+    let args = CommandArgs::parse(args, &["cron", "every", "input"], &["paused", "json"])?;
+    let subcommand = args.positional(0);
+    let schedule_id = args.positional(1);
+    // Plain confirmation, or `{"schedule_id", "action"}` with `--json`.
+    let report = |schedule_id: &str, action: &str, line: String| {
+        if args.json() {
+            println!("{}", serde_json::json!({"schedule_id": schedule_id, "action": action}));
+        } else {
+            println!("{line}");
+        }
+    };
+
+    match (subcommand, schedule_id) {
+        (Some("create"), Some(schedule_id)) => {
+            let crew_name = args.positional(2).ok_or(usage)?;
+            let spec =
+                CrewScheduleSpec::from_flags(args.values("cron").to_vec(), args.values("every"))?;
+            let paused = args.switch("paused");
+
+            let crew = config_loader::load_crew_type(crew_name)?;
+            if crew.mode != CrewMode::Autonomous {
+                return Err(format!(
+                    "crew '{crew_name}' is interactive; only autonomous crews can be scheduled"
+                )
+                .into());
+            }
+            let harness_config = config_loader::load_harness_config().await?;
+            let mut input = harness_config.base_input;
+            config_loader::apply_env_overrides(&mut input);
+            input.model_provider = Some(harness_config.model_provider);
+            config_loader::apply_crew_type(&crew, &args.key_values("input")?, &mut input)?;
+
+            ensure_harness(client).await?;
+            let input = schedules::scheduled_session_input(
+                input,
+                schedule_id,
+                crew_name,
+                &harness_workflow_id(),
+            );
+            schedules::create_crew_schedule(
+                client, NAMESPACE, schedule_id, crew_name, &spec, &input, paused,
+            )
+            .await?;
+            let line = format!("Schedule {schedule_id} created for crew {crew_name}");
+            report(schedule_id, "create", line);
+        }
+        (Some("list"), _) => {
+            let list = schedules::list_crew_schedules(client, NAMESPACE).await?;
+            if args.json() {
+                println!("{}", serde_json::to_string_pretty(&list)?);
+                return Ok(());
+            }
+            if list.is_empty() {
+                println!("No crew schedules found.");
+                return Ok(());
+            }
+            println!(
+                "{:<24} {:<20} {:<8} {:<20} LAST SESSION",
+                "SCHEDULE ID", "CREW", "STATE", "NEXT RUN"
+            );
+            for s in &list {
+                let state = if s.paused { "paused" } else { "active" };
+                let next_run = s
+                    .next_run_millis
+                    .and_then(|millis| chrono::DateTime::from_timestamp_millis(millis as i64))
+                    .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+                    .unwrap_or_else(|| "-".to_string());
+                let last = s.recent_sessions.last().map_or("-", String::as_str);
+                println!(
+                    "{:<24} {:<20} {:<8} {:<20} {}",
+                    s.schedule_id, s.crew, state, next_run, last
+                );
+            }
+        }
+        (Some(action @ ("pause" | "unpause")), Some(schedule_id)) => {
+            let paused = action == "pause";
+            let note = args
+                .rest(2)
+                .unwrap_or_else(|| format!("{action}d via codex-temporal-client"));
+            schedules::set_schedule_paused(client, NAMESPACE, schedule_id, paused, &note).await?;
+            report(schedule_id, action, format!("Schedule {schedule_id} {action}d"));
+        }
+        (Some("delete"), Some(schedule_id)) => {
+            schedules::delete_schedule(client, NAMESPACE, schedule_id).await?;
+            report(schedule_id, "delete", format!("Schedule {schedule_id} deleted"));
+        }
+        _ => return Err(usage.into()),
+    }
+    Ok(())
 }
 
 #[tokio::main]
@@ -193,7 +1543,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // --- crews subcommand (no Temporal connection needed) ---
     if first_arg == Some("crews") {
-        return list_crew_types();
+        return list_crew_types(&args[2..]);
     }
 
     // --- start-crew subcommand ---
@@ -218,17 +1568,105 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let connection = Connection::connect(connection_options).await?;
     let client = Client::new(
         connection,
-        ClientOptions::new("default").build(),
+        ClientOptions::new(NAMESPACE).build(),
     )?;
 
     if is_list {
-        return list_sessions(&client).await;
+        return list_sessions(&client, &args[2..]).await;
+    }
+
+    if matches!(
+        first_arg,
+        Some("archive" | "unarchive" | "pin" | "unpin" | "rename" | "tag" | "untag")
+    ) {
+        return edit_session(&client, first_arg.unwrap_or_default(), &args[2..]).await;
+    }
+
+    if first_arg == Some("retention") {
+        return retention(&client, &args[2..]).await;
+    }
+
+    if first_arg == Some("refresh-mcp") {
+        return refresh_mcp(&client, &args[2..]).await;
+    }
+
+    if first_arg == Some("messages") {
+        return list_messages(&client, &args[2..]).await;
+    }
+
+    if first_arg == Some("schedule") {
+        return schedule(&client, &args[2..]).await;
+    }
+
+    if first_arg == Some("import") {
+        return import_rollout(&client, &args[2..]).await;
+    }
+
+    if first_arg == Some("export") {
+        return export_session(&client, &args[2..]).await;
+    }
+
+    if first_arg == Some("timeline") {
+        return timeline(&client, &args[2..]).await;
+    }
+
+    if first_arg == Some("results") {
+        return show_results(&client, &args[2..]).await;
+    }
+
+    if first_arg == Some("exec") {
+        let outcome = exec(&client, &args[2..]).await?;
+        std::process::exit(outcome.exit_code());
+    }
+
+    if first_arg == Some("send") {
+        return send_message(&client, &args[2..]).await;
+    }
+
+    if first_arg == Some("tail") {
+        return tail_events(&client, &args[2..]).await;
+    }
+
+    if first_arg == Some("interrupt") {
+        return interrupt_agent(&client, &args[2..]).await;
+    }
+
+    if first_arg == Some("shutdown") {
+        return shutdown_session(&client, &args[2..]).await;
+    }
+
+    if first_arg == Some("agents") {
+        return list_agents(&client, &args[2..]).await;
+    }
+
+    if first_arg == Some("spawn") {
+        return spawn_agent(&client, &args[2..]).await;
+    }
+
+    if first_arg == Some("show") {
+        return show_session(&client, &args[2..]).await;
+    }
+
+    if first_arg == Some("approvals") {
+        return list_approvals(&client, &args[2..]).await;
+    }
+
+    if matches!(first_arg, Some("approve" | "deny")) {
+        return decide_approval(&client, first_arg == Some("approve"), &args[2..]).await;
+    }
+
+    if let Some(action) = first_arg.and_then(|a| a.parse::<AgentControlAction>().ok()) {
+        return control_agent(&client, action, &args[2..]).await;
     }
 
     if is_start_crew {
-        let crew_name = args.get(2).ok_or("usage: start-crew <name> [--input key=value]...")?;
-        let crew = config_loader::load_crew_type(crew_name)?;
-        let crew_inputs = parse_input_args(&args[3..]);
+        let start_args = CommandArgs::parse(&args[2..], &["input"], &["json"])?;
+        let crew_name = start_args
+            .positional(0)
+            .ok_or("usage: start-crew <name> [--input key=value]... [--json]")?
+            .to_string();
+        let crew = config_loader::load_crew_type(&crew_name)?;
+        let crew_inputs = start_args.key_values("input")?;
 
         // Load base config and apply env overrides.
         let harness_config = config_loader::load_harness_config().await?;
@@ -239,7 +1677,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Apply crew type overrides.
         config_loader::apply_crew_type(&crew, &crew_inputs, &mut input)?;
 
-        let model = input.model.clone();
+        // The session registers itself with the harness (best-effort), so it
+        // can report its status and results there.
+        if ensure_harness(&client).await.is_ok() {
+            input.harness_registration = Some(HarnessRegistration {
+                harness_id: harness_workflow_id(),
+                name: Some(format!("[{crew_name}]")),
+                crew_type: Some(crew_name.clone()),
+                schedule_id: None,
+            });
+        }
+
         let workflow_id = format!("codex-session-{}", uuid::Uuid::new_v4());
         tracing::info!(workflow_id = %workflow_id, crew = %crew_name, "starting crew workflow");
 
@@ -254,33 +1702,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             crew = %crew_name,
             "crew workflow started — use Temporal UI to monitor"
         );
-
-        // Register with harness (best-effort).
-        if ensure_harness(&client).await.is_ok() {
-            let now_millis = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_millis() as u64;
-            let entry = SessionEntry {
-                session_id: workflow_id.clone(),
-                name: Some(format!("[{crew_name}]")),
-                model,
-                created_at_millis: now_millis,
-                status: SessionStatus::Running,
-                crew_type: Some(crew_name.clone()),
-            };
-            if let Err(e) = register_with_harness(&client, entry).await {
-                tracing::warn!(%e, "failed to register session with harness");
-            }
+        if start_args.json() {
+            println!("{}", serde_json::json!({"session_id": workflow_id, "crew": crew_name}));
+        } else {
+            println!("{workflow_id}");
         }
 
         return Ok(());
     }
 
     // --- start new session (default) ---
-    let user_message = first_arg
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| "Hello, Codex!".to_string());
+    let is_prompt = first_arg == Some("prompt");
+    let start_args = if is_prompt {
+        CommandArgs::parse(&args[2..], &["input"], &["json"])?
+    } else {
+        CommandArgs::parse(&args[1..], &[], &["json"])?
+    };
+    let user_message = if is_prompt {
+        // The workflow expands `/mcp-prompt` into the prompt's text.
+        let usage = "usage: prompt <server> <prompt> [--input key=value]... [--json]";
+        let [server, prompt] = start_args.positional.as_slice() else {
+            return Err(usage.into());
+        };
+        let mut command = format!("{MCP_PROMPT_COMMAND} {server} {prompt}");
+        for (k, v) in start_args.key_values("input")? {
+            command.push_str(&format!(" {k}={v}"));
+        }
+        command
+    } else {
+        start_args
+            .rest(0)
+            .unwrap_or_else(|| "Hello, Codex!".to_string())
+    };
 
     tracing::info!(server_url = %server_url, user_message = %user_message, "starting codex workflow");
 
@@ -288,9 +1741,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let harness_config = config_loader::load_harness_config().await?;
     let mut input = harness_config.base_input;
     config_loader::apply_env_overrides(&mut input);
-    input.user_message = user_message.clone();
+    input.user_message = user_message;
     input.model_provider = Some(harness_config.model_provider);
-    let model = input.model.clone();
+
+    // The session registers itself with the harness and reports its
+    // activity and final status there.
+    if ensure_harness(&client).await.is_ok() {
+        input.harness_registration = Some(HarnessRegistration {
+            harness_id: harness_workflow_id(),
+            name: None,
+            crew_type: None,
+            schedule_id: None,
+        });
+    }
 
     let workflow_id = format!("codex-session-{}", uuid::Uuid::new_v4());
     tracing::info!(workflow_id = %workflow_id, "starting workflow");
@@ -307,24 +1770,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         run_id = ?handle.run_id(),
         "workflow started — use Temporal UI to monitor"
     );
-
-    // Register with harness (best-effort).
-    if ensure_harness(&client).await.is_ok() {
-        let now_millis = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
-        let entry = SessionEntry {
-            session_id: workflow_id.clone(),
-            name: Some(user_message),
-            model,
-            created_at_millis: now_millis,
-            status: SessionStatus::Running,
-            crew_type: None,
-        };
-        if let Err(e) = register_with_harness(&client, entry).await {
-            tracing::warn!(%e, "failed to register session with harness");
-        }
+    if start_args.json() {
+        println!("{}", serde_json::json!({"session_id": workflow_id}));
+    } else {
+        println!("{workflow_id}");
     }
 
     Ok(())
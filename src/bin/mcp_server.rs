@@ -0,0 +1,66 @@
+//! MCP server binary exposing durable codex sessions to other agents/IDEs.
+//!
+//! Usage (e.g. as an MCP server entry in another client's config):
+//!   codex-temporal-mcp
+//!
+//! Speaks MCP over stdio; logs go to stderr.
+//!
+//! Environment variables:
+//!   TEMPORAL_ADDRESS  — Temporal server URL (default: http://localhost:7233)
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use temporalio_client::{Client, ClientOptions, Connection, ConnectionOptions};
+use temporalio_common::telemetry::TelemetryOptions;
+use temporalio_sdk_core::{CoreRuntime, RuntimeOptions, Url};
+
+use codex_temporal::config_loader;
+use codex_temporal::harness::harness_workflow_id;
+use codex_temporal::mcp_server::CodexMcpServer;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // stdout carries the protocol, so tracing must go to stderr.
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "info".parse().unwrap()),
+        )
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .init();
+
+    let server_url = std::env::var("TEMPORAL_ADDRESS")
+        .unwrap_or_else(|_| "http://localhost:7233".to_string());
+
+    // Connect to the Temporal server.
+    let connection_options = ConnectionOptions::new(
+        Url::from_str(&server_url)?,
+    )
+    .identity("codex-temporal-mcp")
+    .build();
+    let telemetry_options = TelemetryOptions::builder().build();
+    let runtime_options = RuntimeOptions::builder()
+        .telemetry_options(telemetry_options)
+        .build()?;
+    let _runtime = CoreRuntime::new_assume_tokio(runtime_options)?;
+
+    let connection = Connection::connect(connection_options).await?;
+    let client = Client::new(
+        connection,
+        ClientOptions::new("default").build(),
+    )?;
+
+    // Load config.toml and apply env-var overrides for new sessions.
+    let harness_config = config_loader::load_harness_config().await?;
+    let mut base_input = harness_config.base_input;
+    config_loader::apply_env_overrides(&mut base_input);
+    base_input.model_provider = Some(harness_config.model_provider);
+
+    tracing::info!(%server_url, "codex-temporal MCP server listening on stdio");
+
+    let server = Arc::new(CodexMcpServer::new(client, base_input, harness_workflow_id()));
+    server.serve_stdio().await?;
+    Ok(())
+}
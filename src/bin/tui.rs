@@ -7,6 +7,7 @@
 //! Usage:
 //!   codex-temporal-tui "your prompt here"
 //!   codex-temporal-tui --resume <session_id>
+//!   codex-temporal-tui --follow-agents ...   → also show other agents' events
 //!
 //! Environment variables:
 //!   TEMPORAL_ADDRESS  — Temporal server URL (default: http://localhost:7233)
@@ -28,34 +29,27 @@ use codex_protocol::protocol::SandboxPolicy;
 use codex_protocol::protocol::SessionConfiguredEvent;
 use codex_protocol::ThreadId;
 
+use codex_temporal::TASK_QUEUE;
 use codex_temporal::config_loader;
-use codex_temporal::harness::{CodexHarness, CodexHarnessRun};
+use codex_temporal::harness::{CodexHarness, CodexHarnessRun, harness_workflow_id};
 use codex_temporal::session::TemporalAgentSession;
-use codex_temporal::types::{
-    HarnessInput, SessionEntry, SessionStatus,
-};
+use codex_temporal::types::HarnessInput;
 
 use temporalio_client::{
     Client, ClientOptions, Connection, ConnectionOptions,
-    WorkflowQueryOptions, WorkflowSignalOptions, WorkflowStartOptions,
+    WorkflowQueryOptions, WorkflowStartOptions,
 };
 use temporalio_common::protos::temporal::api::enums::v1::WorkflowIdConflictPolicy;
 use temporalio_common::telemetry::TelemetryOptions;
 use temporalio_sdk_core::{CoreRuntime, RuntimeOptions, Url};
 
-/// Derive the harness workflow ID for the current user.
-fn harness_workflow_id() -> String {
-    let user = std::env::var("USER").unwrap_or_else(|_| "default".to_string());
-    format!("codex-harness-{user}")
-}
-
 /// Ensure the harness workflow is running (start if not).
 async fn ensure_harness(client: &Client) -> Result<(), Box<dyn std::error::Error>> {
     let harness_id = harness_workflow_id();
     let input = HarnessInput {
         continued_state: None,
     };
-    let options = WorkflowStartOptions::new("codex-temporal", &harness_id)
+    let options = WorkflowStartOptions::new(TASK_QUEUE, &harness_id)
         .id_conflict_policy(WorkflowIdConflictPolicy::UseExisting)
         .build();
     client
@@ -64,23 +58,6 @@ async fn ensure_harness(client: &Client) -> Result<(), Box<dyn std::error::Error
     Ok(())
 }
 
-/// Register a new session with the harness.
-async fn register_with_harness(
-    client: &Client,
-    entry: SessionEntry,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let harness_id = harness_workflow_id();
-    let handle = client.get_workflow_handle::<CodexHarnessRun>(&harness_id);
-    handle
-        .signal(
-            CodexHarness::register_session,
-            entry,
-            WorkflowSignalOptions::default(),
-        )
-        .await?;
-    Ok(())
-}
-
 /// Query the harness to check if the worker has API credentials.
 async fn query_credentials_available(client: &Client) -> Result<bool, Box<dyn std::error::Error>> {
     let harness_id = harness_workflow_id();
@@ -95,6 +72,9 @@ async fn query_credentials_available(client: &Client) -> Result<bool, Box<dyn st
     Ok(available)
 }
 
+/// Flag to merge the events of the session's other agents into the TUI.
+const FOLLOW_AGENTS_FLAG: &str = "--follow-agents";
+
 /// Parse --resume flag from args.
 ///
 /// Returns:
@@ -115,6 +95,8 @@ fn parse_resume_arg() -> (Option<String>, Option<String>) {
                 eprintln!("--resume requires a session ID argument");
                 std::process::exit(1);
             }
+        } else if args[i] == FOLLOW_AGENTS_FLAG {
+            i += 1;
         } else if prompt.is_none() {
             prompt = Some(args[i].clone());
             i += 1;
@@ -126,8 +108,6 @@ fn parse_resume_arg() -> (Option<String>, Option<String>) {
     (resume, prompt)
 }
 
-use codex_temporal::session::filter_initial_events;
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Log to file to avoid corrupting the TUI terminal.
@@ -205,36 +185,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         tracing::info!(session_id = %session_id, "resuming session");
         let session = TemporalAgentSession::resume(client.clone(), session_id, base_input);
 
-        // Fetch existing events to seed initial_messages.
-        let events = session.fetch_initial_events().await?;
-        let initial_messages = filter_initial_events(events);
+        // Fetch the conversation transcript to seed initial_messages.
+        let initial_messages = session.fetch_history().await?;
+        // A closed session is restarted with this conversation on the next turn.
+        session.reopen_if_closed().await?;
 
         (session, Some(initial_messages))
     } else {
         // New session mode.
         let workflow_id = format!("codex-tui-{}", uuid::Uuid::new_v4());
-        let session = TemporalAgentSession::new(client.clone(), workflow_id.clone(), base_input);
-
-        // Register with harness.
-        let now_millis = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
-        let entry = SessionEntry {
-            session_id: workflow_id,
-            name: initial_prompt.clone(),
-            model: model.clone(),
-            created_at_millis: now_millis,
-            status: SessionStatus::Running,
-            crew_type: None,
-        };
-        if let Err(e) = register_with_harness(&client, entry).await {
-            tracing::warn!(%e, "failed to register session with harness");
-        }
+        let session = TemporalAgentSession::new(client.clone(), workflow_id, base_input);
 
+        // The session registers itself with the harness when it starts.
         (session, None)
     };
 
+    if std::env::args().any(|a| a == FOLLOW_AGENTS_FLAG) {
+        session.follow_other_agents(true);
+    }
+
     run_tui_session(
         session,
         model,
@@ -3,6 +3,8 @@
 //! Runs both workflow and activity workers on the same task queue.
 //! The workflow worker executes the codex agentic loop deterministically,
 //! while the activity worker performs real I/O (model calls, tool exec).
+//! A second activity worker polls a task queue of this process only, where
+//! answers to MCP elicitations reach the calls this process holds open.
 
 use std::str::FromStr;
 
@@ -12,13 +14,12 @@ use temporalio_common::worker::WorkerTaskTypes;
 use temporalio_sdk::{Worker, WorkerOptions};
 use temporalio_sdk_core::{CoreRuntime, RuntimeOptions, Url};
 
+use codex_temporal::TASK_QUEUE;
 use codex_temporal::activities::CodexActivities;
 use codex_temporal::harness::CodexHarness;
 use codex_temporal::session_workflow::SessionWorkflow;
 use codex_temporal::workflow::AgentWorkflow;
 
-const TASK_QUEUE: &str = "codex-temporal";
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // The apply_patch tool handler spawns a subprocess using the current
@@ -95,19 +96,29 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         ClientOptions::new("default").build(),
     )?;
 
-    // Build the worker with both workflow and activity registrations.
+    // Build the worker with both workflow and activity registrations.  The
+    // activities are shared with the worker-specific queue's worker.
+    let worker_queue = format!("{TASK_QUEUE}-worker-{}", uuid::Uuid::new_v4());
+    let activities = CodexActivities::new()
+        .with_client(client.clone(), "default")
+        .with_worker_task_queue(worker_queue.clone());
     let worker_options = WorkerOptions::new(TASK_QUEUE)
         .task_types(WorkerTaskTypes::all())
         .register_workflow::<SessionWorkflow>()
         .register_workflow::<AgentWorkflow>()
         .register_workflow::<CodexHarness>()
-        .register_activities(CodexActivities::new())
+        .register_activities(activities.clone())
+        .build();
+    let own_queue_options = WorkerOptions::new(worker_queue.as_str())
+        .task_types(WorkerTaskTypes::activity_only())
+        .register_activities(activities)
         .build();
 
-    let mut worker = Worker::new(&runtime, client, worker_options)?;
+    let mut worker = Worker::new(&runtime, client.clone(), worker_options)?;
+    let mut own_queue_worker = Worker::new(&runtime, client, own_queue_options)?;
 
-    tracing::info!("worker ready, polling for tasks…");
-    worker.run().await?;
+    tracing::info!(%worker_queue, "worker ready, polling for tasks…");
+    tokio::try_join!(worker.run(), own_queue_worker.run())?;
 
     Ok(())
 }
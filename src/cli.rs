@@ -0,0 +1,349 @@
+//! Helpers of the `codex-temporal-client` command line.
+//!
+//! [`CommandArgs`] parses the arguments of one subcommand (positionals,
+//! `--name value` options and boolean switches such as `--json`),
+//! [`event_line`] renders the events of an agent as the plain-text lines
+//! `tail` prints, and [`ExecTracker`] decides how an `exec` turn ended.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use codex_protocol::protocol::{AskForApproval, EventMsg};
+
+use crate::types::preview_text;
+
+/// Parsed arguments of one subcommand.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandArgs {
+    /// Arguments that are not flags, in order.
+    pub positional: Vec<String>,
+    options: BTreeMap<String, Vec<String>>,
+    switches: BTreeSet<String>,
+}
+
+impl CommandArgs {
+    /// Parse `args` (the arguments after the subcommand name).
+    ///
+    /// `options` names the flags that take a value (`--name value` or
+    /// `--name=value`), `switches` the boolean ones, both without the
+    /// leading `--`.  Unknown flags are errors; everything after `--` is
+    /// positional.
+    pub fn parse(args: &[String], options: &[&str], switches: &[&str]) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if arg == "--" {
+                parsed.positional.extend(iter.by_ref().cloned());
+                break;
+            }
+            let Some(flag) = arg.strip_prefix("--") else {
+                parsed.positional.push(arg.clone());
+                continue;
+            };
+            let (name, inline_value) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (flag, None),
+            };
+            if options.contains(&name) {
+                let value = match inline_value {
+                    Some(value) => value,
+                    None => iter
+                        .next()
+                        .cloned()
+                        .ok_or_else(|| format!("--{name} requires a value"))?,
+                };
+                parsed.options.entry(name.to_string()).or_default().push(value);
+            } else if switches.contains(&name) && inline_value.is_none() {
+                parsed.switches.insert(name.to_string());
+            } else {
+                return Err(format!("unknown flag '{arg}'"));
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// The positional argument at `index`.
+    pub fn positional(&self, index: usize) -> Option<&str> {
+        self.positional.get(index).map(String::as_str)
+    }
+
+    /// The positional arguments from `index` on, joined by spaces (so a
+    /// message can be passed without quotes).  `None` when there are none.
+    pub fn rest(&self, index: usize) -> Option<String> {
+        let rest = self.positional.get(index..).unwrap_or_default();
+        (!rest.is_empty()).then(|| rest.join(" "))
+    }
+
+    /// Value of the option `name` (the last one when given repeatedly).
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options.get(name)?.last().map(String::as_str)
+    }
+
+    /// Every value of the option `name`, in order.
+    pub fn values(&self, name: &str) -> &[String] {
+        self.options.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The `key=value` values of the option `name` (e.g. `--input`), as a
+    /// map; a value without `=` is an error.
+    pub fn key_values(&self, name: &str) -> Result<BTreeMap<String, String>, String> {
+        self.values(name)
+            .iter()
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value)) => Ok((key.to_string(), value.to_string())),
+                None => Err(format!("--{name} expects key=value, got '{pair}'")),
+            })
+            .collect()
+    }
+
+    /// Whether the switch `name` was given.
+    pub fn switch(&self, name: &str) -> bool {
+        self.switches.contains(name)
+    }
+
+    /// Whether `--json` was given.
+    pub fn json(&self) -> bool {
+        self.switch("json")
+    }
+}
+
+/// Workflow ID of `agent` in `session_id`: a full agent ID is kept, a short
+/// one (`main`, `worker-2`) is qualified with the session; `None` is the
+/// main agent.
+pub fn agent_workflow_id(session_id: &str, agent: Option<&str>) -> String {
+    match agent {
+        Some(agent) if agent.contains('/') => agent.to_string(),
+        Some(agent) => format!("{session_id}/{agent}"),
+        None => format!("{session_id}/main"),
+    }
+}
+
+/// Plain-text line for one event of an agent, as printed by `tail`.
+///
+/// Messages are printed in full; approval requests include the call ID to
+/// pass to `approve`/`deny`.  `None` for streaming deltas and other events
+/// not worth a line.
+pub fn event_line(msg: &EventMsg) -> Option<String> {
+    let line = match msg {
+        EventMsg::TurnStarted(_) => "── turn started".to_string(),
+        EventMsg::TurnComplete(_) => "── turn complete".to_string(),
+        EventMsg::TurnAborted(ev) => format!("── turn aborted ({:?})", ev.reason),
+        EventMsg::UserMessage(ev) => format!("user: {}", ev.message.trim_end()),
+        EventMsg::AgentMessage(ev) => format!("codex: {}", ev.message.trim_end()),
+        EventMsg::AgentReasoning(ev) => format!("thinking: {}", preview_text(&ev.text)),
+        EventMsg::ExecCommandBegin(ev) => format!("$ {}", ev.command.join(" ")),
+        EventMsg::ExecCommandEnd(ev) => format!("  exit {}", ev.exit_code),
+        EventMsg::PatchApplyBegin(ev) => {
+            let mut files: Vec<String> =
+                ev.changes.keys().map(|p| p.display().to_string()).collect();
+            files.sort();
+            format!("applying patch: {}", files.join(", "))
+        }
+        EventMsg::PatchApplyEnd(ev) if !ev.success => {
+            let detail = if ev.stderr.trim().is_empty() { &ev.stdout } else { &ev.stderr };
+            format!("patch failed: {}", preview_text(detail))
+        }
+        EventMsg::ExecApprovalRequest(ev) => format!(
+            "approval needed [{}]: run {}",
+            ev.call_id,
+            ev.command.join(" ")
+        ),
+        EventMsg::ApplyPatchApprovalRequest(ev) => {
+            format!("approval needed [{}]: apply a patch", ev.call_id)
+        }
+        EventMsg::Error(ev) => format!("error: {}", ev.message),
+        EventMsg::BackgroundEvent(ev) => format!("· {}", ev.message),
+        EventMsg::ShutdownComplete => "── shut down".to_string(),
+        _ => return None,
+    };
+    Some(line)
+}
+
+/// Parse an approval policy as written in `config.toml`
+/// (`untrusted`, `on-failure`, `on-request` or `never`).
+pub fn parse_approval_policy(value: &str) -> Result<AskForApproval, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string())).map_err(|_| {
+        format!(
+            "invalid approval policy '{value}' \
+             (expected untrusted, on-failure, on-request or never)"
+        )
+    })
+}
+
+/// How an `exec` run ended; [`exit_code`](Self::exit_code) is the status
+/// the client exits with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecOutcome {
+    /// The turn completed.
+    Success,
+    /// The turn failed or was interrupted, or the session ended first.
+    Error,
+    /// The turn stopped at its iteration budget (`max_iterations`).
+    BudgetExhausted,
+    /// The agent is waiting for an approval nobody answered.
+    ApprovalPending,
+    /// `--timeout` elapsed before the turn ended.
+    TimedOut,
+}
+
+impl ExecOutcome {
+    /// Process exit status (`124` for a timeout, as with `timeout(1)`).
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Success => 0,
+            Self::Error => 1,
+            Self::BudgetExhausted => 3,
+            Self::ApprovalPending => 4,
+            Self::TimedOut => 124,
+        }
+    }
+
+    /// Name used in `--json` output.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Error => "error",
+            Self::BudgetExhausted => "budget_exhausted",
+            Self::ApprovalPending => "approval_pending",
+            Self::TimedOut => "timed_out",
+        }
+    }
+}
+
+/// Follows the events of the agent running an `exec` turn.
+///
+/// Every `TurnStarted` starts over, so a replay of the agent's events ends
+/// with the outcome of its latest turn; [`after_turns`](Self::after_turns)
+/// passes over turns that start before the tracked one.
+#[derive(Debug, Clone, Default)]
+pub struct ExecTracker {
+    /// `TurnStarted` events still to pass before the tracked turn.
+    turns_to_skip: usize,
+    /// Whether the events seen belong to a turn that is passed over.
+    skipping: bool,
+    turn_id: Option<String>,
+    last_message: Option<String>,
+    /// The agent message before `last_message`.
+    previous_message: Option<String>,
+    pending_approvals: Vec<String>,
+    error: Option<String>,
+}
+
+impl ExecTracker {
+    /// Tracker for the turn after the next `turns` turns to start, e.g. the
+    /// one queued behind a turn that is still running.
+    pub fn after_turns(turns: usize) -> Self {
+        Self {
+            turns_to_skip: turns,
+            skipping: turns > 0,
+            ..Self::default()
+        }
+    }
+
+    /// Record one event; returns the outcome once the tracked turn has ended.
+    ///
+    /// A completed turn is a success here: whether it stopped at its
+    /// iteration budget is the agent's to tell (see
+    /// [`budget_exhausted`](Self::budget_exhausted)).  An agent does nothing
+    /// else while it waits for a decision, so pending approvals are
+    /// answered once it makes progress on its turn; background events do
+    /// not count.
+    pub fn observe(&mut self, msg: &EventMsg) -> Option<ExecOutcome> {
+        match msg {
+            EventMsg::TurnStarted(_) => {
+                // Each turn is judged by its own events.
+                *self = Self {
+                    turns_to_skip: self.turns_to_skip.saturating_sub(1),
+                    skipping: self.turns_to_skip > 0,
+                    ..Self::default()
+                };
+                return None;
+            }
+            EventMsg::ShutdownComplete => {
+                self.error = Some("the agent shut down before the turn ended".to_string());
+                return Some(ExecOutcome::Error);
+            }
+            _ if self.skipping => return None,
+            EventMsg::ExecApprovalRequest(ev) => {
+                self.pending_approvals.push(ev.call_id.clone());
+                return None;
+            }
+            EventMsg::ApplyPatchApprovalRequest(ev) => {
+                self.pending_approvals.push(ev.call_id.clone());
+                return None;
+            }
+            _ => {}
+        }
+        if is_turn_progress(msg) {
+            self.pending_approvals.clear();
+        }
+        match msg {
+            EventMsg::AgentMessage(ev) => {
+                self.previous_message = self.last_message.replace(ev.message.clone());
+            }
+            EventMsg::Error(ev) => self.error = Some(ev.message.clone()),
+            EventMsg::TurnComplete(ev) => {
+                self.turn_id = Some(ev.turn_id.clone());
+                if self.last_message.is_none() {
+                    self.last_message = ev.last_agent_message.clone();
+                }
+                return Some(if self.error.is_some() {
+                    ExecOutcome::Error
+                } else {
+                    ExecOutcome::Success
+                });
+            }
+            EventMsg::TurnAborted(ev) => {
+                self.error = Some(format!("turn aborted ({:?})", ev.reason));
+                return Some(ExecOutcome::Error);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Record that the completed turn stopped at its iteration budget: its
+    /// last agent message was the budget notice, so the final message is
+    /// the one before it.
+    pub fn budget_exhausted(&mut self) -> ExecOutcome {
+        self.last_message = self.previous_message.take();
+        ExecOutcome::BudgetExhausted
+    }
+
+    /// ID of the completed turn.
+    pub fn turn_id(&self) -> Option<&str> {
+        self.turn_id.as_deref()
+    }
+
+    /// The agent's final message so far.
+    pub fn last_message(&self) -> Option<&str> {
+        self.last_message.as_deref()
+    }
+
+    /// Call IDs of the approval requests waiting for a decision.
+    pub fn pending_approvals(&self) -> &[String] {
+        &self.pending_approvals
+    }
+
+    /// Why the turn failed, if it did.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+/// Whether `msg` shows an agent working on its turn, which it does not do
+/// while waiting for an approval (unlike e.g. background events, which
+/// other agents and the client cause).
+fn is_turn_progress(msg: &EventMsg) -> bool {
+    matches!(
+        msg,
+        EventMsg::AgentMessage(_)
+            | EventMsg::AgentReasoning(_)
+            | EventMsg::ExecCommandBegin(_)
+            | EventMsg::ExecCommandEnd(_)
+            | EventMsg::PatchApplyBegin(_)
+            | EventMsg::PatchApplyEnd(_)
+            | EventMsg::Error(_)
+            | EventMsg::TurnComplete(_)
+            | EventMsg::TurnAborted(_)
+    )
+}
@@ -14,7 +14,10 @@ use codex_protocol::config_types::{Personality, ReasoningSummary, WebSearchMode}
 use codex_protocol::openai_models::ReasoningEffort;
 use codex_protocol::protocol::AskForApproval;
 
-use crate::types::{CrewAgentDef, CrewMode, CrewType, SessionWorkflowInput};
+use crate::types::{
+    CrewAgentDef, CrewMode, CrewResultsSpec, CrewStage, CrewType, McpApprovalConfig,
+    SessionWorkflowInput,
+};
 
 /// Holds the result of loading config.toml: a template
 /// [`SessionWorkflowInput`] and the resolved model provider info.
@@ -93,8 +96,14 @@ pub async fn load_harness_config() -> Result<HarnessConfig, Box<dyn std::error::
         developer_instructions,
         model_provider: None, // Set by caller from HarnessConfig.model_provider
         crew_agents,
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
         continued_state: None,
+        main_agent_state: None,
         max_iterations: None,
+        output_schema: None,
     };
 
     Ok(HarnessConfig {
@@ -231,6 +240,10 @@ pub fn built_in_default_crew() -> CrewType {
         main_agent: "default".to_string(),
         agents,
         approval_policy: None,
+        stages: Vec::new(),
+        supervisor: None,
+        outputs: BTreeMap::new(),
+        artifacts: Vec::new(),
     }
 }
 
@@ -256,6 +269,8 @@ pub fn discover_crew_types() -> Result<Vec<CrewType>, Box<dyn std::error::Error>
                 let crew: CrewType = toml::from_str(&content).map_err(|e| {
                     format!("failed to parse {}: {e}", path.display())
                 })?;
+                validate_crew_type(&crew)
+                    .map_err(|e| format!("invalid crew {}: {e}", path.display()))?;
                 if crew.name == "codex-default" {
                     has_user_default = true;
                 }
@@ -290,9 +305,194 @@ pub fn load_crew_type(name: &str) -> Result<CrewType, Box<dyn std::error::Error>
     let content = std::fs::read_to_string(&path)?;
     let crew: CrewType = toml::from_str(&content)
         .map_err(|e| format!("failed to parse {}: {e}", path.display()))?;
+    validate_crew_type(&crew).map_err(|e| format!("invalid crew {}: {e}", path.display()))?;
     Ok(crew)
 }
 
+/// Built-in roles a pipeline stage may use without a crew agent definition.
+const BUILT_IN_STAGE_AGENTS: &[&str] = &["explorer", "worker"];
+
+/// Validate a crew type's approval `supervisor` and pipeline `stages`.
+///
+/// Checks that the supervisor is the main agent or a crew agent, that stage and branch names are unique, each stage runs either an
+/// agent with a prompt or parallel branches, stage agents are crew
+/// sub-agents (or built-in roles), `{stage.*}` placeholders name a stage
+/// that has run by then (or runs later in an enclosing loop), and `repeat`
+/// loops point back to this or an earlier stage.
+pub fn validate_crew_type(crew: &CrewType) -> Result<(), String> {
+    if let Some(supervisor) = &crew.supervisor
+        && supervisor != &crew.main_agent
+        && !crew.agents.contains_key(supervisor)
+        && !BUILT_IN_STAGE_AGENTS.contains(&supervisor.as_str())
+    {
+        return Err(format!("supervisor '{supervisor}' is not an agent of the crew"));
+    }
+
+    for (name, output) in &crew.outputs {
+        match (&output.agent, &output.stage) {
+            (Some(_), Some(_)) => {
+                return Err(format!("output '{name}' must not set both `agent` and `stage`"));
+            }
+            (Some(agent), None) => {
+                if agent != &crew.main_agent
+                    && !crew.agents.contains_key(agent)
+                    && !BUILT_IN_STAGE_AGENTS.contains(&agent.as_str())
+                {
+                    return Err(format!("output '{name}' uses unknown agent '{agent}'"));
+                }
+            }
+            (None, Some(stage)) => {
+                let (stage_name, branch) = match stage.split_once('.') {
+                    Some((stage_name, branch)) => (stage_name, Some(branch)),
+                    None => (stage.as_str(), None),
+                };
+                let known = crew.stages.iter().any(|s| {
+                    s.name == stage_name
+                        && branch.is_none_or(|b| s.parallel.iter().any(|p| p.name == b))
+                });
+                if !known {
+                    return Err(format!("output '{name}' uses unknown stage '{stage}'"));
+                }
+            }
+            (None, None) => {}
+        }
+    }
+    for path in &crew.artifacts {
+        let relative = std::path::Path::new(path);
+        if path.is_empty()
+            || relative.is_absolute()
+            || relative.components().any(|c| c == std::path::Component::ParentDir)
+        {
+            return Err(format!(
+                "artifact '{path}' must be a path inside the workspace (relative, without `..`)"
+            ));
+        }
+    }
+
+    let stages = &crew.stages;
+    let index_of = |name: &str| stages.iter().position(|s| s.name == name);
+
+    let check_agent = |stage: &str, agent: &str| -> Result<(), String> {
+        if agent == crew.main_agent {
+            return Err(format!(
+                "stage '{stage}' uses the main agent '{agent}'; stages run crew sub-agents"
+            ));
+        }
+        if !crew.agents.contains_key(agent) && !BUILT_IN_STAGE_AGENTS.contains(&agent) {
+            return Err(format!("stage '{stage}' uses unknown agent '{agent}'"));
+        }
+        Ok(())
+    };
+
+    for (i, stage) in stages.iter().enumerate() {
+        if stage.name.is_empty() {
+            return Err(format!("stage #{} has no name", i + 1));
+        }
+        if index_of(&stage.name) != Some(i) {
+            return Err(format!("duplicate stage name '{}'", stage.name));
+        }
+
+        let mut prompts = Vec::new();
+        match (&stage.agent, &stage.prompt, stage.parallel.is_empty()) {
+            (Some(agent), Some(prompt), true) => {
+                check_agent(&stage.name, agent)?;
+                prompts.push(prompt);
+            }
+            (None, None, false) => {
+                let mut seen = std::collections::BTreeSet::new();
+                for branch in &stage.parallel {
+                    if branch.name.is_empty() || !seen.insert(branch.name.as_str()) {
+                        return Err(format!(
+                            "stage '{}' has an empty or duplicate branch name",
+                            stage.name
+                        ));
+                    }
+                    check_agent(&stage.name, &branch.agent)?;
+                    prompts.push(&branch.prompt);
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "stage '{}' must set either `agent` and `prompt`, or `parallel`",
+                    stage.name
+                ));
+            }
+        }
+
+        if let Some(repeat) = &stage.repeat {
+            match index_of(&repeat.from) {
+                Some(from) if from <= i => {}
+                _ => {
+                    return Err(format!(
+                        "stage '{}' repeats from '{}', which is not this or an earlier stage",
+                        stage.name, repeat.from
+                    ));
+                }
+            }
+            if repeat.until.is_empty() {
+                return Err(format!("stage '{}' has an empty `repeat.until`", stage.name));
+            }
+            if repeat.max_iterations == 0 {
+                return Err(format!("stage '{}' has `repeat.max_iterations = 0`", stage.name));
+            }
+        }
+
+        for prompt in prompts {
+            for reference in stage_references(prompt) {
+                let (name, branch) = match reference.split_once('.') {
+                    Some((name, branch)) => (name, Some(branch)),
+                    None => (reference, None),
+                };
+                let Some(j) = index_of(name) else {
+                    return Err(format!(
+                        "stage '{}' references unknown stage '{name}'",
+                        stage.name
+                    ));
+                };
+                if let Some(branch) = branch
+                    && !stages[j].parallel.iter().any(|b| b.name == branch)
+                {
+                    return Err(format!(
+                        "stage '{}' references unknown branch '{reference}'",
+                        stage.name
+                    ));
+                }
+                if j >= i && !in_loop_with(stages, i, j) {
+                    return Err(format!(
+                        "stage '{}' references stage '{name}', which has not run yet",
+                        stage.name
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Names inside `{stage.<name>}` / `{stage.<name>.<branch>}` placeholders.
+fn stage_references(prompt: &str) -> Vec<&str> {
+    let mut refs = Vec::new();
+    let mut rest = prompt;
+    while let Some(start) = rest.find("{stage.") {
+        rest = &rest[start + "{stage.".len()..];
+        if let Some(end) = rest.find('}') {
+            refs.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+    }
+    refs
+}
+
+/// Whether stage `i` runs again after stage `j` (`j >= i`) because both are
+/// inside a `repeat` loop.
+fn in_loop_with(stages: &[CrewStage], i: usize, j: usize) -> bool {
+    stages[j..].iter().any(|stage| {
+        stage.repeat.as_ref().is_some_and(|r| {
+            stages.iter().position(|s| s.name == r.from).is_some_and(|from| from <= i)
+        })
+    })
+}
+
 /// Apply a crew type definition to a [`SessionWorkflowInput`].
 ///
 /// 1. Validates all required inputs are provided (errors on missing).
@@ -301,6 +501,10 @@ pub fn load_crew_type(name: &str) -> Result<CrewType, Box<dyn std::error::Error>
 /// 4. Applies main agent's `model` override to `base`.
 /// 5. Applies main agent's `instructions` to `base`.
 /// 6. Sets `base.user_message` from interpolated `initial_prompt` (autonomous mode).
+/// 7. Sets `base.pipeline` from `stages`, with inputs interpolated in prompts.
+/// 8. Sets `base.approval_supervisor` from `supervisor` (`default` for the
+///    main agent).
+/// 9. Sets `base.crew_results` from `outputs` and `artifacts`.
 pub fn apply_crew_type(
     crew: &CrewType,
     inputs: &BTreeMap<String, String>,
@@ -368,6 +572,48 @@ pub fn apply_crew_type(
         );
     }
 
+    // --- pipeline stages (stage outputs are substituted at run time) ---
+    base.pipeline = crew
+        .stages
+        .iter()
+        .cloned()
+        .map(|mut stage| {
+            stage.prompt = stage.prompt.map(|p| interpolate(&p, &vars));
+            for branch in &mut stage.parallel {
+                branch.prompt = interpolate(&branch.prompt, &vars);
+            }
+            stage
+        })
+        .collect();
+
+    // --- approval supervisor (the main agent runs with the "default" role) ---
+    base.approval_supervisor = crew.supervisor.as_ref().map(|agent| {
+        if agent == &crew.main_agent {
+            "default".to_string()
+        } else {
+            agent.clone()
+        }
+    });
+
+    // --- outputs and artifacts (an output of the main agent has no `agent`) ---
+    if !crew.outputs.is_empty() || !crew.artifacts.is_empty() {
+        let outputs = crew
+            .outputs
+            .iter()
+            .map(|(name, output)| {
+                let mut output = output.clone();
+                if output.agent.as_ref() == Some(&crew.main_agent) {
+                    output.agent = None;
+                }
+                (name.clone(), output)
+            })
+            .collect();
+        base.crew_results = Some(CrewResultsSpec {
+            outputs,
+            artifacts: crew.artifacts.clone(),
+        });
+    }
+
     Ok(())
 }
 
@@ -426,6 +672,69 @@ pub fn inject_crew_roles_into_toml(
     Ok(toml::to_string(&doc)?)
 }
 
+/// Extract MCP tool approval rules from the `[mcp_tool_approval]` table of a
+/// config TOML string.
+///
+/// Pure (no I/O), so the workflow can call it on the merged config string.
+/// A missing or malformed table yields the default rules.
+pub fn mcp_approval_from_toml(toml_str: &str) -> McpApprovalConfig {
+    let Ok(doc) = toml::from_str::<toml::Value>(toml_str) else {
+        return McpApprovalConfig::default();
+    };
+    match doc.get("mcp_tool_approval") {
+        Some(table) => table.clone().try_into().unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "invalid [mcp_tool_approval] table, using defaults");
+            McpApprovalConfig::default()
+        }),
+        None => McpApprovalConfig::default(),
+    }
+}
+
+/// Replace the MCP settings (`[mcp_servers]` and `[mcp_tool_approval]`) of
+/// `toml_str` with those of `refreshed`, keeping everything else (e.g. an
+/// agent's role overrides).  Returns `toml_str` unchanged if either fails
+/// to parse.
+pub fn with_refreshed_mcp_config(toml_str: &str, refreshed: &str) -> String {
+    let (Ok(mut doc), Ok(new)) = (
+        toml::from_str::<toml::Table>(toml_str),
+        toml::from_str::<toml::Table>(refreshed),
+    ) else {
+        return toml_str.to_string();
+    };
+    for key in ["mcp_servers", "mcp_tool_approval"] {
+        match new.get(key) {
+            Some(value) => doc.insert(key.to_string(), value.clone()),
+            None => doc.remove(key),
+        };
+    }
+    toml::to_string(&doc).unwrap_or_else(|_| toml_str.to_string())
+}
+
+/// Extract the `[agents.<name>]` roles (with their optional descriptions)
+/// from a config TOML string.
+///
+/// Pure (no I/O), like [`mcp_approval_from_toml`]; used to describe the
+/// roles accepted by the `spawn_subagent` tool.
+pub fn agent_roles_from_toml(toml_str: &str) -> BTreeMap<String, Option<String>> {
+    let Ok(doc) = toml::from_str::<toml::Value>(toml_str) else {
+        return BTreeMap::new();
+    };
+    let Some(agents) = doc.get("agents").and_then(|v| v.as_table()) else {
+        return BTreeMap::new();
+    };
+    agents
+        .iter()
+        .filter(|(_, def)| def.is_table())
+        .map(|(name, def)| {
+            let description = def
+                .get("description")
+                .and_then(|d| d.as_str())
+                .map(String::from);
+            (name.clone(), description)
+        })
+        .collect()
+}
+
 /// Reconstruct a [`Config`] from a TOML string previously produced by
 /// [`ConfigBuilder::build_toml_string()`].
 ///
@@ -2,41 +2,79 @@
 //!
 //! This workflow maintains a list of known sessions (started via
 //! `CodexWorkflow`) and exposes queries to list/get them and signals to
-//! register/update/remove entries.  It does no activities; it's purely
-//! a state container.
+//! register/update/remove entries, plus a filtered `search_sessions` query
+//! and an `edit_session` signal (title, tags, pin).  Sessions register
+//! themselves with their workspace (cwd, repository, branch) and report
+//! their activity and final status; the harness also stores the results
+//! (outputs and artifacts) crew sessions report when they end.
+//! Sessions that closed without reporting (e.g. terminated ones) are
+//! detected by a periodic status check, which also terminates the agents
+//! they left running.
+//!
+//! The same timer applies the [`RetentionPolicy`]: old or excess closed
+//! sessions are archived (hidden from `list_sessions`), the oldest archived
+//! ones are purged, and idle running sessions are shut down or terminated.
 //!
 //! Workflow ID convention: `codex-harness-<user>`.
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::time::Duration;
 
 use temporalio_common::protos::coresdk::workflow_commands::ContinueAsNewWorkflowExecution;
 use temporalio_common::protos::coresdk::AsJsonPayloadExt;
 use temporalio_macros::{workflow, workflow_methods};
+use futures::future::Either;
 use temporalio_sdk::{
     ActivityOptions, SyncWorkflowContext, WorkflowContext, WorkflowContextView, WorkflowResult,
     WorkflowTermination,
 };
 
-use crate::activities::CodexActivities;
-use crate::types::{HarnessInput, HarnessState, SessionEntry, SessionStatus};
+use crate::activities::{CodexActivities, activity_opts};
+use crate::startup::signal_workflow;
+use crate::types::{
+    CrewResults, HarnessInput, HarnessState, IdleAction, RetentionPolicy, SessionActivity,
+    SessionEdit, SessionEntry, SessionFilter, SessionStatus, sort_sessions_for_display,
+};
+
+/// How often sessions still marked `Running` are checked for having closed,
+/// and the retention policy is applied.
+const SESSION_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Workflow ID of the current user's harness (`codex-harness-<user>`, from
+/// `$USER`, or `default` when it is unset).
+pub fn harness_workflow_id() -> String {
+    let user = std::env::var("USER").unwrap_or_else(|_| "default".to_string());
+    format!("codex-harness-{user}")
+}
 
 #[workflow]
 pub struct CodexHarness {
     sessions: Vec<SessionEntry>,
     credentials_available: Option<bool>,
+    /// Crew results by session ID.
+    results: BTreeMap<String, CrewResults>,
+    retention: RetentionPolicy,
+    /// Idle sessions already asked to shut down.
+    idle_shutdown_sent: BTreeSet<String>,
 }
 
 #[workflow_methods]
 impl CodexHarness {
     #[init]
     pub fn new(_ctx: &WorkflowContextView, input: HarnessInput) -> Self {
-        let (sessions, credentials_available) = match input.continued_state {
-            Some(s) => (s.sessions, s.credentials_available),
-            None => (Vec::new(), None),
-        };
+        let state = input.continued_state.unwrap_or_else(|| HarnessState {
+            sessions: Vec::new(),
+            credentials_available: None,
+            results: BTreeMap::new(),
+            retention: RetentionPolicy::default(),
+            idle_shutdown_sent: BTreeSet::new(),
+        });
         Self {
-            sessions,
-            credentials_available,
+            sessions: state.sessions,
+            credentials_available: state.credentials_available,
+            results: state.results,
+            retention: state.retention,
+            idle_shutdown_sent: state.idle_shutdown_sent,
         }
     }
 
@@ -45,12 +83,14 @@ impl CodexHarness {
     /// Register (or upsert) a session entry.
     #[signal]
     pub fn register_session(&mut self, _ctx: &mut SyncWorkflowContext<Self>, entry: SessionEntry) {
+        // A re-registered session (e.g. a reopened one) is live again.
+        self.idle_shutdown_sent.remove(&entry.session_id);
         if let Some(existing) = self
             .sessions
             .iter_mut()
             .find(|s| s.session_id == entry.session_id)
         {
-            *existing = entry;
+            existing.merge(entry);
         } else {
             self.sessions.push(entry);
         }
@@ -73,18 +113,131 @@ impl CodexHarness {
         }
     }
 
-    /// Remove a session from the registry.
+    /// Record a session's latest activity (after each turn).
+    #[signal]
+    pub fn record_session_activity(
+        &mut self,
+        _ctx: &mut SyncWorkflowContext<Self>,
+        activity: SessionActivity,
+    ) {
+        if let Some(s) = self
+            .sessions
+            .iter_mut()
+            .find(|s| s.session_id == activity.session_id)
+        {
+            s.last_activity_millis = s.last_activity_millis.max(activity.at_millis);
+            if activity.preview.is_some() {
+                s.last_message_preview = activity.preview;
+            }
+        }
+    }
+
+    /// Store the summary of the results a crew session reported when it
+    /// ended (outputs and artifact metadata, see [`CrewResults::summary`]).
+    #[signal]
+    pub fn record_session_results(
+        &mut self,
+        _ctx: &mut SyncWorkflowContext<Self>,
+        payload: (String, CrewResults),
+    ) {
+        let (session_id, results) = payload;
+        self.results.insert(session_id, results.summary());
+    }
+
+    /// Remove a session (and its results) from the registry.
     #[signal]
     pub fn remove_session(&mut self, _ctx: &mut SyncWorkflowContext<Self>, session_id: String) {
         self.sessions.retain(|s| s.session_id != session_id);
+        self.results.remove(&session_id);
+    }
+
+    /// Pin or unpin a session; with `keep_pinned`, retention skips pinned
+    /// sessions.
+    #[signal]
+    pub fn set_session_pinned(
+        &mut self,
+        _ctx: &mut SyncWorkflowContext<Self>,
+        payload: (String, bool),
+    ) {
+        let (session_id, pinned) = payload;
+        if let Some(s) = self.sessions.iter_mut().find(|s| s.session_id == session_id) {
+            s.pinned = pinned;
+        }
+    }
+
+    /// Rename, tag/untag or pin a session.
+    #[signal]
+    pub fn edit_session(&mut self, _ctx: &mut SyncWorkflowContext<Self>, edit: SessionEdit) {
+        if let Some(s) = self.sessions.iter_mut().find(|s| s.session_id == edit.session_id) {
+            s.apply_edit(&edit);
+        }
+    }
+
+    /// Archive a session by hand (it stays queryable via
+    /// `list_archived_sessions`).  Its last activity counts as the archive
+    /// time, so it is purged in line with how long it has been idle.
+    #[signal]
+    pub fn archive_session(&mut self, _ctx: &mut SyncWorkflowContext<Self>, session_id: String) {
+        if let Some(s) = self.sessions.iter_mut().find(|s| s.session_id == session_id) {
+            let archived_at = s.last_active_millis();
+            s.archived_at_millis.get_or_insert(archived_at);
+        }
+    }
+
+    /// Bring an archived session back into `list_sessions`.
+    #[signal]
+    pub fn unarchive_session(&mut self, _ctx: &mut SyncWorkflowContext<Self>, session_id: String) {
+        if let Some(s) = self.sessions.iter_mut().find(|s| s.session_id == session_id) {
+            s.archived_at_millis = None;
+        }
+    }
+
+    /// Replace the retention policy; it is applied on the next timer tick.
+    #[signal]
+    pub fn set_retention_policy(
+        &mut self,
+        _ctx: &mut SyncWorkflowContext<Self>,
+        policy: RetentionPolicy,
+    ) {
+        self.retention = policy;
     }
 
     // ----- queries -----
 
-    /// Return all sessions as a JSON array.
+    /// Return all sessions that are not archived as a JSON array.
     #[query]
     pub fn list_sessions(&self, _ctx: &WorkflowContextView) -> String {
-        serde_json::to_string(&self.sessions).unwrap_or_default()
+        let sessions: Vec<&SessionEntry> =
+            self.sessions.iter().filter(|s| !s.is_archived()).collect();
+        serde_json::to_string(&sessions).unwrap_or_default()
+    }
+
+    /// Return the archived sessions as a JSON array.
+    #[query]
+    pub fn list_archived_sessions(&self, _ctx: &WorkflowContextView) -> String {
+        let sessions: Vec<&SessionEntry> =
+            self.sessions.iter().filter(|s| s.is_archived()).collect();
+        serde_json::to_string(&sessions).unwrap_or_default()
+    }
+
+    /// Return the sessions matching `filter` as a JSON array, pinned first,
+    /// then most recently active.
+    #[query]
+    pub fn search_sessions(&self, _ctx: &WorkflowContextView, filter: SessionFilter) -> String {
+        let mut sessions: Vec<SessionEntry> = self
+            .sessions
+            .iter()
+            .filter(|s| filter.matches(s))
+            .cloned()
+            .collect();
+        sort_sessions_for_display(&mut sessions);
+        serde_json::to_string(&sessions).unwrap_or_default()
+    }
+
+    /// Return the retention policy as JSON.
+    #[query]
+    pub fn retention_policy(&self, _ctx: &WorkflowContextView) -> String {
+        serde_json::to_string(&self.retention).unwrap_or_default()
     }
 
     /// Return a single session entry by ID, or `"null"`.
@@ -94,6 +247,12 @@ impl CodexHarness {
         serde_json::to_string(&entry).unwrap_or_else(|_| "null".to_string())
     }
 
+    /// Return a session's crew results by ID, or `"null"`.
+    #[query]
+    pub fn get_session_results(&self, _ctx: &WorkflowContextView, session_id: String) -> String {
+        serde_json::to_string(&self.results.get(&session_id)).unwrap_or_else(|_| "null".to_string())
+    }
+
     /// Whether the worker has API credentials available.
     #[query]
     pub fn credentials_available(&self, _ctx: &WorkflowContextView) -> bool {
@@ -102,7 +261,8 @@ impl CodexHarness {
 
     // ----- run -----
 
-    /// The harness sits idle, maintaining state via signals/queries.
+    /// The harness maintains state via signals/queries and wakes up
+    /// periodically to reconcile session statuses and apply retention.
     /// It triggers continue-as-new when the server suggests it (to keep
     /// history bounded).
     #[run]
@@ -122,18 +282,39 @@ impl CodexHarness {
             ctx.state_mut(|s| s.credentials_available = Some(cred_result.unwrap_or(false)));
         }
 
-        // Wait until the server suggests CAN (history too large).
-        ctx.wait_condition(|_s| ctx.continue_as_new_suggested())
-            .await;
+        // Periodically mark sessions that closed without reporting and
+        // apply retention, until the server suggests CAN (history too large).
+        loop {
+            let timer = ctx.timer(SESSION_CHECK_INTERVAL);
+            let can = ctx.wait_condition(|_s| ctx.continue_as_new_suggested());
+            if let Either::Right(_) = futures::future::select(Box::pin(timer), Box::pin(can)).await
+            {
+                break;
+            }
+            check_running_sessions(ctx).await;
+            apply_retention(ctx).await;
+        }
 
-        // Carry sessions and credentials forward.
-        let (sessions, credentials_available) =
-            ctx.state(|s| (s.sessions.clone(), s.credentials_available));
+        // Carry sessions, credentials, results, the retention policy and the
+        // pending idle shutdowns of sessions still running forward.
+        let state = ctx.state(|s| HarnessState {
+            sessions: s.sessions.clone(),
+            credentials_available: s.credentials_available,
+            results: s.results.clone(),
+            retention: s.retention.clone(),
+            idle_shutdown_sent: s
+                .idle_shutdown_sent
+                .iter()
+                .filter(|id| {
+                    s.sessions
+                        .iter()
+                        .any(|e| &e.session_id == *id && e.status == SessionStatus::Running)
+                })
+                .cloned()
+                .collect(),
+        });
         let can_input = HarnessInput {
-            continued_state: Some(HarnessState {
-                sessions,
-                credentials_available,
-            }),
+            continued_state: Some(state),
         };
 
         Err(WorkflowTermination::continue_as_new(
@@ -149,6 +330,118 @@ impl CodexHarness {
     }
 }
 
+/// Mark sessions still registered as `Running` whose workflow has closed
+/// (terminated, timed out, or ended without reporting), and terminate the
+/// agents such a session left running.
+async fn check_running_sessions(ctx: &mut WorkflowContext<CodexHarness>) {
+    let running: Vec<String> = ctx.state(|s| {
+        s.sessions
+            .iter()
+            .filter(|e| e.status == SessionStatus::Running)
+            .map(|e| e.session_id.clone())
+            .collect()
+    });
+    if running.is_empty() {
+        return;
+    }
+    let closed = match ctx
+        .start_activity(CodexActivities::session_statuses, running, activity_opts(60))
+        .await
+    {
+        Ok(closed) => closed,
+        Err(e) => {
+            tracing::warn!(error = %e, "session status check failed");
+            return;
+        }
+    };
+    if closed.is_empty() {
+        return;
+    }
+    let closed_ids: Vec<String> = closed.iter().map(|(id, _)| id.clone()).collect();
+    ctx.state_mut(|s| {
+        for (session_id, status) in closed {
+            if let Some(entry) = s.sessions.iter_mut().find(|e| e.session_id == session_id) {
+                entry.status = status;
+            }
+        }
+    });
+    // Agents outlive their session (`Abandon` parent close policy), and a
+    // session that did not end on its own could not stop them.
+    match ctx
+        .start_activity(CodexActivities::terminate_session_agents, closed_ids, activity_opts(120))
+        .await
+    {
+        Ok(agents) if !agents.is_empty() => {
+            tracing::info!(agents = agents.len(), "terminated agents of closed sessions")
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!(error = %e, "terminating agents of closed sessions failed"),
+    }
+}
+
+/// Archive and purge entries per the retention policy, then shut down or
+/// terminate idle running sessions.
+async fn apply_retention(ctx: &mut WorkflowContext<CodexHarness>) {
+    let now = ctx
+        .workflow_time()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64);
+    let (outcome, idle_action) = ctx.state_mut(|s| {
+        let outcome = s.retention.apply(&mut s.sessions, now);
+        for session_id in &outcome.purged {
+            s.results.remove(session_id);
+        }
+        (outcome, s.retention.idle_action)
+    });
+    if !outcome.archived.is_empty() || !outcome.purged.is_empty() {
+        tracing::info!(
+            archived = outcome.archived.len(),
+            purged = outcome.purged.len(),
+            "applied session retention"
+        );
+    }
+    if outcome.idle.is_empty() {
+        return;
+    }
+
+    match idle_action {
+        IdleAction::Shutdown => {
+            for session_id in outcome.idle {
+                if ctx.state(|s| s.idle_shutdown_sent.contains(&session_id)) {
+                    continue;
+                }
+                match signal_workflow!(ctx, &session_id, "shutdown", &()) {
+                    Ok(()) => ctx.state_mut(|s| {
+                        s.idle_shutdown_sent.insert(session_id);
+                    }),
+                    Err(e) => {
+                        tracing::warn!(session_id = %session_id, error = %e, "idle shutdown failed")
+                    }
+                }
+            }
+        }
+        IdleAction::Terminate => {
+            match ctx
+                .start_activity(
+                    CodexActivities::terminate_sessions,
+                    outcome.idle,
+                    activity_opts(60),
+                )
+                .await
+            {
+                Ok(terminated) => ctx.state_mut(|s| {
+                    for entry in s.sessions.iter_mut() {
+                        if terminated.contains(&entry.session_id) {
+                            entry.status = SessionStatus::Terminated;
+                        }
+                    }
+                }),
+                Err(e) => tracing::warn!(error = %e, "terminating idle sessions failed"),
+            }
+        }
+    }
+}
+
 /// Re-export the macro-generated `Run` marker type so other modules can
 /// parameterize `WorkflowHandle<Client, CodexHarnessRun>`.
 pub use codex_harness::Run as CodexHarnessRun;
@@ -0,0 +1,528 @@
+//! Transcript reconstruction from an agent's durable rollout items.
+//!
+//! The event ring buffer ([`BufferEventSink`](crate::sink::BufferEventSink))
+//! only keeps the most recent events, so a long session resumed from it
+//! starts mid-conversation.  The rollout items saved by the session (and
+//! carried over continue-as-new) are complete; [`history_from_rollout`]
+//! rebuilds the transcript from them as the events the TUI renders on
+//! resume: user and agent messages, reasoning summaries, command executions
+//! with their output, patch applications and compactions.
+//! [`markdown_transcript`] renders the same transcript as Markdown for
+//! `codex-temporal-client export`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use codex_protocol::models::{ContentItem, ReasoningItemReasoningSummary, ResponseItem};
+use codex_protocol::protocol::{
+    AgentMessageEvent, AgentReasoningEvent, ContextCompactedEvent, Event, EventMsg,
+    ExecCommandBeginEvent, ExecCommandEndEvent, ExecCommandSource, ExecCommandStatus,
+    PatchApplyBeginEvent, PatchApplyEndEvent, PatchApplyStatus, RolloutItem,
+};
+
+use crate::rollout::rollout_lines;
+use crate::storage::InMemoryStorage;
+use crate::tools::{command_from_arguments, is_shell_tool};
+use crate::types::{
+    DEFAULT_HISTORY_PAGE_SIZE, ExportFormat, ExportPage, ExportRequest, HistoryPage,
+    HistoryRequest, RolloutPage,
+};
+
+/// Upper bound on the items of one history page as encoded in the page's
+/// JSON.  The query result encodes the page once more as a string, which at
+/// most doubles it, so results stay below 2 MiB against the 4 MiB gRPC
+/// message limit.  A page always holds at least one event.
+pub const MAX_HISTORY_PAGE_BYTES: usize = 1024 * 1024;
+
+/// Prefixes of the context messages injected as user-role items (AGENTS.md
+/// instructions, environment context); they are not part of the transcript.
+const CONTEXT_MESSAGE_PREFIXES: &[&str] = &[
+    "<environment_context>",
+    "<user_instructions>",
+    "# AGENTS.md instructions for ",
+];
+
+/// A tool call waiting for its output.
+enum OpenCall {
+    Exec { command: Vec<String> },
+    Patch,
+}
+
+/// Rebuild the transcript of an agent from its rollout items.
+///
+/// Messages and reasoning come from the persisted `EventMsg` items when the
+/// rollout has them (rollouts written by the Codex CLI) and are otherwise
+/// derived from the response items.  Tool calls are paired with their
+/// outputs by call ID and rendered as `ExecCommandBegin`/`End` (or
+/// `PatchApplyBegin`/`End` for `apply_patch`) events run in `cwd`.
+pub fn history_from_rollout(items: &[RolloutItem], cwd: &str) -> Vec<EventMsg> {
+    let persisted_messages = items
+        .iter()
+        .any(|item| matches!(item, RolloutItem::EventMsg(EventMsg::UserMessage(_))));
+    let cwd = PathBuf::from(cwd);
+    let mut open_calls: HashMap<String, OpenCall> = HashMap::new();
+    let mut events = Vec::new();
+
+    for item in items {
+        match item {
+            RolloutItem::EventMsg(msg) if persisted_messages => {
+                if matches!(
+                    msg,
+                    EventMsg::UserMessage(_)
+                        | EventMsg::AgentMessage(_)
+                        | EventMsg::AgentReasoning(_)
+                ) {
+                    events.push(msg.clone());
+                }
+            }
+            RolloutItem::Compacted(_) => {
+                events.push(EventMsg::ContextCompacted(ContextCompactedEvent));
+            }
+            RolloutItem::ResponseItem(ri) => match ri {
+                ResponseItem::Message { role, content, .. } if !persisted_messages => {
+                    let text = message_text(content);
+                    if text.trim().is_empty() {
+                        continue;
+                    }
+                    match role.as_str() {
+                        "user" if !is_context_message(&text) => {
+                            events.extend(user_message_event(text));
+                        }
+                        "assistant" => {
+                            events.push(EventMsg::AgentMessage(AgentMessageEvent {
+                                message: text,
+                                phase: None,
+                                memory_citation: None,
+                            }));
+                        }
+                        _ => {}
+                    }
+                }
+                ResponseItem::Reasoning { summary, .. } if !persisted_messages => {
+                    let text = summary
+                        .iter()
+                        .map(|s| match s {
+                            ReasoningItemReasoningSummary::SummaryText { text } => text.as_str(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n\n");
+                    if !text.trim().is_empty() {
+                        events.push(EventMsg::AgentReasoning(AgentReasoningEvent { text }));
+                    }
+                }
+                ResponseItem::FunctionCall {
+                    name,
+                    arguments,
+                    call_id,
+                    ..
+                } => {
+                    events.push(open_call(&mut open_calls, call_id, name, arguments, &cwd));
+                }
+                ResponseItem::CustomToolCall {
+                    name,
+                    input,
+                    call_id,
+                    ..
+                } => {
+                    events.push(open_call(&mut open_calls, call_id, name, input, &cwd));
+                }
+                ResponseItem::FunctionCallOutput { call_id, output, .. } => {
+                    let text = output.body.to_text().unwrap_or_default();
+                    let success = output.success.unwrap_or(true);
+                    if let Some(call) = open_calls.remove(call_id) {
+                        events.push(close_call(call, call_id, &text, success, &cwd));
+                    }
+                }
+                ResponseItem::CustomToolCallOutput { call_id, output, .. } => {
+                    let text = output.body.to_text().unwrap_or_default();
+                    let success = output.success.unwrap_or(true);
+                    if let Some(call) = open_calls.remove(call_id) {
+                        events.push(close_call(call, call_id, &text, success, &cwd));
+                    }
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    events
+}
+
+/// Transcripts built by the paged `get_history` and `export_session`
+/// queries, kept so that the following pages are sliced out of them instead
+/// of being rebuilt from the whole rollout each time.
+///
+/// A transcript is rebuilt for a request at offset 0, and whenever the
+/// rollout grew or the export options changed since it was built.
+#[derive(Default)]
+pub struct TranscriptCache {
+    history: Mutex<Option<CachedTranscript<EventMsg>>>,
+    export: Mutex<Option<CachedTranscript<String>>>,
+}
+
+struct CachedTranscript<T> {
+    /// Rollout length and options the transcript was built with.
+    key: String,
+    entries: Arc<Vec<T>>,
+}
+
+impl TranscriptCache {
+    /// One page of the transcript of `storage` (`get_history` query).
+    pub fn history_page(
+        &self,
+        storage: &InMemoryStorage,
+        cwd: &str,
+        req: &HistoryRequest,
+    ) -> HistoryPage {
+        let events = storage.read(|items| {
+            let key = format!("{}\n{cwd}", items.len());
+            cached(&self.history, key, req.offset, || history_from_rollout(items, cwd))
+        });
+        history_page(&events, req)
+    }
+
+    /// One page of `storage` exported as `req.format` (`export_session`
+    /// query).
+    pub fn export_page(
+        &self,
+        storage: &InMemoryStorage,
+        cwd: &str,
+        req: &ExportRequest,
+    ) -> ExportPage {
+        let lines = storage.read(|items| {
+            let key = format!(
+                "{}\n{cwd}\n{:?}\n{}\n{}",
+                items.len(),
+                req.format,
+                req.conversation_id,
+                req.timestamp
+            );
+            cached(&self.export, key, req.offset, || match req.format {
+                ExportFormat::Rollout => rollout_lines(items, req, cwd),
+                ExportFormat::Markdown => markdown_transcript(items, cwd),
+            })
+        });
+        export_page(&lines, req)
+    }
+}
+
+/// The transcript in `slot` if it was built for `key` and the request
+/// continues paging through it, otherwise a freshly built one.
+fn cached<T>(
+    slot: &Mutex<Option<CachedTranscript<T>>>,
+    key: String,
+    offset: usize,
+    build: impl FnOnce() -> Vec<T>,
+) -> Arc<Vec<T>> {
+    let mut slot = slot.lock().expect("lock poisoned");
+    if offset > 0
+        && let Some(cached) = slot.as_ref().filter(|cached| cached.key == key)
+    {
+        return Arc::clone(&cached.entries);
+    }
+    let entries = Arc::new(build());
+    *slot = Some(CachedTranscript {
+        key,
+        entries: Arc::clone(&entries),
+    });
+    entries
+}
+
+/// Slice one page out of a transcript (see [`HistoryRequest`]).
+pub fn history_page(events: &[EventMsg], req: &HistoryRequest) -> HistoryPage {
+    let (events, next_offset, total) = json_page(events, req, |msg| {
+        serde_json::to_string(&Event {
+            id: String::new(),
+            msg: msg.clone(),
+        })
+    });
+    HistoryPage {
+        events,
+        next_offset,
+        total,
+    }
+}
+
+/// Slice one page out of the raw rollout items (`get_rollout` query).
+pub fn rollout_page(items: &[RolloutItem], req: &HistoryRequest) -> RolloutPage {
+    let (items, next_offset, total) = json_page(items, req, serde_json::to_string);
+    RolloutPage {
+        items,
+        next_offset,
+        total,
+    }
+}
+
+/// Slice one page out of exported lines (`export_session` query).
+pub fn export_page(lines: &[String], req: &ExportRequest) -> ExportPage {
+    let req = HistoryRequest {
+        offset: req.offset,
+        limit: req.limit,
+    };
+    let (lines, next_offset, total) = json_page(lines, &req, |line| Ok(line.clone()));
+    ExportPage {
+        lines,
+        next_offset,
+        total,
+    }
+}
+
+/// Render an agent's transcript as Markdown blocks: messages under `User`
+/// and `Assistant` headings, reasoning as quotes, commands with their
+/// output and exit code, and the patches of `apply_patch` calls as diffs.
+pub fn markdown_transcript(items: &[RolloutItem], cwd: &str) -> Vec<String> {
+    let patches = patch_inputs(items);
+    let mut blocks = Vec::new();
+    for msg in history_from_rollout(items, cwd) {
+        let block = match msg {
+            EventMsg::UserMessage(e) => format!("## User\n\n{}\n", e.message.trim_end()),
+            EventMsg::AgentMessage(e) => format!("## Assistant\n\n{}\n", e.message.trim_end()),
+            EventMsg::AgentReasoning(e) => {
+                let quoted: Vec<String> = e
+                    .text
+                    .trim_end()
+                    .lines()
+                    .map(|line| format!("> {line}").trim_end().to_string())
+                    .collect();
+                format!("> _Reasoning_\n>\n{}\n", quoted.join("\n"))
+            }
+            EventMsg::ExecCommandBegin(e) => {
+                format!("**Command**\n\n{}", fenced("sh", &e.command.join(" ")))
+            }
+            EventMsg::ExecCommandEnd(e) if e.aggregated_output.trim().is_empty() => {
+                format!("_Exit code {}, no output._\n", e.exit_code)
+            }
+            EventMsg::ExecCommandEnd(e) => format!(
+                "_Exit code {}:_\n\n{}",
+                e.exit_code,
+                fenced("text", &e.aggregated_output)
+            ),
+            EventMsg::PatchApplyBegin(e) => {
+                let patch = patches.get(&e.call_id).map_or("", String::as_str);
+                format!("**Patch**\n\n{}", fenced("diff", patch))
+            }
+            EventMsg::PatchApplyEnd(e) if !e.success => {
+                format!("_Patch failed:_\n\n{}", fenced("text", &e.stdout))
+            }
+            EventMsg::ContextCompacted(_) => {
+                "---\n\n_Context compacted; earlier turns were summarized._\n".to_string()
+            }
+            _ => continue,
+        };
+        blocks.push(block);
+    }
+    blocks
+}
+
+/// Patch text of each `apply_patch` call, by call ID.  The freeform tool
+/// sends the patch as its input; the function variant as the `input`
+/// argument.
+fn patch_inputs(items: &[RolloutItem]) -> HashMap<String, String> {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            RolloutItem::ResponseItem(ResponseItem::CustomToolCall {
+                name,
+                input,
+                call_id,
+                ..
+            }) if name == "apply_patch" => Some((call_id.clone(), input.clone())),
+            RolloutItem::ResponseItem(ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            }) if name == "apply_patch" => {
+                let patch = serde_json::from_str::<serde_json::Value>(arguments)
+                    .ok()
+                    .and_then(|v| v.get("input").and_then(|i| i.as_str()).map(String::from))
+                    .unwrap_or_else(|| arguments.clone());
+                Some((call_id.clone(), patch))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// A fenced code block around `body`, with a fence longer than any
+/// backtick run inside it.
+fn fenced(lang: &str, body: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in body.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{fence}{lang}\n{}\n{fence}\n", body.trim_end_matches('\n'))
+}
+
+/// Serialize the page of `items` selected by `req`, stopping early once
+/// the items, as encoded in the page's JSON, reach
+/// [`MAX_HISTORY_PAGE_BYTES`].  Returns the page, the next offset and the
+/// total item count.
+fn json_page<T>(
+    items: &[T],
+    req: &HistoryRequest,
+    encode: impl Fn(&T) -> serde_json::Result<String>,
+) -> (Vec<String>, Option<usize>, usize) {
+    let limit = req.limit.unwrap_or(DEFAULT_HISTORY_PAGE_SIZE).max(1);
+    let total = items.len();
+    let mut page = Vec::new();
+    let mut bytes = 0;
+    for item in items.iter().skip(req.offset).take(limit) {
+        let json = encode(item).unwrap_or_default();
+        let encoded_len = json_string_len(&json);
+        if !page.is_empty() && bytes + encoded_len > MAX_HISTORY_PAGE_BYTES {
+            break;
+        }
+        bytes += encoded_len;
+        page.push(json);
+    }
+    let end = req.offset.min(total) + page.len();
+    (page, (end < total).then_some(end), total)
+}
+
+/// Length of `s` encoded as a JSON string, quotes and escapes included.
+pub fn json_string_len(s: &str) -> usize {
+    let escaped: usize = s
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' | '\n' | '\r' | '\t' | '\u{8}' | '\u{c}' => 2,
+            c if c < ' ' => 6,
+            c => c.len_utf8(),
+        })
+        .sum();
+    escaped + 2
+}
+
+/// Concatenated text of a message's content items.
+pub(crate) fn message_text(content: &[ContentItem]) -> String {
+    content
+        .iter()
+        .filter_map(|c| match c {
+            ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                Some(text.as_str())
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub(crate) fn is_context_message(text: &str) -> bool {
+    let text = text.trim_start();
+    CONTEXT_MESSAGE_PREFIXES.iter().any(|p| text.starts_with(p))
+}
+
+/// A `UserMessage` event for `text` (built through serde so the optional
+/// image fields keep their defaults).
+pub(crate) fn user_message_event(text: String) -> Option<EventMsg> {
+    serde_json::from_value(serde_json::json!({
+        "type": "user_message",
+        "message": text,
+    }))
+    .ok()
+}
+
+/// Record a tool call and return its begin event.
+fn open_call(
+    open_calls: &mut HashMap<String, OpenCall>,
+    call_id: &str,
+    name: &str,
+    arguments: &str,
+    cwd: &Path,
+) -> EventMsg {
+    if name == "apply_patch" {
+        open_calls.insert(call_id.to_string(), OpenCall::Patch);
+        return EventMsg::PatchApplyBegin(PatchApplyBeginEvent {
+            call_id: call_id.to_string(),
+            turn_id: String::new(),
+            auto_approved: false,
+            changes: HashMap::new(),
+        });
+    }
+    let command = if is_shell_tool(name) {
+        command_from_arguments(arguments)
+    } else {
+        vec![name.to_string()]
+    };
+    open_calls.insert(call_id.to_string(), OpenCall::Exec {
+        command: command.clone(),
+    });
+    EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
+        call_id: call_id.to_string(),
+        process_id: None,
+        turn_id: String::new(),
+        command,
+        cwd: cwd.to_path_buf(),
+        parsed_cmd: Vec::new(),
+        source: ExecCommandSource::Agent,
+        interaction_input: None,
+    })
+}
+
+/// The end event of a tool call, from its output item.
+fn close_call(call: OpenCall, call_id: &str, text: &str, success: bool, cwd: &Path) -> EventMsg {
+    let (output, exit_code) = tool_output(text, success);
+    match call {
+        OpenCall::Patch => {
+            let success = exit_code == 0;
+            EventMsg::PatchApplyEnd(PatchApplyEndEvent {
+                call_id: call_id.to_string(),
+                turn_id: String::new(),
+                stdout: output,
+                stderr: String::new(),
+                success,
+                changes: HashMap::new(),
+                status: if success {
+                    PatchApplyStatus::Completed
+                } else {
+                    PatchApplyStatus::Failed
+                },
+            })
+        }
+        OpenCall::Exec { command } => EventMsg::ExecCommandEnd(ExecCommandEndEvent {
+            call_id: call_id.to_string(),
+            process_id: None,
+            turn_id: String::new(),
+            command,
+            cwd: cwd.to_path_buf(),
+            parsed_cmd: Vec::new(),
+            source: ExecCommandSource::Agent,
+            interaction_input: None,
+            stdout: output.clone(),
+            stderr: String::new(),
+            aggregated_output: output.clone(),
+            exit_code,
+            duration: Duration::ZERO,
+            formatted_output: output,
+            status: if exit_code == 0 {
+                ExecCommandStatus::Completed
+            } else {
+                ExecCommandStatus::Failed
+            },
+        }),
+    }
+}
+
+/// Split a tool output into its text and exit code.  Outputs of the
+/// `tool_exec` activity are `{"output": ..., "metadata": {"exit_code": ...}}`
+/// JSON (see `ToolExecOutput::into_response_input_item`); anything else is
+/// plain text whose exit code follows the `success` flag.
+fn tool_output(text: &str, success: bool) -> (String, i32) {
+    let fallback_code = if success { 0 } else { 1 };
+    if let Ok(v) = serde_json::from_str::<serde_json::Value>(text)
+        && let Some(output) = v.get("output").and_then(|o| o.as_str())
+    {
+        let exit_code = v
+            .pointer("/metadata/exit_code")
+            .and_then(|c| c.as_i64())
+            .map_or(fallback_code, |c| c as i32);
+        return (output.to_string(), exit_code);
+    }
+    (text.to_string(), fallback_code)
+}
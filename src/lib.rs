@@ -7,11 +7,16 @@
 //! using Temporal's workflow engine.
 
 pub mod activities;
+pub mod cli;
 pub mod config_loader;
 pub mod entropy;
 pub mod harness;
+pub mod history;
 pub mod mcp;
+pub mod mcp_server;
 pub mod picker;
+pub mod rollout;
+pub mod schedules;
 pub mod session;
 pub mod session_workflow;
 pub mod sink;
@@ -24,5 +29,9 @@ pub mod types;
 pub mod watcher;
 pub mod workflow;
 
+/// Task queue the codex workflows and activities run on: the worker polls
+/// it, and sessions, crews, schedules and the harness are started on it.
+pub const TASK_QUEUE: &str = "codex-temporal";
+
 #[cfg(test)]
 mod unit_tests;
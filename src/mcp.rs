@@ -2,54 +2,232 @@
 //!
 //! [`HarnessMcpManager`] holds persistent connections to user-configured MCP
 //! servers, supporting tool discovery and tool execution as Temporal activities.
+//!
+//! Connections live only in worker memory, while discovered tool schemas live
+//! in workflow state.  After a worker restart the manager therefore starts
+//! empty; servers are reconnected lazily on first use from the session's
+//! config, with health checks and a backoff policy for servers that crash or
+//! fail to start.
 
 use std::collections::HashMap;
 use std::ffi::OsString;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{Result, anyhow};
 use codex_core::config::types::{McpServerConfig, McpServerTransportConfig};
 use codex_core::mcp::split_qualified_tool_name;
 use codex_rmcp_client::{OAuthCredentialsStoreMode, RmcpClient, SendElicitation};
 use rmcp::model::{InitializeRequestParams, Tool};
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Notify, oneshot};
+use tokio::task::JoinHandle;
 
-use crate::types::CapturedElicitation;
+use crate::types::{CapturedElicitation, McpServerUnavailable};
 
 const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
 const DEFAULT_TOOL_TIMEOUT: Duration = Duration::from_secs(60);
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a tool call may wait for an elicitation answer before it is
+/// aborted, so calls whose workflow never answers do not leak.
+pub const ELICITATION_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Upper bound on the resource content returned by one `resources/read`,
+/// so a single large resource cannot flood the conversation history.
+pub const MAX_RESOURCE_CONTENT_BYTES: usize = 64 * 1024;
+
+/// Backoff policy for reconnecting to MCP servers that failed to start or
+/// crashed.
+#[derive(Debug, Clone, Copy)]
+pub struct McpReconnectPolicy {
+    /// Delay before the first retry after a failed connection attempt.
+    pub initial_delay: Duration,
+    /// Upper bound on the delay between attempts.
+    pub max_delay: Duration,
+}
+
+impl Default for McpReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl McpReconnectPolicy {
+    /// Delay to wait after `attempts` consecutive failures (exponential,
+    /// capped at `max_delay`).
+    pub fn delay_for(&self, attempts: u32) -> Duration {
+        if attempts == 0 {
+            return Duration::ZERO;
+        }
+        let factor = 2u32.saturating_pow(attempts - 1);
+        self.initial_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
 
 /// Manages persistent MCP server connections for the worker.
 pub struct HarnessMcpManager {
     clients: HashMap<String, ManagedMcpServer>,
-    /// Captured elicitation from the most recent tool call (if any).
-    captured_elicitation: Arc<Mutex<Option<CapturedElicitation>>>,
+    /// Last known config for every server, used for lazy reconnection.
+    configs: HashMap<String, McpServerConfig>,
+    /// Consecutive connection failures per server.
+    failures: HashMap<String, ServerFailure>,
+    reconnect_policy: McpReconnectPolicy,
+    /// Elicitation requests waiting for the user's answer.
+    elicitations: Arc<Mutex<ElicitationBroker>>,
+    /// Woken whenever a server issues an elicitation request.
+    elicitation_notify: Arc<Notify>,
+    /// Tool calls that are blocked on an elicitation, keyed by call_id.
+    pending_calls: HashMap<String, PendingCall>,
+}
+
+/// A tool call waiting for the user's answer to an elicitation.
+struct PendingCall {
+    server_name: String,
+    handle: JoinHandle<Result<codex_protocol::mcp::CallToolResult>>,
+    /// When the call started waiting, for the [`ELICITATION_TTL`] sweep.
+    since: Instant,
+}
+
+/// Connection failure bookkeeping for a single server.
+struct ServerFailure {
+    attempts: u32,
+    last_error: String,
+    retry_after: Instant,
 }
 
 struct ManagedMcpServer {
-    client: RmcpClient,
-    /// Raw tool name → rmcp Tool (unqualified names).
+    client: Arc<RmcpClient>,
+    /// Transport the client was connected with; a session whose config
+    /// names a different transport gets a fresh connection.
+    transport: McpServerTransportConfig,
+    /// Raw tool name → rmcp Tool (unqualified names), unfiltered: each
+    /// session applies its own `enabled_tools` / `disabled_tools`.
     tools: HashMap<String, Tool>,
     tool_timeout: Option<Duration>,
 }
 
-/// Elicitation callback that captures the request details and declines.
+/// Progress of an MCP tool call driven by the manager.
+pub enum McpCallProgress {
+    /// The call finished (successfully or not).
+    Completed(Result<codex_protocol::mcp::CallToolResult>),
+    /// The server asked the user for input; the call stays pending until
+    /// [`HarnessMcpManager::resolve_elicitation`] delivers the answer.
+    Elicitation(CapturedElicitation),
+}
+
+/// Elicitation requests waiting for the user's answer, shared with the
+/// per-server elicitation callbacks.
+///
+/// Requests are attributed to the call being driven on their server: the
+/// manager drives one call at a time, and the other calls of that server
+/// are blocked on their own elicitation, so they cannot elicit again.
+#[derive(Default)]
+pub(crate) struct ElicitationBroker {
+    /// Call being driven on each server, keyed by server name.
+    active: HashMap<String, String>,
+    /// Captured requests not yet handed to the workflow, keyed by call_id.
+    captured: HashMap<String, CapturedElicitation>,
+    /// Response channels keyed by [`elicitation_key`].  Responses are the
+    /// wire-format `elicitation/create` result (`{action, content}`).
+    responders: HashMap<String, Responder>,
+}
+
+struct Responder {
+    call_id: String,
+    sender: oneshot::Sender<serde_json::Value>,
+}
+
+impl ElicitationBroker {
+    /// Attribute requests from `server_name` to `call_id` from now on.
+    pub(crate) fn activate(&mut self, server_name: &str, call_id: &str) {
+        self.active.insert(server_name.to_string(), call_id.to_string());
+    }
+
+    /// Stop attributing requests from `server_name` to `call_id`.
+    pub(crate) fn deactivate(&mut self, server_name: &str, call_id: &str) {
+        if self.active.get(server_name).is_some_and(|active| active == call_id) {
+            self.active.remove(server_name);
+        }
+    }
+
+    /// Capture a request from `server_name` for the call active on it and
+    /// return the channel its answer arrives on.  Fails when no call is
+    /// being driven on the server, since nobody would surface the request.
+    pub(crate) fn capture(
+        &mut self,
+        server_name: &str,
+        request_id: codex_protocol::mcp::RequestId,
+        params: &serde_json::Value,
+    ) -> Result<oneshot::Receiver<serde_json::Value>> {
+        let call_id = self.active.get(server_name).cloned().ok_or_else(|| {
+            anyhow!("no tool call of MCP server '{server_name}' is waiting for elicitation")
+        })?;
+        let (sender, receiver) = oneshot::channel();
+        self.responders.insert(
+            elicitation_key(server_name, &request_id),
+            Responder {
+                call_id: call_id.clone(),
+                sender,
+            },
+        );
+        self.captured.insert(
+            call_id,
+            CapturedElicitation::from_params(server_name.to_string(), request_id, params),
+        );
+        Ok(receiver)
+    }
+
+    /// Take the request captured for `call_id`, if any.
+    pub(crate) fn take_captured(&mut self, call_id: &str) -> Option<CapturedElicitation> {
+        self.captured.remove(call_id)
+    }
+
+    /// Take the response channel of a request.
+    pub(crate) fn take_responder(
+        &mut self,
+        server_name: &str,
+        request_id: &codex_protocol::mcp::RequestId,
+    ) -> Option<oneshot::Sender<serde_json::Value>> {
+        self.responders
+            .remove(&elicitation_key(server_name, request_id))
+            .map(|responder| responder.sender)
+    }
+
+    /// Drop the captures and response channels of calls for which `is_live`
+    /// is false.  Dropping a channel fails the server's request.
+    pub(crate) fn retain_calls(&mut self, is_live: impl Fn(&str) -> bool) {
+        self.captured.retain(|call_id, _| is_live(call_id));
+        self.responders.retain(|_, responder| is_live(&responder.call_id));
+    }
+}
+
+fn elicitation_key(server_name: &str, request_id: &codex_protocol::mcp::RequestId) -> String {
+    format!(
+        "{server_name}/{}",
+        serde_json::to_string(request_id).unwrap_or_default()
+    )
+}
+
+/// Elicitation callback that hands the request to the workflow and waits
+/// for the user's answer.
 ///
-/// Instead of erroring (which would fail the MCP handshake), we capture the
-/// elicitation parameters and return `Decline` so the server can proceed.
-/// The captured details are surfaced to the workflow for proper handling.
+/// The structured message and schema are captured and the activity driving
+/// the tool call is woken up; the MCP request itself stays open until the
+/// workflow resolves it, so the server sees the user's real answer.
 fn capturing_elicitation(
     server_name: String,
-    captured: Arc<Mutex<Option<CapturedElicitation>>>,
+    broker: Arc<Mutex<ElicitationBroker>>,
+    notify: Arc<Notify>,
 ) -> SendElicitation {
     Box::new(move |id, params| {
-        let captured = captured.clone();
+        let broker = broker.clone();
+        let notify = notify.clone();
         let server_name = server_name.clone();
         Box::pin(async move {
-            // Extract the message from the elicitation params.
-            let message = format!("{params:?}");
-
             // Convert rmcp RequestId to protocol RequestId.
             let request_id = match id {
                 rmcp::model::NumberOrString::String(ref s) => {
@@ -60,17 +238,18 @@ fn capturing_elicitation(
                 }
             };
 
-            // Capture the elicitation details.
-            *captured.lock().await = Some(CapturedElicitation {
-                server_name,
-                request_id,
-                message,
-            });
+            // Capture the wire-format params so message and schema survive
+            // the activity boundary intact.
+            let params = serde_json::to_value(&params)
+                .map_err(|e| anyhow!("failed to serialize elicitation params: {e}"))?;
+            let rx = broker.lock().await.capture(&server_name, request_id, &params)?;
+            notify.notify_one();
 
-            // Return an error so the MCP server knows elicitation failed.
-            // The tool call will fail, and the activity will surface the
-            // captured elicitation in its output.
-            Err(anyhow!("elicitation captured for workflow handling"))
+            let response = rx
+                .await
+                .map_err(|_| anyhow!("elicitation abandoned before the user responded"))?;
+            serde_json::from_value(response)
+                .map_err(|e| anyhow!("invalid elicitation response: {e}"))
         })
     })
 }
@@ -84,43 +263,53 @@ impl Default for HarnessMcpManager {
 impl HarnessMcpManager {
     /// Create an empty manager (no servers connected yet).
     pub fn new() -> Self {
+        Self::with_reconnect_policy(McpReconnectPolicy::default())
+    }
+
+    /// Create an empty manager with a custom reconnect policy.
+    pub fn with_reconnect_policy(reconnect_policy: McpReconnectPolicy) -> Self {
         Self {
             clients: HashMap::new(),
-            captured_elicitation: Arc::new(Mutex::new(None)),
+            configs: HashMap::new(),
+            failures: HashMap::new(),
+            reconnect_policy,
+            elicitations: Arc::new(Mutex::new(ElicitationBroker::default())),
+            elicitation_notify: Arc::new(Notify::new()),
+            pending_calls: HashMap::new(),
         }
     }
 
-    /// Take the captured elicitation (if any) from the most recent operation.
-    pub async fn take_captured_elicitation(&self) -> Option<CapturedElicitation> {
-        self.captured_elicitation.lock().await.take()
-    }
-
-    /// Connect to all enabled MCP servers and discover their tools.
+    /// Discover the tools of one session's enabled MCP servers.
     ///
     /// Returns a map of qualified tool names (`mcp__server__tool`) to
-    /// `rmcp::model::Tool` objects.
+    /// `rmcp::model::Tool` objects, filtered by the session's config.
+    /// Connections are shared by every session on the worker, so this only
+    /// builds the session's view of them: a server already connected with
+    /// the same transport keeps its connection and only has its tool list
+    /// re-fetched (mid-session refresh), other servers are (re)connected.
+    /// Servers the session disables are skipped without touching their
+    /// shared connection.
     pub async fn initialize(
         &mut self,
         servers: &HashMap<String, McpServerConfig>,
     ) -> Result<HashMap<String, Tool>> {
         let mut all_tools = HashMap::new();
 
+        self.register_configs(servers);
+
         for (server_name, server_config) in servers {
             if !server_config.enabled {
-                tracing::info!(server = %server_name, "MCP server disabled, skipping");
+                tracing::info!(server = %server_name, "MCP server disabled for session, skipping");
                 continue;
             }
 
-            match self
-                .connect_server(server_name, server_config)
-                .await
-            {
+            match self.refresh_server(server_name, server_config).await {
                 Ok(tools) => {
-                    for (qualified_name, tool) in tools {
-                        all_tools.insert(qualified_name, tool);
-                    }
+                    self.failures.remove(server_name);
+                    all_tools.extend(session_tools(server_name, server_config, tools));
                 }
                 Err(e) => {
+                    self.record_failure(server_name, &e);
                     if server_config.required {
                         return Err(anyhow!(
                             "required MCP server '{}' failed to initialize: {}",
@@ -140,12 +329,188 @@ impl HarnessMcpManager {
         Ok(all_tools)
     }
 
+    /// Remember server configs without connecting, so that servers can be
+    /// reconnected lazily on first use.
+    ///
+    /// One session disabling a server does not replace the enabled config
+    /// other sessions reconnect it with.
+    pub fn register_configs(&mut self, servers: &HashMap<String, McpServerConfig>) {
+        for (server_name, server_config) in servers {
+            if !server_config.enabled
+                && self.configs.get(server_name).is_some_and(|known| known.enabled)
+            {
+                continue;
+            }
+            self.configs
+                .insert(server_name.clone(), server_config.clone());
+        }
+    }
+
+    /// Whether the manager knows the config for `server_name`.
+    pub fn has_server_config(&self, server_name: &str) -> bool {
+        self.configs.contains_key(server_name)
+    }
+
+    /// Whether the manager knows any server configs at all.
+    pub fn has_server_configs(&self) -> bool {
+        !self.configs.is_empty()
+    }
+
+    /// Connect every enabled server that is not connected yet.  Failures are
+    /// recorded (and backed off) but not returned.
+    pub async fn ensure_all_connected(&mut self) {
+        let mut names: Vec<String> = self
+            .configs
+            .iter()
+            .filter(|(_, config)| config.enabled)
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        for name in names {
+            if let Err(e) = self.ensure_connected(&name).await {
+                tracing::debug!(server = %name, error = %e, "skipping unavailable MCP server");
+            }
+        }
+    }
+
+    /// Make sure `server_name` has a live connection, reconnecting from its
+    /// remembered config if needed.
+    ///
+    /// Reconnection attempts honour the [`McpReconnectPolicy`]: while a
+    /// server is backing off, this fails fast without spawning it again.
+    pub async fn ensure_connected(&mut self, server_name: &str) -> Result<()> {
+        if self.clients.contains_key(server_name) {
+            return Ok(());
+        }
+
+        let config = self
+            .configs
+            .get(server_name)
+            .cloned()
+            .ok_or_else(|| anyhow!("MCP server '{}' not found", server_name))?;
+        if !config.enabled {
+            return Err(anyhow!("MCP server '{}' is disabled", server_name));
+        }
+
+        if let Some(failure) = self.failures.get(server_name) {
+            let now = Instant::now();
+            if now < failure.retry_after {
+                return Err(anyhow!(
+                    "MCP server '{}' unavailable after {} attempt(s), next retry in {}s: {}",
+                    server_name,
+                    failure.attempts,
+                    (failure.retry_after - now).as_secs().max(1),
+                    failure.last_error
+                ));
+            }
+        }
+
+        tracing::info!(server = %server_name, "reconnecting MCP server");
+        match self.connect_server(server_name, &config).await {
+            Ok(_) => {
+                self.failures.remove(server_name);
+                Ok(())
+            }
+            Err(e) => {
+                self.record_failure(server_name, &e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Ping a connected server with a cheap `tools/list` request.
+    ///
+    /// Unhealthy servers are dropped so the next [`ensure_connected`]
+    /// respawns them.  Returns `false` if the server is not connected.
+    ///
+    /// [`ensure_connected`]: Self::ensure_connected
+    pub async fn check_health(&mut self, server_name: &str) -> bool {
+        let Some(server) = self.clients.get(server_name) else {
+            return false;
+        };
+        match server
+            .client
+            .list_tools(None, Some(HEALTH_CHECK_TIMEOUT))
+            .await
+        {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::warn!(server = %server_name, error = %e, "MCP server failed health check");
+                self.clients.remove(server_name);
+                false
+            }
+        }
+    }
+
+    /// Describe a server that is currently failing to connect, if any.
+    pub fn server_unavailable(&self, server_name: &str) -> Option<McpServerUnavailable> {
+        let failure = self.failures.get(server_name)?;
+        Some(McpServerUnavailable {
+            server_name: server_name.to_string(),
+            required: self.configs.get(server_name).is_some_and(|c| c.required),
+            attempts: failure.attempts,
+            error: failure.last_error.clone(),
+        })
+    }
+
+    fn record_failure(&mut self, server_name: &str, error: &anyhow::Error) {
+        let attempts = self.failures.get(server_name).map_or(0, |f| f.attempts) + 1;
+        let delay = self.reconnect_policy.delay_for(attempts);
+        tracing::warn!(
+            server = %server_name,
+            attempts,
+            retry_in_secs = delay.as_secs(),
+            error = %error,
+            "MCP server connection failed"
+        );
+        self.failures.insert(
+            server_name.to_string(),
+            ServerFailure {
+                attempts,
+                last_error: error.to_string(),
+                retry_after: Instant::now() + delay,
+            },
+        );
+    }
+
+    /// Re-fetch the tools of `server_name` over its shared connection when
+    /// it was made with the same transport, and connect it otherwise.
+    async fn refresh_server(
+        &mut self,
+        server_name: &str,
+        config: &McpServerConfig,
+    ) -> Result<Vec<Tool>> {
+        if let Some(server) = self.clients.get_mut(server_name)
+            && server.transport == config.transport
+        {
+            let timeout = config.startup_timeout_sec.or(Some(DEFAULT_STARTUP_TIMEOUT));
+            match server.client.list_tools(None, timeout).await {
+                Ok(list) => {
+                    server.tools = list
+                        .tools
+                        .iter()
+                        .map(|tool| (tool.name.to_string(), tool.clone()))
+                        .collect();
+                    return Ok(list.tools);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        server = %server_name,
+                        error = %e,
+                        "MCP server failed to list tools, reconnecting"
+                    );
+                }
+            }
+        }
+        self.connect_server(server_name, config).await
+    }
+
     /// Connect to a single MCP server and discover its tools.
     async fn connect_server(
         &mut self,
         server_name: &str,
         config: &McpServerConfig,
-    ) -> Result<HashMap<String, Tool>> {
+    ) -> Result<Vec<Tool>> {
         let startup_timeout = config.startup_timeout_sec.or(Some(DEFAULT_STARTUP_TIMEOUT));
         let tool_timeout = config.tool_timeout_sec.or(Some(DEFAULT_TOOL_TIMEOUT));
 
@@ -201,7 +566,8 @@ impl HarnessMcpManager {
         let init_params = InitializeRequestParams::default();
         let elicitation_cb = capturing_elicitation(
             server_name.to_string(),
-            Arc::clone(&self.captured_elicitation),
+            Arc::clone(&self.elicitations),
+            Arc::clone(&self.elicitation_notify),
         );
         client
             .initialize(init_params, startup_timeout, elicitation_cb)
@@ -214,55 +580,36 @@ impl HarnessMcpManager {
             .await
             .map_err(|e| anyhow!("MCP server '{}' list_tools failed: {}", server_name, e))?;
 
-        let mut server_tools = HashMap::new();
-        let mut qualified_tools = HashMap::new();
-
-        for tool in list_result.tools {
-            let raw_name = tool.name.to_string();
-
-            // Apply enabled/disabled filters.
-            if let Some(ref enabled) = config.enabled_tools
-                && !enabled.contains(&raw_name)
-            {
-                continue;
-            }
-            if let Some(ref disabled) = config.disabled_tools
-                && disabled.contains(&raw_name)
-            {
-                continue;
-            }
-
-            let qualified_name = format!("mcp__{}__{}", server_name, raw_name);
-            qualified_tools.insert(qualified_name, tool.clone());
-            server_tools.insert(raw_name, tool);
-        }
+        let server_tools: HashMap<String, Tool> = list_result
+            .tools
+            .iter()
+            .map(|tool| (tool.name.to_string(), tool.clone()))
+            .collect();
 
         tracing::info!(
             server = %server_name,
-            tools = qualified_tools.len(),
+            tools = server_tools.len(),
             "MCP server connected and tools discovered"
         );
 
         self.clients.insert(
             server_name.to_string(),
             ManagedMcpServer {
-                client,
+                client: Arc::new(client),
+                transport: config.transport.clone(),
                 tools: server_tools,
                 tool_timeout,
             },
         );
 
-        Ok(qualified_tools)
+        Ok(list_result.tools)
     }
 
-    /// Call a tool on the appropriate MCP server.
-    ///
-    /// `qualified_name` must be in the format `mcp__server__tool`.
-    pub async fn call_tool(
+    /// Look up the client, raw tool name and timeout for a qualified tool.
+    fn resolve_tool(
         &self,
         qualified_name: &str,
-        arguments: Option<serde_json::Value>,
-    ) -> Result<codex_protocol::mcp::CallToolResult> {
+    ) -> Result<(Arc<RmcpClient>, String, Option<Duration>)> {
         let (server_name, tool_name) = split_qualified_tool_name(qualified_name)
             .ok_or_else(|| anyhow!("invalid MCP tool name: {}", qualified_name))?;
 
@@ -279,19 +626,344 @@ impl HarnessMcpManager {
             ));
         }
 
-        let rmcp_result = server
-            .client
-            .call_tool(tool_name, arguments, None, server.tool_timeout)
+        Ok((Arc::clone(&server.client), tool_name, server.tool_timeout))
+    }
+
+    /// Call a tool on the appropriate MCP server.
+    ///
+    /// `qualified_name` must be in the format `mcp__server__tool`.
+    /// Elicitation requests issued during the call are not answered; use
+    /// [`start_tool_call`](Self::start_tool_call) for calls that may elicit.
+    pub async fn call_tool(
+        &self,
+        qualified_name: &str,
+        arguments: Option<serde_json::Value>,
+    ) -> Result<codex_protocol::mcp::CallToolResult> {
+        let (client, tool_name, timeout) = self.resolve_tool(qualified_name)?;
+        invoke_tool(client, tool_name, arguments, timeout).await
+    }
+
+    /// Start a tool call, reconnecting the server first if needed, and drive
+    /// it until it completes or the server requests elicitation.
+    ///
+    /// If the call fails and the server no longer answers a health check
+    /// (e.g. a crashed stdio process), the server is respawned and the call
+    /// is retried once.
+    pub async fn start_tool_call(
+        &mut self,
+        call_id: &str,
+        qualified_name: &str,
+        arguments: Option<serde_json::Value>,
+    ) -> Result<McpCallProgress> {
+        let (server_name, _) = split_qualified_tool_name(qualified_name)
+            .ok_or_else(|| anyhow!("invalid MCP tool name: {}", qualified_name))?;
+
+        self.sweep_pending_calls().await;
+        self.ensure_connected(&server_name).await?;
+
+        let progress = self
+            .spawn_tool_call(call_id, qualified_name, arguments.clone())
+            .await?;
+        if let McpCallProgress::Completed(Err(ref e)) = progress
+            && !self.check_health(&server_name).await
+        {
+            tracing::warn!(
+                server = %server_name,
+                error = %e,
+                "MCP server unhealthy after failed call, reconnecting"
+            );
+            self.ensure_connected(&server_name).await?;
+            return self.spawn_tool_call(call_id, qualified_name, arguments).await;
+        }
+        Ok(progress)
+    }
+
+    /// Deliver the user's answer to a pending elicitation and keep driving
+    /// the original tool call.
+    ///
+    /// `response` is the wire-format `elicitation/create` result
+    /// (`{"action": ..., "content": ...}`).  Pending calls live in worker
+    /// memory, so this fails if the worker restarted since the call began
+    /// or the call was aborted after waiting longer than [`ELICITATION_TTL`].
+    pub async fn resolve_elicitation(
+        &mut self,
+        call_id: &str,
+        server_name: &str,
+        request_id: &codex_protocol::mcp::RequestId,
+        response: serde_json::Value,
+    ) -> Result<McpCallProgress> {
+        self.sweep_pending_calls().await;
+        let responder = {
+            let mut broker = self.elicitations.lock().await;
+            let responder = broker.take_responder(server_name, request_id);
+            if responder.is_some() {
+                // Follow-up requests the answer triggers belong to this call.
+                broker.activate(server_name, call_id);
+            }
+            responder
+        };
+        let Some(responder) = responder else {
+            if let Some(call) = self.pending_calls.remove(call_id) {
+                call.handle.abort();
+            }
+            return Err(anyhow!(
+                "no pending elicitation {:?} from MCP server '{}' on this worker",
+                request_id,
+                server_name
+            ));
+        };
+        if responder.send(response).is_err() {
+            tracing::warn!(server = %server_name, "MCP call ended before elicitation response");
+        }
+        self.await_tool_call(call_id).await
+    }
+
+    async fn spawn_tool_call(
+        &mut self,
+        call_id: &str,
+        qualified_name: &str,
+        arguments: Option<serde_json::Value>,
+    ) -> Result<McpCallProgress> {
+        let (client, tool_name, timeout) = self.resolve_tool(qualified_name)?;
+        let (server_name, _) = split_qualified_tool_name(qualified_name)
+            .ok_or_else(|| anyhow!("invalid MCP tool name: {}", qualified_name))?;
+        {
+            let mut broker = self.elicitations.lock().await;
+            // Drop a stale capture of an earlier attempt of this call.
+            broker.take_captured(call_id);
+            broker.activate(&server_name, call_id);
+        }
+        let handle = tokio::spawn(invoke_tool(client, tool_name, arguments, timeout));
+        self.pending_calls.insert(
+            call_id.to_string(),
+            PendingCall {
+                server_name,
+                handle,
+                since: Instant::now(),
+            },
+        );
+        self.await_tool_call(call_id).await
+    }
+
+    /// Wait until the pending call completes or the server elicits.
+    async fn await_tool_call(&mut self, call_id: &str) -> Result<McpCallProgress> {
+        let mut call = self
+            .pending_calls
+            .remove(call_id)
+            .ok_or_else(|| anyhow!("no pending MCP call '{}' on this worker", call_id))?;
+        let server_name = call.server_name.clone();
+
+        let notify = Arc::clone(&self.elicitation_notify);
+        let progress = loop {
+            let joined = tokio::select! {
+                joined = &mut call.handle => Some(joined),
+                _ = notify.notified() => None,
+            };
+            match joined {
+                Some(joined) => {
+                    let result = joined
+                        .map_err(|e| anyhow!("MCP tool call task failed: {e}"))
+                        .and_then(|r| r);
+                    break McpCallProgress::Completed(result);
+                }
+                None => {
+                    let captured = self.elicitations.lock().await.take_captured(call_id);
+                    if let Some(captured) = captured {
+                        call.since = Instant::now();
+                        self.pending_calls.insert(call_id.to_string(), call);
+                        break McpCallProgress::Elicitation(captured);
+                    }
+                }
+            }
+        };
+        self.elicitations
+            .lock()
             .await
-            .map_err(|e| anyhow!("MCP tool call failed: {}", e))?;
+            .deactivate(&server_name, call_id);
+        Ok(progress)
+    }
 
-        // Convert rmcp::model::CallToolResult → codex_protocol::mcp::CallToolResult
-        // via JSON round-trip.
-        let value = serde_json::to_value(&rmcp_result)
-            .map_err(|e| anyhow!("failed to serialize rmcp CallToolResult: {}", e))?;
-        let result: codex_protocol::mcp::CallToolResult = serde_json::from_value(value)
-            .map_err(|e| anyhow!("failed to deserialize into protocol CallToolResult: {}", e))?;
+    /// Abort tool calls that waited longer than [`ELICITATION_TTL`] for an
+    /// answer (e.g. their workflow ended without resolving it) and drop the
+    /// elicitation requests of calls that are gone.
+    async fn sweep_pending_calls(&mut self) {
+        self.pending_calls.retain(|call_id, call| {
+            let live = call.since.elapsed() < ELICITATION_TTL;
+            if !live {
+                tracing::warn!(
+                    call_id = %call_id,
+                    server = %call.server_name,
+                    "MCP call waited too long for an elicitation answer, aborting"
+                );
+                call.handle.abort();
+            }
+            live
+        });
+        let pending = &self.pending_calls;
+        self.elicitations
+            .lock()
+            .await
+            .retain_calls(|call_id| pending.contains_key(call_id));
+    }
 
+    /// List resources of one server, or of all servers when `server` is
+    /// `None`.  Each resource is tagged with its `server`.
+    pub async fn list_resources(
+        &mut self,
+        server: Option<&str>,
+        cursor: Option<String>,
+    ) -> Result<serde_json::Value> {
+        self.list_paginated(server, cursor, "resources", |client, cursor| async move {
+            let params = paginated_params(cursor)?;
+            let result = client
+                .list_resources(params, Some(DEFAULT_REQUEST_TIMEOUT))
+                .await?;
+            Ok(serde_json::to_value(result)?)
+        })
+        .await
+    }
+
+    /// List resource templates of one server, or of all servers.
+    pub async fn list_resource_templates(
+        &mut self,
+        server: Option<&str>,
+        cursor: Option<String>,
+    ) -> Result<serde_json::Value> {
+        self.list_paginated(server, cursor, "resourceTemplates", |client, cursor| async move {
+            let params = paginated_params(cursor)?;
+            let result = client
+                .list_resource_templates(params, Some(DEFAULT_REQUEST_TIMEOUT))
+                .await?;
+            Ok(serde_json::to_value(result)?)
+        })
+        .await
+    }
+
+    /// List prompts of one server, or of all servers.
+    pub async fn list_prompts(
+        &mut self,
+        server: Option<&str>,
+        cursor: Option<String>,
+    ) -> Result<serde_json::Value> {
+        self.list_paginated(server, cursor, "prompts", |client, cursor| async move {
+            let params = paginated_params(cursor)?;
+            let result = client
+                .list_prompts(params, Some(DEFAULT_REQUEST_TIMEOUT))
+                .await?;
+            Ok(serde_json::to_value(result)?)
+        })
+        .await
+    }
+
+    /// Read a resource, capping its content at
+    /// [`MAX_RESOURCE_CONTENT_BYTES`].
+    pub async fn read_resource(&mut self, server: &str, uri: &str) -> Result<serde_json::Value> {
+        self.ensure_connected(server).await?;
+        let client = self.client_for(server)?;
+
+        let params = serde_json::from_value(serde_json::json!({ "uri": uri }))?;
+        let result = client
+            .read_resource(params, Some(DEFAULT_REQUEST_TIMEOUT))
+            .await
+            .map_err(|e| anyhow!("MCP server '{}' read_resource failed: {}", server, e))?;
+
+        let mut value = serde_json::to_value(result)?;
+        limit_resource_contents(&mut value, MAX_RESOURCE_CONTENT_BYTES);
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("server".to_string(), server.into());
+            obj.insert("uri".to_string(), uri.into());
+        }
+        Ok(value)
+    }
+
+    /// Fetch a prompt, rendered by the server with `arguments`.
+    pub async fn get_prompt(
+        &mut self,
+        server: &str,
+        name: &str,
+        arguments: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        self.ensure_connected(server).await?;
+        let client = self.client_for(server)?;
+
+        let mut params = serde_json::json!({ "name": name });
+        if let Some(arguments) = arguments {
+            params["arguments"] = arguments;
+        }
+        let result = client
+            .get_prompt(serde_json::from_value(params)?, Some(DEFAULT_REQUEST_TIMEOUT))
+            .await
+            .map_err(|e| anyhow!("MCP server '{}' get_prompt failed: {}", server, e))?;
+        Ok(serde_json::to_value(result)?)
+    }
+
+    fn client_for(&self, server_name: &str) -> Result<Arc<RmcpClient>> {
+        self.clients
+            .get(server_name)
+            .map(|server| Arc::clone(&server.client))
+            .ok_or_else(|| anyhow!("MCP server '{}' not found", server_name))
+    }
+
+    /// Run a paginated `*/list` request against one server, or against all
+    /// servers (first page only) when `server` is `None`.
+    async fn list_paginated<F, Fut>(
+        &mut self,
+        server: Option<&str>,
+        cursor: Option<String>,
+        items_key: &str,
+        list_page: F,
+    ) -> Result<serde_json::Value>
+    where
+        F: Fn(Arc<RmcpClient>, Option<String>) -> Fut,
+        Fut: std::future::Future<Output = Result<serde_json::Value>>,
+    {
+        let server_names = match server {
+            Some(name) => {
+                self.ensure_connected(name).await?;
+                vec![name.to_string()]
+            }
+            None => {
+                if cursor.is_some() {
+                    return Err(anyhow!("a cursor can only be used together with a server"));
+                }
+                self.ensure_all_connected().await;
+                let mut names: Vec<String> = self.clients.keys().cloned().collect();
+                names.sort();
+                names
+            }
+        };
+
+        let mut items = Vec::new();
+        let mut next_cursor = None;
+        for name in server_names {
+            let client = self.client_for(&name)?;
+            let page = match list_page(client, cursor.clone()).await {
+                Ok(page) => page,
+                // When listing across servers, one server without support
+                // for this capability should not hide the others.
+                Err(e) if server.is_none() => {
+                    tracing::debug!(server = %name, error = %e, "MCP list request failed, skipping");
+                    continue;
+                }
+                Err(e) => return Err(anyhow!("MCP server '{}': {}", name, e)),
+            };
+            if let Some(page_items) = page.get(items_key).and_then(|v| v.as_array()) {
+                items.extend(page_items.iter().cloned().map(|mut item| {
+                    if let Some(obj) = item.as_object_mut() {
+                        obj.insert("server".to_string(), name.clone().into());
+                    }
+                    item
+                }));
+            }
+            if server.is_some() {
+                next_cursor = page.get("nextCursor").filter(|c| !c.is_null()).cloned();
+            }
+        }
+
+        let mut result = serde_json::json!({ items_key: items });
+        if let Some(cursor) = next_cursor {
+            result["nextCursor"] = cursor;
+        }
         Ok(result)
     }
 
@@ -300,3 +972,98 @@ impl HarnessMcpManager {
         self.clients.values().any(|s| !s.tools.is_empty())
     }
 }
+
+/// Qualify a server's tools (`mcp__server__tool`) for one session, applying
+/// the session's `enabled_tools` / `disabled_tools` filters.
+pub fn session_tools(
+    server_name: &str,
+    config: &McpServerConfig,
+    tools: Vec<Tool>,
+) -> HashMap<String, Tool> {
+    tools
+        .into_iter()
+        .filter(|tool| {
+            let name = tool.name.as_ref();
+            config
+                .enabled_tools
+                .as_ref()
+                .is_none_or(|enabled| enabled.iter().any(|t| t == name))
+                && !config
+                    .disabled_tools
+                    .as_ref()
+                    .is_some_and(|disabled| disabled.iter().any(|t| t == name))
+        })
+        .map(|tool| (format!("mcp__{}__{}", server_name, tool.name), tool))
+        .collect()
+}
+
+/// Invoke a tool and convert the result to the protocol type.
+async fn invoke_tool(
+    client: Arc<RmcpClient>,
+    tool_name: String,
+    arguments: Option<serde_json::Value>,
+    timeout: Option<Duration>,
+) -> Result<codex_protocol::mcp::CallToolResult> {
+    let rmcp_result = client
+        .call_tool(tool_name, arguments, None, timeout)
+        .await
+        .map_err(|e| anyhow!("MCP tool call failed: {}", e))?;
+
+    // Convert rmcp::model::CallToolResult → codex_protocol::mcp::CallToolResult
+    // via JSON round-trip.
+    let value = serde_json::to_value(&rmcp_result)
+        .map_err(|e| anyhow!("failed to serialize rmcp CallToolResult: {}", e))?;
+    let result: codex_protocol::mcp::CallToolResult = serde_json::from_value(value)
+        .map_err(|e| anyhow!("failed to deserialize into protocol CallToolResult: {}", e))?;
+
+    Ok(result)
+}
+
+/// Build optional pagination params for a `*/list` request.
+fn paginated_params<P: serde::de::DeserializeOwned>(cursor: Option<String>) -> Result<Option<P>> {
+    cursor
+        .map(|cursor| serde_json::from_value(serde_json::json!({ "cursor": cursor })))
+        .transpose()
+        .map_err(|e| anyhow!("invalid pagination cursor: {e}"))
+}
+
+/// Cap the total size of `contents[].text` / `contents[].blob` in a
+/// `resources/read` result.  Text is truncated with a marker; blobs that do
+/// not fit are replaced by a short note.
+pub fn limit_resource_contents(result: &mut serde_json::Value, max_bytes: usize) {
+    let Some(contents) = result.get_mut("contents").and_then(|c| c.as_array_mut()) else {
+        return;
+    };
+
+    let mut remaining = max_bytes;
+    for item in contents {
+        if let Some(text) = item.get("text").and_then(|t| t.as_str()) {
+            if text.len() <= remaining {
+                remaining -= text.len();
+                continue;
+            }
+            let mut cut = remaining;
+            while !text.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            let truncated = format!(
+                "{}\n[... truncated {} of {} bytes]",
+                &text[..cut],
+                text.len() - cut,
+                text.len()
+            );
+            item["text"] = truncated.into();
+            remaining = 0;
+        } else if let Some(blob) = item.get("blob").and_then(|b| b.as_str()) {
+            if blob.len() <= remaining {
+                remaining -= blob.len();
+                continue;
+            }
+            let note = format!("[binary content omitted: {} bytes base64]", blob.len());
+            if let Some(obj) = item.as_object_mut() {
+                obj.remove("blob");
+                obj.insert("text".to_string(), note.into());
+            }
+        }
+    }
+}
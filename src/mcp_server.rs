@@ -0,0 +1,545 @@
+//! MCP server that exposes codex-temporal sessions as tools.
+//!
+//! Lets other agents and IDEs delegate long-running work to durable codex
+//! sessions and check on it later without a TUI.  Speaks newline-delimited
+//! JSON-RPC (MCP stdio transport); streamable HTTP is not supported.
+//!
+//! | Tool            | Temporal action                                           |
+//! |-----------------|-----------------------------------------------------------|
+//! | `start_session` | `TemporalAgentSession::submit(UserTurn)`, self-registering |
+//! | `send_message`  | `TemporalAgentSession::submit(UserTurn)` on a running agent |
+//! | `get_events`    | bounded `AgentWorkflow::get_state_update`                 |
+//! | `approve`       | `TemporalAgentSession::submit(ExecApproval/PatchApproval)` |
+//! | `list_sessions` | `CodexHarness::list_sessions` query                       |
+//! | `list_agents`   | `SessionWorkflow::list_agents` query                      |
+//! | `spawn_agent`   | `SessionWorkflow::spawn_agent` signal                     |
+//!
+//! Requests are handled concurrently so a long `get_events` wait does not
+//! block other calls.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use codex_core::AgentSession;
+use codex_protocol::protocol::{Event, Op, ReviewDecision};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use temporalio_client::{Client, WorkflowQueryOptions};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc;
+
+use crate::harness::{CodexHarness, CodexHarnessRun};
+use crate::session::{TemporalAgentSession, user_turn_op};
+use crate::session_workflow::{SessionWorkflow, SessionWorkflowRun};
+use crate::types::{HarnessRegistration, SessionEntry, SessionWorkflowInput, SpawnAgentInput};
+
+/// MCP protocol revision advertised in `initialize`.
+pub const MCP_PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Default time `get_events` blocks waiting for new events.
+pub const DEFAULT_EVENT_WAIT_SECS: u64 = 10;
+
+/// Upper bound for the `wait_seconds` argument of `get_events`.
+pub const MAX_EVENT_WAIT_SECS: u64 = 60;
+
+// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+// ---------------------------------------------------------------------------
+// Tool arguments
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct StartSessionArgs {
+    pub message: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SendMessageArgs {
+    pub session_id: String,
+    pub message: String,
+    #[serde(default)]
+    pub agent_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct GetEventsArgs {
+    pub session_id: String,
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    #[serde(default)]
+    pub since: usize,
+    #[serde(default)]
+    pub wait_seconds: Option<u64>,
+}
+
+impl GetEventsArgs {
+    /// Requested wait, defaulted and clamped to [`MAX_EVENT_WAIT_SECS`].
+    pub(crate) fn wait(&self) -> Duration {
+        Duration::from_secs(
+            self.wait_seconds
+                .unwrap_or(DEFAULT_EVENT_WAIT_SECS)
+                .min(MAX_EVENT_WAIT_SECS),
+        )
+    }
+}
+
+/// What a pending approval is for.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ApprovalKind {
+    #[default]
+    Exec,
+    Patch,
+}
+
+/// Caller's answer to a pending approval.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ApprovalAnswer {
+    #[default]
+    Approve,
+    ApproveForSession,
+    Deny,
+    Abort,
+}
+
+impl From<ApprovalAnswer> for ReviewDecision {
+    fn from(answer: ApprovalAnswer) -> Self {
+        match answer {
+            ApprovalAnswer::Approve => ReviewDecision::Approved,
+            ApprovalAnswer::ApproveForSession => ReviewDecision::ApprovedForSession,
+            ApprovalAnswer::Deny => ReviewDecision::Denied,
+            ApprovalAnswer::Abort => ReviewDecision::Abort,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ApproveArgs {
+    pub session_id: String,
+    pub call_id: String,
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    #[serde(default)]
+    pub kind: ApprovalKind,
+    #[serde(default)]
+    pub decision: ApprovalAnswer,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SessionArgs {
+    pub session_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SpawnAgentArgs {
+    pub session_id: String,
+    pub role: String,
+    pub message: String,
+}
+
+// ---------------------------------------------------------------------------
+// Protocol helpers
+// ---------------------------------------------------------------------------
+
+/// Tool definitions returned from `tools/list`.
+pub fn tool_definitions() -> Vec<Value> {
+    let session_id = json!({
+        "type": "string",
+        "description": "Session workflow ID (e.g. codex-session-<uuid>)."
+    });
+    let agent_id = json!({
+        "type": "string",
+        "description": "Agent workflow ID within the session (default: <session_id>/main)."
+    });
+    vec![
+        json!({
+            "name": "start_session",
+            "description": "Start a new durable codex session with an initial message. Returns the session_id; poll progress with get_events.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "message": {"type": "string", "description": "First user message."},
+                    "model": {"type": "string", "description": "Model override."},
+                    "name": {"type": "string", "description": "Display name in the session list."}
+                },
+                "required": ["message"]
+            }
+        }),
+        json!({
+            "name": "send_message",
+            "description": "Send a follow-up user message to a running session.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "session_id": session_id,
+                    "message": {"type": "string"},
+                    "agent_id": agent_id
+                },
+                "required": ["session_id", "message"]
+            }
+        }),
+        json!({
+            "name": "get_events",
+            "description": "Return events after the `since` watermark, waiting briefly for new ones. Pass the returned watermark as `since` on the next call. Approval requests appear as exec_approval_request / apply_patch_approval_request events.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "session_id": session_id,
+                    "agent_id": agent_id,
+                    "since": {"type": "integer", "minimum": 0, "description": "Watermark from the previous call (default 0)."},
+                    "wait_seconds": {"type": "integer", "minimum": 0, "maximum": MAX_EVENT_WAIT_SECS, "description": "How long to wait for new events (default 10)."}
+                },
+                "required": ["session_id"]
+            }
+        }),
+        json!({
+            "name": "approve",
+            "description": "Answer a pending command (exec) or patch approval request.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "session_id": session_id,
+                    "call_id": {"type": "string", "description": "call_id from the approval request event."},
+                    "agent_id": agent_id,
+                    "kind": {"type": "string", "enum": ["exec", "patch"], "description": "Default: exec."},
+                    "decision": {"type": "string", "enum": ["approve", "approve_for_session", "deny", "abort"], "description": "Default: approve."}
+                },
+                "required": ["session_id", "call_id"]
+            }
+        }),
+        json!({
+            "name": "list_sessions",
+            "description": "List sessions known to the harness.",
+            "inputSchema": {"type": "object", "properties": {}}
+        }),
+        json!({
+            "name": "list_agents",
+            "description": "List the agents of a session.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {"session_id": session_id},
+                "required": ["session_id"]
+            }
+        }),
+        json!({
+            "name": "spawn_agent",
+            "description": "Spawn an additional role-based agent in a running session.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "session_id": session_id,
+                    "role": {"type": "string", "description": "Agent role or crew agent name."},
+                    "message": {"type": "string", "description": "Initial message for the agent."}
+                },
+                "required": ["session_id", "role", "message"]
+            }
+        }),
+    ]
+}
+
+/// Build a JSON-RPC success response.
+pub(crate) fn jsonrpc_result(id: Value, result: Value) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "result": result})
+}
+
+/// Build a JSON-RPC error response.
+pub(crate) fn jsonrpc_error(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message.into()}})
+}
+
+/// Wrap a tool outcome as an MCP `CallToolResult`.
+pub(crate) fn tool_result(outcome: Result<Value, String>) -> Value {
+    match outcome {
+        Ok(value) => json!({
+            "content": [{"type": "text", "text": value.to_string()}],
+            "structuredContent": value,
+            "isError": false
+        }),
+        Err(message) => json!({
+            "content": [{"type": "text", "text": message}],
+            "isError": true
+        }),
+    }
+}
+
+/// Deserialize tool arguments, mapping errors to a caller-facing message.
+pub(crate) fn parse_args<T: DeserializeOwned>(tool: &str, args: Value) -> Result<T, String> {
+    let args = if args.is_null() { json!({}) } else { args };
+    serde_json::from_value(args).map_err(|e| format!("invalid arguments for {tool}: {e}"))
+}
+
+/// Parse the JSON event strings of a state update into JSON values.
+pub(crate) fn events_to_json(events: &[String]) -> Vec<Value> {
+    events
+        .iter()
+        .filter_map(|s| serde_json::from_str::<Event>(s).ok())
+        .filter_map(|event| serde_json::to_value(event).ok())
+        .collect()
+}
+
+/// Handle the protocol methods that need no Temporal access.
+///
+/// Returns `None` for `tools/call` and unknown methods.
+pub(crate) fn handle_static_method(method: &str) -> Option<Value> {
+    match method {
+        "initialize" => Some(json!({
+            "protocolVersion": MCP_PROTOCOL_VERSION,
+            "capabilities": {"tools": {"listChanged": false}},
+            "serverInfo": {"name": "codex-temporal", "version": env!("CARGO_PKG_VERSION")}
+        })),
+        "ping" => Some(json!({})),
+        "tools/list" => Some(json!({"tools": tool_definitions()})),
+        _ => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Server
+// ---------------------------------------------------------------------------
+
+/// MCP server bound to a Temporal client.
+pub struct CodexMcpServer {
+    client: Client,
+    /// Template input for new sessions (from config.toml).
+    base_input: SessionWorkflowInput,
+    harness_workflow_id: String,
+}
+
+impl CodexMcpServer {
+    pub fn new(
+        client: Client,
+        base_input: SessionWorkflowInput,
+        harness_workflow_id: String,
+    ) -> Self {
+        Self {
+            client,
+            base_input,
+            harness_workflow_id,
+        }
+    }
+
+    /// Serve MCP over stdin/stdout until stdin closes.
+    pub async fn serve_stdio(self: Arc<Self>) -> anyhow::Result<()> {
+        let (tx, mut rx) = mpsc::channel::<Value>(64);
+
+        let writer = tokio::spawn(async move {
+            let mut stdout = tokio::io::stdout();
+            while let Some(message) = rx.recv().await {
+                let mut line = message.to_string();
+                line.push('\n');
+                if stdout.write_all(line.as_bytes()).await.is_err()
+                    || stdout.flush().await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let message: Value = match serde_json::from_str(&line) {
+                Ok(message) => message,
+                Err(e) => {
+                    let _ = tx
+                        .send(jsonrpc_error(Value::Null, PARSE_ERROR, format!("parse error: {e}")))
+                        .await;
+                    continue;
+                }
+            };
+            let server = Arc::clone(&self);
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                if let Some(response) = server.handle_message(message).await {
+                    let _ = tx.send(response).await;
+                }
+            });
+        }
+
+        drop(tx);
+        let _ = writer.await;
+        Ok(())
+    }
+
+    /// Handle one JSON-RPC message.  Returns `None` for notifications.
+    pub async fn handle_message(&self, message: Value) -> Option<Value> {
+        let method = message.get("method")?.as_str()?.to_string();
+        // Notifications (no id) never get a response.
+        let id = message.get("id").cloned()?;
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        if let Some(result) = handle_static_method(&method) {
+            return Some(jsonrpc_result(id, result));
+        }
+        if method != "tools/call" {
+            return Some(jsonrpc_error(
+                id,
+                METHOD_NOT_FOUND,
+                format!("method not found: {method}"),
+            ));
+        }
+
+        let Some(name) = params.get("name").and_then(Value::as_str) else {
+            return Some(jsonrpc_error(id, INVALID_PARAMS, "tools/call requires a tool name"));
+        };
+        let args = params.get("arguments").cloned().unwrap_or(Value::Null);
+        let outcome = self.call_tool(name, args).await;
+        Some(jsonrpc_result(id, tool_result(outcome)))
+    }
+
+    async fn call_tool(&self, name: &str, args: Value) -> Result<Value, String> {
+        match name {
+            "start_session" => self.start_session(parse_args(name, args)?).await,
+            "send_message" => self.send_message(parse_args(name, args)?).await,
+            "get_events" => self.get_events(parse_args(name, args)?).await,
+            "approve" => self.approve(parse_args(name, args)?).await,
+            "list_sessions" => self.list_sessions().await,
+            "list_agents" => self.list_agents(parse_args(name, args)?).await,
+            "spawn_agent" => self.spawn_agent(parse_args(name, args)?).await,
+            _ => Err(format!("unknown tool: {name}")),
+        }
+    }
+
+    /// Attach to a session (no watcher) targeting `agent_id`.
+    fn attach(&self, session_id: String, agent_id: Option<String>) -> TemporalAgentSession {
+        TemporalAgentSession::attach(
+            self.client.clone(),
+            session_id,
+            agent_id,
+            self.base_input.clone(),
+            Some(self.harness_workflow_id.clone()),
+        )
+    }
+
+    async fn start_session(&self, args: StartSessionArgs) -> Result<Value, String> {
+        let mut input = self.base_input.clone();
+        if let Some(model) = args.model {
+            input.model = model;
+        }
+        // The session registers itself with the harness when it starts.
+        input.harness_registration = Some(HarnessRegistration {
+            harness_id: self.harness_workflow_id.clone(),
+            name: args.name,
+            crew_type: None,
+            schedule_id: None,
+        });
+        let session_id = format!("codex-session-{}", uuid::Uuid::new_v4());
+
+        let session = TemporalAgentSession::new_with_harness(
+            self.client.clone(),
+            session_id.clone(),
+            input.clone(),
+            Some(self.harness_workflow_id.clone()),
+        );
+        let result = session.submit(user_turn_op(&input, args.message)).await;
+        // Callers poll with `get_events`; no background watcher needed.
+        session.detach();
+        result.map_err(|e| format!("failed to start session: {e}"))?;
+
+        Ok(json!({
+            "session_id": session_id,
+            "agent_id": format!("{session_id}/main"),
+        }))
+    }
+
+    async fn send_message(&self, args: SendMessageArgs) -> Result<Value, String> {
+        let session = self.attach(args.session_id, args.agent_id);
+        session
+            .submit(user_turn_op(&self.base_input, args.message))
+            .await
+            .map_err(|e| format!("failed to send message: {e}"))?;
+        Ok(json!({"agent_id": session.active_agent_id(), "status": "sent"}))
+    }
+
+    async fn get_events(&self, args: GetEventsArgs) -> Result<Value, String> {
+        let wait = args.wait();
+        let session = self.attach(args.session_id, args.agent_id);
+        let update = session
+            .events_since(args.since, wait)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(json!({
+            "events": events_to_json(&update.events),
+            "watermark": update.watermark,
+            "completed": update.completed,
+        }))
+    }
+
+    async fn approve(&self, args: ApproveArgs) -> Result<Value, String> {
+        let decision: ReviewDecision = args.decision.into();
+        let op = match args.kind {
+            ApprovalKind::Exec => Op::ExecApproval {
+                id: args.call_id.clone(),
+                turn_id: None,
+                decision,
+            },
+            ApprovalKind::Patch => Op::PatchApproval {
+                id: args.call_id.clone(),
+                decision,
+            },
+        };
+        let session = self.attach(args.session_id, args.agent_id);
+        session
+            .submit(op)
+            .await
+            .map_err(|e| format!("failed to send approval: {e}"))?;
+        Ok(json!({"call_id": args.call_id, "status": "sent"}))
+    }
+
+    async fn list_sessions(&self) -> Result<Value, String> {
+        let handle = self
+            .client
+            .get_workflow_handle::<CodexHarnessRun>(&self.harness_workflow_id);
+        let json: String = handle
+            .query(
+                CodexHarness::list_sessions,
+                (),
+                WorkflowQueryOptions::default(),
+            )
+            .await
+            .map_err(|e| format!("failed to query sessions: {e}"))?;
+        let sessions: Vec<SessionEntry> = serde_json::from_str(&json).unwrap_or_default();
+        serde_json::to_value(sessions).map_err(|e| e.to_string())
+    }
+
+    async fn list_agents(&self, args: SessionArgs) -> Result<Value, String> {
+        let handle = self
+            .client
+            .get_workflow_handle::<SessionWorkflowRun>(&args.session_id);
+        let json: String = handle
+            .query(
+                SessionWorkflow::list_agents,
+                (),
+                WorkflowQueryOptions::default(),
+            )
+            .await
+            .map_err(|e| format!("failed to query agents: {e}"))?;
+        serde_json::from_str(&json).map_err(|e| e.to_string())
+    }
+
+    async fn spawn_agent(&self, args: SpawnAgentArgs) -> Result<Value, String> {
+        let session = self.attach(args.session_id, None);
+        session
+            .spawn_agent(SpawnAgentInput {
+                role: args.role.clone(),
+                message: args.message,
+                requested_by: None,
+                request_id: None,
+                pipeline_task: None,
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(json!({"role": args.role, "status": "spawn requested"}))
+    }
+}
@@ -11,7 +11,7 @@ use std::path::{Path, PathBuf};
 use chrono::{DateTime, TimeZone, Utc};
 use codex_core::{ThreadItem, ThreadsPage};
 
-use crate::types::SessionEntry;
+use crate::types::{SessionEntry, sort_sessions_for_display};
 
 /// Scheme prefix used in synthetic `PathBuf` values to carry a Temporal
 /// session ID through the picker's `SessionSelection::Resume(PathBuf)`.
@@ -22,31 +22,36 @@ const TEMPORAL_SCHEME: &str = "temporal://";
 ///
 /// Each entry becomes a `ThreadItem` with:
 /// - `path` set to `temporal://<session_id>` (synthetic, for ID recovery)
-/// - `first_user_message` set from `SessionEntry.name`
-/// - `created_at` / `updated_at` as RFC-3339 strings from `created_at_millis`
+/// - `first_user_message` set from the title or name (plus its tags)
+/// - `cwd`, `git_branch` and `git_origin_url` from the session's workspace
+/// - `created_at` as an RFC-3339 string from `created_at_millis`, and
+///   `updated_at` from the latest activity (falling back to creation)
 ///
-/// The returned page has `next_cursor: None` (no pagination — the harness
+/// Pinned sessions come first, then the most recently active.  The
+/// returned page has `next_cursor: None` (no pagination — the harness
 /// returns all sessions at once).
-pub fn sessions_to_threads_page(sessions: Vec<SessionEntry>) -> ThreadsPage {
+pub fn sessions_to_threads_page(mut sessions: Vec<SessionEntry>) -> ThreadsPage {
+    sort_sessions_for_display(&mut sessions);
     let items: Vec<ThreadItem> = sessions
         .into_iter()
         .map(|entry| {
-            let ts = millis_to_rfc3339(entry.created_at_millis);
+            let created_at = millis_to_rfc3339(entry.created_at_millis);
+            let updated_at = millis_to_rfc3339(entry.last_active_millis());
             ThreadItem {
                 path: PathBuf::from(format!("{TEMPORAL_SCHEME}{}", entry.session_id)),
                 thread_id: None,
-                first_user_message: entry.name.or(Some(entry.session_id.clone())),
-                cwd: None,
-                git_branch: None,
+                first_user_message: Some(picker_label(&entry)),
+                cwd: entry.cwd.map(PathBuf::from),
+                git_branch: entry.branch,
                 git_sha: None,
-                git_origin_url: None,
+                git_origin_url: entry.repository,
                 source: None,
                 agent_nickname: None,
                 agent_role: None,
                 model_provider: None,
                 cli_version: None,
-                created_at: Some(ts.clone()),
-                updated_at: Some(ts),
+                created_at: Some(created_at),
+                updated_at: Some(updated_at),
             }
         })
         .collect();
@@ -58,6 +63,20 @@ pub fn sessions_to_threads_page(sessions: Vec<SessionEntry>) -> ThreadsPage {
     }
 }
 
+/// Label of a session in the picker: its title or name (the session ID
+/// without either), a pin marker and its tags.
+pub fn picker_label(entry: &SessionEntry) -> String {
+    let mut label = entry.display_name().unwrap_or(&entry.session_id).to_string();
+    if entry.pinned {
+        label.push_str(" (pinned)");
+    }
+    if !entry.tags.is_empty() {
+        let tags: Vec<String> = entry.tags.iter().map(|t| format!("#{t}")).collect();
+        label.push_str(&format!(" [{}]", tags.join(" ")));
+    }
+    label
+}
+
 /// Convert Unix-millisecond timestamp to an RFC-3339 string.
 pub fn millis_to_rfc3339(millis: u64) -> String {
     let secs = (millis / 1000) as i64;
@@ -90,6 +109,16 @@ mod tests {
                 created_at_millis: 1_700_000_000_000,
                 status: SessionStatus::Running,
                 crew_type: None,
+                schedule_id: None,
+                last_activity_millis: 1_700_000_120_000,
+                last_message_preview: None,
+                pinned: false,
+                archived_at_millis: None,
+                title: None,
+                tags: Default::default(),
+                cwd: None,
+                repository: None,
+                branch: None,
             },
             SessionEntry {
                 session_id: "sess-2".to_string(),
@@ -98,6 +127,16 @@ mod tests {
                 created_at_millis: 1_700_000_060_000,
                 status: SessionStatus::Running,
                 crew_type: None,
+                schedule_id: None,
+                last_activity_millis: 0,
+                last_message_preview: None,
+                pinned: false,
+                archived_at_millis: None,
+                title: None,
+                tags: Default::default(),
+                cwd: None,
+                repository: None,
+                branch: None,
             },
         ];
         let page = sessions_to_threads_page(entries);
@@ -114,6 +153,10 @@ mod tests {
             PathBuf::from("temporal://sess-1")
         );
 
+        // updated_at follows the latest activity, or the creation time.
+        assert!(page.items[0].updated_at.as_deref().unwrap().starts_with("2023-11-14T22:15:20"));
+        assert_eq!(page.items[1].updated_at, page.items[1].created_at);
+
         // Second entry: unnamed → falls back to session_id
         assert_eq!(
             page.items[1].first_user_message.as_deref(),
@@ -121,6 +164,55 @@ mod tests {
         );
     }
 
+    #[test]
+    fn sessions_to_threads_page_orders_and_labels() {
+        let entry = |id: &str, last_activity_millis: u64| SessionEntry {
+            session_id: id.to_string(),
+            name: Some(format!("{id} prompt")),
+            model: "gpt-4o".to_string(),
+            created_at_millis: 1_700_000_000_000,
+            status: SessionStatus::Completed,
+            crew_type: None,
+            schedule_id: None,
+            last_activity_millis,
+            last_message_preview: None,
+            pinned: false,
+            archived_at_millis: None,
+            title: None,
+            tags: Default::default(),
+            cwd: None,
+            repository: None,
+            branch: None,
+        };
+        let older = entry("older", 1_700_000_100_000);
+        let newer = entry("newer", 1_700_000_200_000);
+        let mut pinned = entry("pinned", 0);
+        pinned.pinned = true;
+        pinned.title = Some("Release prep".to_string());
+        pinned.tags = ["release".to_string(), "ci".to_string()].into();
+        pinned.cwd = Some("/work/app".to_string());
+        pinned.repository = Some("https://github.com/acme/app".to_string());
+        pinned.branch = Some("main".to_string());
+
+        let page = sessions_to_threads_page(vec![older, pinned, newer]);
+        let ids: Vec<String> = page
+            .items
+            .iter()
+            .filter_map(|item| extract_session_id_from_path(&item.path))
+            .collect();
+        assert_eq!(ids, vec!["pinned", "newer", "older"]);
+
+        let first = &page.items[0];
+        assert_eq!(
+            first.first_user_message.as_deref(),
+            Some("Release prep (pinned) [#ci #release]")
+        );
+        assert_eq!(first.cwd, Some(PathBuf::from("/work/app")));
+        assert_eq!(first.git_branch.as_deref(), Some("main"));
+        assert_eq!(first.git_origin_url.as_deref(), Some("https://github.com/acme/app"));
+        assert_eq!(page.items[1].first_user_message.as_deref(), Some("newer prompt"));
+    }
+
     #[test]
     fn extract_session_id_roundtrip() {
         let path = PathBuf::from("temporal://my-workflow-id");
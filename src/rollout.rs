@@ -0,0 +1,167 @@
+//! Codex CLI rollout files (`~/.codex/sessions/**/rollout-*.jsonl`).
+//!
+//! Each line of a rollout file is a JSON object holding a `timestamp` and a
+//! `RolloutItem` (`{"type": ..., "payload": ...}`).  [`parse_rollout_jsonl`]
+//! reads one for `codex-temporal-client import`, which restores the
+//! conversation into the main agent of a new durable session;
+//! [`rollout_lines`] writes an agent's items back out for
+//! `codex-temporal-client export`.
+
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::{EventMsg, RolloutItem};
+use serde::Serialize;
+
+use crate::history::{history_from_rollout, is_context_message, message_text, user_message_event};
+use crate::types::ExportRequest;
+
+/// `originator` recorded in the `session_meta` line of exported rollouts.
+pub const EXPORT_ORIGINATOR: &str = "codex_temporal";
+
+/// Largest serialized workflow input an import starts a session with;
+/// Temporal rejects payloads above its blob size limit (2 MiB by default).
+pub const MAX_START_PAYLOAD_BYTES: usize = 2 * 1024 * 1024;
+
+/// A conversation read from a codex rollout file.
+#[derive(Debug, Clone, Default)]
+pub struct ImportedRollout {
+    /// Conversation items, in order (session metadata and per-turn context
+    /// lines are dropped).
+    pub items: Vec<RolloutItem>,
+    /// ID of the original codex session (from `session_meta`).
+    pub source_id: Option<String>,
+    /// Model of the latest turn (from `turn_context`).
+    pub model: Option<String>,
+    /// Lines with item types this build does not know (skipped).
+    pub skipped: usize,
+}
+
+impl ImportedRollout {
+    /// Text of the first user message, e.g. to name the imported session.
+    pub fn first_user_message(&self) -> Option<String> {
+        history_from_rollout(&self.items, "")
+            .into_iter()
+            .find_map(|msg| match msg {
+                EventMsg::UserMessage(e) => Some(e.message),
+                _ => None,
+            })
+    }
+}
+
+/// Parse the contents of a codex rollout JSONL file.
+///
+/// Blank lines are ignored and lines with unknown item types are counted in
+/// [`ImportedRollout::skipped`]; a line that is not a JSON object is an
+/// error, as is a file without any conversation items.
+pub fn parse_rollout_jsonl(text: &str) -> Result<ImportedRollout, String> {
+    let mut rollout = ImportedRollout::default();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut value: serde_json::Value = serde_json::from_str(line)
+            .map_err(|e| format!("line {}: invalid JSON: {e}", index + 1))?;
+        let Some(object) = value.as_object_mut() else {
+            return Err(format!("line {}: expected a JSON object", index + 1));
+        };
+        object.remove("timestamp");
+
+        let item_type = object.get("type").and_then(|t| t.as_str()).map(String::from);
+        let payload_field = |name: &str| {
+            object
+                .get("payload")
+                .and_then(|p| p.get(name))
+                .and_then(|v| v.as_str())
+                .map(String::from)
+        };
+        match item_type.as_deref() {
+            Some("session_meta") => {
+                rollout.source_id = payload_field("id");
+            }
+            Some("turn_context") => {
+                rollout.model = payload_field("model").or(rollout.model.take());
+            }
+            _ => match serde_json::from_value::<RolloutItem>(value) {
+                Ok(item) => rollout.items.push(item),
+                Err(_) => rollout.skipped += 1,
+            },
+        }
+    }
+
+    if rollout.items.is_empty() {
+        return Err("no conversation items found in the rollout".to_string());
+    }
+    Ok(rollout)
+}
+
+/// Check that `input` fits in a workflow start payload, so an oversized
+/// import fails with a clear error instead of a rejected start request.
+pub fn check_start_payload<T: Serialize>(input: &T) -> Result<(), String> {
+    let size = serde_json::to_vec(input)
+        .map_err(|e| format!("failed to serialize the session input: {e}"))?
+        .len();
+    if size > MAX_START_PAYLOAD_BYTES {
+        return Err(format!(
+            "the imported conversation is too large to start a session: {size} bytes \
+             (limit {MAX_START_PAYLOAD_BYTES}); import a shorter rollout"
+        ));
+    }
+    Ok(())
+}
+
+/// One line of a rollout file.
+#[derive(Serialize)]
+struct RolloutLine<'a> {
+    timestamp: &'a str,
+    #[serde(flatten)]
+    item: &'a RolloutItem,
+}
+
+/// Write an agent's rollout items as the lines of a codex rollout file.
+///
+/// The file starts with a `session_meta` line for `req.conversation_id`
+/// run in `cwd`, and every line is stamped with `req.timestamp`.  The codex
+/// CLI lists a rollout only once it has `user_message` events, so when the
+/// items carry none (conversations recorded by the workflow), one is added
+/// after each user message.
+pub fn rollout_lines(items: &[RolloutItem], req: &ExportRequest, cwd: &str) -> Vec<String> {
+    let timestamp = req.timestamp.as_str();
+    let meta = serde_json::json!({
+        "timestamp": timestamp,
+        "type": "session_meta",
+        "payload": {
+            "id": req.conversation_id,
+            "timestamp": timestamp,
+            "cwd": cwd,
+            "originator": EXPORT_ORIGINATOR,
+            "cli_version": env!("CARGO_PKG_VERSION"),
+        },
+    });
+    let persisted_messages = items
+        .iter()
+        .any(|item| matches!(item, RolloutItem::EventMsg(EventMsg::UserMessage(_))));
+
+    let mut lines = vec![meta.to_string()];
+    let mut push = |item: &RolloutItem| {
+        if let Ok(line) = serde_json::to_string(&RolloutLine { timestamp, item }) {
+            lines.push(line);
+        }
+    };
+    for item in items {
+        push(item);
+        if persisted_messages {
+            continue;
+        }
+        if let RolloutItem::ResponseItem(ResponseItem::Message { role, content, .. }) = item
+            && role == "user"
+        {
+            let text = message_text(content);
+            if !text.trim().is_empty()
+                && !is_context_message(&text)
+                && let Some(event) = user_message_event(text)
+            {
+                push(&RolloutItem::EventMsg(event));
+            }
+        }
+    }
+    lines
+}
@@ -0,0 +1,297 @@
+//! Temporal Schedules that start crew sessions.
+//!
+//! A crew schedule is a Temporal Schedule whose action starts a
+//! `SessionWorkflow` with a crew type (and fixed crew inputs) already
+//! applied.  Each run registers itself with `CodexHarness` via
+//! [`HarnessRegistration`], carrying the schedule ID as a link back.
+//! Crew schedules are tagged with the [`CREW_MEMO_KEY`] memo so `list`
+//! only shows schedules created here.
+//!
+//! The Rust client has no high-level schedule API, so this module talks to
+//! the `WorkflowService` schedule RPCs directly.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Serialize;
+use temporalio_client::tonic::IntoRequest;
+use temporalio_client::{Client, WorkflowService};
+use temporalio_common::protos::coresdk::{AsJsonPayloadExt, FromJsonPayloadExt};
+use temporalio_common::protos::temporal::api::common::v1::{Memo, Payloads, WorkflowType};
+use temporalio_common::protos::temporal::api::enums::v1::ScheduleOverlapPolicy;
+use temporalio_common::protos::temporal::api::schedule::v1::{
+    IntervalSpec, Schedule, ScheduleAction, SchedulePatch, SchedulePolicies, ScheduleSpec,
+    ScheduleState, schedule_action,
+};
+use temporalio_common::protos::temporal::api::taskqueue::v1::TaskQueue;
+use temporalio_common::protos::temporal::api::workflow::v1::NewWorkflowExecutionInfo;
+use temporalio_common::protos::temporal::api::workflowservice::v1::{
+    CreateScheduleRequest, DeleteScheduleRequest, ListSchedulesRequest, PatchScheduleRequest,
+};
+
+use crate::TASK_QUEUE;
+use crate::types::{HarnessRegistration, SessionWorkflowInput};
+
+/// Memo key marking a schedule as a crew schedule (value: crew name).
+pub const CREW_MEMO_KEY: &str = "codex-crew";
+
+/// When a crew schedule fires: cron expressions and/or fixed intervals.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CrewScheduleSpec {
+    /// Cron expressions, e.g. `"0 6 * * *"`.
+    pub cron: Vec<String>,
+    /// Fixed intervals between runs.
+    pub every: Vec<Duration>,
+}
+
+impl CrewScheduleSpec {
+    /// Build a spec from `--cron <expr>` and `--every <interval>` values.
+    pub fn from_flags(cron: Vec<String>, every: &[String]) -> Result<Self, String> {
+        let every = every
+            .iter()
+            .map(|value| parse_interval(value))
+            .collect::<Result<Vec<_>, _>>()?;
+        if cron.is_empty() && every.is_empty() {
+            return Err("a schedule needs at least one --cron or --every".to_string());
+        }
+        Ok(Self { cron, every })
+    }
+}
+
+/// A crew schedule as shown by `list`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CrewScheduleSummary {
+    pub schedule_id: String,
+    /// Crew type the schedule starts.
+    pub crew: String,
+    pub paused: bool,
+    /// Note recorded with the last pause/unpause.
+    pub notes: String,
+    /// Next planned run (Unix millis).
+    pub next_run_millis: Option<u64>,
+    /// Workflow IDs of the most recent runs, oldest first.
+    pub recent_sessions: Vec<String>,
+}
+
+/// Parse an interval such as `90s`, `15m`, `6h`, `1d` or `1h30m`.
+pub fn parse_interval(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid interval '{value}' (expected e.g. 30m, 6h, 1d or 1h30m)");
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in value.trim().chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let amount: u64 = digits.parse().map_err(|_| invalid())?;
+        total = amount
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(invalid)?;
+        digits.clear();
+    }
+    if !digits.is_empty() || total == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// Workflow ID prefix of a schedule's runs; Temporal appends the scheduled
+/// time to it.
+pub fn schedule_workflow_id(schedule_id: &str) -> String {
+    format!("codex-session-{schedule_id}")
+}
+
+/// Prepare a crew session input for a schedule: each run registers itself
+/// with the harness, linked to the schedule.
+pub fn scheduled_session_input(
+    mut input: SessionWorkflowInput,
+    schedule_id: &str,
+    crew: &str,
+    harness_id: &str,
+) -> SessionWorkflowInput {
+    input.harness_registration = Some(HarnessRegistration {
+        harness_id: harness_id.to_string(),
+        name: Some(format!("{crew} (schedule {schedule_id})")),
+        crew_type: Some(crew.to_string()),
+        schedule_id: Some(schedule_id.to_string()),
+    });
+    input
+}
+
+/// Create a schedule that starts `input` as a new `SessionWorkflow`.
+/// Overlapping runs are skipped.
+pub async fn create_crew_schedule(
+    client: &Client,
+    namespace: &str,
+    schedule_id: &str,
+    crew: &str,
+    spec: &CrewScheduleSpec,
+    input: &SessionWorkflowInput,
+    paused: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let interval = spec
+        .every
+        .iter()
+        .map(|every| {
+            Ok(IntervalSpec {
+                interval: Some((*every).try_into().map_err(|e| format!("{e:?}"))?),
+                phase: None,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let start_workflow = NewWorkflowExecutionInfo {
+        workflow_id: schedule_workflow_id(schedule_id),
+        workflow_type: Some(WorkflowType {
+            name: "SessionWorkflow".to_string(),
+        }),
+        task_queue: Some(TaskQueue {
+            name: TASK_QUEUE.to_string(),
+            ..Default::default()
+        }),
+        input: Some(Payloads {
+            payloads: vec![input.as_json_payload()?],
+        }),
+        ..Default::default()
+    };
+
+    let schedule = Schedule {
+        spec: Some(ScheduleSpec {
+            cron_string: spec.cron.clone(),
+            interval,
+            ..Default::default()
+        }),
+        action: Some(ScheduleAction {
+            action: Some(schedule_action::Action::StartWorkflow(start_workflow)),
+        }),
+        policies: Some(SchedulePolicies {
+            overlap_policy: ScheduleOverlapPolicy::Skip as i32,
+            ..Default::default()
+        }),
+        state: Some(ScheduleState {
+            paused,
+            notes: if paused { "created paused".to_string() } else { String::new() },
+            ..Default::default()
+        }),
+    };
+
+    let memo = Memo {
+        fields: HashMap::from([(CREW_MEMO_KEY.to_string(), crew.to_string().as_json_payload()?)]),
+    };
+    let request = CreateScheduleRequest {
+        namespace: namespace.to_string(),
+        schedule_id: schedule_id.to_string(),
+        schedule: Some(schedule),
+        memo: Some(memo),
+        request_id: uuid::Uuid::new_v4().to_string(),
+        ..Default::default()
+    };
+    WorkflowService::create_schedule(&mut client.clone(), request.into_request()).await?;
+    Ok(())
+}
+
+/// List the crew schedules of `namespace`.
+pub async fn list_crew_schedules(
+    client: &Client,
+    namespace: &str,
+) -> Result<Vec<CrewScheduleSummary>, Box<dyn std::error::Error>> {
+    let mut summaries = Vec::new();
+    let mut next_page_token = Vec::new();
+    loop {
+        let request = ListSchedulesRequest {
+            namespace: namespace.to_string(),
+            maximum_page_size: 100,
+            next_page_token,
+            ..Default::default()
+        };
+        let response = WorkflowService::list_schedules(&mut client.clone(), request.into_request())
+            .await?
+            .into_inner();
+
+        for entry in response.schedules {
+            let Some(crew) = entry
+                .memo
+                .as_ref()
+                .and_then(|memo| memo.fields.get(CREW_MEMO_KEY))
+                .and_then(|payload| String::from_json_payload(payload).ok())
+            else {
+                continue;
+            };
+            let info = entry.info.unwrap_or_default();
+            summaries.push(CrewScheduleSummary {
+                schedule_id: entry.schedule_id,
+                crew,
+                paused: info.paused,
+                notes: info.notes,
+                next_run_millis: info.future_action_times.first().map(|t| {
+                    (t.seconds.max(0) as u64) * 1000 + (t.nanos.max(0) as u64) / 1_000_000
+                }),
+                recent_sessions: info
+                    .recent_actions
+                    .iter()
+                    .filter_map(|action| action.start_workflow_result.as_ref())
+                    .map(|execution| execution.workflow_id.clone())
+                    .collect(),
+            });
+        }
+
+        if response.next_page_token.is_empty() {
+            break;
+        }
+        next_page_token = response.next_page_token;
+    }
+    Ok(summaries)
+}
+
+/// Pause or unpause a schedule, recording `note`.
+pub async fn set_schedule_paused(
+    client: &Client,
+    namespace: &str,
+    schedule_id: &str,
+    paused: bool,
+    note: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let patch = if paused {
+        SchedulePatch {
+            pause: note.to_string(),
+            ..Default::default()
+        }
+    } else {
+        SchedulePatch {
+            unpause: note.to_string(),
+            ..Default::default()
+        }
+    };
+    let request = PatchScheduleRequest {
+        namespace: namespace.to_string(),
+        schedule_id: schedule_id.to_string(),
+        patch: Some(patch),
+        request_id: uuid::Uuid::new_v4().to_string(),
+        ..Default::default()
+    };
+    WorkflowService::patch_schedule(&mut client.clone(), request.into_request()).await?;
+    Ok(())
+}
+
+/// Delete a schedule.  Sessions it already started keep running.
+pub async fn delete_schedule(
+    client: &Client,
+    namespace: &str,
+    schedule_id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let request = DeleteScheduleRequest {
+        namespace: namespace.to_string(),
+        schedule_id: schedule_id.to_string(),
+        ..Default::default()
+    };
+    WorkflowService::delete_schedule(&mut client.clone(), request.into_request()).await?;
+    Ok(())
+}
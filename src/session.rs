@@ -10,11 +10,15 @@
 //! | Op variant        | Temporal action                                      |
 //! |-------------------|------------------------------------------------------|
 //! | `UserTurn`        | start SessionWorkflow (first) or signal AgentWorkflow |
+//! |                   | (restarts a closed session, see `reopen_if_closed`)   |
 //! | `Shutdown`        | signal both SessionWorkflow and AgentWorkflow         |
 //! | all other Ops     | signal active AgentWorkflow                          |
 //!
 //! Events are received via a background [`Watcher`] that calls the blocking
-//! `get_state_update` update handler in a loop.
+//! `get_state_update` update handler in a loop.  With
+//! [`follow_other_agents`](TemporalAgentSession::follow_other_agents), the
+//! events of the session's other agents are merged in as agent-tagged
+//! background events and tracked in a per-agent [`SessionTimeline`].
 
 use std::sync::{Arc, Mutex};
 
@@ -35,19 +39,36 @@ use codex_protocol::protocol::{
     BackgroundEventEvent, Event, EventMsg, Op, SandboxPolicy, SessionConfiguredEvent,
     TurnAbortReason, TurnAbortedEvent,
 };
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::user_input::UserInput;
+use temporalio_client::tonic::{self, IntoRequest};
 use temporalio_client::{
-    Client, WorkflowExecuteUpdateOptions, WorkflowQueryOptions, WorkflowSignalOptions,
-    WorkflowStartOptions,
+    Client, WorkflowExecuteUpdateOptions, WorkflowQueryOptions, WorkflowService,
+    WorkflowSignalOptions, WorkflowStartOptions,
+};
+use temporalio_common::protos::coresdk::FromJsonPayloadExt;
+use temporalio_common::protos::temporal::api::common::v1::WorkflowExecution;
+use temporalio_common::protos::temporal::api::enums::v1::WorkflowExecutionStatus;
+use temporalio_common::protos::temporal::api::history::v1::history_event;
+use temporalio_common::protos::temporal::api::workflowservice::v1::{
+    DescribeWorkflowExecutionRequest, GetWorkflowExecutionHistoryRequest,
 };
 use tokio_util::sync::CancellationToken;
 
-use crate::harness::{CodexHarness, CodexHarnessRun};
+use crate::TASK_QUEUE;
+use crate::harness::{self, CodexHarness, CodexHarnessRun};
 use crate::session_workflow::{SessionWorkflow, SessionWorkflowRun};
-use crate::types::{SessionWorkflowInput, SpawnAgentInput, StateUpdateRequest, extract_message};
-use crate::watcher::{Watcher, WatcherEvent};
+use crate::types::{
+    AgentControlAction, AgentControlRequest, AgentRecord, AgentStatusLine, AgentWorkflowInput,
+    ApprovalDecisionRequest, ContinueAsNewState, HarnessRegistration, HistoryPage,
+    HistoryRequest, McpRefreshRequest, RolloutPage, SessionApproval, SessionTimeline,
+    SessionWorkflowInput, SpawnAgentInput, StateUpdateRequest, StateUpdateResponse,
+    extract_message,
+};
+use crate::watcher::{Watcher, WatcherEvent, watch_session};
 use crate::workflow::{AgentWorkflow, AgentWorkflowRun};
 
-const TASK_QUEUE: &str = "codex-temporal";
+const DEFAULT_NAMESPACE: &str = "default";
 
 /// An [`AgentSession`] that backs the TUI with a Temporal workflow.
 ///
@@ -78,6 +99,21 @@ pub struct TemporalAgentSession {
     submit_cancel: Mutex<Option<CancellationToken>>,
     /// Workflow ID of the CodexHarness (for querying session list).
     harness_workflow_id: String,
+    /// Merge the events of the session's other agents into the buffer.
+    follow_agents: Mutex<bool>,
+    /// Handle of the session-wide watcher used while following agents.
+    follow_handle: Mutex<Option<tokio::task::JoinHandle<()>>>,
+    /// Cancels the session-wide watcher's discovery loop and per-agent
+    /// watchers.
+    follow_cancel: Mutex<Option<CancellationToken>>,
+    /// Per-agent watermarks and status of the followed session.
+    timeline: Arc<Mutex<SessionTimeline>>,
+    /// Temporal namespace (for describing and reading closed workflows).
+    namespace: String,
+    /// Conversation of a closed session, restored into the main agent when
+    /// the next user turn starts a new run (see
+    /// [`reopen_if_closed`](Self::reopen_if_closed)).
+    reopen_state: Mutex<Option<ContinueAsNewState>>,
 }
 
 impl TemporalAgentSession {
@@ -105,7 +141,7 @@ impl TemporalAgentSession {
     ) -> Self {
         let base_input = base_input.into();
         let agent_workflow_id = format!("{workflow_id}/main");
-        let harness_workflow_id = harness_id.unwrap_or_else(derive_harness_workflow_id);
+        let harness_workflow_id = harness_id.unwrap_or_else(harness::harness_workflow_id);
         Self {
             client,
             session_workflow_id: Mutex::new(workflow_id),
@@ -119,6 +155,12 @@ impl TemporalAgentSession {
             watch_handle: Mutex::new(None),
             submit_cancel: Mutex::new(None),
             harness_workflow_id,
+            follow_agents: Mutex::new(false),
+            follow_handle: Mutex::new(None),
+            follow_cancel: Mutex::new(None),
+            timeline: Arc::new(Mutex::new(SessionTimeline::new())),
+            namespace: DEFAULT_NAMESPACE.to_string(),
+            reopen_state: Mutex::new(None),
         }
     }
 
@@ -142,11 +184,43 @@ impl TemporalAgentSession {
         session_id: String,
         base_input: impl Into<SessionWorkflowInput>,
         harness_id: Option<String>,
+    ) -> Self {
+        Self::resume_from(client, session_id, base_input, harness_id, 0)
+    }
+
+    /// [`resume_with_harness`](Self::resume_with_harness), following the
+    /// main agent's events from watermark `since_index` instead of
+    /// replaying them from the first one.
+    pub fn resume_from(
+        client: Client,
+        session_id: String,
+        base_input: impl Into<SessionWorkflowInput>,
+        harness_id: Option<String>,
+        since_index: usize,
+    ) -> Self {
+        let session = Self::attach(client, session_id, None, base_input, harness_id);
+        session.start_watching_from(since_index);
+        session
+    }
+
+    /// Attach to a running session without starting the background watcher.
+    ///
+    /// For request/response callers (e.g. the MCP server) that send ops via
+    /// `submit` and read events with [`events_since`](Self::events_since)
+    /// instead of `next_event`.  `agent_workflow_id` selects the agent ops
+    /// are sent to (default: the session's main agent).
+    pub fn attach(
+        client: Client,
+        session_id: String,
+        agent_workflow_id: Option<String>,
+        base_input: impl Into<SessionWorkflowInput>,
+        harness_id: Option<String>,
     ) -> Self {
         let base_input = base_input.into();
-        let agent_workflow_id = format!("{session_id}/main");
-        let harness_workflow_id = harness_id.unwrap_or_else(derive_harness_workflow_id);
-        let session = Self {
+        let agent_workflow_id =
+            agent_workflow_id.unwrap_or_else(|| format!("{session_id}/main"));
+        let harness_workflow_id = harness_id.unwrap_or_else(harness::harness_workflow_id);
+        Self {
             client,
             session_workflow_id: Mutex::new(session_id),
             active_agent_workflow_id: Mutex::new(agent_workflow_id),
@@ -159,9 +233,19 @@ impl TemporalAgentSession {
             watch_handle: Mutex::new(None),
             submit_cancel: Mutex::new(None),
             harness_workflow_id,
-        };
-        session.start_watching();
-        session
+            follow_agents: Mutex::new(false),
+            follow_handle: Mutex::new(None),
+            follow_cancel: Mutex::new(None),
+            timeline: Arc::new(Mutex::new(SessionTimeline::new())),
+            namespace: DEFAULT_NAMESPACE.to_string(),
+            reopen_state: Mutex::new(None),
+        }
+    }
+
+    /// Use the Temporal namespace `namespace` (default `"default"`).
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
     }
 
     /// Return the session workflow ID.
@@ -172,6 +256,11 @@ impl TemporalAgentSession {
             .clone()
     }
 
+    /// Session-level input used for new runs and user turns.
+    pub fn base_input(&self) -> &SessionWorkflowInput {
+        &self.base_input
+    }
+
     /// Build a `SessionConfiguredEvent` from the given input.
     ///
     /// The Temporal workflow does not emit this event itself; instead the
@@ -236,12 +325,19 @@ impl TemporalAgentSession {
         *self.active_agent_workflow_id.get() =
             format!("{new_session_id}/main");
         self.event_buffer.get().clear();
+        *self.timeline.get() = SessionTimeline::new();
         *self.started.get() = true;
         self.start_watching();
     }
 
     /// Start the background watcher for the active agent workflow.
     fn start_watching(&self) {
+        self.start_watching_from(0);
+    }
+
+    /// Start the background watcher for the active agent workflow, after
+    /// watermark `since_index`.
+    fn start_watching_from(&self, since_index: usize) {
         self.stop_watching();
         let client = self.client.clone();
         let workflow_id = self.active_agent_id();
@@ -255,20 +351,133 @@ impl TemporalAgentSession {
             let (tx, mut rx) = tokio::sync::mpsc::channel(64);
 
             tokio::spawn(async move {
-                watcher.run_watching(tx).await;
+                watcher.run_watching_from(since_index, tx).await;
             });
 
             drain_watcher_into_buffer(&mut rx, &buffer, &notify, &generation, gen_at_start).await;
         });
 
         *self.watch_handle.get() = Some(handle);
+
+        if *self.follow_agents.get() {
+            self.start_following();
+        }
+    }
+
+    /// Merge the events of the session's other agents into this session's
+    /// event stream (as background events tagged with the agent, e.g.
+    /// `[worker-2] runs: cargo test`) and track every agent's status for
+    /// [`agent_status_panel`](Self::agent_status_panel).
+    pub fn follow_other_agents(&self, enabled: bool) {
+        *self.follow_agents.get() = enabled;
+        if enabled {
+            self.start_following();
+        } else {
+            self.stop_following();
+        }
+    }
+
+    /// Start the session-wide watcher.  Agents resume from the timeline's
+    /// watermarks, so restarts (e.g. on `switch_agent`) repeat nothing.
+    fn start_following(&self) {
+        self.stop_following();
+        let active_agent_id = self.active_agent_id();
+        let watermarks = self.timeline.get().watermarks();
+        let cancel = CancellationToken::new();
+        let mut rx =
+            watch_session(self.client.clone(), self.session_id(), watermarks, cancel.clone());
+        let buffer = Arc::clone(&self.event_buffer);
+        let notify = Arc::clone(&self.event_notify);
+        let timeline = Arc::clone(&self.timeline);
+        let generation = Arc::clone(&self.generation);
+        let gen_at_start = *generation.get();
+
+        let handle = tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                if *generation.get() != gen_at_start {
+                    return;
+                }
+                if !timeline.get().record(&event) || event.agent_id == active_agent_id {
+                    continue;
+                }
+                if let Some(message) = event.timeline_line() {
+                    buffer.get().push(Event {
+                        id: String::new(),
+                        msg: EventMsg::BackgroundEvent(BackgroundEventEvent { message }),
+                    });
+                    notify.notify_one();
+                }
+            }
+        });
+        *self.follow_handle.get() = Some(handle);
+        *self.follow_cancel.get() = Some(cancel);
+    }
+
+    /// Stop the session-wide watcher, including its per-agent watchers.
+    fn stop_following(&self) {
+        if let Some(cancel) = self.follow_cancel.get().take() {
+            cancel.cancel();
+        }
+        if let Some(handle) = self.follow_handle.get().take() {
+            handle.abort();
+        }
+    }
+
+    /// Status of every agent seen while following the session (see
+    /// [`follow_other_agents`](Self::follow_other_agents)).
+    pub fn agent_status_panel(&self) -> Vec<AgentStatusLine> {
+        self.timeline.get().status_panel()
+    }
+
+    /// Stop watching events; the workflows keep running and can be
+    /// re-attached later.
+    pub fn detach(&self) {
+        self.stop_watching();
     }
 
-    /// Stop the background watcher.
+    /// Stop the background watcher (and the session-wide one, if any).
     fn stop_watching(&self) {
         if let Some(handle) = self.watch_handle.get().take() {
             handle.abort();
         }
+        self.stop_following();
+    }
+
+    /// Query the SessionWorkflow for its agents and their status.
+    pub async fn list_agents(&self) -> CodexResult<Vec<AgentRecord>> {
+        let session_id = self.session_id();
+        let handle = self
+            .client
+            .get_workflow_handle::<SessionWorkflowRun>(&session_id);
+
+        let json: String = handle
+            .query(
+                SessionWorkflow::list_agents,
+                (),
+                WorkflowQueryOptions::default(),
+            )
+            .await
+            .map_err(|e| CodexErr::Fatal(format!("failed to query agents: {e}")))?;
+
+        Ok(serde_json::from_str(&json).unwrap_or_default())
+    }
+
+    /// Whether turn `turn_id` of the active agent stopped at its iteration
+    /// budget (`max_iterations`).
+    pub async fn turn_budget_exhausted(&self, turn_id: &str) -> CodexResult<bool> {
+        let agent_id = self.active_agent_id();
+        let handle = self
+            .client
+            .get_workflow_handle::<AgentWorkflowRun>(&agent_id);
+
+        handle
+            .query(
+                AgentWorkflow::turn_budget_exhausted,
+                turn_id.to_string(),
+                WorkflowQueryOptions::default(),
+            )
+            .await
+            .map_err(|e| CodexErr::Fatal(format!("failed to query turn {turn_id}: {e}")))
     }
 
     /// Query the harness for the list of known sessions.
@@ -310,6 +519,107 @@ impl TemporalAgentSession {
         Ok(())
     }
 
+    /// Signal the SessionWorkflow to re-run MCP discovery (optionally
+    /// enabling/disabling servers) and push the new tools to every agent.
+    pub async fn refresh_mcp(&self, request: McpRefreshRequest) -> CodexResult<()> {
+        let session_id = self.session_id();
+        let handle = self
+            .client
+            .get_workflow_handle::<SessionWorkflowRun>(&session_id);
+
+        handle
+            .signal(
+                SessionWorkflow::refresh_mcp,
+                request,
+                WorkflowSignalOptions::default(),
+            )
+            .await
+            .map_err(|e| CodexErr::Fatal(format!("failed to signal refresh_mcp: {e}")))?;
+
+        Ok(())
+    }
+
+    /// Signal the SessionWorkflow to pause, resume, cancel or terminate one
+    /// of its agents (full or short agent ID).
+    pub async fn control_agent(
+        &self,
+        agent_id: impl Into<String>,
+        action: AgentControlAction,
+    ) -> CodexResult<()> {
+        let session_id = self.session_id();
+        let handle = self
+            .client
+            .get_workflow_handle::<SessionWorkflowRun>(&session_id);
+
+        let request = AgentControlRequest {
+            agent_id: agent_id.into(),
+            action,
+        };
+        handle
+            .signal(
+                SessionWorkflow::control_agent,
+                request,
+                WorkflowSignalOptions::default(),
+            )
+            .await
+            .map_err(|e| CodexErr::Fatal(format!("failed to signal control_agent: {e}")))?;
+
+        Ok(())
+    }
+
+    /// Query the SessionWorkflow for the approval requests of its agents
+    /// that are still waiting for a decision.
+    pub async fn pending_approvals(&self) -> CodexResult<Vec<SessionApproval>> {
+        let session_id = self.session_id();
+        let handle = self
+            .client
+            .get_workflow_handle::<SessionWorkflowRun>(&session_id);
+
+        let json: String = handle
+            .query(
+                SessionWorkflow::pending_approvals,
+                (),
+                WorkflowQueryOptions::default(),
+            )
+            .await
+            .map_err(|e| CodexErr::Fatal(format!("failed to query approvals: {e}")))?;
+
+        Ok(serde_json::from_str(&json).unwrap_or_default())
+    }
+
+    /// Ask the SessionWorkflow to approve or deny a pending approval of one
+    /// of its agents (full or short agent ID).  Fails if the session
+    /// rejects the decision (no such pending approval, or the agent cannot
+    /// be reached).
+    pub async fn decide_approval(
+        &self,
+        agent_id: impl Into<String>,
+        call_id: impl Into<String>,
+        approved: bool,
+    ) -> CodexResult<()> {
+        let session_id = self.session_id();
+        let handle = self
+            .client
+            .get_workflow_handle::<SessionWorkflowRun>(&session_id);
+
+        let request = ApprovalDecisionRequest {
+            agent_id: agent_id.into(),
+            call_id: call_id.into(),
+            approved,
+            decided_by: None,
+        };
+        handle
+            .execute_update(
+                SessionWorkflow::decide_approval,
+                request,
+                WorkflowExecuteUpdateOptions::default(),
+            )
+            .await
+            .map_err(|e| CodexErr::Fatal(format!("approval decision rejected: {e}")))?;
+
+        Ok(())
+    }
+
     /// Fetch all existing events from the workflow via a one-shot
     /// `get_state_update` call.
     ///
@@ -353,6 +663,185 @@ impl TemporalAgentSession {
         Ok(events.into_iter().map(|e| e.msg).collect())
     }
 
+    /// Fetch the full transcript of the active agent from its paginated
+    /// `get_history` query (rebuilt from the durable rollout items, so it is
+    /// not limited to the event buffer).
+    ///
+    /// Call **before** launching the TUI to populate `initial_messages` on
+    /// resume.  Falls back to the buffered events of
+    /// [`fetch_initial_events`](Self::fetch_initial_events) when the query
+    /// fails (e.g. a worker that predates it, or no such workflow).
+    pub async fn fetch_history(&self) -> CodexResult<Vec<EventMsg>> {
+        let agent_id = self.active_agent_id();
+        let handle = self
+            .client
+            .get_workflow_handle::<AgentWorkflowRun>(&agent_id);
+
+        let mut events = Vec::new();
+        let mut offset = 0;
+        loop {
+            let json: String = match handle
+                .query(
+                    AgentWorkflow::get_history,
+                    HistoryRequest {
+                        offset,
+                        limit: None,
+                    },
+                    WorkflowQueryOptions::default(),
+                )
+                .await
+            {
+                Ok(json) => json,
+                Err(e) => {
+                    tracing::warn!(error = %e, "get_history failed; using buffered events");
+                    return Ok(filter_initial_events(self.fetch_initial_events().await?));
+                }
+            };
+            let page: HistoryPage = serde_json::from_str(&json)
+                .map_err(|e| CodexErr::Fatal(format!("invalid history page: {e}")))?;
+            events.extend(
+                page.events
+                    .iter()
+                    .filter_map(|s| serde_json::from_str::<Event>(s).ok())
+                    .map(|e| e.msg),
+            );
+            match page.next_offset {
+                Some(next) if next > offset => offset = next,
+                _ => break,
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Prepare to continue a session whose workflow has already closed
+    /// (completed, failed, terminated, or removed by retention).
+    ///
+    /// Recovers the main agent's conversation and re-arms the session so the
+    /// next user turn starts a new run of the `SessionWorkflow` under the
+    /// same ID, with the main agent restored from that conversation.
+    /// Returns `false` when the session is still running.
+    pub async fn reopen_if_closed(&self) -> CodexResult<bool> {
+        let session_id = self.session_id();
+        if !self.session_is_closed().await? {
+            return Ok(false);
+        }
+
+        let main_agent_id = format!("{session_id}/main");
+        let recovered = recover_agent_state(&self.client, &self.namespace, &main_agent_id).await;
+        let message = match &recovered {
+            Some(state) => format!(
+                "This session has ended; your next message restarts it with the previous \
+                 conversation ({} items).",
+                state.rollout_items.len()
+            ),
+            None => "This session has ended and its conversation could not be recovered; \
+                     your next message starts it afresh."
+                .to_string(),
+        };
+        tracing::info!(
+            session_id = %session_id,
+            recovered = recovered.is_some(),
+            "reopening session"
+        );
+
+        // Drop the closed workflow's watcher and anything it buffered
+        // (e.g. its `ShutdownComplete`).
+        self.stop_watching();
+        *self.generation.get() += 1;
+        *self.active_agent_workflow_id.get() = main_agent_id;
+        *self.reopen_state.get() =
+            Some(recovered.unwrap_or_else(|| ContinueAsNewState::from_rollout(Vec::new())));
+        *self.started.get() = false;
+        {
+            let mut buf = self.event_buffer.get();
+            buf.clear();
+            buf.push(Event {
+                id: String::new(),
+                msg: EventMsg::BackgroundEvent(BackgroundEventEvent { message }),
+            });
+        }
+        self.event_notify.notify_one();
+        Ok(true)
+    }
+
+    /// Status of the session's latest run, or `None` when the session was
+    /// never started (or has been removed by retention).
+    pub async fn run_status(&self) -> CodexResult<Option<WorkflowExecutionStatus>> {
+        let session_id = self.session_id();
+        let request = DescribeWorkflowExecutionRequest {
+            namespace: self.namespace.clone(),
+            execution: Some(WorkflowExecution {
+                workflow_id: session_id.clone(),
+                run_id: String::new(),
+            }),
+            ..Default::default()
+        };
+        match WorkflowService::describe_workflow_execution(
+            &mut self.client.clone(),
+            request.into_request(),
+        )
+        .await
+        {
+            Ok(response) => Ok(Some(
+                response
+                    .into_inner()
+                    .workflow_execution_info
+                    .map_or(WorkflowExecutionStatus::Unspecified, |info| info.status()),
+            )),
+            Err(e) if e.code() == tonic::Code::NotFound => Ok(None),
+            Err(e) => Err(CodexErr::Fatal(format!(
+                "failed to describe session {session_id}: {e}"
+            ))),
+        }
+    }
+
+    /// Whether the session's latest run has closed (or no longer exists).
+    async fn session_is_closed(&self) -> CodexResult<bool> {
+        Ok(self.run_status().await? != Some(WorkflowExecutionStatus::Running))
+    }
+
+    /// Interrupt the active agent's current turn, waiting until the signal
+    /// is delivered.  (`submit(Op::Interrupt)` answers the caller at once
+    /// and signals in the background, which a short-lived process would
+    /// cut off.)
+    pub async fn interrupt(&self) -> CodexResult<()> {
+        self.signal_agent_op(Op::Interrupt).await.map(|_| ())
+    }
+
+    /// Signal the SessionWorkflow to shut down all of its agents after their
+    /// current turns, waiting until the signal is delivered.
+    pub async fn shutdown_session(&self) -> CodexResult<()> {
+        let session_id = self.session_id();
+        let handle = self
+            .client
+            .get_workflow_handle::<SessionWorkflowRun>(&session_id);
+
+        handle
+            .signal(
+                SessionWorkflow::shutdown,
+                (),
+                WorkflowSignalOptions::default(),
+            )
+            .await
+            .map_err(|e| CodexErr::Fatal(format!("failed to signal shutdown: {e}")))?;
+
+        Ok(())
+    }
+
+    /// Read events of the active agent after watermark `since_index`,
+    /// blocking for at most `wait` if none are available yet.
+    pub async fn events_since(
+        &self,
+        since_index: usize,
+        wait: std::time::Duration,
+    ) -> CodexResult<StateUpdateResponse> {
+        Watcher::new(self.client.clone(), self.active_agent_id())
+            .poll_once(since_index, wait)
+            .await
+            .map_err(|e| CodexErr::Fatal(format!("failed to fetch events: {e}")))
+    }
+
     /// Signal an operation to the active agent workflow.
     async fn signal_agent_op(&self, op: Op) -> CodexResult<String> {
         let agent_id = self.active_agent_id();
@@ -446,10 +935,104 @@ async fn drain_watcher_into_buffer(
     }
 }
 
-/// Derive the harness workflow ID for the current user.
-fn derive_harness_workflow_id() -> String {
-    let user = std::env::var("USER").unwrap_or_else(|_| "default".to_string());
-    format!("codex-harness-{user}")
+/// Recover an agent's conversation from its (possibly closed) workflow.
+///
+/// Pages through the `get_rollout` query, which Temporal answers for closed
+/// workflows too.  When the query fails (e.g. the worker can no longer
+/// replay the run), falls back to the continue-as-new state in the input of
+/// the agent's latest run, read from the workflow history.
+async fn recover_agent_state(
+    client: &Client,
+    namespace: &str,
+    agent_id: &str,
+) -> Option<ContinueAsNewState> {
+    match query_rollout(client, agent_id).await {
+        Ok(items) => return Some(ContinueAsNewState::from_rollout(items)),
+        Err(e) => {
+            tracing::warn!(agent_id, error = %e, "get_rollout failed; reading workflow history")
+        }
+    }
+
+    let request = GetWorkflowExecutionHistoryRequest {
+        namespace: namespace.to_string(),
+        execution: Some(WorkflowExecution {
+            workflow_id: agent_id.to_string(),
+            run_id: String::new(),
+        }),
+        maximum_page_size: 1,
+        ..Default::default()
+    };
+    let history = WorkflowService::get_workflow_execution_history(
+        &mut client.clone(),
+        request.into_request(),
+    )
+    .await
+    .ok()?
+    .into_inner()
+    .history?;
+    let Some(history_event::Attributes::WorkflowExecutionStartedEventAttributes(started)) =
+        history.events.into_iter().next()?.attributes
+    else {
+        return None;
+    };
+    let payload = started.input?.payloads.into_iter().next()?;
+    let input = AgentWorkflowInput::from_json_payload(&payload).ok()?;
+    input
+        .continued_state
+        .map(|state| ContinueAsNewState::from_rollout(state.rollout_items))
+}
+
+/// Read all rollout items of an agent through its paginated `get_rollout`
+/// query.
+async fn query_rollout(client: &Client, agent_id: &str) -> Result<Vec<RolloutItem>, String> {
+    let handle = client.get_workflow_handle::<AgentWorkflowRun>(agent_id);
+    let mut items = Vec::new();
+    let mut offset = 0;
+    loop {
+        let json: String = handle
+            .query(
+                AgentWorkflow::get_rollout,
+                HistoryRequest {
+                    offset,
+                    limit: None,
+                },
+                WorkflowQueryOptions::default(),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        let page: RolloutPage = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        items.extend(
+            page.items
+                .iter()
+                .filter_map(|s| serde_json::from_str::<RolloutItem>(s).ok()),
+        );
+        match page.next_offset {
+            Some(next) if next > offset => offset = next,
+            _ => return Ok(items),
+        }
+    }
+}
+
+/// Build an `Op::UserTurn` carrying `text`, with the turn settings of
+/// `input`.
+pub fn user_turn_op(input: &SessionWorkflowInput, text: String) -> Op {
+    Op::UserTurn {
+        items: vec![UserInput::Text {
+            text,
+            text_elements: vec![],
+        }],
+        cwd: std::env::current_dir().unwrap_or_default(),
+        approval_policy: input.approval_policy,
+        sandbox_policy: SandboxPolicy::DangerFullAccess,
+        model: input.model.clone(),
+        effort: input.reasoning_effort,
+        summary: Some(input.reasoning_summary),
+        service_tier: None,
+        final_output_json_schema: input.output_schema.clone(),
+        collaboration_mode: None,
+        personality: input.personality,
+        approvals_reviewer: None,
+    }
 }
 
 /// Filter replayed events into the set suitable for `initial_messages`.
@@ -513,6 +1096,7 @@ impl codex_core::AgentSession for TemporalAgentSession {
             effort,
             summary,
             personality,
+            ref final_output_json_schema,
             ..
         } = op
         {
@@ -548,9 +1132,33 @@ impl codex_core::AgentSession for TemporalAgentSession {
                     developer_instructions: base_input.developer_instructions.clone(),
                     model_provider: base_input.model_provider.clone(),
                     crew_agents: base_input.crew_agents.clone(),
+                    pipeline: base_input.pipeline.clone(),
+                    approval_supervisor: base_input.approval_supervisor.clone(),
+                    // Sessions register themselves; default to this
+                    // client's harness when the input names none.
+                    harness_registration: base_input
+                        .harness_registration
+                        .clone()
+                        .or_else(|| {
+                            Some(HarnessRegistration {
+                                harness_id: self.harness_workflow_id.clone(),
+                                name: None,
+                                crew_type: None,
+                                schedule_id: None,
+                            })
+                        }),
+                    crew_results: base_input.crew_results.clone(),
                     continued_state: None,
+                    // Set when reopening a closed session.
+                    main_agent_state: self.reopen_state.get().take(),
                     max_iterations: base_input.max_iterations,
+                    output_schema: final_output_json_schema
+                        .clone()
+                        .or_else(|| base_input.output_schema.clone()),
                 };
+                // A reopened session keeps the TUI's transcript, so it gets
+                // no new `SessionConfigured`.
+                let reopened = input.main_agent_state.is_some();
 
                 // Try once synchronously first.
                 let options =
@@ -564,7 +1172,7 @@ impl codex_core::AgentSession for TemporalAgentSession {
                         *self.submit_cancel.get() = None;
                         // Inject SessionConfigured before the watcher starts
                         // so it is the first event consumers see.
-                        {
+                        if !reopened {
                             let evt = Event {
                                 id: String::new(),
                                 msg: EventMsg::SessionConfigured(Self::build_session_configured(&input, None)),
@@ -599,10 +1207,10 @@ impl codex_core::AgentSession for TemporalAgentSession {
                         let session_id2 = session_id.clone();
                         let buffer2 = Arc::clone(&self.event_buffer);
                         let notify2 = Arc::clone(&self.event_notify);
-                        let session_configured_evt = Event {
+                        let session_configured_evt = (!reopened).then(|| Event {
                             id: String::new(),
                             msg: EventMsg::SessionConfigured(Self::build_session_configured(&input, None)),
-                        };
+                        });
                         let generation = Arc::clone(&self.generation);
                         let gen_at_start = *generation.get();
                         let active_agent_id = self.active_agent_id();
@@ -631,8 +1239,10 @@ impl codex_core::AgentSession for TemporalAgentSession {
 
                             if ok && *generation.get() == gen_at_start {
                                 // Inject SessionConfigured before the watcher starts.
-                                buffer2.get().push(session_configured_evt);
-                                notify2.notify_one();
+                                if let Some(evt) = session_configured_evt {
+                                    buffer2.get().push(evt);
+                                    notify2.notify_one();
+                                }
                                 // Start a watcher inline since we can't call self.start_watching().
                                 let watcher = Watcher::new(client2.clone(), active_agent_id.clone());
                                 let (wtx, mut wrx) = tokio::sync::mpsc::channel(64);
@@ -661,6 +1271,15 @@ impl codex_core::AgentSession for TemporalAgentSession {
                         return Ok(result);
                     }
                     Err(first_err) => {
+                        // The session closed underneath us: restart it with
+                        // its conversation and send the turn there.
+                        let msg = first_err.to_string().to_lowercase();
+                        if (msg.contains("already completed") || msg.contains("not found"))
+                            && self.reopen_if_closed().await.unwrap_or(false)
+                        {
+                            *self.submit_cancel.get() = None;
+                            return self.submit(op).await;
+                        }
                         tracing::warn!(error = %first_err, "signal_agent_op failed for UserTurn, retrying in background");
                         // Push connecting status.
                         {
@@ -800,24 +1419,34 @@ impl codex_tui::ExternalAgentBrowser for TemporalAgentSession {
     async fn list_sessions(&self) -> Vec<codex_tui::ExternalSessionEntry> {
         let current_id = self.session_id();
         match self.query_sessions().await {
-            Ok(entries) => entries
-                .into_iter()
-                .map(|e| {
-                    let is_current = e.session_id == current_id;
-                    let is_closed = e.status != crate::types::SessionStatus::Running;
-                    let description = {
-                        let status_str = if is_closed { " [closed]" } else { "" };
-                        Some(format!("{}{}", e.model, status_str))
-                    };
-                    codex_tui::ExternalSessionEntry {
-                        id: e.session_id,
-                        name: e.name.unwrap_or_else(|| "(unnamed)".to_string()),
-                        description,
-                        is_current,
-                        is_closed,
-                    }
-                })
-                .collect(),
+            Ok(mut entries) => {
+                crate::types::sort_sessions_for_display(&mut entries);
+                entries
+                    .into_iter()
+                    .map(|e| {
+                        let is_current = e.session_id == current_id;
+                        let is_closed = e.status != crate::types::SessionStatus::Running;
+                        let description = {
+                            let status_str = if is_closed { " [closed]" } else { "" };
+                            let branch = e
+                                .branch
+                                .as_ref()
+                                .map(|b| format!(" @{b}"))
+                                .unwrap_or_default();
+                            let tags: String = e.tags.iter().map(|t| format!(" #{t}")).collect();
+                            Some(format!("{}{branch}{tags}{status_str}", e.model))
+                        };
+                        let name = e.display_name().unwrap_or("(unnamed)").to_string();
+                        codex_tui::ExternalSessionEntry {
+                            id: e.session_id,
+                            name: if e.pinned { format!("{name} (pinned)") } else { name },
+                            description,
+                            is_current,
+                            is_closed,
+                        }
+                    })
+                    .collect()
+            }
             Err(err) => {
                 tracing::error!(%err, "failed to query sessions from harness");
                 Vec::new()
@@ -831,17 +1460,19 @@ impl codex_tui::ExternalAgentBrowser for TemporalAgentSession {
     ) -> color_eyre::eyre::Result<codex_tui::ExternalSwitchResult> {
         self.switch_session(session_id.to_string());
 
-        // Fetch existing events from the new session to seed initial_messages.
-        let events = self
-            .fetch_initial_events()
+        // Fetch the session's transcript to seed initial_messages.
+        let history = self
+            .fetch_history()
+            .await
+            .map_err(|e| color_eyre::eyre::eyre!("failed to fetch session history: {e}"))?;
+        self.reopen_if_closed()
             .await
-            .map_err(|e| color_eyre::eyre::eyre!("failed to fetch initial events: {e}"))?;
-        let filtered = filter_initial_events(events);
+            .map_err(|e| color_eyre::eyre::eyre!("failed to reopen session: {e}"))?;
 
         Ok(codex_tui::ExternalSwitchResult {
             session_configured: Self::build_session_configured(
                 &self.base_input,
-                Some(filtered),
+                Some(history),
             ),
         })
     }
@@ -4,30 +4,63 @@
 //! - Loads config, project context, and MCP tools once
 //! - Starts a "main" `AgentWorkflow` child
 //! - Accepts `spawn_agent` signals to start additional agent children
-//! - Enforces `max_agents` limit
+//! - Enforces `max_agents` on live agents, queueing excess spawn requests
+//!   until a slot frees up
+//! - Pauses, resumes, cancels or terminates single agents (`control_agent`)
+//! - Awaits child results alongside the control loop and records each
+//!   agent's status and output
+//! - Re-runs MCP discovery on `refresh_mcp` and pushes the new tool set to
+//!   every agent
+//! - Reports spawns, turn completions and results of subagents spawned via
+//!   the subagent tools back to the spawning agent
+//! - Routes messages between agents (`route_message` → `deliver_message`)
+//!   and keeps a log of them
+//! - Collects the exec/patch approval requests of all agents, forwards them
+//!   to an optional supervisor agent and relays decisions back
+//! - Runs crew pipelines: starts one agent per stage (or branch), feeds its
+//!   first-turn output into later stages, loops and reports progress to the
+//!   main agent
+//! - Registers itself with `CodexHarness` (signal-with-start) and reports
+//!   activity after turns and its final status, including failures
+//! - Extracts a crew's named outputs from the agents' last messages and
+//!   collects its workspace artifacts when it ends, storing them in the
+//!   harness
 //! - Supports graceful shutdown and continue-as-new
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
-use temporalio_common::protos::coresdk::AsJsonPayloadExt;
+use futures::future::{Either, LocalBoxFuture};
+use futures::stream::FuturesUnordered;
+use futures::{FutureExt, StreamExt};
+use codex_protocol::protocol::Op;
+use temporalio_common::protos::coresdk::child_workflow::{
+    ChildWorkflowResult, child_workflow_result,
+};
+use temporalio_common::protos::coresdk::{AsJsonPayloadExt, FromJsonPayloadExt};
 use temporalio_macros::{workflow, workflow_methods};
 use temporalio_sdk::{
-    ChildWorkflowOptions, SyncWorkflowContext, WorkflowContext,
+    ChildWorkflowOptions, StartedChildWorkflow, SyncWorkflowContext, WorkflowContext,
     WorkflowContextView, WorkflowResult, WorkflowTermination,
 };
 use temporalio_common::protos::coresdk::workflow_commands::ContinueAsNewWorkflowExecution;
 use temporalio_common::protos::temporal::api::enums::v1::ParentClosePolicy;
 
+use crate::TASK_QUEUE;
 use crate::activities::{CodexActivities, activity_opts};
+use crate::startup::signal_workflow;
 use crate::config_loader::{config_from_toml, inject_crew_roles_into_toml};
 use crate::types::{
-    AgentLifecycle, AgentRecord, AgentSummary, AgentWorkflowInput, CrewAgentDef,
-    ProjectContextOutput, ResolveRoleConfigInput,
-    SessionContinueAsNewState, SessionWorkflowInput, SessionWorkflowOutput, SpawnAgentInput,
+    AgentControlAction, AgentControlRequest, AgentFinishedNotice, AgentLifecycle, AgentMessage,
+    AgentRecord, AgentTurnNotice, AgentWorkflowInput, AgentWorkflowOutput, ApprovalDecision,
+    ApprovalDecisionRequest, ApprovalResolved, CollectArtifactsInput, ConfigOutput, CrewAgentDef,
+    CrewResults, HarnessSignalInput, SESSION_MESSAGE_SENDER, approval_supervisor_address,
+    may_decide_approval, message_recipients, McpRefreshRequest, McpToolsUpdate, PipelineRun,
+    PipelineStatus, PipelineTaskRef, ProjectContextOutput, ResolveRoleConfigInput,
+    SessionActivity, SessionApproval, SessionContinueAsNewState, SessionEntry, SessionStatus,
+    SessionWorkflowInput, SessionWorkflowOutput, SpawnAgentInput, SubagentNotice,
+    TerminateWorkflowsInput, admit_pending_spawn, live_agent_count, preview_text,
 };
 
-const TASK_QUEUE: &str = "codex-temporal";
-
 /// Resolve a role's config via the `resolve_role_config` activity, then merge
 /// optional crew-level overrides for model/instructions.
 ///
@@ -68,9 +101,545 @@ async fn resolve_role(
     Ok((resolved.config_toml, model, instructions))
 }
 
+/// Inject crew agent definitions into the config TOML so they appear as
+/// `[agents.<name>]` entries in `config.agent_roles`.
+fn with_crew_roles(config_toml: String, crew_agents: &BTreeMap<String, CrewAgentDef>) -> String {
+    if crew_agents.is_empty() {
+        return config_toml;
+    }
+    inject_crew_roles_into_toml(&config_toml, crew_agents).unwrap_or_else(|e| {
+        tracing::warn!("failed to inject crew roles into TOML: {e}");
+        config_toml
+    })
+}
+
+/// Reload config.toml and re-run MCP discovery without `disabled` servers.
+///
+/// Returns the freshly loaded (raw) config TOML, or `None` if reloading
+/// failed and the cached config was used, plus the diff against `current`.
+async fn refresh_mcp_tools(
+    ctx: &mut WorkflowContext<SessionWorkflow>,
+    cwd: &str,
+    current: &HashMap<String, serde_json::Value>,
+    disabled: BTreeSet<String>,
+) -> (Option<String>, McpToolsUpdate) {
+    let cwd = cwd.to_string();
+    let loaded: Option<ConfigOutput> = ctx
+        .start_activity(CodexActivities::load_config, (), activity_opts(30))
+        .await
+        .map_err(|e| tracing::warn!("MCP refresh: load_config failed, using cached config: {e}"))
+        .ok();
+    let config_toml = match loaded {
+        Some(ref output) => output.config_toml.clone(),
+        None => ctx.state(|s| s.config_toml.clone()).unwrap_or_default(),
+    };
+
+    let disabled: Vec<String> = disabled.into_iter().collect();
+    let tools = crate::startup::discover_mcp!(ctx, config_toml, cwd, disabled);
+
+    (loaded.map(|o| o.config_toml), McpToolsUpdate::diff(current, tools))
+}
+
+/// Push a refreshed MCP tool set to every running agent.  Failures (e.g. an
+/// agent that already exited) are logged and skipped.
+async fn notify_agents_of_mcp_tools(
+    ctx: &mut WorkflowContext<SessionWorkflow>,
+    update: &McpToolsUpdate,
+) {
+    let targets: Vec<String> = ctx.state(|s| {
+        s.agents
+            .iter()
+            .filter(|a| a.status.is_live())
+            .map(|a| a.workflow_id.clone())
+            .collect()
+    });
+    for workflow_id in targets {
+        signal_target(ctx, &workflow_id, "update_mcp_tools", update).await;
+    }
+}
+
+/// Send `signal_name` with `value` to the workflow `target`: one of the
+/// session's agents or its harness.  Failures (e.g. the workflow already
+/// exited) are logged and skipped.
+async fn signal_target<T: AsJsonPayloadExt>(
+    ctx: &mut WorkflowContext<SessionWorkflow>,
+    target: &str,
+    signal_name: &str,
+    value: &T,
+) {
+    if let Err(e) = signal_workflow!(ctx, target, signal_name, value) {
+        tracing::warn!(workflow_id = %target, error = %e, "failed to signal {signal_name}");
+    }
+}
+
+/// Current workflow time as Unix millis (0 if unknown).
+fn workflow_millis(ctx: &WorkflowContext<SessionWorkflow>) -> u64 {
+    ctx.workflow_time()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Register the session with its harness via signal-with-start, so the
+/// harness is started if it is not running yet.  Workers without a
+/// Temporal client for activities fall back to a plain signal.
+async fn register_with_harness(
+    ctx: &mut WorkflowContext<SessionWorkflow>,
+    harness_id: &str,
+    entry: &SessionEntry,
+) {
+    let signal_input = HarnessSignalInput {
+        harness_id: harness_id.to_string(),
+        signal_name: "register_session".to_string(),
+        payload: serde_json::to_value(entry).unwrap_or_default(),
+    };
+    let started = ctx
+        .start_activity(
+            CodexActivities::signal_with_start_harness,
+            signal_input,
+            activity_opts(30),
+        )
+        .await;
+    match started {
+        Ok(true) => {}
+        Ok(false) => signal_target(ctx, harness_id, "register_session", entry).await,
+        Err(e) => {
+            tracing::warn!(error = %e, "signal-with-start of the harness failed");
+            signal_target(ctx, harness_id, "register_session", entry).await;
+        }
+    }
+}
+
+/// Deliver a [`SubagentNotice`] to the agent that spawned a subagent.
+async fn notify_agent(
+    ctx: &mut WorkflowContext<SessionWorkflow>,
+    agent_id: &str,
+    notice: SubagentNotice,
+) {
+    signal_target(ctx, agent_id, "subagent_notice", &notice).await;
+}
+
+/// Deliver a routed message to each of its recipients, or bounce a note
+/// back to the sender if nobody matched.  Returns the message as logged.
+async fn route_message(
+    ctx: &mut WorkflowContext<SessionWorkflow>,
+    mut message: AgentMessage,
+) -> AgentMessage {
+    let recipients =
+        ctx.state(|s| message_recipients(&s.agents, &message.from, &message.to));
+    if recipients.is_empty() {
+        let bounce = AgentMessage {
+            seq: message.seq,
+            from: SESSION_MESSAGE_SENDER.to_string(),
+            to: message.from.clone(),
+            body: format!(
+                "No running agent matches '{}'; message not delivered.",
+                message.to
+            ),
+            wake: false,
+            delivered_to: Vec::new(),
+        };
+        signal_target(ctx, &message.from, "deliver_message", &bounce).await;
+    }
+    for recipient in &recipients {
+        signal_target(ctx, recipient, "deliver_message", &message).await;
+    }
+    message.delivered_to = recipients;
+    message
+}
+
+/// Tell the requester that a spawn was rejected: the spawning agent for
+/// tool-initiated spawns, otherwise the main agent's event stream.
+async fn reject_spawn(
+    ctx: &mut WorkflowContext<SessionWorkflow>,
+    spawn_input: &SpawnAgentInput,
+    error: String,
+) {
+    // A pipeline agent that cannot be started fails the pipeline.
+    if let Some(task) = &spawn_input.pipeline_task {
+        ctx.state_mut(|s| {
+            if let Some(pipeline) = s.pipeline.as_mut()
+                && !pipeline.is_finished()
+            {
+                pipeline.fail(format!("stage {} could not start: {error}", task.label()));
+                s.report_pipeline_progress();
+            }
+        });
+        return;
+    }
+    if let (Some(requester), Some(request_id)) =
+        (&spawn_input.requested_by, &spawn_input.request_id)
+    {
+        let notice = SubagentNotice::SpawnFailed {
+            request_id: request_id.clone(),
+            error,
+        };
+        notify_agent(ctx, requester, notice).await;
+    } else {
+        let main_agent_id = ctx.state(|s| s.main_agent_id());
+        let message = format!("Spawn of a {} agent was rejected: {error}", spawn_input.role);
+        signal_target(ctx, &main_agent_id, "session_event", &message).await;
+    }
+}
+
+/// Tell the requester that a spawn is waiting for a free agent slot.
+async fn report_spawn_queued(
+    ctx: &mut WorkflowContext<SessionWorkflow>,
+    spawn_input: &SpawnAgentInput,
+    position: usize,
+    max_agents: usize,
+) {
+    tracing::info!(role = %spawn_input.role, position, max_agents,
+        "max_agents limit reached, queueing spawn request");
+    if let (Some(requester), Some(request_id)) =
+        (&spawn_input.requested_by, &spawn_input.request_id)
+    {
+        let notice = SubagentNotice::SpawnQueued {
+            request_id: request_id.clone(),
+            position,
+            max_agents,
+        };
+        notify_agent(ctx, requester, notice).await;
+    } else {
+        let main_agent_id = ctx.state(|s| s.main_agent_id());
+        let message = format!(
+            "Spawn of a {} agent queued at position {position}: \
+             the session is at its max_agents limit ({max_agents}).",
+            spawn_input.role
+        );
+        signal_target(ctx, &main_agent_id, "session_event", &message).await;
+    }
+}
+
+/// Start a child `AgentWorkflow` for `spawn_input` and record it.
+///
+/// Enforces `max_agents` and resolves the role (crew agent, configured role
+/// or the session defaults).  Returns the new agent's ID and its started
+/// child, or the reason the spawn was rejected.
+async fn start_agent(
+    ctx: &mut WorkflowContext<SessionWorkflow>,
+    session_id: &str,
+    input: &SessionWorkflowInput,
+    config_toml: &str,
+    project_context: &ProjectContextOutput,
+    mcp_tools: &HashMap<String, serde_json::Value>,
+    spawn_input: &SpawnAgentInput,
+) -> Result<(String, StartedChildWorkflow), String> {
+    let (current_count, max) = ctx.state(|s| (s.live_agent_count(), s.max_agents));
+
+    if current_count >= max {
+        tracing::warn!(
+            current = current_count,
+            max = max,
+            role = %spawn_input.role,
+            "max_agents limit reached, rejecting spawn request"
+        );
+        return Err(format!("max_agents limit ({max}) reached"));
+    }
+
+    let agent_num = ctx.state_mut(|s| {
+        s.agent_counter += 1;
+        s.agent_counter
+    });
+
+    let agent_role = spawn_input.role.clone();
+    let agent_id = format!("{session_id}/{}-{}", agent_role, agent_num);
+
+    // Look up crew agent definition (if any).
+    let crew_def = ctx.state(|s| s.crew_agents.get(&agent_role).cloned());
+
+    // Resolve role config: crew-aware resolution.
+    let (resolved_config_toml, resolved_model, resolved_instructions) =
+        if let Some(ref crew_agent) = crew_def {
+            if let Some(ref base_role) = crew_agent.role {
+                // Crew agent with base role — resolve then overlay crew values.
+                resolve_role(
+                    ctx, config_toml, &project_context.cwd, base_role,
+                    crew_agent.model.as_deref(),
+                    crew_agent.instructions.as_deref(),
+                    &input.model, &input.instructions,
+                ).await.map_err(|e| {
+                    tracing::error!(role = %agent_role, base_role = %base_role, error = %e,
+                        "failed to resolve base role for crew agent, skipping spawn");
+                    format!("failed to resolve role {agent_role}: {e}")
+                })?
+            } else {
+                // Pure inline crew agent — no activity call needed.
+                let model = crew_agent.model.clone().unwrap_or_else(|| input.model.clone());
+                let instructions = crew_agent.instructions.clone().unwrap_or_else(|| input.instructions.clone());
+                (config_toml.to_string(), model, instructions)
+            }
+        } else if agent_role != "default" {
+            // Non-crew, non-default role — resolve via activity.
+            resolve_role(
+                ctx, config_toml, &project_context.cwd, &agent_role,
+                None, None, &input.model, &input.instructions,
+            ).await.map_err(|e| {
+                tracing::error!(role = %agent_role, error = %e,
+                    "failed to resolve role config, skipping spawn");
+                format!("failed to resolve role {agent_role}: {e}")
+            })?
+        } else {
+            (config_toml.to_string(), input.model.clone(), input.instructions.clone())
+        };
+
+    let child_input = AgentWorkflowInput::from_session(
+        input,
+        spawn_input.message.clone(),
+        resolved_model,
+        resolved_instructions,
+        agent_role.clone(),
+        resolved_config_toml,
+        project_context.clone(),
+        mcp_tools.clone(),
+    );
+    let payload = child_input
+        .as_json_payload()
+        .map_err(|e| format!("failed to serialize agent input: {e}"))?;
+
+    let child = ctx.child_workflow(ChildWorkflowOptions {
+        workflow_id: agent_id.clone(),
+        workflow_type: "AgentWorkflow".to_string(),
+        task_queue: Some(TASK_QUEUE.to_string()),
+        input: vec![payload],
+        parent_close_policy: ParentClosePolicy::Abandon,
+        ..Default::default()
+    });
+
+    let Some(started) = child.start().await.into_started() else {
+        tracing::error!(agent_id = %agent_id, "failed to start child agent");
+        return Err("failed to start agent workflow".to_string());
+    };
+    let parent = spawn_input.requested_by.clone();
+    ctx.state_mut(|s| {
+        s.agents
+            .push(AgentRecord::running(agent_id.clone(), agent_role).with_parent(parent));
+    });
+    tracing::info!(agent_id = %agent_id, "child agent started");
+    Ok((agent_id, started))
+}
+
+/// A child agent's pending result, tagged with its agent ID.
+type ChildResultFuture = LocalBoxFuture<'static, (String, ChildWorkflowResult)>;
+
+/// Turn a started child into a future resolving to its tagged result.
+fn await_child(agent_id: String, started: StartedChildWorkflow) -> ChildResultFuture {
+    async move { (agent_id, started.result().await) }.boxed_local()
+}
+
+/// Decode a child `AgentWorkflow` result into its output or a failure reason.
+pub(crate) fn agent_outcome(result: ChildWorkflowResult) -> Result<AgentWorkflowOutput, String> {
+    match result.status {
+        Some(child_workflow_result::Status::Completed(success)) => {
+            let payload = success
+                .result
+                .ok_or_else(|| "agent completed without a result".to_string())?;
+            AgentWorkflowOutput::from_json_payload(&payload)
+                .map_err(|e| format!("failed to decode agent result: {e}"))
+        }
+        Some(child_workflow_result::Status::Failed(failed)) => Err(failed
+            .failure
+            .map(|f| f.message)
+            .unwrap_or_else(|| "agent failed".to_string())),
+        Some(child_workflow_result::Status::Cancelled(_)) => Err("agent cancelled".to_string()),
+        None => Err("agent finished without a status".to_string()),
+    }
+}
+
+/// Relay an approval decision to the waiting agent via `resolve_approval`.
+/// Fails if there is no such pending approval, the decider may not decide
+/// it, or the agent cannot be signalled (the approval then stays pending).
+async fn relay_approval_decision(
+    ctx: &mut WorkflowContext<SessionWorkflow>,
+    decision: &ApprovalDecisionRequest,
+) -> Result<(), String> {
+    let (approval, workflow_id) = ctx.state_mut(|s| {
+        let workflow_id = s
+            .agents
+            .iter()
+            .find(|a| a.matches_id(&decision.agent_id))
+            .map(|a| a.workflow_id.clone())
+            .ok_or_else(|| format!("unknown agent: {}", decision.agent_id))?;
+        let index = s
+            .approvals
+            .iter()
+            .position(|a| a.agent_id == workflow_id && a.call_id == decision.call_id)
+            .ok_or_else(|| {
+                format!(
+                    "no pending approval {} for agent {}",
+                    decision.call_id, decision.agent_id
+                )
+            })?;
+        if !may_decide_approval(
+            &s.agents,
+            s.input.approval_supervisor.as_deref(),
+            decision.decided_by.as_deref(),
+            &s.approvals[index],
+        ) {
+            return Err("only the session's approval supervisor may decide approvals of \
+                        other agents"
+                .to_string());
+        }
+        Ok((s.approvals.remove(index), workflow_id))
+    })?;
+    tracing::info!(agent_id = %workflow_id, call_id = %approval.call_id,
+        approved = decision.approved, "relaying approval decision");
+    let forward = ApprovalDecision {
+        call_id: approval.call_id.clone(),
+        approved: decision.approved,
+    };
+    if let Err(e) = signal_workflow!(ctx, &workflow_id, "resolve_approval", &forward) {
+        ctx.state_mut(|s| s.approvals.push(approval));
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Record that agent `agent_id` finished with `outcome`: complete its
+/// pipeline task, store the result and tell its spawner.  An agent reports
+/// its outcome twice (its `agent_finished` signal and, within the run that
+/// started it, its child result); only the first report is applied.
+async fn record_agent_finished(
+    ctx: &mut WorkflowContext<SessionWorkflow>,
+    agent_id: &str,
+    outcome: Result<AgentWorkflowOutput, String>,
+) {
+    let notice = ctx.state_mut(|s| {
+        let record = s.agents.iter().find(|a| a.agent_id == agent_id)?;
+        if !record.is_active() {
+            return None;
+        }
+        match &outcome {
+            Ok(_) => tracing::info!(agent_id = %agent_id, "child agent completed"),
+            Err(e) => tracing::warn!(agent_id = %agent_id, error = %e, "child agent failed"),
+        }
+        let stage_outcome = match &outcome {
+            _ if record.status == AgentLifecycle::Terminated => {
+                Err("agent terminated".to_string())
+            }
+            Ok(output) => Ok(output.last_agent_message.clone().unwrap_or_default()),
+            Err(e) => Err(e.clone()),
+        };
+        s.pipeline_task_finished(agent_id, stage_outcome);
+        let record = s.agents.iter_mut().find(|a| a.agent_id == agent_id)?;
+        record.finish(outcome);
+        let parent = record.parent_agent_id.clone()?;
+        let summary = record.summary();
+        Some((parent, SubagentNotice::Finished {
+            agent_id: agent_id.to_string(),
+            last_agent_message: summary.last_agent_message,
+            error: summary.error,
+        }))
+    });
+    if let Some((parent, notice)) = notice {
+        notify_agent(ctx, &parent, notice).await;
+    }
+}
+
+/// Reconcile the agents restored from continue-as-new: agents whose
+/// workflow closed without their `agent_finished` signal reaching the
+/// session (e.g. terminated from outside) are recorded as finished.  The
+/// others report their outcome with `agent_finished` when they end.
+async fn reconcile_restored_agents(ctx: &mut WorkflowContext<SessionWorkflow>) {
+    let records: Vec<AgentRecord> = ctx.state(|s| {
+        s.agents
+            .iter()
+            .filter(|a| a.is_active())
+            .filter(|a| !s.finished_agents.iter().any(|n| n.agent_id == a.agent_id))
+            .cloned()
+            .collect()
+    });
+    if records.is_empty() {
+        return;
+    }
+    let workflow_ids = records.iter().map(|a| a.workflow_id.clone()).collect();
+    let closed = match ctx
+        .start_activity(CodexActivities::session_statuses, workflow_ids, activity_opts(60))
+        .await
+    {
+        Ok(closed) => closed,
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to check restored agents");
+            return;
+        }
+    };
+    for (workflow_id, status) in closed {
+        if let Some(record) = records.iter().find(|a| a.workflow_id == workflow_id) {
+            tracing::info!(agent_id = %record.agent_id, ?status,
+                "restored agent closed while the session continued as new");
+            record_agent_finished(ctx, &record.agent_id, record.closed_outcome(status)).await;
+        }
+    }
+}
+
+/// Terminate an agent's workflow for `control_agent terminate`.  Returns
+/// `Ok(true)` once the workflow is terminated; if that is unavailable (no
+/// Temporal client) the agent is told to abort via its `control` signal
+/// (`Ok(false)`).  `Err` if neither worked.
+async fn terminate_agent(
+    ctx: &mut WorkflowContext<SessionWorkflow>,
+    workflow_id: &str,
+) -> Result<bool, String> {
+    let request = TerminateWorkflowsInput {
+        workflow_ids: vec![workflow_id.to_string()],
+        reason: "agent terminated by control_agent".to_string(),
+    };
+    match ctx
+        .start_activity(CodexActivities::terminate_workflows, request, activity_opts(60))
+        .await
+    {
+        Ok(terminated) if !terminated.is_empty() => return Ok(true),
+        Ok(_) => {}
+        Err(e) => tracing::warn!(agent_id = %workflow_id, error = %e, "terminate_workflows failed"),
+    }
+    signal_workflow!(ctx, workflow_id, "control", &AgentControlAction::Terminate).map(|()| false)
+}
+
+/// Stop the agents still running when the session ends.  Agents are
+/// started with `ParentClosePolicy::Abandon` so that they survive the
+/// session's continue-as-new; they are terminated here instead, or asked
+/// to shut down if termination is unavailable.  When the session itself
+/// is terminated or times out, the harness terminates them (see
+/// `terminate_session_agents`).
+async fn stop_live_agents(ctx: &mut WorkflowContext<SessionWorkflow>) {
+    let workflow_ids: Vec<String> = ctx.state(|s| {
+        s.agents
+            .iter()
+            .filter(|a| a.is_active())
+            .map(|a| a.workflow_id.clone())
+            .collect()
+    });
+    if workflow_ids.is_empty() {
+        return;
+    }
+    let request = TerminateWorkflowsInput {
+        workflow_ids: workflow_ids.clone(),
+        reason: "session ended".to_string(),
+    };
+    let terminated = match ctx
+        .start_activity(CodexActivities::terminate_workflows, request, activity_opts(60))
+        .await
+    {
+        Ok(terminated) => terminated,
+        Err(e) => {
+            tracing::warn!(error = %e, "failed to terminate the session's agents");
+            Vec::new()
+        }
+    };
+    for workflow_id in workflow_ids.iter().filter(|id| !terminated.contains(id)) {
+        signal_target(ctx, workflow_id, "receive_op", &Op::Shutdown).await;
+    }
+}
+
 /// Default maximum number of concurrent agents per session.
 const DEFAULT_MAX_AGENTS: usize = 8;
 
+/// Maximum number of spawn requests waiting for a free agent slot; further
+/// requests are rejected.
+const MAX_PENDING_SPAWNS: usize = 64;
+
+/// Number of inter-agent messages kept in the session's log.
+const MAX_MESSAGE_LOG: usize = 500;
+
 #[workflow]
 pub struct SessionWorkflow {
     input: SessionWorkflowInput,
@@ -81,11 +650,143 @@ pub struct SessionWorkflow {
     project_context: Option<ProjectContextOutput>,
     mcp_tools: HashMap<String, serde_json::Value>,
     spawn_queue: Vec<SpawnAgentInput>,
+    /// Spawn requests admitted to the queue but waiting for a free slot.
+    pending_spawns: Vec<SpawnAgentInput>,
     agent_counter: u32,
     max_agents: usize,
     shutdown_requested: bool,
     /// Crew agent definitions for non-main agents (from crew type).
     crew_agents: BTreeMap<String, CrewAgentDef>,
+    /// Pending `refresh_mcp` requests, handled by the control loop.
+    mcp_refresh_queue: Vec<McpRefreshRequest>,
+    /// MCP servers disabled at runtime for this session.
+    disabled_mcp_servers: BTreeSet<String>,
+    /// Turn completions reported by agents, forwarded to their spawners.
+    turn_notices: Vec<AgentTurnNotice>,
+    /// Messages posted by agents, waiting to be routed.
+    message_queue: Vec<AgentMessage>,
+    /// Log of routed messages (most recent [`MAX_MESSAGE_LOG`]).
+    messages: Vec<AgentMessage>,
+    /// Crew pipeline progress (crews with `stages`).
+    pipeline: Option<PipelineRun>,
+    /// The pipeline moved to a new stage whose agents must be started.
+    pipeline_advanced: bool,
+    /// Pending per-agent lifecycle actions, handled by the control loop.
+    control_queue: Vec<AgentControlRequest>,
+    /// Approval requests of all agents still waiting for a decision.
+    approvals: Vec<SessionApproval>,
+    /// New approval requests to forward to the approval supervisor.
+    supervisor_queue: Vec<SessionApproval>,
+    /// Approval decisions waiting to be relayed to the agents.
+    approval_decisions: Vec<ApprovalDecisionRequest>,
+    /// Outcomes reported by agents whose workflow ended.
+    finished_agents: Vec<AgentFinishedNotice>,
+    /// Crew results (with artifact contents) once the session has ended.
+    final_results: Option<CrewResults>,
+}
+
+impl SessionWorkflow {
+    fn main_agent_id(&self) -> String {
+        format!("{}/main", self.session_id)
+    }
+
+    /// Whether signals are queued for the control loop.  These queues are
+    /// not carried across continue-as-new, so they must be empty first.
+    fn has_queued_signals(&self) -> bool {
+        !self.spawn_queue.is_empty()
+            || !self.mcp_refresh_queue.is_empty()
+            || !self.turn_notices.is_empty()
+            || !self.message_queue.is_empty()
+            || self.pipeline_advanced
+            || !self.control_queue.is_empty()
+            || !self.supervisor_queue.is_empty()
+            || !self.approval_decisions.is_empty()
+            || !self.finished_agents.is_empty()
+    }
+
+    /// Agents occupying a `max_agents` slot (finished agents do not).
+    fn live_agent_count(&self) -> usize {
+        live_agent_count(&self.agents)
+    }
+
+    /// Queue the agents of the pipeline's current stage as spawn requests;
+    /// they start as `max_agents` slots free up, like other spawns.
+    fn queue_pipeline_stage(&mut self) {
+        let Some(pipeline) = self.pipeline.as_mut() else {
+            return;
+        };
+        for task in pipeline.stage_tasks() {
+            tracing::info!(stage = %task.stage, branch = ?task.branch, agent = %task.agent,
+                "queueing pipeline stage");
+            pipeline.task_queued();
+            self.spawn_queue.push(SpawnAgentInput {
+                role: task.agent,
+                message: task.prompt,
+                requested_by: None,
+                request_id: None,
+                pipeline_task: Some(PipelineTaskRef {
+                    stage: task.stage,
+                    branch: task.branch,
+                }),
+            });
+        }
+    }
+
+    /// Record the result of a pipeline agent.  Returns `false` if the agent
+    /// is not part of the running pipeline stage.
+    fn pipeline_task_finished(&mut self, agent_id: &str, outcome: Result<String, String>) -> bool {
+        let Some(pipeline) = self.pipeline.as_mut().filter(|p| p.owns(agent_id)) else {
+            return false;
+        };
+        if pipeline.task_finished(agent_id, outcome) {
+            self.pipeline_advanced = !pipeline.is_finished();
+            self.report_pipeline_progress();
+        }
+        true
+    }
+
+    /// Queue a session message to the main agent describing the pipeline's
+    /// latest step.  The final result wakes the main agent for a turn.
+    fn report_pipeline_progress(&mut self) {
+        let Some(pipeline) = self.pipeline.as_ref() else {
+            return;
+        };
+        let last = pipeline.history.last();
+        let (body, wake) = match pipeline.status {
+            PipelineStatus::Failed => (
+                format!(
+                    "Pipeline failed: {}",
+                    pipeline.error.as_deref().unwrap_or("unknown error")
+                ),
+                true,
+            ),
+            PipelineStatus::Completed => (
+                format!(
+                    "Pipeline completed. Final output:\n{}",
+                    pipeline.final_output().unwrap_or_default()
+                ),
+                true,
+            ),
+            PipelineStatus::Running => match last {
+                Some(result) => (
+                    format!(
+                        "Pipeline stage '{}' finished (run {}):\n{}",
+                        result.stage, result.iteration, result.output
+                    ),
+                    false,
+                ),
+                None => return,
+            },
+        };
+        self.message_queue.push(AgentMessage {
+            seq: 0,
+            from: SESSION_MESSAGE_SENDER.to_string(),
+            to: self.main_agent_id(),
+            body,
+            wake,
+            delivered_to: Vec::new(),
+        });
+    }
 }
 
 #[workflow_methods]
@@ -104,10 +805,24 @@ impl SessionWorkflow {
                 project_context: Some(state.project_context.clone()),
                 mcp_tools: state.mcp_tools.clone(),
                 spawn_queue: Vec::new(),
-                agent_counter: state.agents.len() as u32,
-                max_agents: DEFAULT_MAX_AGENTS,
+                pending_spawns: state.pending_spawns.clone(),
+                agent_counter: state.agent_counter(),
+                max_agents: state.max_agents.unwrap_or(DEFAULT_MAX_AGENTS),
                 shutdown_requested: false,
                 crew_agents,
+                mcp_refresh_queue: Vec::new(),
+                disabled_mcp_servers: state.disabled_mcp_servers.clone(),
+                turn_notices: Vec::new(),
+                message_queue: Vec::new(),
+                messages: state.messages.clone(),
+                pipeline: state.pipeline.clone(),
+                pipeline_advanced: false,
+                control_queue: Vec::new(),
+                approvals: state.approvals.clone(),
+                supervisor_queue: Vec::new(),
+                approval_decisions: Vec::new(),
+                finished_agents: Vec::new(),
+                final_results: None,
                 input,
             };
         }
@@ -121,10 +836,24 @@ impl SessionWorkflow {
             project_context: None,
             mcp_tools: HashMap::new(),
             spawn_queue: Vec::new(),
+            pending_spawns: Vec::new(),
             agent_counter: 0,
             max_agents: DEFAULT_MAX_AGENTS,
             shutdown_requested: false,
             crew_agents,
+            mcp_refresh_queue: Vec::new(),
+            disabled_mcp_servers: BTreeSet::new(),
+            turn_notices: Vec::new(),
+            message_queue: Vec::new(),
+            messages: Vec::new(),
+            pipeline: None,
+            pipeline_advanced: false,
+            control_queue: Vec::new(),
+            approvals: Vec::new(),
+            supervisor_queue: Vec::new(),
+            approval_decisions: Vec::new(),
+            finished_agents: Vec::new(),
+            final_results: None,
         }
     }
 
@@ -136,12 +865,122 @@ impl SessionWorkflow {
         self.spawn_queue.push(input);
     }
 
+    /// Signal to re-run MCP discovery, optionally enabling or disabling
+    /// servers first.  The new tool set is pushed to every running agent.
+    #[signal]
+    pub fn refresh_mcp(
+        &mut self,
+        _ctx: &mut SyncWorkflowContext<Self>,
+        request: McpRefreshRequest,
+    ) {
+        self.mcp_refresh_queue.push(request);
+    }
+
+    /// Signal from an agent that it finished a turn.
+    #[signal]
+    pub fn agent_turn_complete(
+        &mut self,
+        _ctx: &mut SyncWorkflowContext<Self>,
+        notice: AgentTurnNotice,
+    ) {
+        self.turn_notices.push(notice);
+    }
+
+    /// Signal from an agent whose workflow ended, with its outcome.
+    #[signal]
+    pub fn agent_finished(
+        &mut self,
+        _ctx: &mut SyncWorkflowContext<Self>,
+        notice: AgentFinishedNotice,
+    ) {
+        self.finished_agents.push(notice);
+    }
+
+    /// Signal from an agent posting a message to other agents.
+    #[signal]
+    pub fn route_message(&mut self, _ctx: &mut SyncWorkflowContext<Self>, message: AgentMessage) {
+        self.message_queue.push(message);
+    }
+
+    /// Signal to pause, resume, cancel or terminate a single agent.
+    #[signal]
+    pub fn control_agent(
+        &mut self,
+        _ctx: &mut SyncWorkflowContext<Self>,
+        request: AgentControlRequest,
+    ) {
+        self.control_queue.push(request);
+    }
+
+    /// Signal from an agent waiting for an exec/patch approval.  Requests
+    /// of agents other than the supervisor are forwarded to it.
+    #[signal]
+    pub fn approval_requested(
+        &mut self,
+        _ctx: &mut SyncWorkflowContext<Self>,
+        approval: SessionApproval,
+    ) {
+        let requester_role = self
+            .agents
+            .iter()
+            .find(|a| a.agent_id == approval.agent_id)
+            .map(|a| a.role.as_str());
+        if let Some(supervisor) = self.input.approval_supervisor.as_deref()
+            && requester_role != Some(supervisor)
+        {
+            self.supervisor_queue.push(approval.clone());
+        }
+        self.approvals
+            .retain(|a| !(a.agent_id == approval.agent_id && a.call_id == approval.call_id));
+        self.approvals.push(approval);
+    }
+
+    /// Signal from an agent whose approval request was answered (by any
+    /// path) or abandoned.
+    #[signal]
+    pub fn approval_resolved(
+        &mut self,
+        _ctx: &mut SyncWorkflowContext<Self>,
+        resolved: ApprovalResolved,
+    ) {
+        self.approvals
+            .retain(|a| !(a.agent_id == resolved.agent_id && a.call_id == resolved.call_id));
+        self.supervisor_queue
+            .retain(|a| !(a.agent_id == resolved.agent_id && a.call_id == resolved.call_id));
+    }
+
+    /// Signal from the supervisor agent's `decide_approval` tool (workflows
+    /// cannot send updates).  Rejections are reported back to the agent as
+    /// a session event.
+    #[signal]
+    pub fn supervisor_decision(
+        &mut self,
+        _ctx: &mut SyncWorkflowContext<Self>,
+        decision: ApprovalDecisionRequest,
+    ) {
+        self.approval_decisions.push(decision);
+    }
+
     /// Signal to request graceful shutdown of all agents.
     #[signal]
     pub fn shutdown(&mut self, _ctx: &mut SyncWorkflowContext<Self>) {
         self.shutdown_requested = true;
     }
 
+    // ----- updates -----
+
+    /// Approve or deny a pending approval (from a client).  Fails if there
+    /// is no such pending approval, the decider may not decide it, or the
+    /// waiting agent cannot be reached.
+    #[update]
+    pub async fn decide_approval(
+        ctx: &mut WorkflowContext<Self>,
+        decision: ApprovalDecisionRequest,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        relay_approval_decision(ctx, &decision).await?;
+        Ok(())
+    }
+
     // ----- queries -----
 
     /// Return JSON-serialized list of tracked agents.
@@ -150,15 +989,111 @@ impl SessionWorkflow {
         serde_json::to_string(&self.agents).unwrap_or_else(|_| "[]".to_string())
     }
 
+    /// Return the JSON-serialized inter-agent message log.
+    #[query]
+    pub fn list_messages(&self, _ctx: &WorkflowContextView) -> String {
+        serde_json::to_string(&self.messages).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Return the JSON-serialized approval requests waiting for a decision.
+    #[query]
+    pub fn pending_approvals(&self, _ctx: &WorkflowContextView) -> String {
+        serde_json::to_string(&self.approvals).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Return the JSON-serialized crew pipeline state (`null` without one).
+    #[query]
+    pub fn pipeline_status(&self, _ctx: &WorkflowContextView) -> String {
+        serde_json::to_string(&self.pipeline).unwrap_or_else(|_| "null".to_string())
+    }
+
+    /// Return the crew results (JSON `CrewResults`; `null` for sessions
+    /// without declared results).  Once the session has ended these are the
+    /// final results with the artifact contents; before, the outputs
+    /// extracted so far (artifacts are collected when the session ends).
+    #[query]
+    pub fn crew_results(&self, _ctx: &WorkflowContextView) -> String {
+        if let Some(results) = &self.final_results {
+            return serde_json::to_string(results).unwrap_or_else(|_| "null".to_string());
+        }
+        let results = self.input.crew_results.as_ref().map(|spec| {
+            let (outputs, missing_outputs) =
+                spec.extract_outputs(&self.agents, self.pipeline.as_ref());
+            CrewResults {
+                outputs,
+                missing_outputs,
+                ..Default::default()
+            }
+        });
+        serde_json::to_string(&results).unwrap_or_else(|_| "null".to_string())
+    }
+
+    /// Return the JSON-serialized list of MCP servers disabled at runtime.
+    #[query]
+    pub fn disabled_mcp_servers(&self, _ctx: &WorkflowContextView) -> String {
+        serde_json::to_string(&self.disabled_mcp_servers).unwrap_or_else(|_| "[]".to_string())
+    }
+
     // ----- run -----
 
+    /// Runs the session, stops the agents still running and reports its
+    /// final status (`Completed`, or `Failed` if it fails) to the harness it
+    /// registered with.
     #[run]
     pub async fn run(ctx: &mut WorkflowContext<Self>) -> WorkflowResult<SessionWorkflowOutput> {
+        let result = Self::run_session(ctx).await;
+        let status = match &result {
+            Ok(_) => SessionStatus::Completed,
+            Err(WorkflowTermination::ContinueAsNew(_)) => return result,
+            Err(_) => SessionStatus::Failed,
+        };
+        stop_live_agents(ctx).await;
+        if let Some(registration) = ctx.state(|s| s.input.harness_registration.clone()) {
+            let update = (ctx.state(|s| s.session_id.clone()), status);
+            signal_target(ctx, &registration.harness_id, "update_session_status", &update).await;
+        }
+        result
+    }
+
+    /// The session's main body: startup, the main agent and the control loop.
+    async fn run_session(ctx: &mut WorkflowContext<Self>) -> WorkflowResult<SessionWorkflowOutput> {
         let input = ctx.state(|s| s.input.clone());
         let session_id = ctx.state(|s| s.session_id.clone());
 
+        // Register with the harness first, so a failing startup is reported too.
+        let mut registered = None;
+        if let Some(registration) = &input.harness_registration
+            && input.continued_state.is_none()
+        {
+            let created_at_millis = workflow_millis(ctx);
+            let name = registration.name.clone().or_else(|| {
+                let preview = preview_text(&input.user_message);
+                (!preview.is_empty()).then_some(preview)
+            });
+            let entry = SessionEntry {
+                session_id: session_id.clone(),
+                name,
+                model: input.model.clone(),
+                created_at_millis,
+                status: SessionStatus::Running,
+                crew_type: registration.crew_type.clone(),
+                schedule_id: registration.schedule_id.clone(),
+                last_activity_millis: created_at_millis,
+                last_message_preview: None,
+                pinned: false,
+                archived_at_millis: None,
+                title: None,
+                tags: Default::default(),
+                cwd: None,
+                repository: None,
+                branch: None,
+            };
+            register_with_harness(ctx, &registration.harness_id, &entry).await;
+            registered = Some((registration.harness_id.clone(), entry));
+        }
+
         // --- Phase 1: load config + project context (or restore from CAN) ---
-        let (config_toml, project_context, mcp_tools) = {
+        let (config_toml, project_context, mut mcp_tools) = {
             let existing = ctx.state(|s| {
                 (
                     s.config_toml.clone(),
@@ -185,18 +1120,17 @@ impl SessionWorkflow {
             s.mcp_tools = mcp_tools.clone();
         });
 
+        // Complete the registration with the session's workspace.
+        if let Some((harness_id, mut entry)) = registered {
+            entry.set_workspace(&project_context);
+            signal_target(ctx, &harness_id, "register_session", &entry).await;
+        }
+
         // Inject crew agent definitions into the config TOML so they appear
         // as `[agents.<name>]` entries in `config.agent_roles`, making them
         // visible in the `spawn_agent` tool description.
         let crew_agents = ctx.state(|s| s.crew_agents.clone());
-        let config_toml = if crew_agents.is_empty() {
-            config_toml
-        } else {
-            inject_crew_roles_into_toml(&config_toml, &crew_agents).unwrap_or_else(|e| {
-                tracing::warn!("failed to inject crew roles into TOML: {e}");
-                config_toml
-            })
-        };
+        let mut config_toml = with_crew_roles(config_toml, &crew_agents);
 
         // Parse max_agents from config.
         if let Ok(config) = config_from_toml(
@@ -210,182 +1144,423 @@ impl SessionWorkflow {
             ctx.state_mut(|s| s.max_agents = max_threads);
         }
 
-        // --- Phase 2: start the main agent ---
-        let main_agent_id = format!("{session_id}/main");
-        let main_input = AgentWorkflowInput::from_session(
-            &input,
-            input.user_message.clone(),
-            input.model.clone(),
-            input.instructions.clone(),
-            "default".to_string(),
-            config_toml.clone(),
-            project_context.clone(),
-            mcp_tools.clone(),
-        );
+        // Results of children started by this run, awaited alongside the
+        // control loop.  Agents restored from continue-as-new report their
+        // outcome with the `agent_finished` signal instead.
+        let mut child_results: FuturesUnordered<ChildResultFuture> = FuturesUnordered::new();
+
+        if input.continued_state.is_some() {
+            reconcile_restored_agents(ctx).await;
+        } else {
+            // --- Phase 2: start the main agent ---
+            let main_agent_id = format!("{session_id}/main");
+            let mut main_input = AgentWorkflowInput::from_session(
+                &input,
+                input.user_message.clone(),
+                input.model.clone(),
+                input.instructions.clone(),
+                "default".to_string(),
+                config_toml.clone(),
+                project_context.clone(),
+                mcp_tools.clone(),
+            );
+            main_input.output_schema = input.output_schema.clone();
+            // A reopened session restores the main agent's conversation; the
+            // new message becomes its next turn.
+            if let Some(mut state) = input.main_agent_state.clone() {
+                state.mcp_tools = mcp_tools.clone();
+                if !input.user_message.is_empty() {
+                    state.queue_turn(
+                        input.user_message.clone(),
+                        input.reasoning_effort,
+                        input.reasoning_summary,
+                        input.personality,
+                    );
+                    if let Some(turn) = state.pending_user_turns.last_mut() {
+                        turn.output_schema = input.output_schema.clone();
+                    }
+                }
+                main_input.continued_state = Some(state);
+            }
+
+            let child = ctx.child_workflow(ChildWorkflowOptions {
+                workflow_id: main_agent_id.clone(),
+                workflow_type: "AgentWorkflow".to_string(),
+                task_queue: Some(TASK_QUEUE.to_string()),
+                input: vec![main_input.as_json_payload().map_err(|e| {
+                    WorkflowTermination::failed(anyhow::anyhow!(
+                        "failed to serialize main agent input: {e}"
+                    ))
+                })?],
+                parent_close_policy: ParentClosePolicy::Abandon,
+                ..Default::default()
+            });
 
-        let child = ctx.child_workflow(ChildWorkflowOptions {
-            workflow_id: main_agent_id.clone(),
-            workflow_type: "AgentWorkflow".to_string(),
-            task_queue: Some(TASK_QUEUE.to_string()),
-            input: vec![main_input.as_json_payload().map_err(|e| {
+            let pending = child.start().await;
+            let started = pending.into_started().ok_or_else(|| {
                 WorkflowTermination::failed(anyhow::anyhow!(
-                    "failed to serialize main agent input: {e}"
+                    "failed to start main agent workflow"
                 ))
-            })?],
-            parent_close_policy: ParentClosePolicy::Terminate,
-            ..Default::default()
-        });
+            })?;
 
-        let pending = child.start().await;
-        let _started = pending.into_started().ok_or_else(|| {
-            WorkflowTermination::failed(anyhow::anyhow!(
-                "failed to start main agent workflow"
-            ))
-        })?;
+            child_results.push(await_child(main_agent_id.clone(), started));
 
-        ctx.state_mut(|s| {
-            s.agents.push(AgentRecord {
-                agent_id: main_agent_id.clone(),
-                workflow_id: main_agent_id,
-                role: "default".to_string(),
-                status: AgentLifecycle::Running,
+            ctx.state_mut(|s| {
+                s.agents.push(AgentRecord::running(main_agent_id, "default"));
+                s.agent_counter = 1;
             });
-            s.agent_counter = 1;
-        });
 
-        tracing::info!("main agent started");
+            tracing::info!("main agent started");
+        }
+
+        // Kick off the crew pipeline, if any.
+        ctx.state_mut(|s| {
+            if s.pipeline.is_none() && !s.input.pipeline.is_empty() {
+                s.pipeline = Some(PipelineRun::new(s.input.pipeline.clone()));
+                s.queue_pipeline_stage();
+            }
+        });
 
         // --- Phase 3: control loop ---
         loop {
-            ctx.wait_condition(|s| {
-                !s.spawn_queue.is_empty() || s.shutdown_requested
-            })
-            .await;
+            let finished = {
+                let work = ctx.wait_condition(|s| {
+                    s.has_queued_signals()
+                        || (!s.pending_spawns.is_empty() && s.live_agent_count() < s.max_agents)
+                        || s.shutdown_requested
+                });
+                if child_results.is_empty() {
+                    work.await;
+                    None
+                } else {
+                    match futures::future::select(Box::pin(work), child_results.next()).await {
+                        Either::Left(_) => None,
+                        Either::Right((finished, _)) => finished,
+                    }
+                }
+            };
 
-            // Check shutdown.
-            let shutdown = ctx.state(|s| s.shutdown_requested);
-            if shutdown {
-                tracing::info!("shutdown requested, exiting control loop");
-                break;
+            // A child finished — record its outcome and keep waiting.
+            if let Some((agent_id, result)) = finished {
+                record_agent_finished(ctx, &agent_id, agent_outcome(result)).await;
+                continue;
+            }
+            let finished_agents: Vec<AgentFinishedNotice> =
+                ctx.state_mut(|s| std::mem::take(&mut s.finished_agents));
+            for notice in finished_agents {
+                record_agent_finished(ctx, &notice.agent_id, notice.outcome).await;
             }
 
-            // Process spawn queue.
-            let spawn_requests: Vec<SpawnAgentInput> =
-                ctx.state_mut(|s| std::mem::take(&mut s.spawn_queue));
-
-            for spawn_input in spawn_requests {
-                let (current_count, max) =
-                    ctx.state(|s| (s.agents.len(), s.max_agents));
-
-                if current_count >= max {
-                    tracing::warn!(
-                        current = current_count,
-                        max = max,
-                        role = %spawn_input.role,
-                        "max_agents limit reached, ignoring spawn request"
-                    );
+            // Report the activity to the harness, record each agent's latest
+            // message and forward turn completions of tool-spawned agents to
+            // their spawner.
+            let turn_notices: Vec<AgentTurnNotice> =
+                ctx.state_mut(|s| std::mem::take(&mut s.turn_notices));
+            if let (Some(registration), Some(latest)) =
+                (&input.harness_registration, turn_notices.last())
+            {
+                let activity = SessionActivity {
+                    session_id: session_id.clone(),
+                    at_millis: workflow_millis(ctx),
+                    preview: latest
+                        .last_agent_message
+                        .as_deref()
+                        .map(preview_text)
+                        .filter(|p| !p.is_empty()),
+                };
+                signal_target(ctx, &registration.harness_id, "record_session_activity", &activity)
+                    .await;
+            }
+            for turn in turn_notices {
+                ctx.state_mut(|s| {
+                    if let Some(record) = s.agents.iter_mut().find(|a| a.agent_id == turn.agent_id)
+                    {
+                        record.last_agent_message = turn.last_agent_message.clone();
+                        record.iterations = turn.iterations;
+                        record.token_usage = turn.token_usage.clone();
+                    }
+                });
+                // A pipeline agent's first turn is its stage output; it is
+                // then shut down.
+                let pipeline_agent = ctx.state_mut(|s| {
+                    let output = turn.last_agent_message.clone().unwrap_or_default();
+                    s.pipeline_task_finished(&turn.agent_id, Ok(output))
+                });
+                if pipeline_agent {
+                    signal_target(ctx, &turn.agent_id, "receive_op", &Op::Shutdown).await;
                     continue;
                 }
-
-                let agent_num = ctx.state_mut(|s| {
-                    s.agent_counter += 1;
-                    s.agent_counter
+                let parent = ctx.state(|s| {
+                    s.agents
+                        .iter()
+                        .find(|a| a.agent_id == turn.agent_id)
+                        .and_then(|a| a.parent_agent_id.clone())
                 });
+                if let Some(parent) = parent {
+                    let notice = SubagentNotice::TurnComplete {
+                        agent_id: turn.agent_id,
+                        last_agent_message: turn.last_agent_message,
+                    };
+                    notify_agent(ctx, &parent, notice).await;
+                }
+            }
 
-                let agent_role = spawn_input.role.clone();
-                let agent_id = format!("{session_id}/{}-{}", agent_role, agent_num);
-
-                // Look up crew agent definition (if any).
-                let crew_def = ctx.state(|s| s.crew_agents.get(&agent_role).cloned());
-
-                // Resolve role config: crew-aware resolution.
-                let (resolved_config_toml, resolved_model, resolved_instructions) =
-                    if let Some(ref crew_agent) = crew_def {
-                        if let Some(ref base_role) = crew_agent.role {
-                            // Crew agent with base role — resolve then overlay crew values.
-                            match resolve_role(
-                                ctx, &config_toml, &project_context.cwd, base_role,
-                                crew_agent.model.as_deref(),
-                                crew_agent.instructions.as_deref(),
-                                &input.model, &input.instructions,
-                            ).await {
-                                Ok(resolved) => resolved,
-                                Err(e) => {
-                                    tracing::error!(role = %agent_role, base_role = %base_role, error = %e,
-                                        "failed to resolve base role for crew agent, skipping spawn");
-                                    continue;
-                                }
-                            }
-                        } else {
-                            // Pure inline crew agent — no activity call needed.
-                            let model = crew_agent.model.clone().unwrap_or_else(|| input.model.clone());
-                            let instructions = crew_agent.instructions.clone().unwrap_or_else(|| input.instructions.clone());
-                            (config_toml.clone(), model, instructions)
+            // Apply per-agent lifecycle actions.
+            let control_requests: Vec<AgentControlRequest> =
+                ctx.state_mut(|s| std::mem::take(&mut s.control_queue));
+            for request in control_requests {
+                let target = ctx.state(|s| {
+                    let record = s.agents.iter().find(|a| a.matches_id(&request.agent_id))?;
+                    let allowed = match request.action {
+                        AgentControlAction::Pause => record.status == AgentLifecycle::Running,
+                        AgentControlAction::Resume => record.status == AgentLifecycle::Paused,
+                        // Terminate also stops an agent still finishing a
+                        // cancelled turn.
+                        AgentControlAction::Cancel => record.status.is_live(),
+                        AgentControlAction::Terminate => {
+                            record.status.is_live() || record.status == AgentLifecycle::Cancelled
                         }
-                    } else if agent_role != "default" {
-                        // Non-crew, non-default role — resolve via activity.
-                        match resolve_role(
-                            ctx, &config_toml, &project_context.cwd, &agent_role,
-                            None, None, &input.model, &input.instructions,
-                        ).await {
-                            Ok(resolved) => resolved,
-                            Err(e) => {
-                                tracing::error!(role = %agent_role, error = %e,
-                                    "failed to resolve role config, skipping spawn");
-                                continue;
+                    };
+                    if !allowed {
+                        tracing::warn!(agent_id = %record.agent_id, status = ?record.status,
+                            action = ?request.action, "ignoring agent control action");
+                        return None;
+                    }
+                    Some((record.agent_id.clone(), record.workflow_id.clone()))
+                });
+                let Some((agent_id, workflow_id)) = target else {
+                    tracing::warn!(agent_id = %request.agent_id,
+                        "control_agent: no matching agent in a valid state");
+                    continue;
+                };
+                tracing::info!(agent_id = %workflow_id, action = ?request.action,
+                    "applying agent control action");
+                let applied = match request.action {
+                    AgentControlAction::Terminate => terminate_agent(ctx, &workflow_id).await,
+                    action => signal_workflow!(ctx, &workflow_id, "control", &action)
+                        .map(|()| false),
+                };
+                match applied {
+                    Ok(terminated) => {
+                        ctx.state_mut(|s| {
+                            if let Some(record) =
+                                s.agents.iter_mut().find(|a| a.agent_id == agent_id)
+                            {
+                                record.status = request.action.status();
                             }
+                        });
+                        // A terminated workflow reports no outcome itself.
+                        if terminated {
+                            let outcome = Err("agent terminated".to_string());
+                            record_agent_finished(ctx, &agent_id, outcome).await;
                         }
-                    } else {
-                        (config_toml.clone(), input.model.clone(), input.instructions.clone())
-                    };
+                    }
+                    Err(e) => tracing::warn!(agent_id = %workflow_id, error = %e,
+                        action = ?request.action, "agent control action failed"),
+                }
+            }
 
-                let child_input = AgentWorkflowInput::from_session(
-                    &input,
-                    spawn_input.message,
-                    resolved_model,
-                    resolved_instructions,
-                    agent_role.clone(),
-                    resolved_config_toml,
-                    project_context.clone(),
-                    mcp_tools.clone(),
+            // Forward new approval requests to the supervisor as messages
+            // that start a turn; they are routed below.
+            let forwarded: Vec<SessionApproval> =
+                ctx.state_mut(|s| std::mem::take(&mut s.supervisor_queue));
+            if let Some(supervisor) = input.approval_supervisor.as_deref() {
+                let to = approval_supervisor_address(supervisor).to_string();
+                ctx.state_mut(|s| {
+                    for approval in forwarded {
+                        let mut body = format!(
+                            "Approval requested: {}.\nagent_id: {}\ncall_id: {}",
+                            approval.summary(),
+                            approval.agent_id,
+                            approval.call_id,
+                        );
+                        if let Some(reason) = &approval.reason {
+                            body.push_str(&format!("\nreason: {reason}"));
+                        }
+                        body.push_str("\nUse decide_approval to approve or deny it.");
+                        s.message_queue.push(AgentMessage {
+                            seq: 0,
+                            from: SESSION_MESSAGE_SENDER.to_string(),
+                            to: to.clone(),
+                            body,
+                            wake: true,
+                            delivered_to: Vec::new(),
+                        });
+                    }
+                });
+            }
+
+            // Relay approval decisions to the waiting agents.
+            let decisions: Vec<ApprovalDecisionRequest> =
+                ctx.state_mut(|s| std::mem::take(&mut s.approval_decisions));
+            for decision in decisions {
+                if let Err(e) = relay_approval_decision(ctx, &decision).await {
+                    tracing::warn!(error = %e, "decide_approval rejected");
+                    if let Some(decided_by) = &decision.decided_by {
+                        let message = format!("Approval decision rejected: {e}");
+                        signal_target(ctx, decided_by, "session_event", &message).await;
+                    }
+                }
+            }
+
+            // Route inter-agent messages.
+            let posted: Vec<AgentMessage> =
+                ctx.state_mut(|s| std::mem::take(&mut s.message_queue));
+            for mut message in posted {
+                message.seq = ctx.state(|s| s.messages.last().map_or(1, |m| m.seq + 1));
+                let logged = route_message(ctx, message).await;
+                tracing::info!(
+                    from = %logged.from,
+                    to = %logged.to,
+                    recipients = logged.delivered_to.len(),
+                    "routed agent message"
                 );
+                ctx.state_mut(|s| {
+                    s.messages.push(logged);
+                    if s.messages.len() > MAX_MESSAGE_LOG {
+                        let excess = s.messages.len() - MAX_MESSAGE_LOG;
+                        s.messages.drain(..excess);
+                    }
+                });
+            }
 
-                let child = ctx.child_workflow(ChildWorkflowOptions {
-                    workflow_id: agent_id.clone(),
-                    workflow_type: "AgentWorkflow".to_string(),
-                    task_queue: Some(TASK_QUEUE.to_string()),
-                    input: vec![child_input.as_json_payload().map_err(|e| {
-                        WorkflowTermination::failed(anyhow::anyhow!(
-                            "failed to serialize agent input: {e}"
-                        ))
-                    })?],
-                    parent_close_policy: ParentClosePolicy::Terminate,
-                    ..Default::default()
+            // Check shutdown.
+            let shutdown = ctx.state(|s| s.shutdown_requested);
+            if shutdown {
+                tracing::info!("shutdown requested, exiting control loop");
+                break;
+            }
+
+            // Process MCP refresh requests (coalesced into one discovery).
+            let refresh_requests: Vec<McpRefreshRequest> =
+                ctx.state_mut(|s| std::mem::take(&mut s.mcp_refresh_queue));
+            if !refresh_requests.is_empty() {
+                let disabled = ctx.state_mut(|s| {
+                    for request in &refresh_requests {
+                        request.apply(&mut s.disabled_mcp_servers);
+                    }
+                    s.disabled_mcp_servers.clone()
                 });
+                let (new_config_toml, mut update) =
+                    refresh_mcp_tools(ctx, &project_context.cwd, &mcp_tools, disabled).await;
+                let mut config_changed = false;
+                if let Some(raw_config_toml) = new_config_toml {
+                    config_changed = ctx.state_mut(|s| {
+                        let changed = s.config_toml.as_ref() != Some(&raw_config_toml);
+                        s.config_toml = Some(raw_config_toml.clone());
+                        changed
+                    });
+                    let crew_agents = ctx.state(|s| s.crew_agents.clone());
+                    config_toml = with_crew_roles(raw_config_toml.clone(), &crew_agents);
+                    update.config_toml = Some(raw_config_toml);
+                }
+                if update.is_empty() && !config_changed {
+                    tracing::info!("MCP refresh: tool set and config unchanged");
+                } else {
+                    tracing::info!(changes = %update.summary(), config_changed,
+                        "MCP refresh: tool set or config changed");
+                    mcp_tools = update.mcp_tools.clone();
+                    ctx.state_mut(|s| s.mcp_tools = mcp_tools.clone());
+                    notify_agents_of_mcp_tools(ctx, &update).await;
+                }
+            }
 
-                match child.start().await.into_started() {
-                    Some(_started) => {
-                        ctx.state_mut(|s| {
-                            s.agents.push(AgentRecord {
-                                agent_id: agent_id.clone(),
-                                workflow_id: agent_id.clone(),
-                                role: agent_role,
-                                status: AgentLifecycle::Running,
+            // Admit spawn requests — queued ones first — while agent slots
+            // are free; the rest wait until a running agent finishes.
+            let new_requests: Vec<SpawnAgentInput> =
+                ctx.state_mut(|s| std::mem::take(&mut s.spawn_queue));
+            let previously_queued = ctx.state_mut(|s| {
+                let queued = s.pending_spawns.len();
+                s.pending_spawns.extend(new_requests);
+                queued
+            });
+
+            let mut admitted = 0;
+            loop {
+                let next = ctx.state_mut(|s| {
+                    admit_pending_spawn(&s.agents, &mut s.pending_spawns, s.max_agents)
+                });
+                let Some(spawn_input) = next else {
+                    break;
+                };
+                admitted += 1;
+                // Skip agents of a pipeline that failed while they waited.
+                if spawn_input.pipeline_task.is_some()
+                    && ctx.state(|s| s.pipeline.as_ref().is_none_or(PipelineRun::is_finished))
+                {
+                    continue;
+                }
+                match start_agent(
+                    ctx, &session_id, &input, &config_toml, &project_context, &mcp_tools,
+                    &spawn_input,
+                )
+                .await
+                {
+                    Ok((agent_id, started)) => {
+                        child_results.push(await_child(agent_id.clone(), started));
+                        if let Some(task) = &spawn_input.pipeline_task {
+                            ctx.state_mut(|s| {
+                                if let Some(pipeline) = s.pipeline.as_mut() {
+                                    pipeline.task_started(agent_id.clone(), task.branch.clone());
+                                }
                             });
-                        });
-                        tracing::info!(agent_id = %agent_id, "child agent started");
-                    }
-                    None => {
-                        tracing::error!(
-                            agent_id = %agent_id,
-                            "failed to start child agent"
-                        );
+                        }
+                        if let (Some(requester), Some(request_id)) =
+                            (&spawn_input.requested_by, &spawn_input.request_id)
+                        {
+                            let notice = SubagentNotice::Spawned {
+                                request_id: request_id.clone(),
+                                agent_id,
+                                role: spawn_input.role.clone(),
+                            };
+                            notify_agent(ctx, requester, notice).await;
+                        }
                     }
+                    Err(e) => reject_spawn(ctx, &spawn_input, e).await,
                 }
             }
 
-            // Check if CAN is suggested.
-            if ctx.continue_as_new_suggested() {
+            // Report requests that just joined the queue; reject overflow.
+            let (newly_queued, overflow, max) = ctx.state_mut(|s| {
+                let overflow = if s.pending_spawns.len() > MAX_PENDING_SPAWNS {
+                    s.pending_spawns.split_off(MAX_PENDING_SPAWNS)
+                } else {
+                    Vec::new()
+                };
+                let first_new = previously_queued.saturating_sub(admitted);
+                let newly_queued: Vec<(usize, SpawnAgentInput)> = s
+                    .pending_spawns
+                    .iter()
+                    .enumerate()
+                    .skip(first_new)
+                    .map(|(i, spawn)| (i + 1, spawn.clone()))
+                    .collect();
+                (newly_queued, overflow, s.max_agents)
+            });
+            for (position, spawn_input) in newly_queued {
+                report_spawn_queued(ctx, &spawn_input, position, max).await;
+            }
+            for spawn_input in overflow {
+                tracing::warn!(role = %spawn_input.role, "spawn queue full, rejecting spawn request");
+                let error = format!(
+                    "max_agents limit ({max}) reached and {MAX_PENDING_SPAWNS} spawns are \
+                     already queued"
+                );
+                reject_spawn(ctx, &spawn_input, error).await;
+            }
+
+            // Queue the next pipeline stage once the previous one finished;
+            // its agents are admitted on the next round.
+            ctx.state_mut(|s| {
+                if std::mem::take(&mut s.pipeline_advanced) {
+                    s.queue_pipeline_stage();
+                }
+            });
+
+            // Check if CAN is suggested.  Signals that arrived while this
+            // round was handled are processed first: the control queues are
+            // not part of the carried state.
+            if ctx.continue_as_new_suggested() && !ctx.state(|s| s.has_queued_signals()) {
                 tracing::info!("server suggested continue-as-new for session");
                 let agents = ctx.state(|s| s.agents.clone());
                 let crew_agents = ctx.state(|s| s.crew_agents.clone());
@@ -395,10 +1570,18 @@ impl SessionWorkflow {
                     project_context: project_context.clone(),
                     mcp_tools: mcp_tools.clone(),
                     crew_agents,
+                    disabled_mcp_servers: ctx.state(|s| s.disabled_mcp_servers.clone()),
+                    messages: ctx.state(|s| s.messages.clone()),
+                    pipeline: ctx.state(|s| s.pipeline.clone()),
+                    max_agents: Some(ctx.state(|s| s.max_agents)),
+                    pending_spawns: ctx.state(|s| s.pending_spawns.clone()),
+                    approvals: ctx.state(|s| s.approvals.clone()),
+                    agent_counter: Some(ctx.state(|s| s.agent_counter)),
                 };
 
                 let mut can_input = input.clone();
                 can_input.user_message = String::new();
+                can_input.main_agent_state = None;
                 can_input.continued_state = Some(state);
 
                 return Err(WorkflowTermination::continue_as_new(
@@ -414,21 +1597,58 @@ impl SessionWorkflow {
             }
         }
 
+        // Extract crew outputs and collect artifacts.
+        let results = match &input.crew_results {
+            Some(spec) => {
+                let (outputs, missing_outputs) =
+                    ctx.state(|s| spec.extract_outputs(&s.agents, s.pipeline.as_ref()));
+                let mut results = CrewResults {
+                    outputs,
+                    missing_outputs,
+                    ..Default::default()
+                };
+                if !spec.artifacts.is_empty() {
+                    let collect_input = CollectArtifactsInput {
+                        cwd: project_context.cwd.clone(),
+                        paths: spec.artifacts.clone(),
+                    };
+                    match ctx
+                        .start_activity(
+                            CodexActivities::collect_artifacts,
+                            collect_input,
+                            activity_opts(60),
+                        )
+                        .await
+                    {
+                        Ok(collected) => {
+                            results.artifacts = collected.artifacts;
+                            results.artifact_errors = collected.errors;
+                        }
+                        Err(e) => {
+                            tracing::warn!(error = %e, "collect_artifacts failed");
+                            results.artifact_errors.push(format!("collection failed: {e}"));
+                        }
+                    }
+                }
+                Some(results)
+            }
+            None => None,
+        };
+
+        ctx.state_mut(|s| s.final_results = results.clone());
+
+        // The harness keeps a summary; artifact contents stay in the session.
+        if let Some(registration) = &input.harness_registration
+            && let Some(results) = &results
+        {
+            let payload = (session_id.clone(), results.summary());
+            signal_target(ctx, &registration.harness_id, "record_session_results", &payload).await;
+        }
+
         // Build output summary.
-        let agents = ctx.state(|s| {
-            s.agents
-                .iter()
-                .map(|a| AgentSummary {
-                    agent_id: a.agent_id.clone(),
-                    role: a.role.clone(),
-                    status: a.status,
-                    iterations: 0,
-                    token_usage: None,
-                })
-                .collect()
-        });
+        let agents = ctx.state(|s| s.agents.iter().map(AgentRecord::summary).collect());
 
-        Ok(SessionWorkflowOutput { agents })
+        Ok(SessionWorkflowOutput { agents, results })
     }
 }
 
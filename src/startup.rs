@@ -1,4 +1,5 @@
-//! Shared startup helpers for loading config, project context, and MCP tools.
+//! Shared workflow helpers for loading config, project context, and MCP tools,
+//! and for signalling other workflows.
 
 /// Load config and project context via Temporal activities (in parallel).
 ///
@@ -33,10 +34,14 @@ macro_rules! load_config_and_context {
 /// Discover MCP tools via a Temporal activity.
 ///
 /// Returns `HashMap<String, Value>`, falling back to an empty map on failure.
+/// The optional fourth argument lists servers to skip (disabled at runtime).
 ///
 /// `$ctx` must be a `&mut WorkflowContext<T>` that supports `start_activity`.
 macro_rules! discover_mcp {
-    ($ctx:expr, $config_toml:expr, $cwd:expr) => {{
+    ($ctx:expr, $config_toml:expr, $cwd:expr) => {
+        crate::startup::discover_mcp!($ctx, $config_toml, $cwd, Vec::new())
+    };
+    ($ctx:expr, $config_toml:expr, $cwd:expr, $disabled_servers:expr) => {{
         use crate::activities::{activity_opts, CodexActivities};
         use crate::types::{McpDiscoverInput, McpDiscoverOutput};
         use std::collections::HashMap;
@@ -44,6 +49,7 @@ macro_rules! discover_mcp {
         let mcp_discover_input = McpDiscoverInput {
             config_toml: $config_toml.clone(),
             cwd: $cwd.clone(),
+            disabled_servers: $disabled_servers,
         };
         let mcp_output: McpDiscoverOutput = $ctx
             .start_activity(
@@ -85,6 +91,36 @@ macro_rules! load_startup_context {
     }};
 }
 
+/// Send `$signal_name` with `$value` (any `AsJsonPayloadExt`) to workflow
+/// `$workflow_id`.
+///
+/// Returns `Result<(), String>` describing a serialization or signal failure;
+/// callers decide whether to log it or surface it.
+///
+/// `$ctx` must be a `&WorkflowContext<T>` (or `&mut`) for any workflow `T`.
+macro_rules! signal_workflow {
+    ($ctx:expr, $workflow_id:expr, $signal_name:expr, $value:expr) => {{
+        use temporalio_common::protos::coresdk::AsJsonPayloadExt;
+
+        let workflow_id: &str = $workflow_id;
+        let signal_name: &str = $signal_name;
+        match $value.as_json_payload() {
+            Ok(payload) => $ctx
+                .signal_workflow(temporalio_sdk::SignalWorkflowOptions::new(
+                    workflow_id,
+                    "",
+                    signal_name,
+                    [payload],
+                ))
+                .await
+                .map(|_| ())
+                .map_err(|e| format!("failed to signal {signal_name} to {workflow_id}: {e:?}")),
+            Err(e) => Err(format!("failed to serialize {signal_name} signal: {e}")),
+        }
+    }};
+}
+
 pub(crate) use discover_mcp;
 pub(crate) use load_config_and_context;
 pub(crate) use load_startup_context;
+pub(crate) use signal_workflow;
@@ -22,10 +22,23 @@ impl InMemoryStorage {
         }
     }
 
+    /// Create a storage pre-populated with `items` (e.g. the rollout carried
+    /// over a continue-as-new).
+    pub fn with_items(items: Vec<RolloutItem>) -> Self {
+        Self {
+            items: Mutex::new(items),
+        }
+    }
+
     /// Read all stored items.
     pub fn items(&self) -> Vec<RolloutItem> {
         self.items.lock().expect("lock poisoned").clone()
     }
+
+    /// Run `f` over the stored items without copying them.
+    pub fn read<R>(&self, f: impl FnOnce(&[RolloutItem]) -> R) -> R {
+        f(&self.items.lock().expect("lock poisoned"))
+    }
 }
 
 #[async_trait::async_trait]
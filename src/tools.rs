@@ -8,7 +8,7 @@
 //! 4. If approved, executes the tool as a Temporal activity
 //! 5. If denied, returns an error response
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::future::Future;
 use std::path::PathBuf;
 use std::pin::Pin;
@@ -21,25 +21,32 @@ use codex_core::ExecPolicyDecision as Decision;
 use codex_core::ToolCall;
 use codex_core::ToolCallHandler;
 use codex_protocol::models::{FunctionCallOutputPayload, ResponseInputItem, SandboxPermissions};
-use codex_protocol::approvals::{ElicitationRequest, ElicitationRequestEvent};
-use codex_protocol::dynamic_tools::DynamicToolCallRequest;
+use codex_protocol::approvals::{ElicitationAction, ElicitationRequestEvent};
+use codex_protocol::dynamic_tools::{DynamicToolCallRequest, DynamicToolSpec};
 use codex_protocol::models::FunctionCallOutputBody;
 use codex_protocol::permissions::FileSystemSandboxPolicy;
 use codex_protocol::protocol::{
-    AskForApproval, ApplyPatchApprovalRequestEvent, Event, EventMsg, ExecApprovalRequestEvent,
-    ExecCommandBeginEvent, ExecCommandEndEvent, ExecCommandSource, ExecCommandStatus,
-    PatchApplyBeginEvent, PatchApplyEndEvent, PatchApplyStatus, SandboxPolicy,
+    AskForApproval, ApplyPatchApprovalRequestEvent, BackgroundEventEvent, Event, EventMsg,
+    ExecApprovalRequestEvent, ExecCommandBeginEvent, ExecCommandEndEvent, ExecCommandSource,
+    ExecCommandStatus, Op, PatchApplyBeginEvent, PatchApplyEndEvent, PatchApplyStatus,
+    SandboxPolicy, WarningEvent,
 };
 use codex_protocol::request_user_input::{RequestUserInputArgs, RequestUserInputEvent};
+use codex_protocol::user_input::UserInput;
+use serde::Deserialize;
 use temporalio_common::protos::coresdk::workflow_commands::ActivityCancellationType;
 use temporalio_sdk::{ActivityOptions, CancellableFuture, WorkflowContext};
 use tokio_util::sync::CancellationToken;
 
 use crate::activities::CodexActivities;
 use crate::sink::BufferEventSink;
+use crate::startup::signal_workflow;
 use crate::types::{
-    McpToolCallInput, PendingApproval, PendingDynamicTool, PendingElicitation,
-    PendingPatchApproval, PendingUserInput, ToolExecInput,
+    AgentMessage, ApprovalDecisionRequest, ApprovalResolved, McpApprovalMode,
+    McpElicitationResolveInput, McpGetPromptInput, McpListInput, McpReadResourceInput,
+    McpRequestOutput, McpToolCallInput, McpToolCallOutput, PendingApproval, PendingDynamicTool,
+    PendingElicitation, PendingPatchApproval, PendingUserInput, SessionApproval, SpawnAgentInput,
+    SubagentNotice, ToolExecInput, short_agent_id,
 };
 use crate::workflow::AgentWorkflow;
 
@@ -71,7 +78,9 @@ async fn wait_for_resolution<T>(
 /// Set pending state, emit an event, bump version, then wait for resolution.
 ///
 /// Combines the common "set pending → emit → bump → wait → clear" sequence
-/// used by all approval/response flows.
+/// used by all approval/response flows.  Exec and patch approval requests of
+/// agents in a session are also reported to the session, which aggregates
+/// them across agents and can answer them.
 async fn request_and_wait<T>(
     ctx: &WorkflowContext<AgentWorkflow>,
     events: &BufferEventSink,
@@ -80,22 +89,38 @@ async fn request_and_wait<T>(
     get_resolved: impl Fn(&AgentWorkflow) -> Option<T>,
     clear: impl FnOnce(&mut AgentWorkflow),
 ) -> Option<T> {
+    let own_id = ctx.state(|s| s.workflow_id.clone());
+    let session_approval = session_of_agent(&own_id)
+        .and_then(|session_id| {
+            SessionApproval::from_event(&own_id, &event.msg).map(|a| (session_id.to_string(), a))
+        });
+
     ctx.state_mut(set_pending);
     AgentWorkflow::emit_and_bump(ctx, events, event);
-    wait_for_resolution(ctx, get_resolved, clear).await
+
+    let Some((session_id, approval)) = session_approval else {
+        return wait_for_resolution(ctx, get_resolved, clear).await;
+    };
+    if let Err(e) = signal_workflow!(ctx, &session_id, "approval_requested", &approval) {
+        tracing::warn!(error = %e, "failed to report approval request to session");
+    }
+    let resolved = wait_for_resolution(ctx, get_resolved, clear).await;
+    let done = ApprovalResolved {
+        agent_id: approval.agent_id,
+        call_id: approval.call_id,
+    };
+    if let Err(e) = signal_workflow!(ctx, &session_id, "approval_resolved", &done) {
+        tracing::warn!(error = %e, "failed to report approval resolution to session");
+    }
+    resolved
 }
 
 /// Execute an activity with cancellation support. On cancellation, returns
-/// a denied response. Builds `ActivityOptions` from the given timeout.
+/// a denied response. Builds `ActivityOptions` from the given timeout; the
+/// `@opts` form takes the options and evaluates to the activity's `Result`.
 macro_rules! run_with_cancellation {
-    ($ctx:expr, $activity_fn:expr, $input:expr, $timeout:expr, $cancel:expr, $call_id:expr, $label:expr) => {{
-        let opts = ActivityOptions {
-            start_to_close_timeout: Some(Duration::from_secs($timeout)),
-            heartbeat_timeout: Some(Duration::from_secs(30)),
-            cancellation_type: ActivityCancellationType::TryCancel,
-            ..Default::default()
-        };
-        let activity = $ctx.start_activity($activity_fn, $input, opts);
+    (@opts $ctx:expr, $activity_fn:expr, $input:expr, $opts:expr, $cancel:expr, $call_id:expr) => {{
+        let activity = $ctx.start_activity($activity_fn, $input, $opts);
         tokio::pin!(activity);
         tokio::select! {
             biased;
@@ -103,11 +128,33 @@ macro_rules! run_with_cancellation {
                 activity.cancel();
                 return Ok(denied_response($call_id.clone()));
             }
-            result = &mut activity => result.map_err(|e| {
-                CodexErr::Fatal(format!(concat!($label, " activity failed: {}"), e))
-            })?,
+            result = &mut activity => result,
         }
     }};
+    ($ctx:expr, $activity_fn:expr, $input:expr, $timeout:expr, $cancel:expr, $call_id:expr, $label:expr) => {{
+        let opts = ActivityOptions {
+            start_to_close_timeout: Some(Duration::from_secs($timeout)),
+            heartbeat_timeout: Some(Duration::from_secs(30)),
+            cancellation_type: ActivityCancellationType::TryCancel,
+            ..Default::default()
+        };
+        run_with_cancellation!(@opts $ctx, $activity_fn, $input, opts, $cancel, $call_id)
+            .map_err(|e| CodexErr::Fatal(format!(concat!($label, " activity failed: {}"), e)))?
+    }};
+}
+
+/// Options for activities that continue an MCP call pending on one worker:
+/// scheduled on that worker's own task queue, and failing fast when no
+/// worker polls it any more (e.g. after a restart).
+fn pending_call_opts(task_queue: Option<String>, timeout_secs: u64) -> ActivityOptions {
+    ActivityOptions {
+        schedule_to_start_timeout: task_queue.is_some().then(|| Duration::from_secs(30)),
+        start_to_close_timeout: Some(Duration::from_secs(timeout_secs)),
+        heartbeat_timeout: Some(Duration::from_secs(30)),
+        cancellation_type: ActivityCancellationType::TryCancel,
+        task_queue,
+        ..Default::default()
+    }
 }
 
 /// A [`ToolCallHandler`] that gates tool calls on client approval, then
@@ -129,8 +176,11 @@ pub struct TemporalToolHandler {
     /// Merged config TOML string to forward to tool-execution activities.
     config_toml: Option<String>,
     /// Set of qualified MCP tool names (e.g. "mcp__echo__echo").
-    /// Tool calls matching these names bypass approval and route to MCP.
+    /// Tool calls matching these names route to MCP.
     mcp_tool_names: HashSet<String>,
+    /// MCP tools that need approval before each call, with the mode gating
+    /// them (resolved from the `[mcp_tool_approval]` rules and annotations).
+    mcp_approval_tools: HashMap<String, McpApprovalMode>,
     /// Set of dynamic tool names (client-defined tools handled via signal/wait).
     dynamic_tool_names: HashSet<String>,
     /// Sandbox policy from the user's config — used by
@@ -151,6 +201,7 @@ impl TemporalToolHandler {
         cwd: String,
         config_toml: Option<String>,
         mcp_tool_names: HashSet<String>,
+        mcp_approval_tools: HashMap<String, McpApprovalMode>,
         dynamic_tool_names: HashSet<String>,
         sandbox_policy: SandboxPolicy,
     ) -> Self {
@@ -164,6 +215,7 @@ impl TemporalToolHandler {
             cwd,
             config_toml,
             mcp_tool_names,
+            mcp_approval_tools,
             dynamic_tool_names,
             sandbox_policy,
             file_system_sandbox_policy,
@@ -210,38 +262,64 @@ impl ToolCallHandler for TemporalToolHandler {
         let cwd = self.cwd.clone();
         let config_toml = self.config_toml.clone();
         let is_mcp_tool = self.mcp_tool_names.contains(&tool_name);
+        let mcp_needs_approval = self
+            .mcp_approval_tools
+            .get(&tool_name)
+            .is_some_and(|mode| mode.asks_under(approval_policy));
         let is_dynamic_tool = self.dynamic_tool_names.contains(&tool_name);
 
         // Parse command from arguments for the approval request event.
-        // Handles both `shell`-style {"command": [...]} and
-        // `exec_command`-style {"cmd": "..."} argument formats.
-        let command: Vec<String> = serde_json::from_str(&arguments)
-            .ok()
-            .and_then(|v: serde_json::Value| {
-                // `shell` tool uses {"command": ["cmd", "arg", ...]}
-                if let Some(arr) = v.get("command").and_then(|c| c.as_array()) {
-                    let items: Option<Vec<String>> = arr
-                        .iter()
-                        .map(|v| v.as_str().map(String::from))
-                        .collect();
-                    return items;
-                }
-                // `exec_command` uses {"cmd": "cmd arg ..."}
-                if let Some(cmd_str) = v.get("cmd").and_then(|c| c.as_str()) {
-                    return Some(cmd_str.split_whitespace().map(String::from).collect());
-                }
-                None
-            })
-            .unwrap_or_else(|| vec![arguments.clone()]);
+        let command = command_from_arguments(&arguments);
 
         Box::pin(async move {
-            // MCP tools bypass the approval flow — the user explicitly
-            // configured these servers, so they are trusted.
+            // MCP tools are gated by the `[mcp_tool_approval]` rules, using
+            // the same approval flow and events as shell commands.  As with
+            // shell commands, `approval_policy = never` skips annotation-based
+            // prompts; an explicit `always` rule still asks.
             if is_mcp_tool {
+                if mcp_needs_approval {
+                    let approved = request_and_wait(
+                        &ctx,
+                        &events,
+                        Event {
+                            id: turn_id.clone(),
+                            msg: EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent {
+                                call_id: call_id.clone(),
+                                approval_id: Some(call_id.clone()),
+                                turn_id: turn_id.clone(),
+                                command: vec![tool_name.clone(), arguments.clone()],
+                                cwd: PathBuf::from(&cwd),
+                                reason: Some(format!("MCP tool call: {tool_name}")),
+                                network_approval_context: None,
+                                proposed_execpolicy_amendment: None,
+                                proposed_network_policy_amendments: None,
+                                additional_permissions: None,
+                                skill_metadata: None,
+                                available_decisions: None,
+                                parsed_cmd: Vec::new(),
+                            }),
+                        },
+                        |s| {
+                            s.pending_approval = Some(PendingApproval {
+                                call_id: call_id.clone(),
+                                decision: None,
+                            });
+                        },
+                        |s| s.pending_approval.as_ref().and_then(|p| p.decision),
+                        |s| { s.pending_approval = None; },
+                    ).await.unwrap_or(false);
+
+                    if !approved {
+                        return Ok(denied_response(call_id));
+                    }
+                }
+
                 let mcp_input = McpToolCallInput {
                     qualified_name: tool_name,
                     call_id: call_id.clone(),
                     arguments,
+                    config_toml: config_toml.clone(),
+                    cwd: Some(cwd.clone()),
                 };
 
                 let mut output = run_with_cancellation!(
@@ -249,40 +327,191 @@ impl ToolCallHandler for TemporalToolHandler {
                     cancellation_token, call_id, "mcp_tool_call"
                 );
 
-                // Check if the MCP server requested elicitation during this call.
-                if let Some(elicitation) = output.elicitation.take() {
-                    if request_and_wait(
+                // Surface servers that stayed down after reconnection
+                // attempts: an error for required servers, a warning otherwise.
+                if let Some(unavailable) = output.unavailable.take() {
+                    let message = format!(
+                        "MCP server '{}' is unavailable after {} attempt(s): {}",
+                        unavailable.server_name, unavailable.attempts, unavailable.error
+                    );
+                    let msg = if unavailable.required {
+                        EventMsg::Error(CodexErr::Fatal(message).to_error_event(None))
+                    } else {
+                        EventMsg::Warning(WarningEvent { message })
+                    };
+                    events.emit_event_sync(Event { id: turn_id.clone(), msg });
+                }
+
+                // The MCP server may ask the user for input mid-call.  The
+                // call stays open on the worker; forward the request with its
+                // schema, then deliver the answer and keep driving the call
+                // (servers may elicit more than once).
+                while let Some(elicitation) = output.elicitation.take() {
+                    let worker_queue = output.worker_task_queue.take();
+                    let server_name = elicitation.server_name.clone();
+                    let request_id = elicitation.request_id.clone();
+                    let resolved = request_and_wait(
                         &ctx,
                         &events,
                         Event {
                             id: turn_id.clone(),
                             msg: EventMsg::ElicitationRequest(ElicitationRequestEvent {
                                 turn_id: Some(turn_id.clone()),
-                                server_name: elicitation.server_name.clone(),
-                                id: elicitation.request_id.clone(),
-                                request: ElicitationRequest::Form {
-                                    meta: None,
-                                    message: elicitation.message,
-                                    requested_schema: serde_json::Value::Object(Default::default()),
-                                },
+                                server_name: server_name.clone(),
+                                id: request_id.clone(),
+                                request: elicitation.to_request(),
                             }),
                         },
                         |s| {
                             s.pending_elicitation = Some(PendingElicitation {
-                                server_name: elicitation.server_name,
-                                request_id: elicitation.request_id,
+                                server_name: server_name.clone(),
+                                request_id: request_id.clone(),
                                 response: None,
+                                content: None,
                             });
                         },
-                        |s| s.pending_elicitation.as_ref().and_then(|p| p.response.as_ref().map(|_| ())),
+                        |s| {
+                            s.pending_elicitation.as_ref().and_then(|p| {
+                                p.response.clone().map(|action| (action, p.content.clone()))
+                            })
+                        },
                         |s| { s.pending_elicitation = None; },
-                    ).await.is_none() {
+                    ).await;
+
+                    let Some((action, content)) = resolved else {
+                        // Interrupted: cancel the elicitation so the pending
+                        // call on the worker is released.
+                        let cancel_input = McpElicitationResolveInput {
+                            call_id: call_id.clone(),
+                            server_name,
+                            request_id,
+                            action: ElicitationAction::Cancel,
+                            content: None,
+                        };
+                        let _ = ctx
+                            .start_activity(
+                                CodexActivities::resolve_mcp_elicitation,
+                                cancel_input,
+                                pending_call_opts(worker_queue, 30),
+                            )
+                            .await;
                         return Ok(denied_response(call_id));
-                    }
+                    };
+
+                    let resolve_input = McpElicitationResolveInput {
+                        call_id: call_id.clone(),
+                        server_name,
+                        request_id,
+                        action,
+                        content,
+                    };
+                    // The call is held in the memory of the worker that
+                    // started it, so the answer goes to that worker's queue.
+                    let opts = pending_call_opts(worker_queue, 120);
+                    output = run_with_cancellation!(@opts
+                        ctx, CodexActivities::resolve_mcp_elicitation, resolve_input, opts,
+                        cancellation_token, call_id
+                    )
+                    .unwrap_or_else(|e| McpToolCallOutput {
+                        call_id: call_id.clone(),
+                        result: Err(format!("MCP call could not be resumed: {e}")),
+                        elicitation: None,
+                        worker_task_queue: None,
+                        unavailable: None,
+                    });
                 }
 
                 return Ok(output.into_response_input_item());
             }
+            // MCP resource and prompt tools — routed to the MCP manager on the
+            // worker (stock codex would use its in-process connection manager).
+            if is_mcp_resource_or_prompt_tool(&tool_name) {
+                let args: serde_json::Value =
+                    serde_json::from_str(&arguments).unwrap_or_default();
+                let str_arg = |key: &str| {
+                    args.get(key)
+                        .and_then(|v| v.as_str())
+                        .filter(|v| !v.is_empty())
+                        .map(String::from)
+                };
+                let list_input = McpListInput {
+                    server: str_arg("server"),
+                    cursor: str_arg("cursor"),
+                    config_toml: config_toml.clone(),
+                    cwd: Some(cwd.clone()),
+                };
+
+                let output = match tool_name.as_str() {
+                    "list_mcp_resources" => run_with_cancellation!(
+                        ctx, CodexActivities::mcp_list_resources, list_input, 120,
+                        cancellation_token, call_id, "mcp_list_resources"
+                    ),
+                    "list_mcp_resource_templates" => run_with_cancellation!(
+                        ctx, CodexActivities::mcp_list_resource_templates, list_input, 120,
+                        cancellation_token, call_id, "mcp_list_resource_templates"
+                    ),
+                    "list_mcp_prompts" => run_with_cancellation!(
+                        ctx, CodexActivities::mcp_list_prompts, list_input, 120,
+                        cancellation_token, call_id, "mcp_list_prompts"
+                    ),
+                    "read_mcp_resource" => {
+                        let (Some(server), Some(uri)) = (str_arg("server"), str_arg("uri")) else {
+                            return Ok(McpRequestOutput {
+                                result: Err("read_mcp_resource requires `server` and `uri`".to_string()),
+                            }
+                            .into_response_input_item(call_id));
+                        };
+                        let input = McpReadResourceInput {
+                            server,
+                            uri,
+                            config_toml: config_toml.clone(),
+                            cwd: Some(cwd.clone()),
+                        };
+                        run_with_cancellation!(
+                            ctx, CodexActivities::mcp_read_resource, input, 120,
+                            cancellation_token, call_id, "mcp_read_resource"
+                        )
+                    }
+                    _ => {
+                        let (Some(server), Some(name)) = (str_arg("server"), str_arg("name")) else {
+                            return Ok(McpRequestOutput {
+                                result: Err("get_mcp_prompt requires `server` and `name`".to_string()),
+                            }
+                            .into_response_input_item(call_id));
+                        };
+                        let input = McpGetPromptInput {
+                            server,
+                            name,
+                            arguments: args.get("arguments").filter(|a| a.is_object()).cloned(),
+                            config_toml: config_toml.clone(),
+                            cwd: Some(cwd.clone()),
+                        };
+                        run_with_cancellation!(
+                            ctx, CodexActivities::mcp_get_prompt, input, 120,
+                            cancellation_token, call_id, "mcp_get_prompt"
+                        )
+                    }
+                };
+
+                return Ok(output.into_response_input_item(call_id));
+            }
+
+            // Subagent and messaging tools — handled entirely in the
+            // workflow, talking to other agents through the parent session.
+            if is_subagent_tool(&tool_name) || is_messaging_tool(&tool_name) {
+                let workflow_call = WorkflowToolCall {
+                    call_id,
+                    tool_name,
+                    arguments,
+                    turn_id,
+                    cwd,
+                    model,
+                    approval_policy,
+                    sandbox_policy,
+                };
+                return Ok(handle_workflow_tool(&ctx, &events, workflow_call).await);
+            }
+
             // request_user_input — intercept and handle via signal/wait
             // (same pattern as exec approval).
             if tool_name == "request_user_input" {
@@ -513,15 +742,7 @@ impl ToolCallHandler for TemporalToolHandler {
             // list_dir, grep_files, etc.) execute directly with no approval.
             // apply_patch is already handled above, so here we only apply the
             // three-tier shell classification to shell-type tools.
-            let is_shell_tool = matches!(
-                tool_name.as_str(),
-                "shell"
-                    | "container.exec"
-                    | "local_shell"
-                    | "shell_command"
-                    | "unified_exec"
-                    | "exec_command"
-            );
+            let is_shell_tool = is_shell_tool(&tool_name);
 
             // Determine approval requirement using codex-core's
             // `render_decision_for_unmatched_command`, which accounts for
@@ -680,6 +901,603 @@ impl ToolCallHandler for TemporalToolHandler {
     }
 }
 
+/// Parse the command of a shell-type tool call from its JSON arguments.
+///
+/// Handles both `shell`-style `{"command": [...]}` and `exec_command`-style
+/// `{"cmd": "..."}` argument formats; anything else is returned verbatim.
+pub(crate) fn command_from_arguments(arguments: &str) -> Vec<String> {
+    serde_json::from_str(arguments)
+        .ok()
+        .and_then(|v: serde_json::Value| {
+            // `shell` tool uses {"command": ["cmd", "arg", ...]}
+            if let Some(arr) = v.get("command").and_then(|c| c.as_array()) {
+                let items: Option<Vec<String>> = arr
+                    .iter()
+                    .map(|v| v.as_str().map(String::from))
+                    .collect();
+                return items;
+            }
+            // `exec_command` uses {"cmd": "cmd arg ..."}
+            if let Some(cmd_str) = v.get("cmd").and_then(|c| c.as_str()) {
+                return Some(cmd_str.split_whitespace().map(String::from).collect());
+            }
+            None
+        })
+        .unwrap_or_else(|| vec![arguments.to_string()])
+}
+
+/// Tools that run a shell command (approval-classified and shown with their
+/// parsed command line).
+pub(crate) fn is_shell_tool(tool_name: &str) -> bool {
+    matches!(
+        tool_name,
+        "shell"
+            | "container.exec"
+            | "local_shell"
+            | "shell_command"
+            | "unified_exec"
+            | "exec_command"
+    )
+}
+
+/// Resource tools registered by `build_specs` when MCP servers are
+/// configured, plus the harness's prompt tools ([`mcp_prompt_tool_specs`]).
+fn is_mcp_resource_or_prompt_tool(tool_name: &str) -> bool {
+    matches!(
+        tool_name,
+        "list_mcp_resources"
+            | "list_mcp_resource_templates"
+            | "read_mcp_resource"
+            | "list_mcp_prompts"
+            | "get_mcp_prompt"
+    )
+}
+
+/// Tool specs for listing and fetching MCP prompts.
+///
+/// Stock codex only exposes MCP resources to the model; these are passed to
+/// `build_specs` alongside the client's dynamic tools but are handled by
+/// [`TemporalToolHandler`] itself.
+pub fn mcp_prompt_tool_specs() -> Vec<DynamicToolSpec> {
+    vec![
+        DynamicToolSpec {
+            name: "list_mcp_prompts".to_string(),
+            description: "Lists prompts provided by MCP servers. Prompts are reusable \
+                instructions or templates; fetch one with get_mcp_prompt."
+                .to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "server": {
+                        "type": "string",
+                        "description": "Optional MCP server name. When omitted, lists prompts from every configured server."
+                    },
+                    "cursor": {
+                        "type": "string",
+                        "description": "Opaque cursor returned by a previous list_mcp_prompts call for the same server."
+                    }
+                },
+                "additionalProperties": false
+            }),
+        },
+        DynamicToolSpec {
+            name: "get_mcp_prompt".to_string(),
+            description: "Fetches an MCP prompt rendered with the given arguments.".to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "server": {
+                        "type": "string",
+                        "description": "MCP server name exactly as configured."
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "Prompt name as returned by list_mcp_prompts."
+                    },
+                    "arguments": {
+                        "type": "object",
+                        "description": "Prompt arguments as string values."
+                    }
+                },
+                "required": ["server", "name"],
+                "additionalProperties": false
+            }),
+        },
+    ]
+}
+
+// -----------------------------------------------------------------------
+// Subagent tools
+// -----------------------------------------------------------------------
+
+/// Session workflow ID of the session an agent belongs to
+/// (`{session}/{agent}`), or `None` for a standalone agent.
+pub(crate) fn session_of_agent(agent_workflow_id: &str) -> Option<&str> {
+    agent_workflow_id
+        .split_once('/')
+        .map(|(session, _)| session)
+        .filter(|session| !session.is_empty())
+}
+
+/// Session workflow ID if `agent_workflow_id` is a session's main agent —
+/// the only agent that gets the subagent tools.
+pub(crate) fn main_agent_session(agent_workflow_id: &str) -> Option<&str> {
+    agent_workflow_id
+        .strip_suffix("/main")
+        .filter(|session| !session.is_empty() && !session.contains('/'))
+}
+
+/// Tools registered by [`subagent_tool_specs`].
+fn is_subagent_tool(tool_name: &str) -> bool {
+    matches!(
+        tool_name,
+        "spawn_subagent" | "send_subagent_message" | "wait_subagent"
+    )
+}
+
+/// Tools registered by [`messaging_tool_specs`] and
+/// [`approval_supervisor_tool_specs`].
+fn is_messaging_tool(tool_name: &str) -> bool {
+    matches!(tool_name, "post_message" | "read_messages" | "decide_approval")
+}
+
+/// Tool spec for the session's approval supervisor: approve or deny another
+/// agent's pending exec/patch approval.  Handled by [`TemporalToolHandler`].
+pub fn approval_supervisor_tool_specs() -> Vec<DynamicToolSpec> {
+    vec![DynamicToolSpec {
+        name: "decide_approval".to_string(),
+        description: "Approves or denies a pending approval request of another agent in this \
+            session. Requests arrive as messages from the session with the agent_id and \
+            call_id."
+            .to_string(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "agent_id": {
+                    "type": "string",
+                    "description": "Agent waiting for approval (e.g. worker-2)."
+                },
+                "call_id": {
+                    "type": "string",
+                    "description": "call_id of the request."
+                },
+                "approve": {
+                    "type": "boolean",
+                    "description": "true to approve, false to deny."
+                }
+            },
+            "required": ["agent_id", "call_id", "approve"],
+            "additionalProperties": false
+        }),
+    }]
+}
+
+/// Tool specs for the session message bus: posting to other agents and
+/// reading this agent's mailbox.  Handled by [`TemporalToolHandler`].
+pub fn messaging_tool_specs() -> Vec<DynamicToolSpec> {
+    vec![
+        DynamicToolSpec {
+            name: "post_message".to_string(),
+            description: "Posts a message to other agents in this session, e.g. to hand off \
+                findings or send review feedback. Replies arrive in your mailbox \
+                (read_messages)."
+                .to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "to": {
+                        "type": "string",
+                        "description": "Recipient: an agent id (e.g. main, worker-2), a role name (every running agent with that role), or * for all agents."
+                    },
+                    "message": {
+                        "type": "string",
+                        "description": "Message text."
+                    },
+                    "wake": {
+                        "type": "boolean",
+                        "description": "Start a turn for the recipient with this message instead of leaving it in its mailbox. Defaults to false."
+                    }
+                },
+                "required": ["to", "message"],
+                "additionalProperties": false
+            }),
+        },
+        DynamicToolSpec {
+            name: "read_messages".to_string(),
+            description: "Returns unread messages from other agents in this session and marks \
+                them read."
+                .to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {},
+                "additionalProperties": false
+            }),
+        },
+    ]
+}
+
+/// Tool specs for spawning role-based subagents, messaging them, and
+/// waiting for their final message.
+///
+/// Like [`mcp_prompt_tool_specs`] these are passed to `build_specs` as
+/// dynamic tools but handled by [`TemporalToolHandler`] itself.  `roles`
+/// (name → description) come from the `[agents]` config table, which
+/// includes crew roles.
+pub fn subagent_tool_specs(roles: &BTreeMap<String, Option<String>>) -> Vec<DynamicToolSpec> {
+    let mut spawn_description = "Spawns a subagent that works on a task in parallel. \
+        Returns its agent_id; use wait_subagent to get its answer."
+        .to_string();
+    if !roles.is_empty() {
+        spawn_description.push_str("\nAvailable roles:");
+        for (name, description) in roles {
+            match description {
+                Some(description) => {
+                    spawn_description.push_str(&format!("\n- {name}: {description}"))
+                }
+                None => spawn_description.push_str(&format!("\n- {name}")),
+            }
+        }
+    }
+
+    vec![
+        DynamicToolSpec {
+            name: "spawn_subagent".to_string(),
+            description: spawn_description,
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "role": {
+                        "type": "string",
+                        "description": "Role of the new subagent (e.g. explorer, worker)."
+                    },
+                    "message": {
+                        "type": "string",
+                        "description": "Task for the subagent. It does not see this conversation, so include all needed context."
+                    }
+                },
+                "required": ["role", "message"],
+                "additionalProperties": false
+            }),
+        },
+        DynamicToolSpec {
+            name: "send_subagent_message".to_string(),
+            description: "Sends a follow-up message to a subagent. Use wait_subagent to get its reply."
+                .to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "agent_id": {
+                        "type": "string",
+                        "description": "agent_id returned by spawn_subagent."
+                    },
+                    "message": {
+                        "type": "string",
+                        "description": "Message for the subagent."
+                    }
+                },
+                "required": ["agent_id", "message"],
+                "additionalProperties": false
+            }),
+        },
+        DynamicToolSpec {
+            name: "wait_subagent".to_string(),
+            description: "Waits until a subagent has handled all messages sent to it and returns \
+                its final message."
+                .to_string(),
+            input_schema: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "agent_id": {
+                        "type": "string",
+                        "description": "agent_id returned by spawn_subagent."
+                    }
+                },
+                "required": ["agent_id"],
+                "additionalProperties": false
+            }),
+        },
+    ]
+}
+
+/// Arguments of `spawn_subagent`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct SpawnSubagentArgs {
+    pub role: String,
+    pub message: String,
+}
+
+/// Arguments of `send_subagent_message`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct SendSubagentArgs {
+    pub agent_id: String,
+    pub message: String,
+}
+
+/// Arguments of `wait_subagent`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct WaitSubagentArgs {
+    pub agent_id: String,
+}
+
+/// Arguments of `post_message`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct PostMessageArgs {
+    pub to: String,
+    pub message: String,
+    #[serde(default)]
+    pub wake: bool,
+}
+
+/// Arguments of `decide_approval`.
+#[derive(Debug, Deserialize)]
+pub(crate) struct DecideApprovalArgs {
+    pub agent_id: String,
+    pub call_id: String,
+    pub approve: bool,
+}
+
+/// A subagent or messaging tool call, with the turn settings needed to
+/// message a child.
+struct WorkflowToolCall {
+    call_id: String,
+    tool_name: String,
+    arguments: String,
+    turn_id: String,
+    cwd: String,
+    model: String,
+    approval_policy: AskForApproval,
+    sandbox_policy: SandboxPolicy,
+}
+
+/// Build the `Op::UserTurn` delivering `message` to a subagent, using the
+/// parent's current turn settings.
+pub(crate) fn subagent_turn_op(
+    message: String,
+    cwd: &str,
+    model: &str,
+    approval_policy: AskForApproval,
+    sandbox_policy: SandboxPolicy,
+) -> Op {
+    Op::UserTurn {
+        items: vec![UserInput::Text {
+            text: message,
+            text_elements: vec![],
+        }],
+        cwd: PathBuf::from(cwd),
+        approval_policy,
+        sandbox_policy,
+        model: model.to_string(),
+        effort: None,
+        summary: None,
+        service_tier: None,
+        final_output_json_schema: None,
+        collaboration_mode: None,
+        personality: None,
+        approvals_reviewer: None,
+    }
+}
+
+/// Run a subagent or messaging tool call, always producing a function-call
+/// output (failures are reported to the model rather than failing the turn).
+async fn handle_workflow_tool(
+    ctx: &WorkflowContext<AgentWorkflow>,
+    events: &BufferEventSink,
+    call: WorkflowToolCall,
+) -> ResponseInputItem {
+    let call_id = call.call_id.clone();
+    let result = match call.tool_name.as_str() {
+        "spawn_subagent" => spawn_subagent(ctx, events, &call).await,
+        "send_subagent_message" => send_subagent_message(ctx, &call).await,
+        "wait_subagent" => wait_subagent(ctx, &call).await,
+        "post_message" => post_message(ctx, events, &call).await,
+        "decide_approval" => decide_approval(ctx, &call).await,
+        _ => Ok(read_messages(ctx)),
+    };
+    let (body, success) = match result {
+        Ok(value) => (value, true),
+        Err(error) => (serde_json::json!({ "error": error }), false),
+    };
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            body: FunctionCallOutputBody::Text(body.to_string()),
+            success: Some(success),
+        },
+    }
+}
+
+fn parse_tool_args<T: for<'de> Deserialize<'de>>(call: &WorkflowToolCall) -> Result<T, String> {
+    serde_json::from_str(&call.arguments)
+        .map_err(|e| format!("invalid {} arguments: {e}", call.tool_name))
+}
+
+/// Ask the parent session to spawn a child and wait for its agent ID.
+async fn spawn_subagent(
+    ctx: &WorkflowContext<AgentWorkflow>,
+    events: &BufferEventSink,
+    call: &WorkflowToolCall,
+) -> Result<serde_json::Value, String> {
+    let args: SpawnSubagentArgs = parse_tool_args(call)?;
+    let own_id = ctx.state(|s| s.workflow_id.clone());
+    let session_id = main_agent_session(&own_id)
+        .ok_or("subagents can only be spawned by a session's main agent")?;
+
+    let request = SpawnAgentInput {
+        role: args.role.clone(),
+        message: args.message,
+        requested_by: Some(own_id.clone()),
+        request_id: Some(call.call_id.clone()),
+        pipeline_task: None,
+    };
+    signal_workflow!(ctx, session_id, "spawn_agent", &request)?;
+
+    let request_id = call.call_id.clone();
+    ctx.wait_condition(|s| {
+        s.subagents.spawn_results.contains_key(&request_id) || s.interrupt_requested
+    })
+    .await;
+    let agent_id = ctx
+        .state_mut(|s| s.subagents.take_spawn_result(&request_id))
+        .ok_or("interrupted")??;
+
+    AgentWorkflow::emit_and_bump(ctx, events, Event {
+        id: call.turn_id.clone(),
+        msg: EventMsg::BackgroundEvent(BackgroundEventEvent {
+            message: format!("Spawned subagent {agent_id} ({})", args.role),
+        }),
+    });
+    Ok(serde_json::json!({
+        "agent_id": agent_id,
+        "role": args.role,
+        "status": "running",
+    }))
+}
+
+/// Send a follow-up message to a subagent as a new user turn.
+async fn send_subagent_message(
+    ctx: &WorkflowContext<AgentWorkflow>,
+    call: &WorkflowToolCall,
+) -> Result<serde_json::Value, String> {
+    let args: SendSubagentArgs = parse_tool_args(call)?;
+    ctx.state_mut(|s| s.subagents.message_sent(&args.agent_id))?;
+
+    let op = subagent_turn_op(
+        args.message,
+        &call.cwd,
+        &call.model,
+        call.approval_policy,
+        call.sandbox_policy.clone(),
+    );
+    if let Err(e) = signal_workflow!(ctx, &args.agent_id, "receive_op", &op) {
+        // Undo the bookkeeping so a later wait does not hang.
+        ctx.state_mut(|s| {
+            s.subagents.apply(SubagentNotice::TurnComplete {
+                agent_id: args.agent_id.clone(),
+                last_agent_message: None,
+            })
+        });
+        return Err(e);
+    }
+    Ok(serde_json::json!({ "agent_id": args.agent_id, "status": "sent" }))
+}
+
+/// Wait until a subagent has no turn in flight and return its final message.
+async fn wait_subagent(
+    ctx: &WorkflowContext<AgentWorkflow>,
+    call: &WorkflowToolCall,
+) -> Result<serde_json::Value, String> {
+    let args: WaitSubagentArgs = parse_tool_args(call)?;
+    if !ctx.state(|s| s.subagents.agents.contains_key(&args.agent_id)) {
+        return Err(format!("unknown subagent: {}", args.agent_id));
+    }
+
+    ctx.wait_condition(|s| {
+        s.subagents
+            .agents
+            .get(&args.agent_id)
+            .is_none_or(|a| a.is_idle())
+            || s.interrupt_requested
+    })
+    .await;
+    if ctx.state(|s| s.interrupt_requested) {
+        return Err("interrupted".to_string());
+    }
+
+    let state = ctx
+        .state(|s| s.subagents.agents.get(&args.agent_id).cloned())
+        .unwrap_or_default();
+    let status = match (state.finished, &state.error) {
+        (false, _) => "idle",
+        (true, None) => "completed",
+        (true, Some(_)) => "failed",
+    };
+    Ok(serde_json::json!({
+        "agent_id": args.agent_id,
+        "role": state.role,
+        "status": status,
+        "last_agent_message": state.last_agent_message,
+        "error": state.error,
+    }))
+}
+
+/// Hand a message to the session for routing to its recipients.
+async fn post_message(
+    ctx: &WorkflowContext<AgentWorkflow>,
+    events: &BufferEventSink,
+    call: &WorkflowToolCall,
+) -> Result<serde_json::Value, String> {
+    let args: PostMessageArgs = parse_tool_args(call)?;
+    if args.to.trim().is_empty() || args.message.trim().is_empty() {
+        return Err("post_message requires a recipient and a message".to_string());
+    }
+    let own_id = ctx.state(|s| s.workflow_id.clone());
+    let session_id =
+        session_of_agent(&own_id).ok_or("messaging is only available within a session")?;
+
+    let message = AgentMessage {
+        seq: 0,
+        from: own_id.clone(),
+        to: args.to,
+        body: args.message,
+        wake: args.wake,
+        delivered_to: Vec::new(),
+    };
+    signal_workflow!(ctx, session_id, "route_message", &message)?;
+
+    AgentWorkflow::emit_and_bump(ctx, events, Event {
+        id: call.turn_id.clone(),
+        msg: EventMsg::BackgroundEvent(BackgroundEventEvent {
+            message: message.display(),
+        }),
+    });
+    Ok(serde_json::json!({ "to": message.to, "status": "posted" }))
+}
+
+/// Send the supervisor's decision on another agent's approval to the
+/// session, which forwards it to the waiting agent.
+async fn decide_approval(
+    ctx: &WorkflowContext<AgentWorkflow>,
+    call: &WorkflowToolCall,
+) -> Result<serde_json::Value, String> {
+    let args: DecideApprovalArgs = parse_tool_args(call)?;
+    let own_id = ctx.state(|s| s.workflow_id.clone());
+    let session_id = session_of_agent(&own_id)
+        .ok_or("approvals can only be decided within a session")?
+        .to_string();
+
+    let decision = ApprovalDecisionRequest {
+        agent_id: args.agent_id.clone(),
+        call_id: args.call_id.clone(),
+        approved: args.approve,
+        decided_by: Some(own_id),
+    };
+    signal_workflow!(ctx, &session_id, "supervisor_decision", &decision)?;
+    Ok(serde_json::json!({
+        "agent_id": args.agent_id,
+        "call_id": args.call_id,
+        "status": if args.approve { "approved" } else { "denied" },
+    }))
+}
+
+/// Drain this agent's mailbox.
+fn read_messages(ctx: &WorkflowContext<AgentWorkflow>) -> serde_json::Value {
+    let inbox = ctx.state_mut(|s| {
+        s.bump_version();
+        std::mem::take(&mut s.inbox)
+    });
+    let messages: Vec<serde_json::Value> = inbox
+        .iter()
+        .map(|m| {
+            serde_json::json!({
+                "from": short_agent_id(&m.from),
+                "to": m.to,
+                "message": m.body,
+            })
+        })
+        .collect();
+    serde_json::json!({ "messages": messages })
+}
+
 /// Build a function_call_output indicating the tool call was denied.
 fn denied_response(call_id: String) -> ResponseInputItem {
     use codex_protocol::models::{FunctionCallOutputBody, FunctionCallOutputPayload};
@@ -3,13 +3,15 @@
 //! These types are sent across the Temporal activity boundary, so they must
 //! implement `Serialize` + `Deserialize`.
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use codex_core::{ModelProviderInfo, ToolSpec};
 use codex_protocol::config_types::{Personality, ReasoningSummary};
 use codex_protocol::models::{ResponseInputItem, ResponseItem};
 use codex_protocol::openai_models::{ModelInfo, ReasoningEffort};
-use codex_protocol::protocol::{AskForApproval, Event, GitInfo, RolloutItem, TokenUsage};
+use codex_protocol::protocol::{
+    AskForApproval, Event, EventMsg, GitInfo, RolloutItem, TokenUsage,
+};
 use serde::{Deserialize, Serialize};
 
 // ---------------------------------------------------------------------------
@@ -195,6 +197,9 @@ pub struct McpDiscoverInput {
     pub config_toml: String,
     /// Working directory (needed to build Config).
     pub cwd: String,
+    /// Servers disabled at runtime for this session; skipped during discovery.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_servers: Vec<String>,
 }
 
 /// Output from the `discover_mcp_tools` activity.
@@ -204,6 +209,122 @@ pub struct McpDiscoverOutput {
     pub tools: HashMap<String, serde_json::Value>,
 }
 
+// ---------------------------------------------------------------------------
+// Mid-session MCP refresh
+// ---------------------------------------------------------------------------
+
+/// Payload of the `SessionWorkflow::refresh_mcp` signal.
+///
+/// An empty request simply re-runs discovery (e.g. after editing
+/// config.toml or a server reporting `tools/list_changed`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct McpRefreshRequest {
+    /// Servers to re-enable after a previous `disable`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enable: Vec<String>,
+    /// Servers to disable for the rest of the session.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disable: Vec<String>,
+}
+
+impl McpRefreshRequest {
+    /// Apply this request to the session's set of disabled servers.
+    /// `disable` wins when a server appears in both lists.
+    pub fn apply(&self, disabled: &mut BTreeSet<String>) {
+        for server in &self.enable {
+            disabled.remove(server);
+        }
+        for server in &self.disable {
+            disabled.insert(server.clone());
+        }
+    }
+}
+
+/// Refreshed MCP tool set pushed from `SessionWorkflow` to its agents via
+/// the `AgentWorkflow::update_mcp_tools` signal.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct McpToolsUpdate {
+    /// Complete replacement tool set (qualified name → serialized tool).
+    pub mcp_tools: HashMap<String, serde_json::Value>,
+    /// Qualified names of tools that were not present before.
+    #[serde(default)]
+    pub added: Vec<String>,
+    /// Qualified names of tools that are gone.
+    #[serde(default)]
+    pub removed: Vec<String>,
+    /// Qualified names of tools whose definition changed.
+    #[serde(default)]
+    pub changed: Vec<String>,
+    /// The session's reloaded config TOML, whose MCP settings
+    /// (`[mcp_servers]`, `[mcp_tool_approval]`) replace the agent's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_toml: Option<String>,
+}
+
+impl McpToolsUpdate {
+    /// Diff `old` against `new` and build the update carrying `new`.
+    pub fn diff(
+        old: &HashMap<String, serde_json::Value>,
+        new: HashMap<String, serde_json::Value>,
+    ) -> Self {
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        for (name, tool) in &new {
+            match old.get(name) {
+                None => added.push(name.clone()),
+                Some(previous) if previous != tool => changed.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+        let mut removed: Vec<String> = old
+            .keys()
+            .filter(|name| !new.contains_key(*name))
+            .cloned()
+            .collect();
+        added.sort();
+        removed.sort();
+        changed.sort();
+        Self {
+            mcp_tools: new,
+            added,
+            removed,
+            changed,
+            config_toml: None,
+        }
+    }
+
+    /// Whether the tool set differs from the previous one at all.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// One-line human-readable summary, e.g. `+2 added, -1 removed`.
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            return "no changes".to_string();
+        }
+        let mut parts = Vec::new();
+        if !self.added.is_empty() {
+            parts.push(format!("+{} added ({})", self.added.len(), self.added.join(", ")));
+        }
+        if !self.removed.is_empty() {
+            parts.push(format!(
+                "-{} removed ({})",
+                self.removed.len(),
+                self.removed.join(", ")
+            ));
+        }
+        if !self.changed.is_empty() {
+            parts.push(format!(
+                "~{} changed ({})",
+                self.changed.len(),
+                self.changed.join(", ")
+            ));
+        }
+        parts.join(", ")
+    }
+}
+
 // ---------------------------------------------------------------------------
 // MCP tool call activity I/O
 // ---------------------------------------------------------------------------
@@ -217,6 +338,14 @@ pub struct McpToolCallInput {
     pub call_id: String,
     /// JSON string of tool arguments from the model.
     pub arguments: String,
+    /// Merged config.toml of the session. Lets the activity reconnect to
+    /// the server lazily when the worker has no live connection (e.g. after
+    /// a worker restart or failover).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_toml: Option<String>,
+    /// Working directory (needed to build Config for reconnection).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
 }
 
 /// Output from the `mcp_tool_call` activity.
@@ -227,12 +356,38 @@ pub struct McpToolCallOutput {
     /// Serialized `codex_protocol::mcp::CallToolResult`, or error string.
     pub result: Result<serde_json::Value, String>,
     /// If the MCP server requested elicitation during this call, the
-    /// details are captured here for the workflow to handle.
+    /// details are captured here for the workflow to handle.  The call is
+    /// then still pending on the worker and `result` is a placeholder.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub elicitation: Option<CapturedElicitation>,
+    /// Task queue of the worker holding the pending call (set with
+    /// `elicitation`); `resolve_mcp_elicitation` must run there.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker_task_queue: Option<String>,
+    /// Set when the target server could not be (re)connected; the workflow
+    /// surfaces this to the client as a warning or error event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unavailable: Option<McpServerUnavailable>,
+}
+
+/// Details about an MCP server that stayed unavailable after reconnection
+/// attempts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerUnavailable {
+    /// Name of the MCP server.
+    pub server_name: String,
+    /// Whether the server is marked `required` in config.toml.
+    pub required: bool,
+    /// Number of consecutive failed connection attempts.
+    pub attempts: u32,
+    /// The most recent connection error.
+    pub error: String,
 }
 
 /// Elicitation details captured from an MCP server during a tool call.
+///
+/// The originating tool call stays open on the worker while the workflow
+/// collects the user's answer; see [`McpElicitationResolveInput`].
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CapturedElicitation {
     /// Name of the MCP server.
@@ -241,6 +396,80 @@ pub struct CapturedElicitation {
     pub request_id: codex_protocol::mcp::RequestId,
     /// Human-readable elicitation message.
     pub message: String,
+    /// JSON schema of the requested form (`requestedSchema`; an empty
+    /// object when the server sent none).
+    #[serde(default = "empty_schema")]
+    pub requested_schema: serde_json::Value,
+    /// Target URL for URL-mode elicitations.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Server-assigned ID for URL-mode elicitations.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elicitation_id: Option<String>,
+    /// `_meta` from the request, passed through to the client.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<serde_json::Value>,
+}
+
+impl CapturedElicitation {
+    /// Build from the wire-format params of an MCP `elicitation/create`
+    /// request.
+    pub fn from_params(
+        server_name: String,
+        request_id: codex_protocol::mcp::RequestId,
+        params: &serde_json::Value,
+    ) -> Self {
+        let str_field = |key: &str| params.get(key).and_then(|v| v.as_str()).map(String::from);
+        Self {
+            server_name,
+            request_id,
+            message: str_field("message").unwrap_or_default(),
+            requested_schema: params.get("requestedSchema").cloned().unwrap_or_else(empty_schema),
+            url: str_field("url"),
+            elicitation_id: str_field("elicitationId"),
+            meta: params.get("_meta").cloned(),
+        }
+    }
+
+    /// Convert into the protocol request shown to the client.
+    pub fn to_request(&self) -> codex_protocol::approvals::ElicitationRequest {
+        use codex_protocol::approvals::ElicitationRequest;
+
+        match &self.url {
+            Some(url) => ElicitationRequest::Url {
+                meta: self.meta.clone(),
+                message: self.message.clone(),
+                url: url.clone(),
+                elicitation_id: self.elicitation_id.clone().unwrap_or_default(),
+            },
+            None => ElicitationRequest::Form {
+                meta: self.meta.clone(),
+                message: self.message.clone(),
+                requested_schema: self.requested_schema.clone(),
+            },
+        }
+    }
+}
+
+fn empty_schema() -> serde_json::Value {
+    serde_json::Value::Object(Default::default())
+}
+
+/// Input for the `resolve_mcp_elicitation` activity: the user's answer to a
+/// captured elicitation, delivered to the still-running tool call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpElicitationResolveInput {
+    /// call_id of the original `mcp_tool_call`.
+    pub call_id: String,
+    /// Name of the MCP server that issued the request.
+    pub server_name: String,
+    /// Request ID from the MCP protocol.
+    pub request_id: codex_protocol::mcp::RequestId,
+    /// The user's decision.
+    pub action: codex_protocol::approvals::ElicitationAction,
+    /// Structured form content (for `accept`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<serde_json::Value>,
 }
 
 impl McpToolCallOutput {
@@ -265,6 +494,253 @@ impl McpToolCallOutput {
     }
 }
 
+// ---------------------------------------------------------------------------
+// MCP resources & prompts activity I/O
+// ---------------------------------------------------------------------------
+
+/// Input to the `mcp_list_resources`, `mcp_list_resource_templates` and
+/// `mcp_list_prompts` activities.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct McpListInput {
+    /// Server to list; `None` lists across all servers (first page only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server: Option<String>,
+    /// Pagination cursor from a previous `nextCursor` (requires `server`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Merged config.toml, for lazy reconnection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_toml: Option<String>,
+    /// Working directory, for lazy reconnection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+}
+
+/// Input to the `mcp_read_resource` activity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpReadResourceInput {
+    /// Server that owns the resource.
+    pub server: String,
+    /// Resource URI.
+    pub uri: String,
+    /// Merged config.toml, for lazy reconnection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_toml: Option<String>,
+    /// Working directory, for lazy reconnection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+}
+
+/// Input to the `mcp_get_prompt` activity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpGetPromptInput {
+    /// Server that owns the prompt.
+    pub server: String,
+    /// Prompt name.
+    pub name: String,
+    /// Prompt arguments (a JSON object of strings).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<serde_json::Value>,
+    /// Merged config.toml, for lazy reconnection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_toml: Option<String>,
+    /// Working directory, for lazy reconnection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+}
+
+/// Output from the MCP resource and prompt activities.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpRequestOutput {
+    /// The MCP result as JSON, or an error string.
+    pub result: Result<serde_json::Value, String>,
+}
+
+impl McpRequestOutput {
+    /// Convert this output into a `FunctionCallOutput` for the model.
+    pub fn into_response_input_item(self, call_id: String) -> ResponseInputItem {
+        use codex_protocol::models::{FunctionCallOutputBody, FunctionCallOutputPayload};
+
+        let (text, success) = match self.result {
+            Ok(value) => (value.to_string(), true),
+            Err(e) => (e, false),
+        };
+        ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                body: FunctionCallOutputBody::Text(text),
+                success: Some(success),
+            },
+        }
+    }
+}
+
+/// User-turn command that pulls an MCP prompt into the turn:
+/// `/mcp-prompt <server> <prompt> [key=value ...]`.
+pub const MCP_PROMPT_COMMAND: &str = "/mcp-prompt";
+
+/// A parsed [`MCP_PROMPT_COMMAND`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct McpPromptRef {
+    /// Server that owns the prompt.
+    pub server: String,
+    /// Prompt name.
+    pub name: String,
+    /// Prompt arguments.
+    pub arguments: BTreeMap<String, String>,
+}
+
+/// Parse a user message of the form `/mcp-prompt <server> <prompt>
+/// [key=value ...]`.  Returns `None` for any other message.
+pub fn parse_mcp_prompt_command(message: &str) -> Option<McpPromptRef> {
+    let rest = message.trim().strip_prefix(MCP_PROMPT_COMMAND)?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mut parts = rest.split_whitespace();
+    let server = parts.next()?.to_string();
+    let name = parts.next()?.to_string();
+    let arguments = parts
+        .filter_map(|kv| kv.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    Some(McpPromptRef {
+        server,
+        name,
+        arguments,
+    })
+}
+
+/// Render a `prompts/get` result as user-message text.
+///
+/// Text and embedded text resources are kept; other content (images,
+/// audio) is skipped.  Non-user messages are labelled with their role.
+pub fn render_mcp_prompt(result: &serde_json::Value) -> String {
+    let Some(messages) = result.get("messages").and_then(|m| m.as_array()) else {
+        return String::new();
+    };
+    messages
+        .iter()
+        .filter_map(|message| {
+            let content = message.get("content")?;
+            let text = match content.get("type").and_then(|t| t.as_str()) {
+                Some("text") => content.get("text")?.as_str()?,
+                Some("resource") => content.get("resource")?.get("text")?.as_str()?,
+                _ => return None,
+            };
+            match message.get("role").and_then(|r| r.as_str()) {
+                Some("user") | None => Some(text.to_string()),
+                Some(role) => Some(format!("[{role}]\n{text}")),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+// ---------------------------------------------------------------------------
+// MCP tool approval rules
+// ---------------------------------------------------------------------------
+
+/// When a call to an MCP tool must be approved by the user.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum McpApprovalMode {
+    /// Always ask before calling the tool, even with
+    /// `approval_policy = never`.
+    Always,
+    /// Never ask; the tool is trusted (the default, so MCP calls run
+    /// without approval unless rules are configured).
+    #[default]
+    Never,
+    /// Ask only for tools annotated `destructiveHint: true` that are not
+    /// also marked `readOnlyHint: true`.  Tools without annotations run
+    /// without approval, and `approval_policy = never` skips the prompt as
+    /// for shell commands.
+    Annotations,
+}
+
+impl McpApprovalMode {
+    /// Whether a call gated by this mode asks the user under `policy`.
+    pub fn asks_under(self, policy: AskForApproval) -> bool {
+        match self {
+            McpApprovalMode::Always => true,
+            McpApprovalMode::Never => false,
+            McpApprovalMode::Annotations => policy != AskForApproval::Never,
+        }
+    }
+}
+
+/// Approval rules for MCP tool calls, read from the `[mcp_tool_approval]`
+/// table of config.toml:
+///
+/// ```toml
+/// [mcp_tool_approval]
+/// default = "annotations"   # opt-in; the default is "never"
+///
+/// [mcp_tool_approval.servers.jira]
+/// default = "always"
+/// tools = { search_issues = "never" }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct McpApprovalConfig {
+    /// Mode for servers without their own rule.
+    pub default: McpApprovalMode,
+    /// Per-server rules, keyed by MCP server name.
+    pub servers: HashMap<String, McpServerApprovalConfig>,
+}
+
+/// Approval rules for a single MCP server.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct McpServerApprovalConfig {
+    /// Mode for this server's tools without a per-tool rule.
+    pub default: Option<McpApprovalMode>,
+    /// Per-tool rules, keyed by the server's (unqualified) tool name.
+    pub tools: HashMap<String, McpApprovalMode>,
+}
+
+impl McpApprovalConfig {
+    /// Resolve the mode for a tool: per-tool rule, then per-server default,
+    /// then the global default.
+    pub fn mode_for(&self, server_name: &str, tool_name: &str) -> McpApprovalMode {
+        let Some(server) = self.servers.get(server_name) else {
+            return self.default;
+        };
+        server
+            .tools
+            .get(tool_name)
+            .copied()
+            .or(server.default)
+            .unwrap_or(self.default)
+    }
+
+    /// The mode gating a call to `qualified_name` (`mcp__server__tool`), or
+    /// `None` when the call runs without approval.  `tool` is the
+    /// serialized `rmcp::model::Tool`, consulted for its annotations.
+    pub fn approval_mode(
+        &self,
+        qualified_name: &str,
+        tool: &serde_json::Value,
+    ) -> Option<McpApprovalMode> {
+        let (server_name, tool_name) = codex_core::mcp::split_qualified_tool_name(qualified_name)?;
+        let mode = self.mode_for(&server_name, &tool_name);
+        let gated = match mode {
+            McpApprovalMode::Always => true,
+            McpApprovalMode::Never => false,
+            McpApprovalMode::Annotations => {
+                let hint = |key: &str| {
+                    tool.get("annotations")
+                        .and_then(|a| a.get(key))
+                        .and_then(|v| v.as_bool())
+                };
+                hint("destructiveHint") == Some(true) && hint("readOnlyHint") != Some(true)
+            }
+        };
+        gated.then_some(mode)
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Signal payloads
 // ---------------------------------------------------------------------------
@@ -285,6 +761,10 @@ pub struct UserTurnInput {
     /// Optional per-turn personality override.
     #[serde(default)]
     pub personality: Option<Personality>,
+    /// JSON Schema the turn's final message must follow
+    /// (`final_output_json_schema` of the op).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<serde_json::Value>,
 }
 
 /// Signal payload for approving or denying a tool execution.
@@ -344,6 +824,8 @@ pub struct PendingElicitation {
     pub request_id: codex_protocol::mcp::RequestId,
     /// Set when the client responds via `Op::ResolveElicitation`.
     pub response: Option<codex_protocol::approvals::ElicitationAction>,
+    /// Structured form content sent with the response.
+    pub content: Option<serde_json::Value>,
 }
 
 // ---------------------------------------------------------------------------
@@ -381,6 +863,21 @@ pub struct CrewType {
     /// Session-level approval policy override.
     #[serde(default)]
     pub approval_policy: Option<AskForApproval>,
+    /// Pipeline stages run by `SessionWorkflow`, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stages: Vec<CrewStage>,
+    /// Agent that approves or denies the exec/patch requests of the other
+    /// agents (the main agent or a crew agent name).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supervisor: Option<String>,
+    /// Named results extracted from the agents' final messages when the
+    /// session ends.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub outputs: BTreeMap<String, CrewOutputSpec>,
+    /// Workspace files or directories (relative to the session's cwd)
+    /// collected as artifacts when the session ends.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<String>,
 }
 
 fn default_main_agent() -> String {
@@ -401,6 +898,316 @@ pub struct CrewInputSpec {
     pub default: Option<String>,
 }
 
+// ---------------------------------------------------------------------------
+// Crew pipelines
+// ---------------------------------------------------------------------------
+
+/// One stage of a crew pipeline.
+///
+/// A stage runs a single agent (`agent` + `prompt`) or fans out to
+/// `parallel` branches.  Prompts may reference crew inputs (`{name}`) and
+/// the outputs of other stages (`{stage.<name>}`, or
+/// `{stage.<name>.<branch>}` for one branch of a fan-out).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrewStage {
+    pub name: String,
+    /// Crew agent that runs this stage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    /// Prompt sent to the stage's agent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Branches run concurrently instead of a single agent.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parallel: Vec<CrewBranch>,
+    /// Loop back to an earlier stage until this stage's output matches.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<CrewStageRepeat>,
+}
+
+/// One branch of a fan-out stage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrewBranch {
+    pub name: String,
+    pub agent: String,
+    pub prompt: String,
+}
+
+/// Loop definition on the stage that closes a loop: after it runs, the
+/// pipeline goes back to `from` unless the output's last line is `until`,
+/// up to `max_iterations` runs of the loop body.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrewStageRepeat {
+    /// First stage of the loop body (this stage or an earlier one).
+    pub from: String,
+    /// Verdict that ends the loop when it is the last non-empty line of
+    /// this stage's output, ignoring surrounding whitespace (e.g.
+    /// "APPROVED"; "NOT APPROVED" does not match).
+    pub until: String,
+    #[serde(default = "default_max_stage_iterations")]
+    pub max_iterations: u32,
+}
+
+fn default_max_stage_iterations() -> u32 {
+    3
+}
+
+impl CrewStageRepeat {
+    /// Whether `output` ends the loop: its last non-empty line is `until`.
+    pub fn is_done(&self, output: &str) -> bool {
+        output
+            .lines()
+            .map(str::trim)
+            .rfind(|line| !line.is_empty())
+            .is_some_and(|line| line == self.until.trim())
+    }
+}
+
+/// Placeholder key under which a stage's (or branch's) output is stored.
+pub fn stage_output_key(stage: &str, branch: Option<&str>) -> String {
+    match branch {
+        Some(branch) => format!("stage.{stage}.{branch}"),
+        None => format!("stage.{stage}"),
+    }
+}
+
+/// Status of a pipeline run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PipelineStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// An agent to start for the current stage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageTask {
+    pub stage: String,
+    /// Branch name for fan-out stages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Crew agent (role) to run.
+    pub agent: String,
+    /// Prompt with stage outputs substituted.
+    pub prompt: String,
+}
+
+/// A completed stage run, in execution order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageResult {
+    pub stage: String,
+    /// 1-based run count of this stage.
+    pub iteration: u32,
+    pub output: String,
+}
+
+/// Execution state of a crew pipeline inside `SessionWorkflow`.
+///
+/// Pure bookkeeping: the session queues the agents returned by
+/// [`stage_tasks`](Self::stage_tasks) ([`task_queued`](Self::task_queued)),
+/// registers them with [`task_started`](Self::task_started) once started
+/// and reports their results with [`task_finished`](Self::task_finished).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineRun {
+    pub stages: Vec<CrewStage>,
+    /// Index of the stage being run.
+    pub current: usize,
+    pub status: PipelineStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Latest output per placeholder key (see [`stage_output_key`]).
+    #[serde(default)]
+    pub outputs: BTreeMap<String, String>,
+    /// Completed stage runs, oldest first.
+    #[serde(default)]
+    pub history: Vec<StageResult>,
+    /// Agents running the current stage: agent ID → branch (`None` for a
+    /// single-agent stage).
+    #[serde(default)]
+    pub running: BTreeMap<String, Option<String>>,
+    /// Outputs of the current stage's finished branches.
+    #[serde(default)]
+    pub branch_outputs: BTreeMap<String, String>,
+    /// Agents of the current stage queued but not started yet (waiting for
+    /// a free `max_agents` slot).
+    #[serde(default)]
+    pub queued: usize,
+}
+
+impl PipelineRun {
+    pub fn new(stages: Vec<CrewStage>) -> Self {
+        let status = if stages.is_empty() {
+            PipelineStatus::Completed
+        } else {
+            PipelineStatus::Running
+        };
+        Self {
+            stages,
+            current: 0,
+            status,
+            error: None,
+            outputs: BTreeMap::new(),
+            history: Vec::new(),
+            running: BTreeMap::new(),
+            branch_outputs: BTreeMap::new(),
+            queued: 0,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.status != PipelineStatus::Running
+    }
+
+    /// Whether `agent_id` runs part of the current stage.
+    pub fn owns(&self, agent_id: &str) -> bool {
+        self.running.contains_key(agent_id)
+    }
+
+    /// Agents to start for the current stage, with prompts rendered.
+    pub fn stage_tasks(&self) -> Vec<StageTask> {
+        let Some(stage) = self.stages.get(self.current).filter(|_| !self.is_finished()) else {
+            return Vec::new();
+        };
+        if stage.parallel.is_empty() {
+            return vec![StageTask {
+                stage: stage.name.clone(),
+                branch: None,
+                agent: stage.agent.clone().unwrap_or_default(),
+                prompt: self.render(stage.prompt.as_deref().unwrap_or_default()),
+            }];
+        }
+        stage
+            .parallel
+            .iter()
+            .map(|branch| StageTask {
+                stage: stage.name.clone(),
+                branch: Some(branch.name.clone()),
+                agent: branch.agent.clone(),
+                prompt: self.render(&branch.prompt),
+            })
+            .collect()
+    }
+
+    /// Substitute `{stage.*}` placeholders; stages that have not run yet
+    /// (e.g. a review referenced from the first implement run) are empty.
+    fn render(&self, template: &str) -> String {
+        let mut result = template.to_string();
+        for stage in &self.stages {
+            let keys = std::iter::once(stage_output_key(&stage.name, None)).chain(
+                stage
+                    .parallel
+                    .iter()
+                    .map(|b| stage_output_key(&stage.name, Some(&b.name))),
+            );
+            for key in keys {
+                let value = self.outputs.get(&key).map(String::as_str).unwrap_or("");
+                result = result.replace(&format!("{{{key}}}"), value);
+            }
+        }
+        result
+    }
+
+    /// Note that an agent of the current stage was queued for a slot.
+    pub fn task_queued(&mut self) {
+        self.queued += 1;
+    }
+
+    /// Register a started agent of the current stage (queued before).
+    pub fn task_started(&mut self, agent_id: String, branch: Option<String>) {
+        self.queued = self.queued.saturating_sub(1);
+        self.running.insert(agent_id, branch);
+    }
+
+    /// Record the result of one of the current stage's agents.  Returns
+    /// `true` when this completed the stage — the pipeline then moved to
+    /// the next stage (start its [`stage_tasks`](Self::stage_tasks)) or
+    /// finished.
+    pub fn task_finished(&mut self, agent_id: &str, outcome: Result<String, String>) -> bool {
+        let Some(branch) = self.running.remove(agent_id) else {
+            return false;
+        };
+        if self.is_finished() {
+            // Remaining branches of a failed stage.
+            return false;
+        }
+        let stage_name = self.stages[self.current].name.clone();
+        let output = match outcome {
+            Ok(output) => output,
+            Err(error) => {
+                let label = match &branch {
+                    Some(branch) => format!("{stage_name}.{branch}"),
+                    None => stage_name,
+                };
+                return self.fail(format!("stage {label} failed: {error}"));
+            }
+        };
+        match branch {
+            Some(branch) => {
+                self.branch_outputs.insert(branch, output);
+                if !self.running.is_empty() || self.queued > 0 {
+                    return false;
+                }
+                let branches = std::mem::take(&mut self.branch_outputs);
+                let mut combined = Vec::new();
+                for b in &self.stages[self.current].parallel {
+                    let text = branches.get(&b.name).cloned().unwrap_or_default();
+                    combined.push(format!("## {}\n{text}", b.name));
+                    self.outputs
+                        .insert(stage_output_key(&stage_name, Some(&b.name)), text);
+                }
+                self.complete_stage(combined.join("\n\n"));
+            }
+            None => self.complete_stage(output),
+        }
+        true
+    }
+
+    /// Fail the pipeline (e.g. an agent for the stage could not be started).
+    pub fn fail(&mut self, error: String) -> bool {
+        self.status = PipelineStatus::Failed;
+        self.error = Some(error);
+        true
+    }
+
+    fn complete_stage(&mut self, output: String) {
+        let stage = self.stages[self.current].clone();
+        let iteration = self
+            .history
+            .iter()
+            .filter(|r| r.stage == stage.name)
+            .count() as u32
+            + 1;
+        self.outputs
+            .insert(stage_output_key(&stage.name, None), output.clone());
+        let done = stage
+            .repeat
+            .as_ref()
+            .is_none_or(|r| r.is_done(&output) || iteration >= r.max_iterations);
+        self.history.push(StageResult {
+            stage: stage.name,
+            iteration,
+            output,
+        });
+
+        let loop_start = stage
+            .repeat
+            .filter(|_| !done)
+            .and_then(|r| self.stages.iter().position(|s| s.name == r.from));
+        match loop_start {
+            Some(index) => self.current = index,
+            None => self.current += 1,
+        }
+        if self.current >= self.stages.len() {
+            self.status = PipelineStatus::Completed;
+        }
+    }
+
+    /// Output of the last completed stage run.
+    pub fn final_output(&self) -> Option<&str> {
+        self.history.last().map(|r| r.output.as_str())
+    }
+}
+
 /// Agent definition within a crew type.
 ///
 /// Either fully inline (model + instructions) or a reference to an existing
@@ -423,14 +1230,228 @@ pub struct CrewAgentDef {
 }
 
 // ---------------------------------------------------------------------------
-// Harness types (session registry)
+// Crew results
 // ---------------------------------------------------------------------------
 
-/// A session entry in the harness registry.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SessionEntry {
-    /// Workflow ID of the session (e.g. "codex-session-<uuid>").
-    pub session_id: String,
+/// Maximum size of one collected artifact; larger files are truncated.
+pub const MAX_ARTIFACT_BYTES: usize = 256 * 1024;
+
+/// Maximum total size of a session's collected artifacts.
+pub const MAX_TOTAL_ARTIFACT_BYTES: usize = 1024 * 1024;
+
+/// Maximum number of collected artifact files.
+pub const MAX_ARTIFACT_FILES: usize = 100;
+
+/// Maximum size of one output kept in the harness registry; the full value
+/// stays in the session's own results.
+pub const MAX_HARNESS_OUTPUT_BYTES: usize = 4 * 1024;
+
+/// A named crew output.
+///
+/// The value is the last message of an agent (the main agent by default)
+/// or the output of a pipeline stage, optionally narrowed to the rest of
+/// the first line starting with `line_prefix` and/or to a field of a JSON
+/// (structured output) message selected by `json_pointer`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrewOutputSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Crew agent whose last message is used (`None`: the main agent).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    /// Pipeline stage (`name` or `name.branch`) whose output is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stage: Option<String>,
+    /// Use the text after this prefix on the first line that starts with it
+    /// (e.g. `"VERDICT:"`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_prefix: Option<String>,
+    /// JSON pointer into the message parsed as JSON (e.g. `"/summary"`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_pointer: Option<String>,
+}
+
+impl CrewOutputSpec {
+    /// Extract this output's value from a message.  `None` if the prefix or
+    /// JSON field is missing or the value is empty.
+    pub fn extract(&self, message: &str) -> Option<String> {
+        let mut value = message.trim().to_string();
+        if let Some(prefix) = &self.line_prefix {
+            value = message
+                .lines()
+                .find_map(|line| line.trim().strip_prefix(prefix.as_str()))?
+                .trim()
+                .to_string();
+        }
+        if let Some(pointer) = &self.json_pointer {
+            value = match parse_json_message(&value)?.pointer(pointer)? {
+                serde_json::Value::String(s) => s.trim().to_string(),
+                serde_json::Value::Null => return None,
+                other => other.to_string(),
+            };
+        }
+        (!value.is_empty()).then_some(value)
+    }
+}
+
+/// Parse a message as JSON: the whole text, or else the outermost `{...}`
+/// (e.g. inside a fenced code block).
+fn parse_json_message(text: &str) -> Option<serde_json::Value> {
+    if let Ok(value) = serde_json::from_str(text.trim()) {
+        return Some(value);
+    }
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    if start >= end {
+        return None;
+    }
+    serde_json::from_str(&text[start..=end]).ok()
+}
+
+/// What a crew session reports when it ends (from [`CrewType::outputs`]
+/// and [`CrewType::artifacts`]).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrewResultsSpec {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub outputs: BTreeMap<String, CrewOutputSpec>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<String>,
+}
+
+impl CrewResultsSpec {
+    /// Extract the named outputs from the agents' last messages and the
+    /// pipeline's stage outputs.  Returns the values and the names of
+    /// outputs without one.
+    pub fn extract_outputs(
+        &self,
+        agents: &[AgentRecord],
+        pipeline: Option<&PipelineRun>,
+    ) -> (BTreeMap<String, String>, Vec<String>) {
+        let mut values = BTreeMap::new();
+        let mut missing = Vec::new();
+        for (name, spec) in &self.outputs {
+            let message = match (&spec.stage, &spec.agent) {
+                (Some(stage), _) => pipeline
+                    .and_then(|p| p.outputs.get(&stage_output_key(stage, None)))
+                    .cloned(),
+                (None, None) => agents
+                    .iter()
+                    .find(|a| short_agent_id(&a.agent_id) == "main")
+                    .and_then(AgentRecord::last_message),
+                (None, Some(role)) => agents
+                    .iter()
+                    .rev()
+                    .filter(|a| &a.role == role)
+                    .find_map(AgentRecord::last_message),
+            };
+            match message.and_then(|m| spec.extract(&m)) {
+                Some(value) => {
+                    values.insert(name.clone(), value);
+                }
+                None => missing.push(name.clone()),
+            }
+        }
+        (values, missing)
+    }
+}
+
+/// A workspace file collected at the end of a crew session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrewArtifact {
+    /// Path relative to the session's cwd.
+    pub path: String,
+    /// File size in bytes.
+    pub size: u64,
+    /// File content (UTF-8 text; empty for binary files).
+    #[serde(default)]
+    pub content: String,
+    /// The content was cut at [`MAX_ARTIFACT_BYTES`] or the total limit.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// The file is not UTF-8 text; only its size is recorded.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub binary: bool,
+}
+
+/// Input to the `terminate_workflows` activity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminateWorkflowsInput {
+    /// Workflow IDs to terminate (their current run).
+    pub workflow_ids: Vec<String>,
+    /// Reason recorded in each workflow's history.
+    pub reason: String,
+}
+
+/// Input to the `collect_artifacts` activity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectArtifactsInput {
+    /// Workspace directory the paths are relative to.
+    pub cwd: String,
+    /// Files or directories to collect.
+    pub paths: Vec<String>,
+}
+
+/// Output of the `collect_artifacts` activity.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CollectArtifactsOutput {
+    pub artifacts: Vec<CrewArtifact>,
+    /// Paths that could not be collected, with the reason.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// Results of a crew session: the session's output and `crew_results`
+/// query carry them in full, `CodexHarness` stores a [`CrewResults::summary`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrewResults {
+    /// Extracted outputs by name.
+    #[serde(default)]
+    pub outputs: BTreeMap<String, String>,
+    /// Declared outputs no agent produced a value for.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing_outputs: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifacts: Vec<CrewArtifact>,
+    /// Artifact paths that could not be collected, with the reason.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifact_errors: Vec<String>,
+    /// Outputs cut at [`MAX_HARNESS_OUTPUT_BYTES`] (summaries only).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub truncated_outputs: Vec<String>,
+}
+
+impl CrewResults {
+    /// The results without artifact contents and with outputs cut at
+    /// [`MAX_HARNESS_OUTPUT_BYTES`], small enough to keep in the harness
+    /// across continue-as-new.
+    pub fn summary(&self) -> CrewResults {
+        let mut summary = self.clone();
+        for (name, value) in &mut summary.outputs {
+            if value.len() > MAX_HARNESS_OUTPUT_BYTES {
+                let mut cut = MAX_HARNESS_OUTPUT_BYTES;
+                while !value.is_char_boundary(cut) {
+                    cut -= 1;
+                }
+                value.truncate(cut);
+                summary.truncated_outputs.push(name.clone());
+            }
+        }
+        for artifact in &mut summary.artifacts {
+            artifact.content = String::new();
+        }
+        summary
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Harness types (session registry)
+// ---------------------------------------------------------------------------
+
+/// A session entry in the harness registry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionEntry {
+    /// Workflow ID of the session (e.g. "codex-session-<uuid>").
+    pub session_id: String,
     /// Optional human-readable name.
     pub name: Option<String>,
     /// Model slug used by this session.
@@ -442,6 +1463,262 @@ pub struct SessionEntry {
     /// Crew type name, if this session was started from a crew.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub crew_type: Option<String>,
+    /// ID of the Temporal Schedule that started this session, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule_id: Option<String>,
+    /// Time of the latest completed turn (Unix millis; 0 if none yet).
+    #[serde(default)]
+    pub last_activity_millis: u64,
+    /// Preview of the latest agent message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_message_preview: Option<String>,
+    /// Pinned sessions are exempt from retention (see [`RetentionPolicy`]).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// When the session was archived (Unix millis); archived sessions are
+    /// hidden from `list_sessions`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at_millis: Option<u64>,
+    /// Title set by the user; shown instead of `name`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// User tags.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+    /// Working directory of the session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Git remote URL of the workspace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    /// Git branch of the workspace when the session started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+}
+
+impl SessionEntry {
+    /// Re-register an existing entry: fields `entry` leaves unset keep
+    /// their current values, and activity never moves backwards.  The pin,
+    /// title and tags are kept; registering again un-archives the session.
+    pub fn merge(&mut self, entry: SessionEntry) {
+        let previous = std::mem::replace(self, entry);
+        self.pinned |= previous.pinned;
+        self.title = self.title.take().or(previous.title);
+        self.tags.extend(previous.tags);
+        self.cwd = self.cwd.take().or(previous.cwd);
+        self.repository = self.repository.take().or(previous.repository);
+        self.branch = self.branch.take().or(previous.branch);
+        self.name = self.name.take().or(previous.name);
+        self.crew_type = self.crew_type.take().or(previous.crew_type);
+        self.schedule_id = self.schedule_id.take().or(previous.schedule_id);
+        self.last_message_preview =
+            self.last_message_preview.take().or(previous.last_message_preview);
+        self.last_activity_millis = self.last_activity_millis.max(previous.last_activity_millis);
+        if previous.created_at_millis != 0 {
+            self.created_at_millis = self.created_at_millis.min(previous.created_at_millis);
+        }
+    }
+
+    /// Time of the latest activity, or the creation time without any.
+    pub fn last_active_millis(&self) -> u64 {
+        self.last_activity_millis.max(self.created_at_millis)
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.archived_at_millis.is_some()
+    }
+
+    /// Name to show: the user's title, else the registered name.
+    pub fn display_name(&self) -> Option<&str> {
+        self.title.as_deref().or(self.name.as_deref())
+    }
+
+    /// Record the session's workspace from its project context.
+    pub fn set_workspace(&mut self, context: &ProjectContextOutput) {
+        self.cwd = Some(context.cwd.clone());
+        if let Some(git) = &context.git_info {
+            self.repository = git.repository_url.clone();
+            self.branch = git.branch.clone();
+        }
+    }
+
+    /// Apply a user edit (title, tags, pin).
+    pub fn apply_edit(&mut self, edit: &SessionEdit) {
+        if let Some(title) = &edit.title {
+            let title = title.trim();
+            self.title = (!title.is_empty()).then(|| title.to_string());
+        }
+        for tag in &edit.remove_tags {
+            self.tags.remove(tag.trim());
+        }
+        for tag in &edit.add_tags {
+            let tag = tag.trim();
+            if !tag.is_empty() {
+                self.tags.insert(tag.to_string());
+            }
+        }
+        if let Some(pinned) = edit.pinned {
+            self.pinned = pinned;
+        }
+    }
+}
+
+/// Order sessions for display: pinned first, then most recently active.
+pub fn sort_sessions_for_display(sessions: &mut [SessionEntry]) {
+    sessions.sort_by_key(|s| (!s.pinned, std::cmp::Reverse(s.last_active_millis())));
+}
+
+/// User edit of a session entry (`CodexHarness::edit_session` signal).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionEdit {
+    pub session_id: String,
+    /// New title; an empty title clears it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub add_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned: Option<bool>,
+}
+
+/// Filter for the `CodexHarness::search_sessions` query.  All set criteria
+/// must match.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionFilter {
+    /// Case-insensitive text in the ID, title, name, latest message
+    /// preview, crew type or tags.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Tags the session must all have.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Case-insensitive text in the repository URL, branch or cwd.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<SessionStatus>,
+    /// Last active at or after this time (Unix millis).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_since_millis: Option<u64>,
+    /// Last active before this time (Unix millis).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_before_millis: Option<u64>,
+    /// Search archived sessions instead of the listed ones.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+}
+
+impl SessionFilter {
+    /// Set one criterion from a CLI flag value.  `--since`/`--before` take
+    /// an interval (`2h`, `7d`) counted back from `now_millis`.
+    pub fn set_option(&mut self, name: &str, value: &str, now_millis: u64) -> Result<(), String> {
+        let ago = || {
+            crate::schedules::parse_interval(value)
+                .map(|d| now_millis.saturating_sub(d.as_millis() as u64))
+        };
+        match name {
+            "search" => self.text = Some(value.to_string()),
+            "tag" => self.tags.push(value.to_string()),
+            "repo" => self.repo = Some(value.to_string()),
+            "status" => {
+                self.status = Some(match value.to_ascii_lowercase().as_str() {
+                    "running" => SessionStatus::Running,
+                    "completed" => SessionStatus::Completed,
+                    "failed" => SessionStatus::Failed,
+                    "terminated" => SessionStatus::Terminated,
+                    "missing" => SessionStatus::Missing,
+                    _ => return Err(format!("unknown status '{value}'")),
+                })
+            }
+            "since" => self.active_since_millis = Some(ago()?),
+            "before" => self.active_before_millis = Some(ago()?),
+            other => return Err(format!("unknown filter '{other}'")),
+        }
+        Ok(())
+    }
+
+    pub fn matches(&self, entry: &SessionEntry) -> bool {
+        let contains = |haystack: Option<&str>, needle: &str| {
+            haystack.is_some_and(|h| h.to_lowercase().contains(needle))
+        };
+        if entry.is_archived() != self.archived {
+            return false;
+        }
+        if let Some(text) = &self.text {
+            let text = text.to_lowercase();
+            let found = [
+                Some(entry.session_id.as_str()),
+                entry.title.as_deref(),
+                entry.name.as_deref(),
+                entry.last_message_preview.as_deref(),
+                entry.crew_type.as_deref(),
+            ]
+            .into_iter()
+            .any(|field| contains(field, &text))
+                || entry.tags.iter().any(|tag| contains(Some(tag), &text));
+            if !found {
+                return false;
+            }
+        }
+        if !self.tags.iter().all(|tag| entry.tags.contains(tag)) {
+            return false;
+        }
+        if let Some(repo) = &self.repo {
+            let repo = repo.to_lowercase();
+            let found = [entry.repository.as_deref(), entry.branch.as_deref(), entry.cwd.as_deref()]
+                .into_iter()
+                .any(|field| contains(field, &repo));
+            if !found {
+                return false;
+            }
+        }
+        if self.status.is_some_and(|status| status != entry.status) {
+            return false;
+        }
+        let active = entry.last_active_millis();
+        self.active_since_millis.is_none_or(|since| active >= since)
+            && self.active_before_millis.is_none_or(|before| active < before)
+    }
+}
+
+/// Activity a `SessionWorkflow` reports to `CodexHarness` after turns.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionActivity {
+    pub session_id: String,
+    /// Workflow time of the activity (Unix millis).
+    pub at_millis: u64,
+    /// Preview of the latest agent message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+}
+
+/// Input to the `signal_with_start_harness` activity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarnessSignalInput {
+    /// Workflow ID of the harness, started if it is not running.
+    pub harness_id: String,
+    pub signal_name: String,
+    pub payload: serde_json::Value,
+}
+
+/// Registration a `SessionWorkflow` performs itself with `CodexHarness`.
+///
+/// The session registers on start (starting the harness if needed),
+/// reports activity after turns and its final status on exit; crew
+/// sessions also report their results there.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HarnessRegistration {
+    /// Workflow ID of the harness (e.g. "codex-harness-<user>").
+    pub harness_id: String,
+    /// Name shown in session lists (default: the first user message).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crew_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule_id: Option<String>,
 }
 
 /// Status of a tracked session.
@@ -450,6 +1727,12 @@ pub enum SessionStatus {
     Running,
     Completed,
     Failed,
+    /// The session's workflow was terminated (by the harness's idle rule or
+    /// from outside) before it could report an outcome.
+    Terminated,
+    /// The session's workflow does not exist: it was never started, or was
+    /// removed by the namespace's retention.
+    Missing,
 }
 
 /// Input to the `CodexHarness` workflow.
@@ -468,6 +1751,189 @@ pub struct HarnessState {
     /// Whether the worker has API credentials available (checked once at startup).
     #[serde(default)]
     pub credentials_available: Option<bool>,
+    /// Results of finished crew sessions, by session ID.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub results: BTreeMap<String, CrewResults>,
+    /// Retention rules set with `set_retention_policy`.
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+    /// Idle sessions already asked to shut down.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub idle_shutdown_sent: BTreeSet<String>,
+}
+
+/// Default cap on archived sessions kept by the harness.
+pub const DEFAULT_MAX_ARCHIVED_SESSIONS: usize = 500;
+
+fn default_max_archived() -> Option<usize> {
+    Some(DEFAULT_MAX_ARCHIVED_SESSIONS)
+}
+
+/// What the harness does with a session idle for longer than
+/// [`RetentionPolicy::idle_after_secs`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdleAction {
+    /// Signal `SessionWorkflow::shutdown`; agents finish their current turn.
+    #[default]
+    Shutdown,
+    /// Terminate the session workflow (and with it its agents).
+    Terminate,
+}
+
+impl std::str::FromStr for IdleAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "shutdown" => Ok(Self::Shutdown),
+            "terminate" => Ok(Self::Terminate),
+            other => Err(format!(
+                "unknown idle action '{other}' (expected shutdown or terminate)"
+            )),
+        }
+    }
+}
+
+/// Retention rules `CodexHarness` applies on its periodic timer.
+///
+/// Closed sessions are archived when inactive for longer than
+/// `max_age_secs`, or (oldest first) while more than `max_sessions`
+/// sessions are not archived.  Archived sessions beyond `max_archived` are
+/// purged from the registry together with their results.  Running sessions
+/// idle for longer than `idle_after_secs` are shut down or terminated.
+/// With `keep_pinned`, pinned sessions are exempt from all of it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_sessions: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_secs: Option<u64>,
+    #[serde(default = "default_max_archived")]
+    pub max_archived: Option<usize>,
+    #[serde(default = "default_true")]
+    pub keep_pinned: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_after_secs: Option<u64>,
+    #[serde(default)]
+    pub idle_action: IdleAction,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_sessions: None,
+            max_age_secs: None,
+            max_archived: default_max_archived(),
+            keep_pinned: true,
+            idle_after_secs: None,
+            idle_action: IdleAction::default(),
+        }
+    }
+}
+
+/// Entries changed by [`RetentionPolicy::apply`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionOutcome {
+    /// Sessions archived in this pass.
+    pub archived: Vec<String>,
+    /// Archived sessions removed from the registry.
+    pub purged: Vec<String>,
+    /// Running sessions idle for too long (to shut down or terminate).
+    pub idle: Vec<String>,
+}
+
+impl RetentionPolicy {
+    /// Set one option from a CLI flag value; `off` clears a limit.
+    /// Durations take the schedule interval format (`30m`, `6h`, `30d`).
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let off = value == "off";
+        let count = || value.parse::<usize>().map_err(|_| format!("invalid count '{value}'"));
+        let secs = || crate::schedules::parse_interval(value).map(|d| d.as_secs());
+        match name {
+            "max-sessions" => self.max_sessions = if off { None } else { Some(count()?) },
+            "max-age" => self.max_age_secs = if off { None } else { Some(secs()?) },
+            "max-archived" => self.max_archived = if off { None } else { Some(count()?) },
+            "idle-after" => self.idle_after_secs = if off { None } else { Some(secs()?) },
+            "idle-action" => self.idle_action = value.parse()?,
+            "keep-pinned" => {
+                self.keep_pinned = value
+                    .parse()
+                    .map_err(|_| format!("invalid value '{value}' (expected true or false)"))?
+            }
+            other => return Err(format!("unknown retention option '{other}'")),
+        }
+        Ok(())
+    }
+
+    fn protects(&self, entry: &SessionEntry) -> bool {
+        self.keep_pinned && entry.pinned
+    }
+
+    /// Apply the policy to `sessions` at `now_millis`: archive and purge
+    /// entries in place and report idle running sessions.
+    pub fn apply(&self, sessions: &mut Vec<SessionEntry>, now_millis: u64) -> RetentionOutcome {
+        let mut outcome = RetentionOutcome::default();
+        let inactive_for =
+            |entry: &SessionEntry| now_millis.saturating_sub(entry.last_active_millis());
+
+        if let Some(idle_after) = self.idle_after_secs {
+            outcome.idle = sessions
+                .iter()
+                .filter(|e| e.status == SessionStatus::Running && !e.is_archived())
+                .filter(|e| !self.protects(e) && inactive_for(e) >= idle_after * 1000)
+                .map(|e| e.session_id.clone())
+                .collect();
+        }
+
+        // Closed, unprotected, not yet archived sessions, oldest first.
+        let mut candidates: Vec<usize> = (0..sessions.len())
+            .filter(|&i| {
+                let e = &sessions[i];
+                e.status != SessionStatus::Running && !e.is_archived() && !self.protects(e)
+            })
+            .collect();
+        candidates.sort_by_key(|&i| sessions[i].last_active_millis());
+
+        let mut archive = BTreeSet::new();
+        if let Some(max_age) = self.max_age_secs {
+            archive.extend(
+                candidates
+                    .iter()
+                    .copied()
+                    .filter(|&i| inactive_for(&sessions[i]) >= max_age * 1000),
+            );
+        }
+        if let Some(max_sessions) = self.max_sessions {
+            let active = sessions.iter().filter(|e| !e.is_archived()).count();
+            let excess = active.saturating_sub(max_sessions);
+            let mut remaining = excess.saturating_sub(archive.len());
+            for &i in &candidates {
+                if remaining == 0 {
+                    break;
+                }
+                if archive.insert(i) {
+                    remaining -= 1;
+                }
+            }
+        }
+        for i in archive {
+            sessions[i].archived_at_millis = Some(now_millis);
+            outcome.archived.push(sessions[i].session_id.clone());
+        }
+
+        if let Some(max_archived) = self.max_archived {
+            let mut archived: Vec<&SessionEntry> = sessions
+                .iter()
+                .filter(|e| e.is_archived() && !self.protects(e))
+                .collect();
+            archived.sort_by_key(|e| (e.archived_at_millis, e.last_active_millis()));
+            let excess = archived.len().saturating_sub(max_archived);
+            outcome.purged = archived[..excess].iter().map(|e| e.session_id.clone()).collect();
+            sessions.retain(|e| !outcome.purged.contains(&e.session_id));
+        }
+        outcome
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -534,10 +2000,17 @@ pub struct AgentWorkflowInput {
     /// Client-defined dynamic tools (handled via signal/wait).
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub dynamic_tools: Vec<codex_protocol::dynamic_tools::DynamicToolSpec>,
+    /// This agent decides approval requests of the other agents in its
+    /// session (gets the `decide_approval` tool).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub approval_supervisor: bool,
     /// Maximum number of model→tool loop iterations per turn.
     /// Defaults to 50 when `None`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub max_iterations: Option<u32>,
+    /// JSON Schema the reply to `user_message` must follow.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<serde_json::Value>,
 }
 
 fn default_role() -> String {
@@ -570,12 +2043,14 @@ impl AgentWorkflowInput {
             developer_instructions: session.developer_instructions.clone(),
             model_provider: session.model_provider.clone(),
             continued_state: None,
-            role,
             config_toml: Some(config_toml),
             project_context: Some(project_context),
             mcp_tools,
             dynamic_tools: Vec::new(),
+            approval_supervisor: session.approval_supervisor.as_ref() == Some(&role),
+            role,
             max_iterations: session.max_iterations,
+            output_schema: None,
         }
     }
 }
@@ -639,13 +2114,35 @@ pub struct SessionWorkflowInput {
     /// Keyed by agent name (e.g. "helper", "fixer").
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub crew_agents: BTreeMap<String, CrewAgentDef>,
+    /// Crew pipeline stages (prompts already interpolated with crew inputs).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pipeline: Vec<CrewStage>,
+    /// Role of the agent that decides other agents' approval requests
+    /// (`"default"` for the main agent).  Requests still go to clients too.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval_supervisor: Option<String>,
+    /// Register with `CodexHarness` on start and report activity and the
+    /// final status there.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub harness_registration: Option<HarnessRegistration>,
+    /// Crew outputs and artifacts to collect when the session ends.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crew_results: Option<CrewResultsSpec>,
     /// State carried over from a previous continue-as-new execution.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub continued_state: Option<SessionContinueAsNewState>,
+    /// Conversation state to restore the main agent from (reopening a
+    /// closed session); the main agent starts as if continued-as-new.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main_agent_state: Option<ContinueAsNewState>,
     /// Maximum number of model→tool loop iterations per turn.
     /// Defaults to 50 when `None`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub max_iterations: Option<u32>,
+    /// JSON Schema the main agent's reply to `user_message` must follow
+    /// (`codex-temporal-client exec --output-schema`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<serde_json::Value>,
 }
 
 impl From<AgentWorkflowInput> for SessionWorkflowInput {
@@ -662,8 +2159,14 @@ impl From<AgentWorkflowInput> for SessionWorkflowInput {
             developer_instructions: input.developer_instructions,
             model_provider: input.model_provider,
             crew_agents: BTreeMap::new(),
+            pipeline: Vec::new(),
+            approval_supervisor: None,
+            harness_registration: None,
+            crew_results: None,
             continued_state: None,
+            main_agent_state: None,
             max_iterations: input.max_iterations,
+            output_schema: input.output_schema,
         }
     }
 }
@@ -673,6 +2176,9 @@ impl From<AgentWorkflowInput> for SessionWorkflowInput {
 pub struct SessionWorkflowOutput {
     /// Summary of all agents that ran in this session.
     pub agents: Vec<AgentSummary>,
+    /// Crew outputs and artifacts (crews that declare them).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub results: Option<CrewResults>,
 }
 
 /// Summary of an agent's execution within a session.
@@ -689,14 +2195,83 @@ pub struct AgentSummary {
     /// Cumulative token usage.
     #[serde(default)]
     pub token_usage: Option<TokenUsage>,
+    /// The agent's final assistant message, if it completed with one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_agent_message: Option<String>,
+    /// Failure reason, for agents that failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 /// Lifecycle status of an agent within a session.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AgentLifecycle {
     Running,
+    /// Paused via `control_agent`: holds before its next model call and
+    /// does not start queued turns until resumed.
+    Paused,
     Completed,
     Failed,
+    /// Asked to shut down gracefully (finishes its current turn).
+    Cancelled,
+    /// Stopped immediately, aborting its in-flight turn.
+    Terminated,
+}
+
+impl AgentLifecycle {
+    /// Whether the agent can still take turns (running or paused).
+    pub fn is_live(self) -> bool {
+        matches!(self, Self::Running | Self::Paused)
+    }
+}
+
+/// Per-agent lifecycle action, sent to `SessionWorkflow::control_agent`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentControlAction {
+    /// Stop picking up turns and hold before the next model call.
+    Pause,
+    /// Undo a pause.
+    Resume,
+    /// Shut down gracefully via `Op::Shutdown`.
+    Cancel,
+    /// Terminate the agent's workflow (or, without a Temporal client,
+    /// abort the in-flight turn, drop queued turns and exit).
+    Terminate,
+}
+
+impl AgentControlAction {
+    /// Status an agent moves to after this action.
+    pub fn status(self) -> AgentLifecycle {
+        match self {
+            Self::Pause => AgentLifecycle::Paused,
+            Self::Resume => AgentLifecycle::Running,
+            Self::Cancel => AgentLifecycle::Cancelled,
+            Self::Terminate => AgentLifecycle::Terminated,
+        }
+    }
+}
+
+impl std::str::FromStr for AgentControlAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pause" => Ok(Self::Pause),
+            "resume" => Ok(Self::Resume),
+            "cancel" => Ok(Self::Cancel),
+            "terminate" => Ok(Self::Terminate),
+            other => Err(format!("unknown agent action '{other}'")),
+        }
+    }
+}
+
+/// Input to `SessionWorkflow::control_agent`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentControlRequest {
+    /// Full agent ID (`{session}/{name}`) or its short form (`worker-2`).
+    pub agent_id: String,
+    pub action: AgentControlAction,
 }
 
 /// State carried across a continue-as-new boundary for `SessionWorkflow`.
@@ -714,6 +2289,58 @@ pub struct SessionContinueAsNewState {
     /// Crew agent definitions carried across CAN.
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub crew_agents: BTreeMap<String, CrewAgentDef>,
+    /// MCP servers disabled at runtime via `refresh_mcp`.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub disabled_mcp_servers: BTreeSet<String>,
+    /// Inter-agent message log (most recent entries).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<AgentMessage>,
+    /// Crew pipeline progress.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline: Option<PipelineRun>,
+    /// Effective `max_agents` limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_agents: Option<usize>,
+    /// Spawn requests waiting for a free agent slot, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending_spawns: Vec<SpawnAgentInput>,
+    /// Approval requests still waiting for a decision.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub approvals: Vec<SessionApproval>,
+    /// Number of the last agent started (`{session}/{role}-{n}`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_counter: Option<u32>,
+}
+
+impl SessionContinueAsNewState {
+    /// The agent counter to continue from: the carried one, or for states
+    /// without it the highest agent number in use.
+    pub fn agent_counter(&self) -> u32 {
+        self.agent_counter.unwrap_or_else(|| {
+            self.agents
+                .iter()
+                .filter_map(|a| short_agent_id(&a.agent_id).rsplit_once('-'))
+                .filter_map(|(_, n)| n.parse().ok())
+                .max()
+                .unwrap_or(0)
+                .max(1)
+        })
+    }
+}
+
+/// Agents occupying a session's `max_agents` slots.
+pub fn live_agent_count(agents: &[AgentRecord]) -> usize {
+    agents.iter().filter(|a| a.is_active()).count()
+}
+
+/// Take the oldest pending spawn request if an agent slot is free.
+pub fn admit_pending_spawn(
+    agents: &[AgentRecord],
+    pending_spawns: &mut Vec<SpawnAgentInput>,
+    max_agents: usize,
+) -> Option<SpawnAgentInput> {
+    (!pending_spawns.is_empty() && live_agent_count(agents) < max_agents)
+        .then(|| pending_spawns.remove(0))
 }
 
 /// Record of a child agent workflow tracked by `SessionWorkflow`.
@@ -727,15 +2354,742 @@ pub struct AgentRecord {
     pub role: String,
     /// Current lifecycle status.
     pub status: AgentLifecycle,
+    /// The child's result, once it completed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<AgentWorkflowOutput>,
+    /// Failure reason, once it failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Workflow ID of the agent that spawned this one via its subagent
+    /// tools; it is notified of turn completions and the final outcome.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_agent_id: Option<String>,
+    /// Final message of the agent's latest turn.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_agent_message: Option<String>,
+    /// Loop iterations the agent reported with its latest turn.
+    #[serde(default)]
+    pub iterations: u32,
+    /// Cumulative token usage the agent reported with its latest turn.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_usage: Option<TokenUsage>,
+}
+
+impl AgentRecord {
+    /// Record for a freshly started child (workflow ID == agent ID).
+    pub fn running(agent_id: impl Into<String>, role: impl Into<String>) -> Self {
+        let agent_id = agent_id.into();
+        Self {
+            workflow_id: agent_id.clone(),
+            agent_id,
+            role: role.into(),
+            status: AgentLifecycle::Running,
+            output: None,
+            error: None,
+            parent_agent_id: None,
+            last_agent_message: None,
+            iterations: 0,
+            token_usage: None,
+        }
+    }
+
+    /// Set the agent that spawned this one.
+    pub fn with_parent(mut self, parent_agent_id: Option<String>) -> Self {
+        self.parent_agent_id = parent_agent_id;
+        self
+    }
+
+    /// The agent's latest final message: from its result, or else from its
+    /// last turn.
+    pub fn last_message(&self) -> Option<String> {
+        self.output
+            .as_ref()
+            .and_then(|o| o.last_agent_message.clone())
+            .or_else(|| self.last_agent_message.clone())
+    }
+
+    /// Apply the child's outcome: its output on success, the reason on failure.
+    /// Cancelled and terminated agents keep their status.
+    pub fn finish(&mut self, outcome: Result<AgentWorkflowOutput, String>) {
+        let stopped = matches!(
+            self.status,
+            AgentLifecycle::Cancelled | AgentLifecycle::Terminated
+        );
+        match outcome {
+            Ok(output) => {
+                if !stopped {
+                    self.status = AgentLifecycle::Completed;
+                }
+                self.output = Some(output);
+                self.error = None;
+            }
+            Err(error) => {
+                if !stopped {
+                    self.status = AgentLifecycle::Failed;
+                }
+                self.error = Some(error);
+            }
+        }
+    }
+
+    /// Outcome of an agent whose workflow was found closed with `status`
+    /// (e.g. while the session continued as new): what its last turn
+    /// reported if it completed, otherwise a failure reason.
+    pub fn closed_outcome(&self, status: SessionStatus) -> Result<AgentWorkflowOutput, String> {
+        match status {
+            SessionStatus::Completed => Ok(AgentWorkflowOutput {
+                last_agent_message: self.last_agent_message.clone(),
+                iterations: self.iterations,
+                token_usage: self.token_usage.clone(),
+            }),
+            _ => Err("agent workflow closed without reporting a result".to_string()),
+        }
+    }
+
+    /// Whether the agent holds one of the session's `max_agents` slots:
+    /// it is live, or was cancelled/terminated but has not exited yet.
+    pub fn is_active(&self) -> bool {
+        match self.status {
+            AgentLifecycle::Running | AgentLifecycle::Paused => true,
+            AgentLifecycle::Cancelled | AgentLifecycle::Terminated => {
+                self.output.is_none() && self.error.is_none()
+            }
+            AgentLifecycle::Completed | AgentLifecycle::Failed => false,
+        }
+    }
+
+    /// Whether `id` names this agent, by full ID or short ID.
+    pub fn matches_id(&self, id: &str) -> bool {
+        self.agent_id == id || short_agent_id(&self.agent_id) == id
+    }
+
+    /// Summary for `SessionWorkflowOutput`.
+    pub fn summary(&self) -> AgentSummary {
+        AgentSummary {
+            agent_id: self.agent_id.clone(),
+            role: self.role.clone(),
+            status: self.status,
+            iterations: self.output.as_ref().map_or(0, |o| o.iterations),
+            token_usage: self.output.as_ref().and_then(|o| o.token_usage.clone()),
+            last_agent_message: self
+                .output
+                .as_ref()
+                .and_then(|o| o.last_agent_message.clone()),
+            error: self.error.clone(),
+        }
+    }
 }
 
 /// Signal payload for spawning a new agent in the session.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SpawnAgentInput {
     /// Role to assign to the new agent (e.g. "explorer", "worker").
     pub role: String,
     /// Initial message for the new agent.
     pub message: String,
+    /// Workflow ID of the agent requesting the spawn (set by the subagent
+    /// tools).  It receives a [`SubagentNotice`] with the outcome.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requested_by: Option<String>,
+    /// Requester-chosen ID echoed back in the spawn notice (the tool call ID).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// The crew pipeline task this agent runs, if queued by the pipeline.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline_task: Option<PipelineTaskRef>,
+}
+
+/// A pipeline stage (and branch of a fan-out stage) an agent is queued for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PipelineTaskRef {
+    pub stage: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+}
+
+impl PipelineTaskRef {
+    /// `stage` or `stage.branch`, as used in pipeline errors.
+    pub fn label(&self) -> String {
+        match &self.branch {
+            Some(branch) => format!("{}.{branch}", self.stage),
+            None => self.stage.clone(),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Subagent tools
+// ---------------------------------------------------------------------------
+
+/// Signal payload sent by an agent to its `SessionWorkflow` after each
+/// completed turn, so the session can forward it to the spawning agent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentTurnNotice {
+    /// Workflow ID of the agent that finished a turn.
+    pub agent_id: String,
+    /// The agent's final message for that turn.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_agent_message: Option<String>,
+    /// Loop iterations the agent ran so far, across turns.
+    #[serde(default)]
+    pub iterations: u32,
+    /// Cumulative token usage of the agent so far.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_usage: Option<TokenUsage>,
+}
+
+/// Signal payload sent by an agent to its `SessionWorkflow` when its
+/// workflow ends, so the session records the outcome even if it continued
+/// as new since starting the agent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentFinishedNotice {
+    /// Workflow ID of the agent that finished.
+    pub agent_id: String,
+    /// The agent's result, or the reason it failed.
+    pub outcome: Result<AgentWorkflowOutput, String>,
+}
+
+/// Signal payload delivered by `SessionWorkflow` to an agent about the
+/// subagents it spawned.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SubagentNotice {
+    /// The spawn request `request_id` started `agent_id`.
+    Spawned {
+        request_id: String,
+        agent_id: String,
+        role: String,
+    },
+    /// The spawn request `request_id` was rejected.
+    SpawnFailed { request_id: String, error: String },
+    /// The spawn request `request_id` is waiting for a free agent slot
+    /// (`position` in the queue, 1-based).
+    SpawnQueued {
+        request_id: String,
+        position: usize,
+        max_agents: usize,
+    },
+    /// The subagent finished a turn.
+    TurnComplete {
+        agent_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        last_agent_message: Option<String>,
+    },
+    /// The subagent workflow exited (`error` is set if it failed).
+    Finished {
+        agent_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        last_agent_message: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+/// What an agent knows about one of its subagents.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SubagentState {
+    /// Role the subagent was spawned with.
+    pub role: String,
+    /// Messages sent to the subagent whose turn has not completed yet.
+    pub outstanding_turns: u32,
+    /// The subagent's most recent final message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_agent_message: Option<String>,
+    /// Whether the subagent workflow has exited.
+    #[serde(default)]
+    pub finished: bool,
+    /// Failure reason, if the subagent failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl SubagentState {
+    /// True when the subagent has no turn in flight (or has exited).
+    pub fn is_idle(&self) -> bool {
+        self.finished || self.outstanding_turns == 0
+    }
+}
+
+/// Subagents spawned by an agent, keyed by agent ID, plus spawn results not
+/// yet collected by the tool call that requested them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Subagents {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub agents: BTreeMap<String, SubagentState>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub spawn_results: BTreeMap<String, Result<String, String>>,
+}
+
+impl Subagents {
+    pub fn is_empty(&self) -> bool {
+        self.agents.is_empty() && self.spawn_results.is_empty()
+    }
+
+    /// Apply a notice from the session.  A turn completion that arrives
+    /// before the spawn notice still counts against the initial message.
+    pub fn apply(&mut self, notice: SubagentNotice) {
+        match notice {
+            SubagentNotice::Spawned {
+                request_id,
+                agent_id,
+                role,
+            } => {
+                let state = self.agents.entry(agent_id.clone()).or_insert_with(|| {
+                    SubagentState {
+                        outstanding_turns: 1,
+                        ..Default::default()
+                    }
+                });
+                state.role = role;
+                self.spawn_results.insert(request_id, Ok(agent_id));
+            }
+            SubagentNotice::SpawnFailed { request_id, error } => {
+                self.spawn_results.insert(request_id, Err(error));
+            }
+            // Still pending; the tool keeps waiting for Spawned/SpawnFailed.
+            SubagentNotice::SpawnQueued { .. } => {}
+            SubagentNotice::TurnComplete {
+                agent_id,
+                last_agent_message,
+            } => match self.agents.get_mut(&agent_id) {
+                Some(state) => {
+                    state.outstanding_turns = state.outstanding_turns.saturating_sub(1);
+                    if last_agent_message.is_some() {
+                        state.last_agent_message = last_agent_message;
+                    }
+                }
+                None => {
+                    self.agents.insert(agent_id, SubagentState {
+                        last_agent_message,
+                        ..Default::default()
+                    });
+                }
+            },
+            SubagentNotice::Finished {
+                agent_id,
+                last_agent_message,
+                error,
+            } => {
+                let state = self.agents.entry(agent_id).or_default();
+                state.finished = true;
+                state.outstanding_turns = 0;
+                if last_agent_message.is_some() {
+                    state.last_agent_message = last_agent_message;
+                }
+                state.error = error;
+            }
+        }
+    }
+
+    /// Remove and return the outcome of spawn request `request_id`.
+    pub fn take_spawn_result(&mut self, request_id: &str) -> Option<Result<String, String>> {
+        self.spawn_results.remove(request_id)
+    }
+
+    /// Record a follow-up message sent to `agent_id`.  Fails if the agent is
+    /// unknown or has exited.
+    pub fn message_sent(&mut self, agent_id: &str) -> Result<(), String> {
+        match self.agents.get_mut(agent_id) {
+            Some(state) if state.finished => Err(format!("subagent {agent_id} has exited")),
+            Some(state) => {
+                state.outstanding_turns += 1;
+                Ok(())
+            }
+            None => Err(format!("unknown subagent: {agent_id}")),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Session approvals
+// ---------------------------------------------------------------------------
+
+/// Kind of approval an agent is waiting for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalKind {
+    /// Shell command or MCP tool call (`ExecApprovalRequest`).
+    Exec,
+    /// `apply_patch` (`ApplyPatchApprovalRequest`).
+    Patch,
+}
+
+/// An approval request of one agent, aggregated by `SessionWorkflow`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionApproval {
+    /// Workflow ID of the waiting agent.
+    pub agent_id: String,
+    pub call_id: String,
+    pub kind: ApprovalKind,
+    /// Command to run (exec approvals).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub command: Vec<String>,
+    /// Reason given with the request (the patch text for patch approvals).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl SessionApproval {
+    /// Build the session-level request from an agent's approval event.
+    pub fn from_event(agent_id: &str, msg: &EventMsg) -> Option<Self> {
+        match msg {
+            EventMsg::ExecApprovalRequest(ev) => Some(Self {
+                agent_id: agent_id.to_string(),
+                call_id: ev.call_id.clone(),
+                kind: ApprovalKind::Exec,
+                command: ev.command.clone(),
+                reason: ev.reason.clone(),
+            }),
+            EventMsg::ApplyPatchApprovalRequest(ev) => Some(Self {
+                agent_id: agent_id.to_string(),
+                call_id: ev.call_id.clone(),
+                kind: ApprovalKind::Patch,
+                command: Vec::new(),
+                reason: ev.reason.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// One-line description, e.g. "agent worker-2 wants to run `cargo test`".
+    pub fn summary(&self) -> String {
+        let agent = short_agent_id(&self.agent_id);
+        match self.kind {
+            ApprovalKind::Exec => {
+                format!("agent {agent} wants to run `{}`", self.command.join(" "))
+            }
+            ApprovalKind::Patch => format!("agent {agent} wants to apply a patch"),
+        }
+    }
+}
+
+/// Sent by an agent to its session when an approval request was answered
+/// or abandoned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalResolved {
+    pub agent_id: String,
+    pub call_id: String,
+}
+
+/// A decision on a pending session approval (from a client via the
+/// `resolve_approval` update, or from the supervisor agent).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalDecisionRequest {
+    /// Full or short ID of the waiting agent.
+    pub agent_id: String,
+    pub call_id: String,
+    pub approved: bool,
+    /// Agent that made the decision (`None` for clients).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decided_by: Option<String>,
+}
+
+/// Address of the session's approval supervisor for [`message_recipients`]:
+/// the main agent for `default`, otherwise every agent with that role.
+pub fn approval_supervisor_address(supervisor: &str) -> &str {
+    if supervisor == "default" { "main" } else { supervisor }
+}
+
+/// Whether `decided_by` may decide approvals in a session supervised by
+/// `supervisor`: clients (`None`) always may, agents only if they have the
+/// supervisor role and the request is not their own.
+pub fn may_decide_approval(
+    agents: &[AgentRecord],
+    supervisor: Option<&str>,
+    decided_by: Option<&str>,
+    approval: &SessionApproval,
+) -> bool {
+    let Some(decided_by) = decided_by else {
+        return true;
+    };
+    decided_by != approval.agent_id
+        && supervisor.is_some_and(|role| {
+            agents
+                .iter()
+                .any(|a| a.agent_id == decided_by && a.role == role)
+        })
+}
+
+/// Decision forwarded from the session to the waiting agent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalDecision {
+    pub call_id: String,
+    pub approved: bool,
+}
+
+// ---------------------------------------------------------------------------
+// Session event aggregation
+// ---------------------------------------------------------------------------
+
+/// Longest preview of a message shown in timelines and status panels.
+const PREVIEW_CHARS: usize = 120;
+
+/// First line of `text`, cut to [`PREVIEW_CHARS`].
+pub fn preview_text(text: &str) -> String {
+    let line = text.lines().find(|l| !l.trim().is_empty()).unwrap_or("").trim();
+    if line.chars().count() > PREVIEW_CHARS {
+        let cut: String = line.chars().take(PREVIEW_CHARS - 1).collect();
+        format!("{cut}…")
+    } else {
+        line.to_string()
+    }
+}
+
+/// One event of one agent in a session-wide event stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionEvent {
+    /// Workflow ID of the agent that emitted the event.
+    pub agent_id: String,
+    /// Role of that agent.
+    pub role: String,
+    /// The agent's watermark after this event (resume point for it).
+    pub watermark: usize,
+    pub event: Event,
+}
+
+impl SessionEvent {
+    /// Tag a batch of one agent's events ending at `watermark` (as returned
+    /// by `get_state_update`, which counts events).
+    pub fn tag(agent_id: &str, role: &str, events: Vec<Event>, watermark: usize) -> Vec<Self> {
+        let first = watermark.saturating_sub(events.len());
+        events
+            .into_iter()
+            .enumerate()
+            .map(|(i, event)| Self {
+                agent_id: agent_id.to_string(),
+                role: role.to_string(),
+                watermark: first + i + 1,
+                event,
+            })
+            .collect()
+    }
+
+    /// One-line, agent-tagged description for an interleaved timeline, e.g.
+    /// `[worker-2] ran: cargo test`.  `None` for streaming deltas and other
+    /// events that are not worth a timeline entry.
+    pub fn timeline_line(&self) -> Option<String> {
+        let text = match &self.event.msg {
+            EventMsg::TurnStarted(_) => "turn started".to_string(),
+            EventMsg::TurnComplete(ev) => match &ev.last_agent_message {
+                Some(message) => format!("turn complete: {}", preview_text(message)),
+                None => "turn complete".to_string(),
+            },
+            EventMsg::AgentMessage(ev) => format!("says: {}", preview_text(&ev.message)),
+            EventMsg::ExecCommandBegin(ev) => {
+                format!("runs: {}", preview_text(&ev.command.join(" ")))
+            }
+            EventMsg::ExecApprovalRequest(ev) => {
+                format!("awaits approval to run: {}", preview_text(&ev.command.join(" ")))
+            }
+            EventMsg::ApplyPatchApprovalRequest(_) => {
+                "awaits approval to apply a patch".to_string()
+            }
+            EventMsg::PatchApplyBegin(_) => "applies a patch".to_string(),
+            EventMsg::Error(ev) => format!("error: {}", preview_text(&ev.message)),
+            EventMsg::BackgroundEvent(ev) => preview_text(&ev.message),
+            EventMsg::ShutdownComplete => "shut down".to_string(),
+            _ => return None,
+        };
+        Some(format!("[{}] {text}", short_agent_id(&self.agent_id)))
+    }
+}
+
+/// What an agent is doing, derived from its latest events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentActivity {
+    /// Waiting for its next turn.
+    #[default]
+    Idle,
+    /// Running a turn.
+    Working,
+    /// Blocked on an exec/patch approval.
+    AwaitingApproval,
+    /// The agent shut down.
+    Exited,
+}
+
+/// One row of a per-agent status panel.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentStatusLine {
+    pub agent_id: String,
+    pub role: String,
+    pub activity: AgentActivity,
+    /// Preview of the agent's latest message or action.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_update: Option<String>,
+    /// Events seen from the agent so far (its watermark).
+    pub watermark: usize,
+}
+
+/// Client-side merge of the event streams of all agents in a session.
+///
+/// Keeps one watermark per agent, so each agent's `get_state_update` can be
+/// resumed independently, and a status row per agent for status panels.
+#[derive(Debug, Clone, Default)]
+pub struct SessionTimeline {
+    agents: BTreeMap<String, AgentStatusLine>,
+}
+
+impl SessionTimeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Watermark to resume `agent_id` from (0 for agents not seen yet).
+    pub fn watermark(&self, agent_id: &str) -> usize {
+        self.agents.get(agent_id).map_or(0, |a| a.watermark)
+    }
+
+    /// Watermarks of all agents seen so far.
+    pub fn watermarks(&self) -> BTreeMap<String, usize> {
+        self.agents
+            .iter()
+            .map(|(id, a)| (id.clone(), a.watermark))
+            .collect()
+    }
+
+    /// Whether the agent's event stream ended.
+    pub fn has_exited(&self, agent_id: &str) -> bool {
+        self.agents
+            .get(agent_id)
+            .is_some_and(|a| a.activity == AgentActivity::Exited)
+    }
+
+    /// Update the agent's status row and watermark from one of its events.
+    /// Events at or behind the agent's watermark (replays) are ignored;
+    /// returns whether the event was new.
+    pub fn record(&mut self, event: &SessionEvent) -> bool {
+        let status = self
+            .agents
+            .entry(event.agent_id.clone())
+            .or_insert_with(|| AgentStatusLine {
+                agent_id: event.agent_id.clone(),
+                role: event.role.clone(),
+                activity: AgentActivity::Idle,
+                last_update: None,
+                watermark: 0,
+            });
+        if event.watermark <= status.watermark {
+            return false;
+        }
+        status.watermark = event.watermark;
+        if status.activity != AgentActivity::Exited {
+            status.activity = match &event.event.msg {
+                EventMsg::TurnStarted(_)
+                | EventMsg::ExecCommandBegin(_)
+                | EventMsg::PatchApplyBegin(_) => AgentActivity::Working,
+                EventMsg::TurnComplete(_) | EventMsg::TurnAborted(_) => AgentActivity::Idle,
+                EventMsg::ExecApprovalRequest(_) | EventMsg::ApplyPatchApprovalRequest(_) => {
+                    AgentActivity::AwaitingApproval
+                }
+                EventMsg::ShutdownComplete => AgentActivity::Exited,
+                _ => status.activity,
+            };
+        }
+        if let Some(line) = event.timeline_line() {
+            // The row already names the agent; drop the "[agent] " tag.
+            let untagged = line.split_once("] ").map_or(line.as_str(), |(_, rest)| rest);
+            status.last_update = Some(untagged.to_string());
+        }
+        true
+    }
+
+    /// Status rows, one per agent, in agent ID order.
+    pub fn status_panel(&self) -> Vec<AgentStatusLine> {
+        self.agents.values().cloned().collect()
+    }
+
+    /// Status panel as fixed-width text.
+    pub fn render_status_panel(&self) -> String {
+        let mut out = format!("{:<16} {:<12} {:<18} LAST UPDATE\n", "AGENT", "ROLE", "ACTIVITY");
+        for row in self.agents.values() {
+            let activity = match row.activity {
+                AgentActivity::Idle => "idle",
+                AgentActivity::Working => "working",
+                AgentActivity::AwaitingApproval => "awaiting approval",
+                AgentActivity::Exited => "exited",
+            };
+            out.push_str(&format!(
+                "{:<16} {:<12} {:<18} {}\n",
+                short_agent_id(&row.agent_id),
+                row.role,
+                activity,
+                row.last_update.as_deref().unwrap_or("")
+            ));
+        }
+        out
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Inter-agent messaging
+// ---------------------------------------------------------------------------
+
+/// Sender name used for messages generated by `SessionWorkflow` itself.
+pub const SESSION_MESSAGE_SENDER: &str = "session";
+
+/// A message between agents of a session, routed by `SessionWorkflow`.
+///
+/// `to` is an agent ID (full or short, e.g. "explorer-2"), a role name
+/// (delivered to every running agent with that role), or `*` for everyone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AgentMessage {
+    /// Position in the session's message log (assigned when routed).
+    #[serde(default)]
+    pub seq: u64,
+    /// Workflow ID of the sending agent.
+    pub from: String,
+    /// Recipient address as given by the sender.
+    pub to: String,
+    /// Message text.
+    pub body: String,
+    /// Start a turn for the recipient with this message instead of leaving
+    /// it in the mailbox.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub wake: bool,
+    /// Agent IDs the message was delivered to (filled in by the session).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delivered_to: Vec<String>,
+}
+
+impl AgentMessage {
+    /// One-line rendering used for TUI events and tool output.
+    pub fn display(&self) -> String {
+        format!(
+            "[{} → {}] {}",
+            short_agent_id(&self.from),
+            self.to,
+            self.body
+        )
+    }
+}
+
+/// The part of an agent ID after the session prefix ("main", "explorer-2").
+pub fn short_agent_id(agent_id: &str) -> &str {
+    agent_id.rsplit('/').next().unwrap_or(agent_id)
+}
+
+/// Running agents (other than the sender) addressed by `to`.
+pub fn message_recipients(agents: &[AgentRecord], from: &str, to: &str) -> Vec<String> {
+    let running = agents
+        .iter()
+        .filter(|a| a.status.is_live() && a.agent_id != from);
+    if to == "*" {
+        return running.map(|a| a.agent_id.clone()).collect();
+    }
+    let by_id: Vec<String> = running
+        .clone()
+        .filter(|a| a.matches_id(to))
+        .map(|a| a.agent_id.clone())
+        .collect();
+    if !by_id.is_empty() {
+        return by_id;
+    }
+    running
+        .filter(|a| a.role == to)
+        .map(|a| a.agent_id.clone())
+        .collect()
 }
 
 // ---------------------------------------------------------------------------
@@ -803,6 +3157,110 @@ pub struct StateUpdateResponse {
     pub completed: bool,
 }
 
+// ---------------------------------------------------------------------------
+// Transcript history (paginated query)
+// ---------------------------------------------------------------------------
+
+/// Default number of events per `get_history` page.
+pub const DEFAULT_HISTORY_PAGE_SIZE: usize = 500;
+
+/// Request payload for the `get_history` query.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryRequest {
+    /// Index of the first transcript event to return.
+    #[serde(default)]
+    pub offset: usize,
+    /// Maximum number of events to return (default
+    /// [`DEFAULT_HISTORY_PAGE_SIZE`]).
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// One page of an agent's transcript, rebuilt from its rollout items.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryPage {
+    /// JSON-serialized events (same encoding as [`StateUpdateResponse`]).
+    pub events: Vec<String>,
+    /// Offset of the next page; `None` on the last page.
+    pub next_offset: Option<usize>,
+    /// Total number of transcript events.
+    pub total: usize,
+}
+
+/// One page of an agent's raw rollout items (`get_rollout` query).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RolloutPage {
+    /// JSON-serialized `RolloutItem`s.
+    pub items: Vec<String>,
+    /// Offset of the next page; `None` on the last page.
+    pub next_offset: Option<usize>,
+    /// Total number of rollout items.
+    pub total: usize,
+}
+
+// ---------------------------------------------------------------------------
+// Session export
+// ---------------------------------------------------------------------------
+
+/// Output format of the `export_session` query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// Codex rollout JSONL, readable by `codex resume`.
+    #[default]
+    Rollout,
+    /// Human-readable Markdown transcript with tool calls and diffs.
+    Markdown,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rollout" | "jsonl" => Ok(Self::Rollout),
+            "markdown" | "md" => Ok(Self::Markdown),
+            other => Err(format!(
+                "unknown export format '{other}' (expected rollout or markdown)"
+            )),
+        }
+    }
+}
+
+/// Request payload for the `export_session` query.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportRequest {
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// Index of the first exported line (or Markdown block) to return.
+    #[serde(default)]
+    pub offset: usize,
+    /// Maximum number of lines to return (default
+    /// [`DEFAULT_HISTORY_PAGE_SIZE`]).
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Conversation ID written to the rollout's `session_meta` line; a UUID,
+    /// so `codex resume <id>` can find the file.
+    #[serde(default)]
+    pub conversation_id: String,
+    /// RFC 3339 time stamped on every rollout line (rollout items carry no
+    /// time of their own).
+    #[serde(default)]
+    pub timestamp: String,
+}
+
+/// One page of an exported session (`export_session` query).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportPage {
+    /// Rollout JSONL lines, or Markdown blocks; each is written followed by
+    /// a newline.
+    pub lines: Vec<String>,
+    /// Offset of the next page; `None` on the last page.
+    pub next_offset: Option<usize>,
+    /// Total number of lines.
+    pub total: usize,
+}
+
 // ---------------------------------------------------------------------------
 // Turn overrides
 // ---------------------------------------------------------------------------
@@ -875,6 +3333,60 @@ pub struct ContinueAsNewState {
     /// Snapshot of buffered events carried across CAN.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub event_snapshot: Vec<Event>,
+    /// Subagents spawned through the subagent tools.
+    #[serde(default, skip_serializing_if = "Subagents::is_empty")]
+    pub subagents: Subagents,
+    /// Unread messages from other agents.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inbox: Vec<AgentMessage>,
+    /// Agent was paused via `control_agent`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub paused: bool,
+    /// Latest turn that stopped at its iteration budget.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget_exhausted_turn: Option<String>,
+}
+
+impl ContinueAsNewState {
+    /// Fresh agent state holding just a conversation (`rollout_items`), for
+    /// restoring an agent in a new workflow.
+    pub fn from_rollout(rollout_items: Vec<RolloutItem>) -> Self {
+        Self {
+            rollout_items,
+            pending_user_turns: Vec::new(),
+            cumulative_turn_count: 0,
+            cumulative_iterations: 0,
+            cumulative_token_usage: None,
+            mcp_tools: HashMap::new(),
+            overrides: TurnOverrides::default(),
+            event_offset: 0,
+            event_snapshot: Vec::new(),
+            subagents: Subagents::default(),
+            inbox: Vec::new(),
+            paused: false,
+            budget_exhausted_turn: None,
+        }
+    }
+
+    /// Queue `message` as the next user turn (the restored agent ignores
+    /// its input's `user_message`).
+    pub fn queue_turn(
+        &mut self,
+        message: String,
+        effort: Option<ReasoningEffort>,
+        summary: ReasoningSummary,
+        personality: Option<Personality>,
+    ) {
+        self.pending_user_turns.push(UserTurnInput {
+            turn_id: format!("turn-{}", self.cumulative_turn_count),
+            message,
+            effort,
+            summary,
+            personality,
+            output_schema: None,
+        });
+        self.cumulative_turn_count += 1;
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -105,6 +105,810 @@ async fn in_memory_storage_saves_and_reads() {
     assert_eq!(storage.items().len(), 3);
 }
 
+#[test]
+fn in_memory_storage_with_items_keeps_carried_rollout() {
+    let item = RolloutItem::Compacted(codex_protocol::protocol::CompactedItem {
+        message: "carried".to_string(),
+        replacement_history: None,
+    });
+    let storage = InMemoryStorage::with_items(vec![item]);
+    assert_eq!(storage.items().len(), 1);
+}
+
+// ---------------------------------------------------------------------------
+// Transcript history tests
+// ---------------------------------------------------------------------------
+
+fn rollout_message(role: &str, text: &str) -> RolloutItem {
+    use codex_protocol::models::{ContentItem, ResponseItem};
+
+    let content = if role == "assistant" {
+        ContentItem::OutputText {
+            text: text.to_string(),
+        }
+    } else {
+        ContentItem::InputText {
+            text: text.to_string(),
+        }
+    };
+    RolloutItem::ResponseItem(ResponseItem::Message {
+        id: None,
+        role: role.to_string(),
+        content: vec![content],
+        end_turn: None,
+        phase: None,
+    })
+}
+
+/// A response item from its rollout JSON form.
+fn rollout_response(json: serde_json::Value) -> RolloutItem {
+    RolloutItem::ResponseItem(serde_json::from_value(json).expect("valid response item"))
+}
+
+#[test]
+fn history_from_rollout_rebuilds_full_transcript() {
+    use codex_protocol::protocol::EventMsg;
+    use crate::history::history_from_rollout;
+
+    let items = vec![
+        rollout_message(
+            "user",
+            "<environment_context>\n  <cwd>/repo</cwd>\n</environment_context>",
+        ),
+        rollout_message("user", "fix the build"),
+        rollout_response(serde_json::json!({
+            "type": "reasoning",
+            "summary": [{"type": "summary_text", "text": "Run the tests first"}],
+            "content": null,
+            "encrypted_content": null,
+        })),
+        rollout_response(serde_json::json!({
+            "type": "function_call",
+            "name": "shell",
+            "arguments": "{\"command\":[\"cargo\",\"test\"]}",
+            "call_id": "call-1",
+        })),
+        rollout_response(serde_json::json!({
+            "type": "function_call_output",
+            "call_id": "call-1",
+            "output": "{\"output\":\"1 failed\",\"metadata\":{\"exit_code\":101}}",
+        })),
+        rollout_response(serde_json::json!({
+            "type": "function_call",
+            "name": "apply_patch",
+            "arguments": "{\"patch\":\"*** Begin Patch\\n*** End Patch\"}",
+            "call_id": "call-2",
+        })),
+        rollout_response(serde_json::json!({
+            "type": "function_call_output",
+            "call_id": "call-2",
+            "output": "Success. Updated the following files:\nM src/lib.rs",
+        })),
+        RolloutItem::Compacted(codex_protocol::protocol::CompactedItem {
+            message: "summary".to_string(),
+            replacement_history: None,
+        }),
+        rollout_message("assistant", "Fixed the failing test."),
+    ];
+
+    let events = history_from_rollout(&items, "/repo");
+    assert_eq!(events.len(), 8, "got {events:?}");
+
+    match &events[0] {
+        EventMsg::UserMessage(e) => assert_eq!(e.message, "fix the build"),
+        other => panic!("expected UserMessage, got {other:?}"),
+    }
+    match &events[1] {
+        EventMsg::AgentReasoning(e) => assert_eq!(e.text, "Run the tests first"),
+        other => panic!("expected AgentReasoning, got {other:?}"),
+    }
+    match &events[2] {
+        EventMsg::ExecCommandBegin(e) => {
+            assert_eq!(e.command, vec!["cargo", "test"]);
+            assert_eq!(e.cwd, std::path::PathBuf::from("/repo"));
+        }
+        other => panic!("expected ExecCommandBegin, got {other:?}"),
+    }
+    match &events[3] {
+        EventMsg::ExecCommandEnd(e) => {
+            assert_eq!(e.call_id, "call-1");
+            assert_eq!(e.exit_code, 101);
+            assert_eq!(e.aggregated_output, "1 failed");
+        }
+        other => panic!("expected ExecCommandEnd, got {other:?}"),
+    }
+    assert!(matches!(events[4], EventMsg::PatchApplyBegin(_)));
+    match &events[5] {
+        EventMsg::PatchApplyEnd(e) => {
+            assert!(e.success);
+            assert!(e.stdout.contains("M src/lib.rs"));
+        }
+        other => panic!("expected PatchApplyEnd, got {other:?}"),
+    }
+    assert!(matches!(events[6], EventMsg::ContextCompacted(_)));
+    match &events[7] {
+        EventMsg::AgentMessage(e) => assert_eq!(e.message, "Fixed the failing test."),
+        other => panic!("expected AgentMessage, got {other:?}"),
+    }
+}
+
+#[test]
+fn history_from_rollout_uses_non_shell_tool_name_as_command() {
+    use codex_protocol::protocol::EventMsg;
+    use crate::history::history_from_rollout;
+
+    let items = vec![
+        rollout_response(serde_json::json!({
+            "type": "function_call",
+            "name": "read_file",
+            "arguments": "{\"path\":\"README.md\"}",
+            "call_id": "call-1",
+        })),
+        rollout_response(serde_json::json!({
+            "type": "function_call_output",
+            "call_id": "call-1",
+            "output": "# Title",
+        })),
+    ];
+
+    let events = history_from_rollout(&items, "/repo");
+    match &events[..] {
+        [EventMsg::ExecCommandBegin(begin), EventMsg::ExecCommandEnd(end)] => {
+            assert_eq!(begin.command, vec!["read_file"]);
+            assert_eq!(end.exit_code, 0);
+            assert_eq!(end.aggregated_output, "# Title");
+        }
+        other => panic!("unexpected events: {other:?}"),
+    }
+}
+
+#[test]
+fn history_from_rollout_prefers_persisted_messages() {
+    use codex_protocol::protocol::EventMsg;
+    use crate::history::history_from_rollout;
+
+    let user_event: EventMsg = serde_json::from_value(serde_json::json!({
+        "type": "user_message",
+        "message": "hello",
+    }))
+    .unwrap();
+    let items = vec![
+        rollout_message("user", "hello"),
+        RolloutItem::EventMsg(user_event),
+        rollout_message("assistant", "hi there"),
+        RolloutItem::EventMsg(EventMsg::AgentMessage(
+            codex_protocol::protocol::AgentMessageEvent {
+                message: "hi there".to_string(),
+                phase: None,
+                memory_citation: None,
+            },
+        )),
+    ];
+
+    let events = history_from_rollout(&items, "/repo");
+    assert_eq!(events.len(), 2, "messages must not be duplicated: {events:?}");
+    assert!(matches!(events[0], EventMsg::UserMessage(_)));
+    assert!(matches!(events[1], EventMsg::AgentMessage(_)));
+}
+
+#[test]
+fn history_page_paginates_transcript() {
+    use codex_protocol::protocol::{AgentMessageEvent, Event, EventMsg};
+    use crate::history::history_page;
+    use crate::types::HistoryRequest;
+
+    let events: Vec<EventMsg> = (0..5)
+        .map(|i| {
+            EventMsg::AgentMessage(AgentMessageEvent {
+                message: format!("message {i}"),
+                phase: None,
+                memory_citation: None,
+            })
+        })
+        .collect();
+
+    let first = history_page(&events, &HistoryRequest {
+        offset: 0,
+        limit: Some(2),
+    });
+    assert_eq!(first.total, 5);
+    assert_eq!(first.events.len(), 2);
+    assert_eq!(first.next_offset, Some(2));
+
+    let last = history_page(&events, &HistoryRequest {
+        offset: 4,
+        limit: Some(2),
+    });
+    assert_eq!(last.events.len(), 1);
+    assert_eq!(last.next_offset, None);
+    let event: Event = serde_json::from_str(&last.events[0]).unwrap();
+    match event.msg {
+        EventMsg::AgentMessage(e) => assert_eq!(e.message, "message 4"),
+        other => panic!("expected AgentMessage, got {other:?}"),
+    }
+
+    let past_end = history_page(&events, &HistoryRequest {
+        offset: 10,
+        limit: None,
+    });
+    assert!(past_end.events.is_empty());
+    assert_eq!(past_end.next_offset, None);
+}
+
+#[test]
+fn history_page_respects_byte_budget() {
+    use codex_protocol::protocol::{AgentMessageEvent, EventMsg};
+    use crate::history::{MAX_HISTORY_PAGE_BYTES, history_page};
+    use crate::types::HistoryRequest;
+
+    let big = "x".repeat(MAX_HISTORY_PAGE_BYTES / 2 + 1);
+    let events: Vec<EventMsg> = (0..3)
+        .map(|_| {
+            EventMsg::AgentMessage(AgentMessageEvent {
+                message: big.clone(),
+                phase: None,
+                memory_citation: None,
+            })
+        })
+        .collect();
+
+    let page = history_page(&events, &HistoryRequest::default());
+    assert_eq!(page.events.len(), 1, "a page holds at least one event");
+    assert_eq!(page.next_offset, Some(1));
+}
+
+#[test]
+fn history_page_budget_counts_json_escaping() {
+    use codex_protocol::protocol::{AgentMessageEvent, EventMsg};
+    use crate::history::{MAX_HISTORY_PAGE_BYTES, history_page, json_string_len};
+    use crate::types::HistoryRequest;
+
+    let text = "say \"hi\"\n\t\u{1}é";
+    assert_eq!(json_string_len(text), serde_json::to_string(text).unwrap().len());
+
+    // Quotes double with each encoding: three such events fit the budget
+    // raw, but not as encoded in the page.
+    let quotes = "\"".repeat(MAX_HISTORY_PAGE_BYTES / 8);
+    let events: Vec<EventMsg> = (0..3)
+        .map(|_| {
+            EventMsg::AgentMessage(AgentMessageEvent {
+                message: quotes.clone(),
+                phase: None,
+                memory_citation: None,
+            })
+        })
+        .collect();
+
+    let page = history_page(&events, &HistoryRequest::default());
+    assert_eq!(page.events.len(), 1);
+    let page_json = serde_json::to_string(&page).unwrap();
+    assert!(page_json.len() <= MAX_HISTORY_PAGE_BYTES + 1024, "{}", page_json.len());
+}
+
+#[tokio::test]
+async fn transcript_cache_rebuilds_when_the_rollout_grows() {
+    use crate::history::TranscriptCache;
+    use crate::storage::InMemoryStorage;
+    use crate::types::HistoryRequest;
+    use codex_core::StorageBackend;
+
+    let storage = InMemoryStorage::with_items(vec![
+        rollout_message("user", "first"),
+        rollout_message("assistant", "second"),
+    ]);
+    let cache = TranscriptCache::default();
+    let request = |offset| HistoryRequest {
+        offset,
+        limit: Some(1),
+    };
+
+    let first = cache.history_page(&storage, "/repo", &request(0));
+    assert_eq!((first.total, first.next_offset), (2, Some(1)));
+    let second = cache.history_page(&storage, "/repo", &request(1));
+    assert_eq!((second.total, second.next_offset), (2, None));
+
+    // Items saved between pages are picked up by the next page.
+    storage.save(&[rollout_message("user", "third")]).await;
+    let third = cache.history_page(&storage, "/repo", &request(2));
+    assert_eq!((third.total, third.events.len()), (3, 1));
+    assert!(third.events[0].contains("third"));
+}
+
+#[test]
+fn rollout_page_roundtrips_items() {
+    use crate::history::rollout_page;
+    use crate::types::HistoryRequest;
+
+    let items = vec![
+        rollout_message("user", "first"),
+        rollout_message("assistant", "second"),
+        rollout_message("user", "third"),
+    ];
+    let page = rollout_page(&items, &HistoryRequest {
+        offset: 1,
+        limit: Some(5),
+    });
+    assert_eq!(page.total, 3);
+    assert_eq!(page.next_offset, None);
+    let restored: Vec<RolloutItem> = page
+        .items
+        .iter()
+        .map(|s| serde_json::from_str(s).unwrap())
+        .collect();
+    assert_eq!(restored.len(), 2);
+    assert_eq!(
+        serde_json::to_string(&restored[0]).unwrap(),
+        serde_json::to_string(&items[1]).unwrap()
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Reopened session tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn restored_agent_state_queues_new_turn_after_history() {
+    use crate::types::ContinueAsNewState;
+    use codex_protocol::config_types::ReasoningSummary;
+
+    let mut state = ContinueAsNewState::from_rollout(vec![
+        rollout_message("user", "fix the build"),
+        rollout_message("assistant", "Fixed."),
+    ]);
+    assert!(state.pending_user_turns.is_empty());
+    assert_eq!(state.event_offset, 0);
+
+    state.queue_turn("now add a test".to_string(), None, ReasoningSummary::Auto, None);
+    state.queue_turn("and docs".to_string(), None, ReasoningSummary::Auto, None);
+
+    assert_eq!(state.rollout_items.len(), 2);
+    assert_eq!(state.cumulative_turn_count, 2);
+    let turns: Vec<(&str, &str)> = state
+        .pending_user_turns
+        .iter()
+        .map(|t| (t.turn_id.as_str(), t.message.as_str()))
+        .collect();
+    assert_eq!(turns, vec![("turn-0", "now add a test"), ("turn-1", "and docs")]);
+}
+
+#[test]
+fn session_input_main_agent_state_is_optional_in_json() {
+    use crate::types::{ContinueAsNewState, SessionWorkflowInput};
+
+    let mut input: SessionWorkflowInput = serde_json::from_str(
+        r#"{"user_message":"hi","model":"gpt-4o","instructions":""}"#,
+    )
+    .unwrap();
+    assert!(input.main_agent_state.is_none());
+    let json = serde_json::to_string(&input).unwrap();
+    assert!(!json.contains("main_agent_state"));
+
+    input.main_agent_state = Some(ContinueAsNewState::from_rollout(vec![rollout_message(
+        "user", "earlier",
+    )]));
+    let back: SessionWorkflowInput =
+        serde_json::from_str(&serde_json::to_string(&input).unwrap()).unwrap();
+    assert_eq!(back.main_agent_state.unwrap().rollout_items.len(), 1);
+}
+
+// ---------------------------------------------------------------------------
+// Rollout import tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn parse_rollout_jsonl_reads_codex_cli_rollout() {
+    use crate::rollout::parse_rollout_jsonl;
+
+    let text = [
+        r#"{"timestamp":"2026-10-01T10:00:00.000Z","type":"session_meta","payload":{"id":"0199a213-81c0-7800-8aa1-bbab2a035a53","timestamp":"2026-10-01T10:00:00.000Z","cwd":"/repo","originator":"codex_cli_rs","cli_version":"0.50.0"}}"#,
+        r#"{"timestamp":"2026-10-01T10:00:01.000Z","type":"response_item","payload":{"type":"message","role":"user","content":[{"type":"input_text","text":"fix the build"}]}}"#,
+        "",
+        r#"{"timestamp":"2026-10-01T10:00:01.000Z","type":"turn_context","payload":{"cwd":"/repo","model":"gpt-5-codex"}}"#,
+        r#"{"timestamp":"2026-10-01T10:00:02.000Z","type":"event_msg","payload":{"type":"user_message","message":"fix the build"}}"#,
+        r#"{"timestamp":"2026-10-01T10:00:03.000Z","type":"response_item","payload":{"type":"message","role":"assistant","content":[{"type":"output_text","text":"Fixed."}]}}"#,
+        r#"{"timestamp":"2026-10-01T10:00:04.000Z","type":"future_item","payload":{}}"#,
+    ]
+    .join("\n");
+
+    let rollout = parse_rollout_jsonl(&text).unwrap();
+    assert_eq!(rollout.items.len(), 3);
+    assert_eq!(rollout.skipped, 1);
+    assert_eq!(
+        rollout.source_id.as_deref(),
+        Some("0199a213-81c0-7800-8aa1-bbab2a035a53")
+    );
+    assert_eq!(rollout.model.as_deref(), Some("gpt-5-codex"));
+    assert_eq!(rollout.first_user_message().as_deref(), Some("fix the build"));
+}
+
+#[test]
+fn check_start_payload_rejects_oversized_imports() {
+    use crate::rollout::{MAX_START_PAYLOAD_BYTES, check_start_payload};
+
+    assert!(check_start_payload(&"small".to_string()).is_ok());
+    let error = check_start_payload(&"x".repeat(MAX_START_PAYLOAD_BYTES)).unwrap_err();
+    assert!(error.contains("too large"), "{error}");
+}
+
+#[test]
+fn parse_rollout_jsonl_rejects_invalid_and_empty_files() {
+    use crate::rollout::parse_rollout_jsonl;
+
+    let err = parse_rollout_jsonl("{\"type\":\"session_meta\"}\nnot json").unwrap_err();
+    assert!(err.starts_with("line 2:"), "{err}");
+    let err = parse_rollout_jsonl("[1, 2]").unwrap_err();
+    assert!(err.contains("expected a JSON object"), "{err}");
+    let err = parse_rollout_jsonl(
+        r#"{"type":"session_meta","payload":{"id":"s","cwd":"/repo"}}"#,
+    )
+    .unwrap_err();
+    assert!(err.contains("no conversation items"), "{err}");
+}
+
+// ---------------------------------------------------------------------------
+// Session export tests
+// ---------------------------------------------------------------------------
+
+fn export_request(format: crate::types::ExportFormat) -> crate::types::ExportRequest {
+    crate::types::ExportRequest {
+        format,
+        offset: 0,
+        limit: None,
+        conversation_id: "0199a213-81c0-7800-8aa1-bbab2a035a53".to_string(),
+        timestamp: "2026-10-01T10:00:00.000Z".to_string(),
+    }
+}
+
+#[test]
+fn rollout_lines_reimport_as_the_same_conversation() {
+    use crate::rollout::{parse_rollout_jsonl, rollout_lines};
+    use crate::types::ExportFormat;
+
+    let items = vec![
+        rollout_message("user", "<environment_context>\n</environment_context>"),
+        rollout_message("user", "fix the build"),
+        rollout_message("assistant", "Fixed."),
+    ];
+    let lines = rollout_lines(&items, &export_request(ExportFormat::Rollout), "/repo");
+
+    // session_meta, three items and a user_message event for the prompt.
+    assert_eq!(lines.len(), 5, "got {lines:#?}");
+    let meta: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+    assert_eq!(meta["type"], "session_meta");
+    assert_eq!(meta["payload"]["id"], "0199a213-81c0-7800-8aa1-bbab2a035a53");
+    assert_eq!(meta["payload"]["cwd"], "/repo");
+    for line in &lines {
+        let value: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(value["timestamp"], "2026-10-01T10:00:00.000Z");
+    }
+    let event: serde_json::Value = serde_json::from_str(&lines[3]).unwrap();
+    assert_eq!(event["type"], "event_msg");
+    assert_eq!(event["payload"]["type"], "user_message");
+    assert_eq!(event["payload"]["message"], "fix the build");
+
+    let rollout = parse_rollout_jsonl(&lines.join("\n")).unwrap();
+    assert_eq!(rollout.items.len(), 4);
+    assert_eq!(rollout.first_user_message().as_deref(), Some("fix the build"));
+
+    // Items that already carry user_message events are written unchanged.
+    let again = rollout_lines(&rollout.items, &export_request(ExportFormat::Rollout), "/repo");
+    assert_eq!(again.len(), 5);
+}
+
+#[test]
+fn markdown_transcript_renders_commands_and_diffs() {
+    use crate::history::markdown_transcript;
+
+    let items = vec![
+        rollout_message("user", "fix the build"),
+        rollout_response(serde_json::json!({
+            "type": "function_call",
+            "name": "shell",
+            "arguments": "{\"command\":[\"cargo\",\"test\"]}",
+            "call_id": "call-1",
+        })),
+        rollout_response(serde_json::json!({
+            "type": "function_call_output",
+            "call_id": "call-1",
+            "output": "{\"output\":\"```\\n1 failed\",\"metadata\":{\"exit_code\":101}}",
+        })),
+        rollout_response(serde_json::json!({
+            "type": "custom_tool_call",
+            "name": "apply_patch",
+            "input": "*** Begin Patch\n*** Update File: src/lib.rs\n-old\n+new\n*** End Patch",
+            "call_id": "call-2",
+        })),
+        rollout_response(serde_json::json!({
+            "type": "custom_tool_call_output",
+            "call_id": "call-2",
+            "output": "Success. Updated the following files:\nM src/lib.rs",
+        })),
+        rollout_message("assistant", "Fixed."),
+    ];
+
+    let blocks = markdown_transcript(&items, "/repo");
+    assert_eq!(
+        blocks,
+        vec![
+            "## User\n\nfix the build\n".to_string(),
+            "**Command**\n\n```sh\ncargo test\n```\n".to_string(),
+            "_Exit code 101:_\n\n````text\n```\n1 failed\n````\n".to_string(),
+            "**Patch**\n\n```diff\n*** Begin Patch\n*** Update File: src/lib.rs\n-old\n+new\n\
+             *** End Patch\n```\n"
+                .to_string(),
+            "## Assistant\n\nFixed.\n".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn export_page_paginates_lines() {
+    use crate::history::export_page;
+    use crate::types::{ExportFormat, ExportFormat::Markdown};
+
+    let lines: Vec<String> = (0..5).map(|i| format!("line {i}")).collect();
+    let mut req = export_request(Markdown);
+    req.limit = Some(3);
+    let first = export_page(&lines, &req);
+    assert_eq!(first.lines, vec!["line 0", "line 1", "line 2"]);
+    assert_eq!(first.next_offset, Some(3));
+    assert_eq!(first.total, 5);
+
+    req.offset = 3;
+    let last = export_page(&lines, &req);
+    assert_eq!(last.lines, vec!["line 3", "line 4"]);
+    assert_eq!(last.next_offset, None);
+
+    assert_eq!("md".parse::<ExportFormat>(), Ok(Markdown));
+    assert_eq!("rollout".parse::<ExportFormat>(), Ok(ExportFormat::Rollout));
+    assert!("html".parse::<ExportFormat>().is_err());
+}
+
+// ---------------------------------------------------------------------------
+// Client CLI tests
+// ---------------------------------------------------------------------------
+
+fn cli_args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+#[test]
+fn command_args_parse_options_switches_and_positionals() {
+    use crate::cli::CommandArgs;
+
+    let args = CommandArgs::parse(
+        &cli_args(&["s1", "--agent", "worker-2", "fix", "--json", "the", "--agent=main", "build"]),
+        &["agent"],
+        &["json"],
+    )
+    .unwrap();
+    assert_eq!(args.positional(0), Some("s1"));
+    assert_eq!(args.rest(1).as_deref(), Some("fix the build"));
+    assert_eq!(args.value("agent"), Some("main"));
+    assert_eq!(args.values("agent"), ["worker-2", "main"]);
+    assert!(args.json());
+    assert_eq!(args.rest(4), None);
+
+    let args = CommandArgs::parse(&cli_args(&["s1", "--", "--json"]), &[], &["json"]).unwrap();
+    assert!(!args.json());
+    assert_eq!(args.rest(1).as_deref(), Some("--json"));
+}
+
+#[test]
+fn command_args_reject_unknown_and_incomplete_flags() {
+    use crate::cli::CommandArgs;
+
+    let err = CommandArgs::parse(&cli_args(&["s1", "--follow"]), &[], &["json"]).unwrap_err();
+    assert!(err.contains("--follow"), "{err}");
+    let err = CommandArgs::parse(&cli_args(&["s1", "--agent"]), &["agent"], &[]).unwrap_err();
+    assert!(err.contains("requires a value"), "{err}");
+    assert!(CommandArgs::parse(&cli_args(&["--json=yes"]), &[], &["json"]).is_err());
+}
+
+#[test]
+fn command_args_key_values() {
+    use crate::cli::CommandArgs;
+
+    let args = CommandArgs::parse(
+        &cli_args(&["crew", "--input", "repo=app", "--input=branch=main=x"]),
+        &["input"],
+        &[],
+    )
+    .unwrap();
+    let inputs = args.key_values("input").unwrap();
+    assert_eq!(inputs["repo"], "app");
+    assert_eq!(inputs["branch"], "main=x");
+    assert!(args.key_values("other").unwrap().is_empty());
+
+    let args = CommandArgs::parse(&cli_args(&["--input", "oops"]), &["input"], &[]).unwrap();
+    let err = args.key_values("input").unwrap_err();
+    assert!(err.contains("key=value"), "{err}");
+}
+
+#[test]
+fn agent_workflow_id_qualifies_short_ids() {
+    use crate::cli::agent_workflow_id;
+
+    assert_eq!(agent_workflow_id("codex-session-1", None), "codex-session-1/main");
+    assert_eq!(agent_workflow_id("codex-session-1", Some("worker-2")), "codex-session-1/worker-2");
+    assert_eq!(
+        agent_workflow_id("codex-session-1", Some("codex-session-1/explorer")),
+        "codex-session-1/explorer"
+    );
+}
+
+#[test]
+fn event_line_shows_messages_and_approval_call_ids() {
+    use crate::cli::event_line;
+    use codex_protocol::protocol::AgentMessageEvent;
+
+    let message = EventMsg::AgentMessage(AgentMessageEvent {
+        message: "Done.\nAll tests pass.\n".to_string(),
+        phase: None,
+        memory_citation: None,
+    });
+    assert_eq!(event_line(&message).as_deref(), Some("codex: Done.\nAll tests pass."));
+
+    let approval = exec_approval_event("call-7", &["rm", "-rf", "target"]);
+    assert_eq!(
+        event_line(&approval).as_deref(),
+        Some("approval needed [call-7]: run rm -rf target")
+    );
+
+    assert_eq!(event_line(&EventMsg::ShutdownComplete).as_deref(), Some("── shut down"));
+}
+
+fn agent_message(text: &str) -> EventMsg {
+    EventMsg::AgentMessage(codex_protocol::protocol::AgentMessageEvent {
+        message: text.to_string(),
+        phase: None,
+        memory_citation: None,
+    })
+}
+
+fn turn_complete(last_agent_message: Option<&str>) -> EventMsg {
+    EventMsg::TurnComplete(codex_protocol::protocol::TurnCompleteEvent {
+        turn_id: "turn-0".to_string(),
+        last_agent_message: last_agent_message.map(String::from),
+    })
+}
+
+fn turn_started(turn_id: &str) -> EventMsg {
+    EventMsg::TurnStarted(codex_protocol::protocol::TurnStartedEvent {
+        turn_id: turn_id.to_string(),
+        model_context_window: None,
+        collaboration_mode_kind: Default::default(),
+    })
+}
+
+#[test]
+fn exec_tracker_reports_success_with_the_final_message() {
+    use crate::cli::{ExecOutcome, ExecTracker};
+
+    let mut tracker = ExecTracker::default();
+    assert_eq!(tracker.observe(&agent_message("Looking at the tests.")), None);
+    assert_eq!(tracker.observe(&agent_message("{\"ok\": true}")), None);
+    assert_eq!(tracker.observe(&turn_complete(None)), Some(ExecOutcome::Success));
+    assert_eq!(tracker.last_message(), Some("{\"ok\": true}"));
+
+    // Without agent messages, the turn's own last message is used.
+    let mut tracker = ExecTracker::default();
+    tracker.observe(&turn_complete(Some("Done.")));
+    assert_eq!(tracker.last_message(), Some("Done."));
+    assert_eq!(ExecOutcome::Success.exit_code(), 0);
+}
+
+#[test]
+fn exec_tracker_detects_budget_exhaustion_and_aborted_turns() {
+    use crate::cli::{ExecOutcome, ExecTracker};
+    use codex_protocol::protocol::{TurnAbortReason, TurnAbortedEvent};
+
+    // The agent tells whether the completed turn ran out of budget; its
+    // notice is then dropped from the final message.
+    let mut tracker = ExecTracker::default();
+    tracker.observe(&agent_message("Fixed two of three tests."));
+    tracker.observe(&agent_message("⚠️ Maximum iterations (5) reached."));
+    let outcome = tracker.observe(&turn_complete(Some("Maximum iterations (5) reached")));
+    assert_eq!(outcome, Some(ExecOutcome::Success));
+    assert_eq!(tracker.turn_id(), Some("turn-0"));
+    assert_eq!(tracker.budget_exhausted(), ExecOutcome::BudgetExhausted);
+    assert_eq!(tracker.last_message(), Some("Fixed two of three tests."));
+    assert_eq!(ExecOutcome::BudgetExhausted.exit_code(), 3);
+
+    let mut tracker = ExecTracker::default();
+    let aborted = EventMsg::TurnAborted(TurnAbortedEvent {
+        turn_id: Some("turn-0".to_string()),
+        reason: TurnAbortReason::Interrupted,
+    });
+    assert_eq!(tracker.observe(&aborted), Some(ExecOutcome::Error));
+    assert!(tracker.error().is_some_and(|e| e.contains("aborted")));
+    assert_eq!(tracker.observe(&EventMsg::ShutdownComplete), Some(ExecOutcome::Error));
+    assert_eq!(ExecOutcome::TimedOut.exit_code(), 124);
+}
+
+#[test]
+fn exec_tracker_clears_approvals_once_the_agent_moves_on() {
+    use crate::cli::{ExecOutcome, ExecTracker};
+
+    let mut tracker = ExecTracker::default();
+    tracker.observe(&exec_approval_event("call-1", &["cargo", "publish"]));
+    assert_eq!(tracker.pending_approvals(), ["call-1".to_string()]);
+    assert_eq!(ExecOutcome::ApprovalPending.exit_code(), 4);
+
+    // Background events (e.g. from other agents) do not answer it.
+    let background = EventMsg::BackgroundEvent(codex_protocol::protocol::BackgroundEventEvent {
+        message: "[worker-2] ran: cargo test".to_string(),
+    });
+    tracker.observe(&background);
+    assert_eq!(tracker.pending_approvals(), ["call-1".to_string()]);
+
+    // Denied: the model answers instead of running the command.
+    tracker.observe(&agent_message("Skipping the publish step."));
+    assert!(tracker.pending_approvals().is_empty());
+}
+
+#[test]
+fn exec_tracker_reports_the_latest_or_the_queued_turn() {
+    use crate::cli::{ExecOutcome, ExecTracker};
+
+    // A replay reports the outcome of the latest turn, not the first.
+    let mut tracker = ExecTracker::default();
+    tracker.observe(&turn_started("turn-0"));
+    tracker.observe(&agent_message("Old answer."));
+    tracker.observe(&turn_started("turn-1"));
+    tracker.observe(&agent_message("New answer."));
+    assert_eq!(tracker.observe(&turn_complete(None)), Some(ExecOutcome::Success));
+    assert_eq!(tracker.last_message(), Some("New answer."));
+
+    // A prompt sent while a turn runs is answered by the next turn.
+    let mut tracker = ExecTracker::after_turns(1);
+    tracker.observe(&turn_started("turn-0"));
+    tracker.observe(&exec_approval_event("call-1", &["rm", "-rf", "target"]));
+    assert_eq!(tracker.observe(&turn_complete(Some("Old answer."))), None);
+    assert!(tracker.pending_approvals().is_empty());
+    tracker.observe(&turn_started("turn-1"));
+    tracker.observe(&agent_message("Queued answer."));
+    assert_eq!(tracker.observe(&turn_complete(None)), Some(ExecOutcome::Success));
+    assert_eq!(tracker.last_message(), Some("Queued answer."));
+    let mut tracker = ExecTracker::after_turns(1);
+    assert_eq!(tracker.observe(&EventMsg::ShutdownComplete), Some(ExecOutcome::Error));
+}
+
+#[test]
+fn parse_approval_policy_accepts_config_names() {
+    use crate::cli::parse_approval_policy;
+    use codex_protocol::protocol::AskForApproval;
+
+    assert!(matches!(parse_approval_policy("never"), Ok(AskForApproval::Never)));
+    assert!(matches!(parse_approval_policy("untrusted"), Ok(AskForApproval::UnlessTrusted)));
+    assert!(matches!(parse_approval_policy("on-failure"), Ok(AskForApproval::OnFailure)));
+    assert!(parse_approval_policy("sometimes").is_err());
+}
+
+#[test]
+fn user_turn_op_carries_the_output_schema() {
+    use crate::session::user_turn_op;
+    use crate::types::SessionWorkflowInput;
+    use codex_protocol::protocol::Op;
+
+    let mut input: SessionWorkflowInput = serde_json::from_value(serde_json::json!({
+        "user_message": "",
+        "model": "gpt-4o",
+        "instructions": "",
+    }))
+    .unwrap();
+    assert!(input.output_schema.is_none());
+
+    let schema = serde_json::json!({"type": "object", "required": ["ok"]});
+    input.output_schema = Some(schema.clone());
+    match user_turn_op(&input, "check the build".to_string()) {
+        Op::UserTurn { final_output_json_schema, .. } => {
+            assert_eq!(final_output_json_schema, Some(schema));
+        }
+        other => panic!("expected UserTurn, got {other:?}"),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // ToolExecOutput tests
 // ---------------------------------------------------------------------------
@@ -163,11 +967,13 @@ fn workflow_input_roundtrips_through_json() {
         model_provider: None,
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
 
     let json = serde_json::to_string(&input).unwrap();
@@ -261,6 +1067,7 @@ fn user_turn_input_roundtrips_through_json() {
         effort: Some(ReasoningEffort::High),
         summary: ReasoningSummary::Detailed,
         personality: Some(Personality::Friendly),
+        output_schema: None,
     };
 
     let json = serde_json::to_string(&input).unwrap();
@@ -545,11 +1352,13 @@ fn workflow_input_approval_policy_never_roundtrips() {
         model_provider: None,
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
     let json = serde_json::to_string(&input).unwrap();
     let back: CodexWorkflowInput = serde_json::from_str(&json).unwrap();
@@ -571,11 +1380,13 @@ fn workflow_input_approval_policy_untrusted_roundtrips() {
         model_provider: None,
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
     let json = serde_json::to_string(&input).unwrap();
     let back: CodexWorkflowInput = serde_json::from_str(&json).unwrap();
@@ -610,11 +1421,13 @@ fn workflow_input_web_search_mode_live_roundtrips() {
         model_provider: None,
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
     let json = serde_json::to_string(&input).unwrap();
     let back: CodexWorkflowInput = serde_json::from_str(&json).unwrap();
@@ -643,11 +1456,13 @@ fn workflow_input_reasoning_effort_roundtrips() {
         model_provider: None,
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
     let json = serde_json::to_string(&input).unwrap();
     let back: CodexWorkflowInput = serde_json::from_str(&json).unwrap();
@@ -691,6 +1506,7 @@ fn continue_as_new_state_roundtrips_through_json() {
             effort: None,
             summary: codex_protocol::config_types::ReasoningSummary::Auto,
             personality: None,
+            output_schema: None,
         }],
         cumulative_turn_count: 5,
         cumulative_iterations: 42,
@@ -705,6 +1521,10 @@ fn continue_as_new_state_roundtrips_through_json() {
         overrides: Default::default(),
         event_offset: 0,
         event_snapshot: vec![],
+        subagents: Default::default(),
+        inbox: Vec::new(),
+        paused: false,
+        budget_exhausted_turn: Some("turn-4".to_string()),
     };
 
     let json = serde_json::to_string(&state).unwrap();
@@ -716,6 +1536,7 @@ fn continue_as_new_state_roundtrips_through_json() {
     assert_eq!(back.cumulative_turn_count, 5);
     assert_eq!(back.cumulative_iterations, 42);
     assert!(back.cumulative_token_usage.is_some());
+    assert_eq!(back.budget_exhausted_turn.as_deref(), Some("turn-4"));
 }
 
 #[test]
@@ -743,13 +1564,19 @@ fn workflow_input_with_continued_state_roundtrips() {
             overrides: Default::default(),
             event_offset: 0,
             event_snapshot: vec![],
+            subagents: Default::default(),
+            inbox: Vec::new(),
+            paused: false,
+            budget_exhausted_turn: None,
         }),
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
         max_iterations: None,
+        output_schema: None,
     };
 
     let json = serde_json::to_string(&input).unwrap();
@@ -1222,6 +2049,16 @@ fn session_entry_roundtrips_through_json() {
         created_at_millis: 1_700_000_000_000,
         status: SessionStatus::Running,
         crew_type: None,
+        schedule_id: None,
+        last_activity_millis: 0,
+        last_message_preview: None,
+        pinned: false,
+        archived_at_millis: None,
+        title: None,
+        tags: Default::default(),
+        cwd: None,
+        repository: None,
+        branch: None,
     };
 
     let json = serde_json::to_string(&entry).unwrap();
@@ -1248,6 +2085,16 @@ fn session_entry_status_variants_roundtrip() {
             created_at_millis: 0,
             status,
             crew_type: None,
+            schedule_id: None,
+            last_activity_millis: 0,
+            last_message_preview: None,
+            pinned: false,
+            archived_at_millis: None,
+            title: None,
+            tags: Default::default(),
+            cwd: None,
+            repository: None,
+            branch: None,
         };
         let json = serde_json::to_string(&entry).unwrap();
         let back: SessionEntry = serde_json::from_str(&json).unwrap();
@@ -1266,6 +2113,16 @@ fn harness_state_roundtrips() {
                 created_at_millis: 100,
                 status: SessionStatus::Running,
                 crew_type: None,
+                schedule_id: None,
+                last_activity_millis: 0,
+                last_message_preview: None,
+                pinned: false,
+                archived_at_millis: None,
+                title: None,
+                tags: Default::default(),
+                cwd: None,
+                repository: None,
+                branch: None,
             },
             SessionEntry {
                 session_id: "s2".to_string(),
@@ -1274,9 +2131,22 @@ fn harness_state_roundtrips() {
                 created_at_millis: 200,
                 status: SessionStatus::Completed,
                 crew_type: None,
+                schedule_id: None,
+                last_activity_millis: 0,
+                last_message_preview: None,
+                pinned: false,
+                archived_at_millis: None,
+                title: None,
+                tags: Default::default(),
+                cwd: None,
+                repository: None,
+                branch: None,
             },
         ],
         credentials_available: None,
+        results: BTreeMap::new(),
+        retention: Default::default(),
+        idle_shutdown_sent: ["s1".to_string()].into(),
     };
 
     let json = serde_json::to_string(&state).unwrap();
@@ -1285,6 +2155,8 @@ fn harness_state_roundtrips() {
     assert_eq!(back.sessions.len(), 2);
     assert_eq!(back.sessions[0].session_id, "s1");
     assert_eq!(back.sessions[1].status, SessionStatus::Completed);
+    // Sessions already asked to shut down are not asked again after CAN.
+    assert!(back.idle_shutdown_sent.contains("s1"));
 }
 
 #[test]
@@ -1302,6 +2174,9 @@ fn harness_state_credentials_available_roundtrips() {
     let state = HarnessState {
         sessions: vec![],
         credentials_available: Some(true),
+        results: BTreeMap::new(),
+        retention: Default::default(),
+        idle_shutdown_sent: Default::default(),
     };
     let json = serde_json::to_string(&state).unwrap();
     let back: HarnessState = serde_json::from_str(&json).unwrap();
@@ -1503,11 +2378,13 @@ fn workflow_input_developer_instructions_roundtrips() {
         model_provider: None,
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
 
     let json = serde_json::to_string(&input).unwrap();
@@ -1554,11 +2431,13 @@ fn workflow_input_model_provider_roundtrips() {
         model_provider: Some(provider.clone()),
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
 
     let json = serde_json::to_string(&input).unwrap();
@@ -1855,6 +2734,8 @@ fn mcp_tool_call_input_serde_roundtrip() {
         qualified_name: "mcp__echo__echo".to_string(),
         call_id: "call-123".to_string(),
         arguments: r#"{"message":"hello"}"#.to_string(),
+        config_toml: None,
+        cwd: None,
     };
 
     let json = serde_json::to_string(&input).unwrap();
@@ -1862,65 +2743,255 @@ fn mcp_tool_call_input_serde_roundtrip() {
     assert_eq!(back.qualified_name, "mcp__echo__echo");
     assert_eq!(back.call_id, "call-123");
     assert_eq!(back.arguments, r#"{"message":"hello"}"#);
+    assert!(back.config_toml.is_none());
 }
 
 #[test]
-fn mcp_tool_call_output_serde_roundtrip_ok() {
-    use crate::types::McpToolCallOutput;
-
-    let output = McpToolCallOutput {
-        call_id: "call-456".to_string(),
-        result: Ok(serde_json::json!({
-            "content": [{"type": "text", "text": "hello back"}],
-        })),
-        elicitation: None,
-    };
+fn mcp_tool_call_input_deserializes_without_reconnect_fields() {
+    use crate::types::McpToolCallInput;
 
-    let json = serde_json::to_string(&output).unwrap();
-    let back: McpToolCallOutput = serde_json::from_str(&json).unwrap();
-    assert_eq!(back.call_id, "call-456");
-    assert!(back.result.is_ok());
+    // Inputs recorded in history before reconnection support must still
+    // deserialize on replay.
+    let json = r#"{"qualified_name":"mcp__echo__echo","call_id":"c","arguments":""}"#;
+    let back: McpToolCallInput = serde_json::from_str(json).unwrap();
+    assert!(back.config_toml.is_none());
+    assert!(back.cwd.is_none());
 }
 
 #[test]
-fn mcp_tool_call_output_serde_roundtrip_err() {
-    use crate::types::McpToolCallOutput;
+fn mcp_tool_call_output_serde_roundtrip_unavailable() {
+    use crate::types::{McpServerUnavailable, McpToolCallOutput};
 
     let output = McpToolCallOutput {
-        call_id: "call-789".to_string(),
-        result: Err("tool not found".to_string()),
+        call_id: "call-down".to_string(),
+        result: Err("connection refused".to_string()),
         elicitation: None,
+        worker_task_queue: None,
+        unavailable: Some(McpServerUnavailable {
+            server_name: "echo".to_string(),
+            required: true,
+            attempts: 3,
+            error: "connection refused".to_string(),
+        }),
     };
 
     let json = serde_json::to_string(&output).unwrap();
     let back: McpToolCallOutput = serde_json::from_str(&json).unwrap();
-    assert_eq!(back.call_id, "call-789");
-    assert!(back.result.is_err());
-    assert_eq!(back.result.unwrap_err(), "tool not found");
+    let unavailable = back.unavailable.expect("unavailable should roundtrip");
+    assert_eq!(unavailable.server_name, "echo");
+    assert!(unavailable.required);
+    assert_eq!(unavailable.attempts, 3);
 }
 
 #[test]
-fn mcp_tool_call_output_into_response_item_ok() {
-    use crate::types::McpToolCallOutput;
-    use codex_protocol::models::ResponseInputItem;
+fn mcp_tool_call_output_carries_worker_task_queue() {
+    use crate::types::{CapturedElicitation, McpToolCallOutput};
+    use codex_protocol::mcp::RequestId;
 
     let output = McpToolCallOutput {
-        call_id: "call-resp".to_string(),
-        result: Ok(serde_json::json!({
-            "content": [{"type": "text", "text": "result text"}],
-        })),
-        elicitation: None,
+        call_id: "call-1".to_string(),
+        result: Err("awaiting elicitation response".to_string()),
+        elicitation: Some(CapturedElicitation::from_params(
+            "srv".to_string(),
+            RequestId::Integer(1),
+            &serde_json::json!({"message": "Name?"}),
+        )),
+        worker_task_queue: Some("codex-temporal-worker-1".to_string()),
+        unavailable: None,
     };
+    let json = serde_json::to_string(&output).unwrap();
+    let back: McpToolCallOutput = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.worker_task_queue.as_deref(), Some("codex-temporal-worker-1"));
 
-    let item = output.into_response_input_item();
-    match item {
-        ResponseInputItem::McpToolCallOutput { call_id, output } => {
-            assert_eq!(call_id, "call-resp");
-            assert!(output.success());
-            assert_eq!(output.content.len(), 1);
-        }
-        other => panic!("expected McpToolCallOutput, got {:?}", other),
-    }
+    // Outputs of older workers have no queue.
+    let legacy = r#"{"call_id":"call-1","result":{"Err":"boom"}}"#;
+    let back: McpToolCallOutput = serde_json::from_str(legacy).unwrap();
+    assert!(back.worker_task_queue.is_none());
+}
+
+#[test]
+fn mcp_reconnect_policy_backoff() {
+    use crate::mcp::McpReconnectPolicy;
+    use std::time::Duration;
+
+    let policy = McpReconnectPolicy::default();
+    assert_eq!(policy.delay_for(0), Duration::ZERO);
+    assert_eq!(policy.delay_for(1), Duration::from_secs(1));
+    assert_eq!(policy.delay_for(2), Duration::from_secs(2));
+    assert_eq!(policy.delay_for(4), Duration::from_secs(8));
+    // Capped at max_delay, even for huge attempt counts.
+    assert_eq!(policy.delay_for(10), Duration::from_secs(60));
+    assert_eq!(policy.delay_for(u32::MAX), Duration::from_secs(60));
+}
+
+#[test]
+fn mcp_session_tools_apply_the_sessions_filters() {
+    use codex_core::config::types::McpServerConfig;
+    use crate::mcp::session_tools;
+
+    let tools: Vec<rmcp::model::Tool> = ["read", "write", "delete"]
+        .iter()
+        .map(|name| {
+            serde_json::from_value(serde_json::json!({
+                "name": name,
+                "inputSchema": {"type": "object"},
+            }))
+            .unwrap()
+        })
+        .collect();
+    let all: McpServerConfig = toml::from_str(r#"command = "fs""#).unwrap();
+    let mut names: Vec<_> = session_tools("fs", &all, tools.clone()).into_keys().collect();
+    names.sort();
+    assert_eq!(names, ["mcp__fs__delete", "mcp__fs__read", "mcp__fs__write"]);
+
+    // Another session's filters only shape its own view of the same tools.
+    let filtered: McpServerConfig = toml::from_str(
+        r#"
+command = "fs"
+enabled_tools = ["read", "write"]
+disabled_tools = ["write"]
+"#,
+    )
+    .unwrap();
+    let names: Vec<_> = session_tools("fs", &filtered, tools).into_keys().collect();
+    assert_eq!(names, ["mcp__fs__read"]);
+}
+
+#[tokio::test]
+async fn mcp_session_disabling_a_server_keeps_the_shared_config() {
+    use codex_core::config::types::McpServerConfig;
+    use crate::mcp::HarnessMcpManager;
+    use std::collections::HashMap;
+
+    let enabled: McpServerConfig =
+        toml::from_str(r#"command = "/nonexistent/codex-test-mcp-server""#).unwrap();
+    let mut disabled = enabled.clone();
+    disabled.enabled = false;
+
+    let mut manager = HarnessMcpManager::new();
+    manager.register_configs(&HashMap::from([("fs".to_string(), enabled)]));
+    let tools = manager
+        .initialize(&HashMap::from([("fs".to_string(), disabled)]))
+        .await
+        .unwrap();
+    assert!(tools.is_empty());
+
+    // The server is still enabled for the other sessions: reconnecting it
+    // tries to spawn it rather than refusing a disabled server.
+    let err = manager.ensure_connected("fs").await.unwrap_err().to_string();
+    assert!(!err.contains("disabled"), "{err}");
+}
+
+#[test]
+fn mcp_elicitations_are_captured_for_the_call_active_on_their_server() {
+    use crate::mcp::ElicitationBroker;
+    use codex_protocol::mcp::RequestId;
+
+    let params = serde_json::json!({"message": "Pick a repo"});
+    let mut broker = ElicitationBroker::default();
+    broker.activate("github", "call-1");
+    broker.activate("jira", "call-2");
+
+    let _rx = broker.capture("jira", RequestId::Integer(1), &params).unwrap();
+    assert!(broker.take_captured("call-1").is_none());
+    let captured = broker.take_captured("call-2").unwrap();
+    assert_eq!(captured.server_name, "jira");
+
+    // A request from a server no call is driven on is refused.
+    broker.deactivate("github", "call-1");
+    assert!(broker.capture("github", RequestId::Integer(2), &params).is_err());
+    // Deactivating another call keeps the server's active call.
+    broker.deactivate("jira", "call-1");
+    assert!(broker.capture("jira", RequestId::Integer(3), &params).is_ok());
+}
+
+#[test]
+fn mcp_elicitations_of_dropped_calls_are_swept() {
+    use crate::mcp::ElicitationBroker;
+    use codex_protocol::mcp::RequestId;
+
+    let params = serde_json::json!({"message": "Confirm?"});
+    let mut broker = ElicitationBroker::default();
+    broker.activate("fs", "call-old");
+    let mut old = broker.capture("fs", RequestId::Integer(1), &params).unwrap();
+    broker.activate("db", "call-live");
+    let mut live = broker.capture("db", RequestId::Integer(1), &params).unwrap();
+
+    broker.retain_calls(|call_id| call_id == "call-live");
+
+    // The abandoned request is failed and forgotten; the live one waits.
+    assert!(matches!(old.try_recv(), Err(tokio::sync::oneshot::error::TryRecvError::Closed)));
+    assert!(broker.take_captured("call-old").is_none());
+    assert!(broker.take_responder("fs", &RequestId::Integer(1)).is_none());
+    assert!(matches!(live.try_recv(), Err(tokio::sync::oneshot::error::TryRecvError::Empty)));
+    let responder = broker.take_responder("db", &RequestId::Integer(1)).unwrap();
+    responder.send(serde_json::json!({})).unwrap();
+    assert!(live.try_recv().is_ok());
+}
+
+#[test]
+fn mcp_tool_call_output_serde_roundtrip_ok() {
+    use crate::types::McpToolCallOutput;
+
+    let output = McpToolCallOutput {
+        call_id: "call-456".to_string(),
+        result: Ok(serde_json::json!({
+            "content": [{"type": "text", "text": "hello back"}],
+        })),
+        elicitation: None,
+        worker_task_queue: None,
+        unavailable: None,
+    };
+
+    let json = serde_json::to_string(&output).unwrap();
+    let back: McpToolCallOutput = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.call_id, "call-456");
+    assert!(back.result.is_ok());
+}
+
+#[test]
+fn mcp_tool_call_output_serde_roundtrip_err() {
+    use crate::types::McpToolCallOutput;
+
+    let output = McpToolCallOutput {
+        call_id: "call-789".to_string(),
+        result: Err("tool not found".to_string()),
+        elicitation: None,
+        worker_task_queue: None,
+        unavailable: None,
+    };
+
+    let json = serde_json::to_string(&output).unwrap();
+    let back: McpToolCallOutput = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.call_id, "call-789");
+    assert!(back.result.is_err());
+    assert_eq!(back.result.unwrap_err(), "tool not found");
+}
+
+#[test]
+fn mcp_tool_call_output_into_response_item_ok() {
+    use crate::types::McpToolCallOutput;
+    use codex_protocol::models::ResponseInputItem;
+
+    let output = McpToolCallOutput {
+        call_id: "call-resp".to_string(),
+        result: Ok(serde_json::json!({
+            "content": [{"type": "text", "text": "result text"}],
+        })),
+        elicitation: None,
+        worker_task_queue: None,
+        unavailable: None,
+    };
+
+    let item = output.into_response_input_item();
+    match item {
+        ResponseInputItem::McpToolCallOutput { call_id, output } => {
+            assert_eq!(call_id, "call-resp");
+            assert!(output.success());
+            assert_eq!(output.content.len(), 1);
+        }
+        other => panic!("expected McpToolCallOutput, got {:?}", other),
+    }
 }
 
 #[test]
@@ -1932,6 +3003,8 @@ fn mcp_tool_call_output_into_response_item_err() {
         call_id: "call-err".to_string(),
         result: Err("server crashed".to_string()),
         elicitation: None,
+        worker_task_queue: None,
+        unavailable: None,
     };
 
     let item = output.into_response_input_item();
@@ -1944,6 +3017,216 @@ fn mcp_tool_call_output_into_response_item_err() {
     }
 }
 
+#[test]
+fn captured_elicitation_preserves_form_schema() {
+    use crate::types::CapturedElicitation;
+    use codex_protocol::approvals::ElicitationRequest;
+    use codex_protocol::mcp::RequestId;
+
+    let params = serde_json::json!({
+        "message": "Confirm deployment",
+        "requestedSchema": {
+            "type": "object",
+            "properties": {"confirm": {"type": "boolean"}},
+            "required": ["confirm"],
+        },
+    });
+    let captured = CapturedElicitation::from_params(
+        "deploy".to_string(),
+        RequestId::Integer(7),
+        &params,
+    );
+    assert_eq!(captured.message, "Confirm deployment");
+    assert!(captured.url.is_none());
+
+    match captured.to_request() {
+        ElicitationRequest::Form { message, requested_schema, .. } => {
+            assert_eq!(message, "Confirm deployment");
+            assert_eq!(requested_schema["required"][0], "confirm");
+        }
+        other => panic!("expected Form request, got {:?}", other),
+    }
+}
+
+#[test]
+fn captured_elicitation_url_mode() {
+    use crate::types::CapturedElicitation;
+    use codex_protocol::approvals::ElicitationRequest;
+    use codex_protocol::mcp::RequestId;
+
+    let params = serde_json::json!({
+        "mode": "url",
+        "message": "Sign in",
+        "url": "https://example.com/auth",
+        "elicitationId": "el-1",
+    });
+    let captured = CapturedElicitation::from_params(
+        "auth".to_string(),
+        RequestId::String("r1".to_string()),
+        &params,
+    );
+
+    match captured.to_request() {
+        ElicitationRequest::Url { url, elicitation_id, .. } => {
+            assert_eq!(url, "https://example.com/auth");
+            assert_eq!(elicitation_id, "el-1");
+        }
+        other => panic!("expected Url request, got {:?}", other),
+    }
+}
+
+#[test]
+fn captured_elicitation_serde_roundtrip() {
+    use crate::types::CapturedElicitation;
+    use codex_protocol::mcp::RequestId;
+
+    let captured = CapturedElicitation::from_params(
+        "srv".to_string(),
+        RequestId::Integer(1),
+        &serde_json::json!({"message": "Name?", "requestedSchema": {"type": "object"}}),
+    );
+    let json = serde_json::to_string(&captured).unwrap();
+    let back: CapturedElicitation = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.message, "Name?");
+    assert_eq!(back.requested_schema["type"], "object");
+
+    // Older payloads without the schema fields still deserialize.
+    let legacy = r#"{"server_name":"srv","request_id":1,"message":"hi"}"#;
+    let back: CapturedElicitation = serde_json::from_str(legacy).unwrap();
+    assert_eq!(back.requested_schema, serde_json::json!({}));
+    assert!(back.url.is_none());
+
+    // Both paths agree on a missing schema.
+    let without_schema = CapturedElicitation::from_params(
+        "srv".to_string(),
+        RequestId::Integer(1),
+        &serde_json::json!({"message": "hi"}),
+    );
+    assert_eq!(without_schema.requested_schema, back.requested_schema);
+}
+
+#[test]
+fn mcp_elicitation_resolve_input_serde_roundtrip() {
+    use crate::types::McpElicitationResolveInput;
+    use codex_protocol::approvals::ElicitationAction;
+    use codex_protocol::mcp::RequestId;
+
+    let input = McpElicitationResolveInput {
+        call_id: "call-1".to_string(),
+        server_name: "srv".to_string(),
+        request_id: RequestId::Integer(3),
+        action: ElicitationAction::Accept,
+        content: Some(serde_json::json!({"confirm": true})),
+    };
+    let json = serde_json::to_string(&input).unwrap();
+    let back: McpElicitationResolveInput = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.call_id, "call-1");
+    assert_eq!(back.content, Some(serde_json::json!({"confirm": true})));
+}
+
+#[test]
+fn parse_mcp_prompt_command_variants() {
+    use crate::types::parse_mcp_prompt_command;
+
+    let parsed = parse_mcp_prompt_command("/mcp-prompt docs summarize topic=auth lang=en").unwrap();
+    assert_eq!(parsed.server, "docs");
+    assert_eq!(parsed.name, "summarize");
+    assert_eq!(parsed.arguments.get("topic").map(String::as_str), Some("auth"));
+    assert_eq!(parsed.arguments.get("lang").map(String::as_str), Some("en"));
+
+    let parsed = parse_mcp_prompt_command("  /mcp-prompt docs intro\n").unwrap();
+    assert!(parsed.arguments.is_empty());
+
+    assert!(parse_mcp_prompt_command("/mcp-prompt docs").is_none());
+    assert!(parse_mcp_prompt_command("/mcp-prompts docs intro").is_none());
+    assert!(parse_mcp_prompt_command("please run /mcp-prompt docs intro").is_none());
+}
+
+#[test]
+fn render_mcp_prompt_keeps_text_content() {
+    use crate::types::render_mcp_prompt;
+
+    let result = serde_json::json!({
+        "description": "Review code",
+        "messages": [
+            {"role": "user", "content": {"type": "text", "text": "Review this change."}},
+            {"role": "assistant", "content": {"type": "text", "text": "Sure."}},
+            {"role": "user", "content": {"type": "image", "data": "AAAA", "mimeType": "image/png"}},
+            {"role": "user", "content": {"type": "resource", "resource": {"uri": "file:///a.rs", "text": "fn main() {}"}}},
+        ],
+    });
+    assert_eq!(
+        render_mcp_prompt(&result),
+        "Review this change.\n\n[assistant]\nSure.\n\nfn main() {}"
+    );
+    assert_eq!(render_mcp_prompt(&serde_json::json!({})), "");
+}
+
+#[test]
+fn limit_resource_contents_truncates_text_and_blobs() {
+    use crate::mcp::limit_resource_contents;
+
+    let mut result = serde_json::json!({
+        "contents": [
+            {"uri": "a", "text": "0123456789"},
+            {"uri": "b", "text": "héllo wörld"},
+            {"uri": "c", "blob": "QUJDREVGR0g="},
+        ],
+    });
+    limit_resource_contents(&mut result, 13);
+
+    let contents = result["contents"].as_array().unwrap();
+    assert_eq!(contents[0]["text"], "0123456789");
+    // Budget of 3 bytes left; the cut lands on a char boundary.
+    let second = contents[1]["text"].as_str().unwrap();
+    assert!(second.starts_with("hé\n[... truncated"));
+    assert!(contents[2].get("blob").is_none());
+    assert!(contents[2]["text"].as_str().unwrap().contains("binary content omitted"));
+}
+
+#[test]
+fn mcp_request_output_into_response_item() {
+    use crate::types::McpRequestOutput;
+    use codex_protocol::models::{FunctionCallOutputBody, ResponseInputItem};
+
+    let ok = McpRequestOutput {
+        result: Ok(serde_json::json!({"resources": []})),
+    }
+    .into_response_input_item("call-r".to_string());
+    match ok {
+        ResponseInputItem::FunctionCallOutput { call_id, output } => {
+            assert_eq!(call_id, "call-r");
+            assert_eq!(output.success, Some(true));
+            assert!(matches!(output.body, FunctionCallOutputBody::Text(ref t) if t.contains("resources")));
+        }
+        other => panic!("expected FunctionCallOutput, got {:?}", other),
+    }
+
+    let err = McpRequestOutput {
+        result: Err("MCP server 'docs' not found".to_string()),
+    }
+    .into_response_input_item("call-e".to_string());
+    match err {
+        ResponseInputItem::FunctionCallOutput { output, .. } => {
+            assert_eq!(output.success, Some(false));
+        }
+        other => panic!("expected FunctionCallOutput, got {:?}", other),
+    }
+}
+
+#[test]
+fn mcp_prompt_tool_specs_are_well_formed() {
+    use crate::tools::mcp_prompt_tool_specs;
+
+    let specs = mcp_prompt_tool_specs();
+    let names: Vec<&str> = specs.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["list_mcp_prompts", "get_mcp_prompt"]);
+    for spec in &specs {
+        assert_eq!(spec.input_schema["type"], "object");
+    }
+    assert_eq!(specs[1].input_schema["required"], serde_json::json!(["server", "name"]));
+}
+
 #[test]
 fn continue_as_new_state_with_mcp_tools() {
     use crate::types::ContinueAsNewState;
@@ -1964,6 +3247,10 @@ fn continue_as_new_state_with_mcp_tools() {
         overrides: Default::default(),
         event_offset: 0,
         event_snapshot: vec![],
+        subagents: Default::default(),
+        inbox: Vec::new(),
+        paused: false,
+        budget_exhausted_turn: None,
     };
 
     let json = serde_json::to_string(&state).unwrap();
@@ -2006,6 +3293,10 @@ fn continue_as_new_state_with_approval_policy_roundtrips() {
         },
         event_offset: 0,
         event_snapshot: vec![],
+        subagents: Default::default(),
+        inbox: Vec::new(),
+        paused: false,
+        budget_exhausted_turn: None,
     };
 
     let json = serde_json::to_string(&state).unwrap();
@@ -2112,130 +3403,544 @@ fn mcp_tool_name_detection() {
 }
 
 // ---------------------------------------------------------------------------
-// Multi-agent type serde tests
+// MCP tool approval tests
 // ---------------------------------------------------------------------------
 
 #[test]
-fn session_workflow_input_roundtrips_through_json() {
-    use crate::types::SessionWorkflowInput;
+fn mcp_approval_from_toml_parses_rules() {
+    use crate::config_loader::mcp_approval_from_toml;
+    use crate::types::McpApprovalMode;
 
-    let input = SessionWorkflowInput {
-        user_message: "Hello".to_string(),
-        model: "gpt-4o".to_string(),
-        instructions: "Be helpful.".to_string(),
-        approval_policy: Default::default(),
-        web_search_mode: None,
-        reasoning_effort: None,
-        reasoning_summary: codex_protocol::config_types::ReasoningSummary::Auto,
-        personality: None,
-        developer_instructions: None,
-        model_provider: None,
-        crew_agents: BTreeMap::new(),
-        continued_state: None,
-        max_iterations: None,
-    };
+    let toml_str = r#"
+model = "gpt-5"
 
-    let json = serde_json::to_string(&input).unwrap();
-    let back: SessionWorkflowInput = serde_json::from_str(&json).unwrap();
+[mcp_tool_approval]
+default = "never"
 
-    assert_eq!(back.user_message, "Hello");
-    assert_eq!(back.model, "gpt-4o");
-    assert_eq!(back.instructions, "Be helpful.");
+[mcp_tool_approval.servers.jira]
+default = "always"
+tools = { search_issues = "never" }
+
+[mcp_tool_approval.servers.db]
+tools = { drop_table = "always" }
+"#;
+    let config = mcp_approval_from_toml(toml_str);
+    assert_eq!(config.default, McpApprovalMode::Never);
+    assert_eq!(config.mode_for("jira", "delete_issue"), McpApprovalMode::Always);
+    assert_eq!(config.mode_for("jira", "search_issues"), McpApprovalMode::Never);
+    assert_eq!(config.mode_for("db", "drop_table"), McpApprovalMode::Always);
+    // No per-server default falls back to the global default.
+    assert_eq!(config.mode_for("db", "select"), McpApprovalMode::Never);
+    assert_eq!(config.mode_for("other", "anything"), McpApprovalMode::Never);
 }
 
 #[test]
-fn session_workflow_input_backward_compat_defaults() {
-    use crate::types::SessionWorkflowInput;
+fn refreshed_mcp_config_replaces_only_mcp_tables() {
+    use crate::config_loader::{mcp_approval_from_toml, with_refreshed_mcp_config};
+    use crate::types::McpApprovalMode;
 
-    // Minimal JSON without optional fields.
-    let json = r#"{"user_message":"hi","model":"gpt-4o","instructions":"test"}"#;
-    let input: SessionWorkflowInput = serde_json::from_str(json).unwrap();
+    let agent = r#"
+model = "gpt-5-mini"
+developer_instructions = "Review carefully."
 
-    assert_eq!(input.user_message, "hi");
-    assert!(input.continued_state.is_none());
-    assert!(input.developer_instructions.is_none());
-    assert!(input.model_provider.is_none());
+[mcp_servers.old]
+command = "old-server"
+"#;
+    let refreshed = r#"
+model = "gpt-5"
+
+[mcp_servers.jira]
+command = "jira-mcp"
+
+[mcp_tool_approval]
+default = "always"
+"#;
+    let merged = with_refreshed_mcp_config(agent, refreshed);
+    let doc: toml::Table = toml::from_str(&merged).unwrap();
+    // The agent's own (role) settings are kept.
+    assert_eq!(doc["model"].as_str(), Some("gpt-5-mini"));
+    assert_eq!(doc["developer_instructions"].as_str(), Some("Review carefully."));
+    // MCP servers and approval rules come from the refreshed config.
+    let servers = doc["mcp_servers"].as_table().unwrap();
+    assert!(servers.contains_key("jira") && !servers.contains_key("old"));
+    assert_eq!(mcp_approval_from_toml(&merged).default, McpApprovalMode::Always);
+
+    // Tables removed from config.toml are removed from the agent's config.
+    let merged = with_refreshed_mcp_config(&merged, "model = \"gpt-5\"\n");
+    let doc: toml::Table = toml::from_str(&merged).unwrap();
+    assert!(!doc.contains_key("mcp_servers") && !doc.contains_key("mcp_tool_approval"));
+
+    // Unparseable input leaves the agent's config alone.
+    assert_eq!(with_refreshed_mcp_config(agent, "not = [toml"), agent);
 }
 
 #[test]
-fn agent_workflow_input_backward_compat_without_new_fields() {
-    // JSON without the new multi-agent fields should deserialize fine.
-    let json = r#"{
-        "user_message": "test",
-        "model": "gpt-4o",
-        "instructions": "be helpful"
-    }"#;
-    let input: CodexWorkflowInput = serde_json::from_str(json).unwrap();
-
-    assert_eq!(input.role, "default");
-    assert!(input.config_toml.is_none());
-    assert!(input.project_context.is_none());
-    assert!(input.mcp_tools.is_empty());
+fn mcp_approval_from_toml_defaults() {
+    use crate::config_loader::mcp_approval_from_toml;
+    use crate::types::McpApprovalMode;
+
+    // Without rules MCP calls run without approval; annotations are opt-in.
+    let config = mcp_approval_from_toml("model = \"gpt-5\"\n");
+    assert_eq!(config.default, McpApprovalMode::Never);
+    assert!(config.servers.is_empty());
+
+    // Malformed rules fall back to defaults instead of failing the session.
+    let config = mcp_approval_from_toml("[mcp_tool_approval]\ndefault = \"sometimes\"\n");
+    assert_eq!(config.default, McpApprovalMode::Never);
 }
 
 #[test]
-fn agent_workflow_input_with_new_fields_roundtrips() {
-    let input = CodexWorkflowInput {
-        user_message: "test".to_string(),
-        model: "gpt-4o".to_string(),
-        instructions: "test".to_string(),
-        approval_policy: Default::default(),
-        web_search_mode: None,
-        reasoning_effort: None,
-        reasoning_summary: codex_protocol::config_types::ReasoningSummary::Auto,
-        personality: None,
-        developer_instructions: None,
-        model_provider: None,
-        continued_state: None,
-        max_iterations: None,
-        role: "explorer".to_string(),
-        config_toml: Some("model = \"gpt-5\"".to_string()),
-        project_context: Some(ProjectContextOutput {
-            cwd: "/tmp".to_string(),
-            user_instructions: None,
-            git_info: None,
-        }),
-        mcp_tools: {
-            let mut m = std::collections::HashMap::new();
-            m.insert("mcp__echo__echo".to_string(), serde_json::json!({"name": "echo"}));
-            m
-        },
-        dynamic_tools: Vec::new(),
-    };
+fn mcp_approval_annotations_mode() {
+    use crate::types::{McpApprovalConfig, McpApprovalMode};
 
-    let json = serde_json::to_string(&input).unwrap();
-    let back: CodexWorkflowInput = serde_json::from_str(&json).unwrap();
+    let destructive = serde_json::json!({
+        "name": "delete_ticket",
+        "annotations": {"destructiveHint": true},
+    });
+    let default = McpApprovalConfig::default();
+    assert_eq!(default.approval_mode("mcp__jira__delete_ticket", &destructive), None);
 
-    assert_eq!(back.role, "explorer");
-    assert_eq!(back.config_toml.as_deref(), Some("model = \"gpt-5\""));
-    assert!(back.project_context.is_some());
-    assert_eq!(back.mcp_tools.len(), 1);
+    let config = McpApprovalConfig {
+        default: McpApprovalMode::Annotations,
+        ..Default::default()
+    };
+    let read_only = serde_json::json!({
+        "name": "get_ticket",
+        "annotations": {"readOnlyHint": true, "destructiveHint": true},
+    });
+    let unannotated = serde_json::json!({"name": "echo"});
+
+    assert_eq!(
+        config.approval_mode("mcp__jira__delete_ticket", &destructive),
+        Some(McpApprovalMode::Annotations)
+    );
+    assert_eq!(config.approval_mode("mcp__jira__get_ticket", &read_only), None);
+    assert_eq!(config.approval_mode("mcp__echo__echo", &unannotated), None);
+    // Non-MCP names never need MCP approval.
+    assert_eq!(config.approval_mode("shell", &destructive), None);
 }
 
 #[test]
-fn session_continue_as_new_state_roundtrips() {
-    use crate::types::SessionContinueAsNewState;
-    use crate::types::AgentRecord;
-    use crate::types::AgentLifecycle;
+fn mcp_approval_explicit_modes_override_annotations() {
+    use crate::types::{McpApprovalConfig, McpApprovalMode, McpServerApprovalConfig};
 
-    let state = SessionContinueAsNewState {
-        agents: vec![AgentRecord {
-            agent_id: "session/main".to_string(),
-            workflow_id: "session/main".to_string(),
-            role: "default".to_string(),
-            status: AgentLifecycle::Running,
-        }],
-        config_toml: "model = \"gpt-4o\"".to_string(),
-        project_context: ProjectContextOutput {
-            cwd: "/tmp".to_string(),
-            user_instructions: None,
-            git_info: None,
-        },
-        mcp_tools: std::collections::HashMap::new(),
-        crew_agents: BTreeMap::new(),
+    let mut config = McpApprovalConfig {
+        default: McpApprovalMode::Annotations,
+        ..Default::default()
     };
+    config.servers.insert(
+        "jira".to_string(),
+        McpServerApprovalConfig {
+            default: Some(McpApprovalMode::Always),
+            tools: [("delete_ticket".to_string(), McpApprovalMode::Never)]
+                .into_iter()
+                .collect(),
+        },
+    );
+    let read_only = serde_json::json!({"annotations": {"readOnlyHint": true}});
+    let destructive = serde_json::json!({"annotations": {"destructiveHint": true}});
 
-    let json = serde_json::to_string(&state).unwrap();
+    assert_eq!(
+        config.approval_mode("mcp__jira__get_ticket", &read_only),
+        Some(McpApprovalMode::Always)
+    );
+    assert_eq!(config.approval_mode("mcp__jira__delete_ticket", &destructive), None);
+}
+
+#[test]
+fn mcp_approval_always_asks_under_never_policy() {
+    use crate::types::McpApprovalMode;
+
+    // An explicit `always` rule asks even with `approval_policy = never`;
+    // annotation-based gating follows the policy like shell commands.
+    assert!(McpApprovalMode::Always.asks_under(AskForApproval::Never));
+    assert!(McpApprovalMode::Always.asks_under(AskForApproval::OnRequest));
+    assert!(!McpApprovalMode::Annotations.asks_under(AskForApproval::Never));
+    assert!(McpApprovalMode::Annotations.asks_under(AskForApproval::OnRequest));
+    assert!(!McpApprovalMode::Never.asks_under(AskForApproval::OnRequest));
+}
+
+// ---------------------------------------------------------------------------
+// MCP refresh tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn mcp_refresh_request_applies_enable_then_disable() {
+    use crate::types::McpRefreshRequest;
+
+    let mut disabled: std::collections::BTreeSet<String> =
+        ["jira".to_string(), "github".to_string()].into_iter().collect();
+    let request = McpRefreshRequest {
+        enable: vec!["jira".to_string(), "slack".to_string()],
+        disable: vec!["slack".to_string(), "linear".to_string()],
+    };
+    request.apply(&mut disabled);
+
+    let disabled: Vec<&str> = disabled.iter().map(String::as_str).collect();
+    assert_eq!(disabled, vec!["github", "linear", "slack"]);
+}
+
+#[test]
+fn mcp_refresh_request_defaults_from_empty_json() {
+    use crate::types::McpRefreshRequest;
+
+    let request: McpRefreshRequest = serde_json::from_str("{}").unwrap();
+    assert!(request.enable.is_empty());
+    assert!(request.disable.is_empty());
+    assert_eq!(serde_json::to_string(&request).unwrap(), "{}");
+}
+
+#[test]
+fn mcp_tools_update_diffs_tool_sets() {
+    use crate::types::McpToolsUpdate;
+    use std::collections::HashMap;
+
+    let old: HashMap<String, serde_json::Value> = [
+        ("mcp__a__keep".to_string(), serde_json::json!({"name": "keep"})),
+        ("mcp__a__gone".to_string(), serde_json::json!({"name": "gone"})),
+        ("mcp__a__edit".to_string(), serde_json::json!({"name": "edit"})),
+    ]
+    .into_iter()
+    .collect();
+    let new: HashMap<String, serde_json::Value> = [
+        ("mcp__a__keep".to_string(), serde_json::json!({"name": "keep"})),
+        ("mcp__a__edit".to_string(), serde_json::json!({"name": "edit", "description": "v2"})),
+        ("mcp__b__new".to_string(), serde_json::json!({"name": "new"})),
+    ]
+    .into_iter()
+    .collect();
+
+    let update = McpToolsUpdate::diff(&old, new);
+    assert_eq!(update.added, vec!["mcp__b__new"]);
+    assert_eq!(update.removed, vec!["mcp__a__gone"]);
+    assert_eq!(update.changed, vec!["mcp__a__edit"]);
+    assert_eq!(update.mcp_tools.len(), 3);
+    assert!(!update.is_empty());
+    assert_eq!(
+        update.summary(),
+        "+1 added (mcp__b__new), -1 removed (mcp__a__gone), ~1 changed (mcp__a__edit)"
+    );
+}
+
+#[test]
+fn mcp_tools_update_identical_sets_is_empty() {
+    use crate::types::McpToolsUpdate;
+    use std::collections::HashMap;
+
+    let tools: HashMap<String, serde_json::Value> =
+        [("mcp__a__keep".to_string(), serde_json::json!({"name": "keep"}))]
+            .into_iter()
+            .collect();
+    let update = McpToolsUpdate::diff(&tools, tools.clone());
+    assert!(update.is_empty());
+    assert_eq!(update.summary(), "no changes");
+}
+
+#[test]
+fn mcp_discover_input_disabled_servers_defaults_empty() {
+    use crate::types::McpDiscoverInput;
+
+    let input: McpDiscoverInput =
+        serde_json::from_str(r#"{"config_toml": "", "cwd": "/tmp"}"#).unwrap();
+    assert!(input.disabled_servers.is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// MCP server tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn mcp_server_lists_session_tools() {
+    use crate::mcp_server::handle_static_method;
+
+    let result = handle_static_method("tools/list").unwrap();
+    let names: Vec<&str> = result["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "start_session",
+            "send_message",
+            "get_events",
+            "approve",
+            "list_sessions",
+            "list_agents",
+            "spawn_agent"
+        ]
+    );
+    for tool in result["tools"].as_array().unwrap() {
+        assert_eq!(tool["inputSchema"]["type"], "object");
+    }
+}
+
+#[test]
+fn mcp_server_initialize_and_unknown_methods() {
+    use crate::mcp_server::{MCP_PROTOCOL_VERSION, handle_static_method};
+
+    let init = handle_static_method("initialize").unwrap();
+    assert_eq!(init["protocolVersion"], MCP_PROTOCOL_VERSION);
+    assert!(init["capabilities"]["tools"].is_object());
+    assert_eq!(init["serverInfo"]["name"], "codex-temporal");
+
+    assert_eq!(handle_static_method("ping").unwrap(), serde_json::json!({}));
+    assert!(handle_static_method("tools/call").is_none());
+    assert!(handle_static_method("resources/list").is_none());
+}
+
+#[test]
+fn mcp_server_tool_result_wraps_outcomes() {
+    use crate::mcp_server::tool_result;
+
+    let ok = tool_result(Ok(serde_json::json!({"session_id": "s1"})));
+    assert_eq!(ok["isError"], false);
+    assert_eq!(ok["structuredContent"]["session_id"], "s1");
+    assert_eq!(ok["content"][0]["type"], "text");
+
+    let err = tool_result(Err("boom".to_string()));
+    assert_eq!(err["isError"], true);
+    assert_eq!(err["content"][0]["text"], "boom");
+}
+
+#[test]
+fn mcp_server_jsonrpc_envelopes() {
+    use crate::mcp_server::{jsonrpc_error, jsonrpc_result};
+
+    let ok = jsonrpc_result(serde_json::json!(7), serde_json::json!({"a": 1}));
+    assert_eq!(ok["jsonrpc"], "2.0");
+    assert_eq!(ok["id"], 7);
+    assert_eq!(ok["result"]["a"], 1);
+
+    let err = jsonrpc_error(serde_json::json!("x"), -32601, "nope");
+    assert_eq!(err["error"]["code"], -32601);
+    assert_eq!(err["error"]["message"], "nope");
+}
+
+#[test]
+fn mcp_server_parses_tool_arguments() {
+    use crate::mcp_server::{GetEventsArgs, StartSessionArgs, parse_args};
+
+    let args: StartSessionArgs =
+        parse_args("start_session", serde_json::json!({"message": "fix the bug"})).unwrap();
+    assert_eq!(args.message, "fix the bug");
+    assert!(args.model.is_none());
+
+    let err = parse_args::<StartSessionArgs>("start_session", serde_json::Value::Null)
+        .unwrap_err();
+    assert!(err.contains("start_session"), "{err}");
+
+    let events: GetEventsArgs =
+        parse_args("get_events", serde_json::json!({"session_id": "s"})).unwrap();
+    assert_eq!(events.since, 0);
+    assert_eq!(events.wait(), std::time::Duration::from_secs(10));
+
+    let events: GetEventsArgs = parse_args(
+        "get_events",
+        serde_json::json!({"session_id": "s", "since": 4, "wait_seconds": 900}),
+    )
+    .unwrap();
+    assert_eq!(events.since, 4);
+    assert_eq!(events.wait(), std::time::Duration::from_secs(60));
+}
+
+#[test]
+fn mcp_server_approve_args_map_to_review_decisions() {
+    use crate::mcp_server::{ApprovalAnswer, ApprovalKind, ApproveArgs, parse_args};
+    use codex_protocol::protocol::ReviewDecision;
+
+    let args: ApproveArgs = parse_args(
+        "approve",
+        serde_json::json!({"session_id": "s", "call_id": "c1"}),
+    )
+    .unwrap();
+    assert_eq!(args.kind, ApprovalKind::Exec);
+    assert_eq!(args.decision, ApprovalAnswer::Approve);
+
+    let args: ApproveArgs = parse_args(
+        "approve",
+        serde_json::json!({
+            "session_id": "s",
+            "call_id": "c1",
+            "kind": "patch",
+            "decision": "approve_for_session"
+        }),
+    )
+    .unwrap();
+    assert_eq!(args.kind, ApprovalKind::Patch);
+    assert!(matches!(
+        ReviewDecision::from(args.decision),
+        ReviewDecision::ApprovedForSession
+    ));
+    assert!(matches!(
+        ReviewDecision::from(ApprovalAnswer::Deny),
+        ReviewDecision::Denied
+    ));
+}
+
+#[test]
+fn mcp_server_events_to_json_skips_malformed() {
+    use crate::mcp_server::events_to_json;
+    use codex_protocol::protocol::{Event, EventMsg};
+
+    let event = Event {
+        id: "e1".to_string(),
+        msg: EventMsg::ShutdownComplete,
+    };
+    let events = vec![serde_json::to_string(&event).unwrap(), "not json".to_string()];
+    let json = events_to_json(&events);
+    assert_eq!(json.len(), 1);
+    assert_eq!(json[0]["id"], "e1");
+}
+
+// ---------------------------------------------------------------------------
+// Multi-agent type serde tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn session_workflow_input_roundtrips_through_json() {
+    use crate::types::SessionWorkflowInput;
+
+    let input = SessionWorkflowInput {
+        user_message: "Hello".to_string(),
+        model: "gpt-4o".to_string(),
+        instructions: "Be helpful.".to_string(),
+        approval_policy: Default::default(),
+        web_search_mode: None,
+        reasoning_effort: None,
+        reasoning_summary: codex_protocol::config_types::ReasoningSummary::Auto,
+        personality: None,
+        developer_instructions: None,
+        model_provider: None,
+        crew_agents: BTreeMap::new(),
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
+        continued_state: None,
+        main_agent_state: None,
+        max_iterations: None,
+        output_schema: None,
+    };
+
+    let json = serde_json::to_string(&input).unwrap();
+    let back: SessionWorkflowInput = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(back.user_message, "Hello");
+    assert_eq!(back.model, "gpt-4o");
+    assert_eq!(back.instructions, "Be helpful.");
+}
+
+#[test]
+fn session_workflow_input_backward_compat_defaults() {
+    use crate::types::SessionWorkflowInput;
+
+    // Minimal JSON without optional fields.
+    let json = r#"{"user_message":"hi","model":"gpt-4o","instructions":"test"}"#;
+    let input: SessionWorkflowInput = serde_json::from_str(json).unwrap();
+
+    assert_eq!(input.user_message, "hi");
+    assert!(input.continued_state.is_none());
+    assert!(input.developer_instructions.is_none());
+    assert!(input.model_provider.is_none());
+}
+
+#[test]
+fn agent_workflow_input_backward_compat_without_new_fields() {
+    // JSON without the new multi-agent fields should deserialize fine.
+    let json = r#"{
+        "user_message": "test",
+        "model": "gpt-4o",
+        "instructions": "be helpful"
+    }"#;
+    let input: CodexWorkflowInput = serde_json::from_str(json).unwrap();
+
+    assert_eq!(input.role, "default");
+    assert!(input.config_toml.is_none());
+    assert!(input.project_context.is_none());
+    assert!(input.mcp_tools.is_empty());
+}
+
+#[test]
+fn agent_workflow_input_with_new_fields_roundtrips() {
+    let input = CodexWorkflowInput {
+        user_message: "test".to_string(),
+        model: "gpt-4o".to_string(),
+        instructions: "test".to_string(),
+        approval_policy: Default::default(),
+        web_search_mode: None,
+        reasoning_effort: None,
+        reasoning_summary: codex_protocol::config_types::ReasoningSummary::Auto,
+        personality: None,
+        developer_instructions: None,
+        model_provider: None,
+        continued_state: None,
+        max_iterations: None,
+        output_schema: None,
+        role: "explorer".to_string(),
+        config_toml: Some("model = \"gpt-5\"".to_string()),
+        project_context: Some(ProjectContextOutput {
+            cwd: "/tmp".to_string(),
+            user_instructions: None,
+            git_info: None,
+        }),
+        mcp_tools: {
+            let mut m = std::collections::HashMap::new();
+            m.insert("mcp__echo__echo".to_string(), serde_json::json!({"name": "echo"}));
+            m
+        },
+        dynamic_tools: Vec::new(),
+        approval_supervisor: false,
+    };
+
+    let json = serde_json::to_string(&input).unwrap();
+    let back: CodexWorkflowInput = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(back.role, "explorer");
+    assert_eq!(back.config_toml.as_deref(), Some("model = \"gpt-5\""));
+    assert!(back.project_context.is_some());
+    assert_eq!(back.mcp_tools.len(), 1);
+}
+
+#[test]
+fn session_continue_as_new_state_roundtrips() {
+    use crate::types::SessionContinueAsNewState;
+    use crate::types::AgentRecord;
+    use crate::types::AgentLifecycle;
+
+    let state = SessionContinueAsNewState {
+        agents: vec![AgentRecord {
+            agent_id: "session/main".to_string(),
+            workflow_id: "session/main".to_string(),
+            role: "default".to_string(),
+            status: AgentLifecycle::Running,
+            output: None,
+            error: None,
+            parent_agent_id: None,
+            last_agent_message: None,
+            iterations: 0,
+            token_usage: None,
+        }],
+        config_toml: "model = \"gpt-4o\"".to_string(),
+        project_context: ProjectContextOutput {
+            cwd: "/tmp".to_string(),
+            user_instructions: None,
+            git_info: None,
+        },
+        mcp_tools: std::collections::HashMap::new(),
+        crew_agents: BTreeMap::new(),
+        disabled_mcp_servers: Default::default(),
+        messages: Vec::new(),
+        pipeline: None,
+        max_agents: None,
+        pending_spawns: Vec::new(),
+        approvals: Vec::new(),
+        agent_counter: None,
+    };
+
+    let json = serde_json::to_string(&state).unwrap();
     let back: SessionContinueAsNewState = serde_json::from_str(&json).unwrap();
 
     assert_eq!(back.agents.len(), 1);
@@ -2250,6 +3955,9 @@ fn spawn_agent_input_roundtrips() {
     let input = SpawnAgentInput {
         role: "explorer".to_string(),
         message: "Find the auth module".to_string(),
+        requested_by: None,
+        request_id: None,
+        pipeline_task: None,
     };
 
     let json = serde_json::to_string(&input).unwrap();
@@ -2259,190 +3967,1946 @@ fn spawn_agent_input_roundtrips() {
     assert_eq!(back.message, "Find the auth module");
 }
 
-#[test]
-fn agent_record_roundtrips() {
-    use crate::types::{AgentRecord, AgentLifecycle};
+#[test]
+fn agent_record_roundtrips() {
+    use crate::types::{AgentRecord, AgentLifecycle};
+
+    let record = AgentRecord {
+        agent_id: "session/explorer-1".to_string(),
+        workflow_id: "session/explorer-1".to_string(),
+        role: "explorer".to_string(),
+        status: AgentLifecycle::Completed,
+        output: None,
+        error: None,
+        parent_agent_id: None,
+        last_agent_message: None,
+        iterations: 0,
+        token_usage: None,
+    };
+
+    let json = serde_json::to_string(&record).unwrap();
+    let back: AgentRecord = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(back.agent_id, "session/explorer-1");
+    assert_eq!(back.role, "explorer");
+    assert_eq!(back.status, AgentLifecycle::Completed);
+}
+
+#[test]
+fn agent_summary_roundtrips() {
+    use crate::types::{AgentSummary, AgentLifecycle};
+
+    let summary = AgentSummary {
+        agent_id: "session/main".to_string(),
+        role: "default".to_string(),
+        status: AgentLifecycle::Running,
+        iterations: 5,
+        token_usage: None,
+        last_agent_message: None,
+        error: None,
+    };
+
+    let json = serde_json::to_string(&summary).unwrap();
+    let back: AgentSummary = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(back.agent_id, "session/main");
+    assert_eq!(back.iterations, 5);
+    assert_eq!(back.status, AgentLifecycle::Running);
+}
+
+#[test]
+fn agent_record_finish_records_output_and_summary() {
+    use crate::types::{AgentLifecycle, AgentRecord, AgentWorkflowOutput};
+
+    let mut record = AgentRecord::running("session/explorer-1", "explorer");
+    assert_eq!(record.workflow_id, "session/explorer-1");
+    assert_eq!(record.status, AgentLifecycle::Running);
+    assert_eq!(record.summary().iterations, 0);
+
+    record.finish(Ok(AgentWorkflowOutput {
+        last_agent_message: Some("auth lives in src/auth.rs".to_string()),
+        iterations: 4,
+        token_usage: None,
+    }));
+    assert_eq!(record.status, AgentLifecycle::Completed);
+    let summary = record.summary();
+    assert_eq!(summary.iterations, 4);
+    assert_eq!(summary.last_agent_message.as_deref(), Some("auth lives in src/auth.rs"));
+    assert!(summary.error.is_none());
+
+    let json = serde_json::to_string(&record).unwrap();
+    let back: AgentRecord = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.output.unwrap().iterations, 4);
+}
+
+#[test]
+fn agent_record_finish_records_failure() {
+    use crate::types::{AgentLifecycle, AgentRecord};
+
+    let mut record = AgentRecord::running("session/main", "default");
+    record.finish(Err("model call failed".to_string()));
+    assert_eq!(record.status, AgentLifecycle::Failed);
+    let summary = record.summary();
+    assert_eq!(summary.status, AgentLifecycle::Failed);
+    assert_eq!(summary.error.as_deref(), Some("model call failed"));
+}
+
+#[test]
+fn agent_record_without_output_fields_deserializes() {
+    use crate::types::{AgentLifecycle, AgentRecord};
+
+    let json = r#"{"agent_id":"s/main","workflow_id":"s/main","role":"default","status":"Running"}"#;
+    let record: AgentRecord = serde_json::from_str(json).unwrap();
+    assert_eq!(record.status, AgentLifecycle::Running);
+    assert!(record.output.is_none());
+    assert!(record.error.is_none());
+}
+
+#[test]
+fn agent_outcome_decodes_child_results() {
+    use crate::session_workflow::agent_outcome;
+    use crate::types::AgentWorkflowOutput;
+    use temporalio_common::protos::coresdk::AsJsonPayloadExt;
+    use temporalio_common::protos::coresdk::child_workflow::{
+        Cancellation, ChildWorkflowResult, Failure, Success, child_workflow_result::Status,
+    };
+    use temporalio_common::protos::temporal::api::failure::v1::Failure as TemporalFailure;
+
+    let output = AgentWorkflowOutput {
+        last_agent_message: Some("done".to_string()),
+        iterations: 2,
+        token_usage: None,
+    };
+    let completed = ChildWorkflowResult {
+        status: Some(Status::Completed(Success {
+            result: Some(output.as_json_payload().unwrap()),
+        })),
+    };
+    let decoded = agent_outcome(completed).unwrap();
+    assert_eq!(decoded.last_agent_message.as_deref(), Some("done"));
+    assert_eq!(decoded.iterations, 2);
+
+    let failed = ChildWorkflowResult {
+        status: Some(Status::Failed(Failure {
+            failure: Some(TemporalFailure {
+                message: "boom".to_string(),
+                ..Default::default()
+            }),
+        })),
+    };
+    assert_eq!(agent_outcome(failed).unwrap_err(), "boom");
+
+    let cancelled = ChildWorkflowResult {
+        status: Some(Status::Cancelled(Cancellation::default())),
+    };
+    assert_eq!(agent_outcome(cancelled).unwrap_err(), "agent cancelled");
+
+    assert!(agent_outcome(ChildWorkflowResult { status: None }).is_err());
+}
+
+#[test]
+fn resolve_role_config_input_roundtrips() {
+    use crate::types::ResolveRoleConfigInput;
+
+    let input = ResolveRoleConfigInput {
+        config_toml: "model = \"gpt-4o\"".to_string(),
+        cwd: "/home/user/project".to_string(),
+        role_name: "explorer".to_string(),
+    };
+
+    let json = serde_json::to_string(&input).unwrap();
+    let back: ResolveRoleConfigInput = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(back.role_name, "explorer");
+    assert_eq!(back.cwd, "/home/user/project");
+}
+
+#[test]
+fn resolve_role_config_output_roundtrips() {
+    use crate::types::ResolveRoleConfigOutput;
+    use codex_protocol::openai_models::ReasoningEffort;
+
+    let output = ResolveRoleConfigOutput {
+        config_toml: "model = \"gpt-5\"".to_string(),
+        model: Some("gpt-5".to_string()),
+        instructions: Some("Be an explorer.".to_string()),
+        reasoning_effort: Some(ReasoningEffort::Medium),
+        reasoning_summary: codex_protocol::config_types::ReasoningSummary::Auto,
+        personality: None,
+        developer_instructions: None,
+        model_provider: None,
+    };
+
+    let json = serde_json::to_string(&output).unwrap();
+    let back: ResolveRoleConfigOutput = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(back.model.as_deref(), Some("gpt-5"));
+    assert_eq!(back.reasoning_effort, Some(ReasoningEffort::Medium));
+}
+
+#[test]
+fn agent_lifecycle_serde() {
+    use crate::types::AgentLifecycle;
+
+    for status in [
+        AgentLifecycle::Running,
+        AgentLifecycle::Paused,
+        AgentLifecycle::Completed,
+        AgentLifecycle::Failed,
+        AgentLifecycle::Cancelled,
+        AgentLifecycle::Terminated,
+    ] {
+        let json = serde_json::to_string(&status).unwrap();
+        let back: AgentLifecycle = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, status);
+    }
+    assert!(AgentLifecycle::Paused.is_live());
+    assert!(!AgentLifecycle::Cancelled.is_live());
+}
+
+#[test]
+fn agent_record_is_active_counts_only_live_agents() {
+    use crate::types::{AgentLifecycle, AgentRecord, AgentWorkflowOutput};
+
+    let mut record = AgentRecord::running("s/worker-2", "worker");
+    assert!(record.is_active());
+    record.status = AgentLifecycle::Paused;
+    assert!(record.is_active());
+
+    // Cancelled agents hold their slot until they exit.
+    record.status = AgentLifecycle::Cancelled;
+    assert!(record.is_active());
+    record.finish(Ok(AgentWorkflowOutput {
+        last_agent_message: None,
+        iterations: 1,
+        token_usage: None,
+    }));
+    assert!(!record.is_active());
+
+    let mut failed = AgentRecord::running("s/worker-3", "worker");
+    failed.finish(Err("boom".to_string()));
+    assert!(!failed.is_active());
+}
+
+#[test]
+fn agent_record_closed_outcome_keeps_the_last_turn() {
+    use crate::types::{AgentRecord, SessionStatus};
+    use codex_protocol::protocol::TokenUsage;
+
+    let mut record = AgentRecord::running("s/worker-2", "worker");
+    record.last_agent_message = Some("done".to_string());
+    record.iterations = 7;
+    record.token_usage = Some(TokenUsage {
+        total_tokens: 1200,
+        ..Default::default()
+    });
+    let output = record.closed_outcome(SessionStatus::Completed).unwrap();
+    assert_eq!(output.last_agent_message.as_deref(), Some("done"));
+    assert_eq!(output.iterations, 7);
+    assert_eq!(output.token_usage.map(|u| u.total_tokens), Some(1200));
+
+    let error = record.closed_outcome(SessionStatus::Failed).unwrap_err();
+    assert!(error.contains("without reporting"), "{error}");
+    record.finish(Err(error));
+    assert!(!record.is_active());
+}
+
+#[test]
+fn agent_finished_notice_round_trips_both_outcomes() {
+    use crate::types::{AgentFinishedNotice, AgentWorkflowOutput};
+
+    let ok = AgentFinishedNotice {
+        agent_id: "s/worker-1".to_string(),
+        outcome: Ok(AgentWorkflowOutput {
+            last_agent_message: Some("hi".to_string()),
+            iterations: 2,
+            token_usage: None,
+        }),
+    };
+    let json = serde_json::to_string(&ok).unwrap();
+    let back: AgentFinishedNotice = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.outcome.unwrap().iterations, 2);
+
+    let failed = AgentFinishedNotice {
+        agent_id: "s/worker-1".to_string(),
+        outcome: Err("boom".to_string()),
+    };
+    let json = serde_json::to_string(&failed).unwrap();
+    let back: AgentFinishedNotice = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.outcome.unwrap_err(), "boom");
+}
+
+#[test]
+fn session_admission_survives_a_continue_as_new_round_trip() {
+    use crate::types::{
+        AgentRecord, SessionContinueAsNewState, SessionStatus, SpawnAgentInput,
+        admit_pending_spawn, live_agent_count,
+    };
+
+    let spawn = |role: &str| SpawnAgentInput {
+        role: role.to_string(),
+        message: "go".to_string(),
+        requested_by: None,
+        request_id: None,
+        pipeline_task: None,
+    };
+    let state = SessionContinueAsNewState {
+        agents: vec![
+            AgentRecord::running("s/main", "default"),
+            AgentRecord::running("s/worker-2", "worker"),
+        ],
+        config_toml: String::new(),
+        project_context: ProjectContextOutput {
+            cwd: "/tmp".to_string(),
+            user_instructions: None,
+            git_info: None,
+        },
+        mcp_tools: Default::default(),
+        crew_agents: BTreeMap::new(),
+        disabled_mcp_servers: Default::default(),
+        messages: Vec::new(),
+        pipeline: None,
+        max_agents: Some(2),
+        pending_spawns: vec![spawn("reviewer")],
+        approvals: Vec::new(),
+        agent_counter: Some(3),
+    };
+    let json = serde_json::to_string(&state).unwrap();
+    let mut restored: SessionContinueAsNewState = serde_json::from_str(&json).unwrap();
+    let max = restored.max_agents.unwrap();
+
+    // The restored agents still hold their slots: nothing is admitted.
+    assert_eq!(live_agent_count(&restored.agents), 2);
+    assert!(admit_pending_spawn(&restored.agents, &mut restored.pending_spawns, max).is_none());
+    assert_eq!(restored.pending_spawns.len(), 1);
+
+    // A restored agent found closed frees its slot.
+    let worker = &mut restored.agents[1];
+    let outcome = worker.closed_outcome(SessionStatus::Completed);
+    worker.finish(outcome);
+    let admitted = admit_pending_spawn(&restored.agents, &mut restored.pending_spawns, max);
+    assert_eq!(admitted.map(|s| s.role), Some("reviewer".to_string()));
+
+    // Numbering continues past the agents started before continue-as-new.
+    assert_eq!(restored.agent_counter(), 3);
+    restored.agent_counter = None;
+    assert_eq!(restored.agent_counter(), 2);
+}
+
+#[test]
+fn session_can_state_carries_spawn_admission() {
+    use crate::types::{SessionContinueAsNewState, SpawnAgentInput};
+
+    let json = r#"{
+        "agents": [],
+        "config_toml": "",
+        "project_context": {"cwd": "/tmp"},
+        "mcp_tools": {},
+        "max_agents": 3,
+        "pending_spawns": [{"role": "worker", "message": "later", "requested_by": "s/main", "request_id": "c1"}]
+    }"#;
+    let state: SessionContinueAsNewState = serde_json::from_str(json).unwrap();
+    assert_eq!(state.max_agents, Some(3));
+    assert_eq!(state.pending_spawns, vec![SpawnAgentInput {
+        role: "worker".to_string(),
+        message: "later".to_string(),
+        requested_by: Some("s/main".to_string()),
+        request_id: Some("c1".to_string()),
+        pipeline_task: None,
+    }]);
+
+    // Older CAN payloads have neither field.
+    let old = r#"{"agents": [], "config_toml": "", "project_context": {"cwd": "/tmp"}, "mcp_tools": {}}"#;
+    let state: SessionContinueAsNewState = serde_json::from_str(old).unwrap();
+    assert!(state.max_agents.is_none());
+    assert!(state.pending_spawns.is_empty());
+}
+
+#[test]
+fn agent_control_request_parses_and_roundtrips() {
+    use crate::types::{AgentControlAction, AgentControlRequest, AgentLifecycle};
+
+    assert_eq!("pause".parse::<AgentControlAction>(), Ok(AgentControlAction::Pause));
+    assert_eq!("terminate".parse::<AgentControlAction>(), Ok(AgentControlAction::Terminate));
+    assert!("stop".parse::<AgentControlAction>().is_err());
+    assert_eq!(AgentControlAction::Resume.status(), AgentLifecycle::Running);
+    assert_eq!(AgentControlAction::Cancel.status(), AgentLifecycle::Cancelled);
+
+    let request = AgentControlRequest {
+        agent_id: "worker-2".to_string(),
+        action: AgentControlAction::Pause,
+    };
+    let json = serde_json::to_string(&request).unwrap();
+    assert_eq!(json, r#"{"agent_id":"worker-2","action":"pause"}"#);
+    let back: AgentControlRequest = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, request);
+}
+
+#[test]
+fn agent_record_finish_keeps_cancelled_and_terminated_status() {
+    use crate::types::{AgentLifecycle, AgentRecord, AgentWorkflowOutput};
+
+    let mut record = AgentRecord::running("s/worker-2", "worker");
+    assert!(record.matches_id("s/worker-2"));
+    assert!(record.matches_id("worker-2"));
+    assert!(!record.matches_id("worker"));
+
+    record.status = AgentLifecycle::Cancelled;
+    record.finish(Ok(AgentWorkflowOutput {
+        last_agent_message: Some("partial".to_string()),
+        iterations: 1,
+        token_usage: None,
+    }));
+    assert_eq!(record.status, AgentLifecycle::Cancelled);
+    assert!(record.output.is_some());
+
+    let mut record = AgentRecord::running("s/worker-3", "worker");
+    record.status = AgentLifecycle::Terminated;
+    record.finish(Err("agent cancelled".to_string()));
+    assert_eq!(record.status, AgentLifecycle::Terminated);
+
+    // Paused agents still receive messages.
+    let mut paused = AgentRecord::running("s/explorer-4", "explorer");
+    paused.status = AgentLifecycle::Paused;
+    let recipients = crate::types::message_recipients(&[paused], "s/main", "explorer");
+    assert_eq!(recipients, vec!["s/explorer-4".to_string()]);
+}
+
+#[test]
+fn session_workflow_output_roundtrips() {
+    use crate::types::{SessionWorkflowOutput, AgentSummary, AgentLifecycle};
+
+    let output = SessionWorkflowOutput {
+        agents: vec![
+            AgentSummary {
+                agent_id: "session/main".to_string(),
+                role: "default".to_string(),
+                status: AgentLifecycle::Completed,
+                iterations: 10,
+                token_usage: None,
+                last_agent_message: None,
+                error: None,
+            },
+            AgentSummary {
+                agent_id: "session/explorer-1".to_string(),
+                role: "explorer".to_string(),
+                status: AgentLifecycle::Completed,
+                iterations: 3,
+                token_usage: None,
+                last_agent_message: None,
+                error: None,
+            },
+        ],
+        results: None,
+    };
+
+    let json = serde_json::to_string(&output).unwrap();
+    let back: SessionWorkflowOutput = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(back.agents.len(), 2);
+    assert_eq!(back.agents[0].agent_id, "session/main");
+    assert_eq!(back.agents[1].role, "explorer");
+}
+
+#[test]
+fn backward_compat_type_aliases_work() {
+    // Verify the type aliases compile and are equivalent.
+    let _input: CodexWorkflowInput = CodexWorkflowInput {
+        user_message: "test".to_string(),
+        model: "gpt-4o".to_string(),
+        instructions: "test".to_string(),
+        approval_policy: Default::default(),
+        web_search_mode: None,
+        reasoning_effort: None,
+        reasoning_summary: codex_protocol::config_types::ReasoningSummary::Auto,
+        personality: None,
+        developer_instructions: None,
+        model_provider: None,
+        continued_state: None,
+        max_iterations: None,
+        output_schema: None,
+        role: "default".to_string(),
+        config_toml: None,
+        project_context: None,
+        mcp_tools: std::collections::HashMap::new(),
+        dynamic_tools: Vec::new(),
+        approval_supervisor: false,
+    };
+
+    let _output: CodexWorkflowOutput = CodexWorkflowOutput {
+        last_agent_message: None,
+        iterations: 0,
+        token_usage: None,
+    };
+}
+
+#[test]
+fn codex_workflow_input_converts_to_session_workflow_input() {
+    use crate::types::SessionWorkflowInput;
+
+    let agent_input = CodexWorkflowInput {
+        user_message: "hello".to_string(),
+        model: "gpt-4o".to_string(),
+        instructions: "be helpful".to_string(),
+        approval_policy: Default::default(),
+        web_search_mode: None,
+        reasoning_effort: None,
+        reasoning_summary: codex_protocol::config_types::ReasoningSummary::Auto,
+        personality: None,
+        developer_instructions: Some("dev note".to_string()),
+        model_provider: None,
+        continued_state: None,
+        max_iterations: None,
+        output_schema: None,
+        role: "default".to_string(),
+        config_toml: None,
+        project_context: None,
+        mcp_tools: std::collections::HashMap::new(),
+        dynamic_tools: Vec::new(),
+        approval_supervisor: false,
+    };
+
+    let session_input: SessionWorkflowInput = agent_input.into();
+
+    assert_eq!(session_input.user_message, "hello");
+    assert_eq!(session_input.model, "gpt-4o");
+    assert_eq!(
+        session_input.developer_instructions.as_deref(),
+        Some("dev note")
+    );
+    assert!(session_input.continued_state.is_none());
+}
+
+// ---------------------------------------------------------------------------
+// Subagent tool tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn subagents_track_spawn_turns_and_finish() {
+    use crate::types::{SubagentNotice, Subagents};
+
+    let mut subagents = Subagents::default();
+    subagents.apply(SubagentNotice::Spawned {
+        request_id: "call-1".to_string(),
+        agent_id: "s/explorer-2".to_string(),
+        role: "explorer".to_string(),
+    });
+    assert_eq!(
+        subagents.take_spawn_result("call-1"),
+        Some(Ok("s/explorer-2".to_string()))
+    );
+    assert!(subagents.take_spawn_result("call-1").is_none());
+    assert!(!subagents.agents["s/explorer-2"].is_idle(), "initial message is in flight");
+
+    subagents.apply(SubagentNotice::TurnComplete {
+        agent_id: "s/explorer-2".to_string(),
+        last_agent_message: Some("found it".to_string()),
+    });
+    let state = &subagents.agents["s/explorer-2"];
+    assert!(state.is_idle());
+    assert_eq!(state.last_agent_message.as_deref(), Some("found it"));
+
+    subagents.message_sent("s/explorer-2").unwrap();
+    assert!(!subagents.agents["s/explorer-2"].is_idle());
+
+    subagents.apply(SubagentNotice::Finished {
+        agent_id: "s/explorer-2".to_string(),
+        last_agent_message: None,
+        error: Some("boom".to_string()),
+    });
+    let state = &subagents.agents["s/explorer-2"];
+    assert!(state.is_idle() && state.finished);
+    assert_eq!(state.last_agent_message.as_deref(), Some("found it"));
+    assert_eq!(state.error.as_deref(), Some("boom"));
+    assert!(subagents.message_sent("s/explorer-2").is_err());
+}
+
+#[test]
+fn subagents_turn_complete_before_spawned_counts_initial_message() {
+    use crate::types::{SubagentNotice, Subagents};
+
+    let mut subagents = Subagents::default();
+    subagents.apply(SubagentNotice::TurnComplete {
+        agent_id: "s/worker-2".to_string(),
+        last_agent_message: Some("done".to_string()),
+    });
+    subagents.apply(SubagentNotice::Spawned {
+        request_id: "call-1".to_string(),
+        agent_id: "s/worker-2".to_string(),
+        role: "worker".to_string(),
+    });
+    let state = &subagents.agents["s/worker-2"];
+    assert!(state.is_idle());
+    assert_eq!(state.role, "worker");
+    assert_eq!(state.last_agent_message.as_deref(), Some("done"));
+}
+
+#[test]
+fn subagents_record_spawn_failures_and_reject_unknown_agents() {
+    use crate::types::{SubagentNotice, Subagents};
+
+    let mut subagents = Subagents::default();
+    subagents.apply(SubagentNotice::SpawnFailed {
+        request_id: "call-1".to_string(),
+        error: "max_agents limit (8) reached".to_string(),
+    });
+    assert_eq!(
+        subagents.take_spawn_result("call-1"),
+        Some(Err("max_agents limit (8) reached".to_string()))
+    );
+    assert!(subagents.agents.is_empty());
+    assert!(subagents.message_sent("s/nobody-1").is_err());
+}
+
+#[test]
+fn subagent_notice_serializes_tagged() {
+    use crate::types::SubagentNotice;
+
+    let notice = SubagentNotice::TurnComplete {
+        agent_id: "s/explorer-2".to_string(),
+        last_agent_message: None,
+    };
+    let json = serde_json::to_value(&notice).unwrap();
+    assert_eq!(json, serde_json::json!({"type": "turn_complete", "agent_id": "s/explorer-2"}));
+    let back: SubagentNotice = serde_json::from_value(json).unwrap();
+    assert_eq!(back, notice);
+}
+
+#[test]
+fn subagents_roundtrip_in_continue_as_new_state() {
+    use crate::types::{SubagentNotice, Subagents};
+
+    let mut subagents = Subagents::default();
+    subagents.apply(SubagentNotice::Spawned {
+        request_id: "call-1".to_string(),
+        agent_id: "s/explorer-2".to_string(),
+        role: "explorer".to_string(),
+    });
+    let json = serde_json::to_string(&subagents).unwrap();
+    let back: Subagents = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, subagents);
+
+    assert_eq!(serde_json::to_string(&Subagents::default()).unwrap(), "{}");
+}
+
+#[test]
+fn spawn_agent_input_tool_fields_default_to_none() {
+    use crate::types::SpawnAgentInput;
+
+    let input: SpawnAgentInput =
+        serde_json::from_str(r#"{"role":"explorer","message":"hi"}"#).unwrap();
+    assert!(input.requested_by.is_none());
+    assert!(input.request_id.is_none());
+    assert!(!serde_json::to_string(&input).unwrap().contains("requested_by"));
+}
+
+#[test]
+fn agent_ids_map_to_their_session() {
+    use crate::tools::{main_agent_session, session_of_agent};
+
+    assert_eq!(session_of_agent("codex-session-1/main"), Some("codex-session-1"));
+    assert_eq!(session_of_agent("codex-session-1/explorer-2"), Some("codex-session-1"));
+    assert_eq!(session_of_agent("codex-standalone"), None);
+
+    assert_eq!(main_agent_session("codex-session-1/main"), Some("codex-session-1"));
+    assert_eq!(main_agent_session("codex-session-1/explorer-2"), None);
+    assert_eq!(main_agent_session("main"), None);
+    assert_eq!(main_agent_session("/main"), None);
+}
+
+#[test]
+fn subagent_tool_specs_list_roles() {
+    use crate::tools::subagent_tool_specs;
+
+    let mut roles = BTreeMap::new();
+    roles.insert("explorer".to_string(), Some("Answers codebase questions".to_string()));
+    roles.insert("worker".to_string(), None);
+
+    let specs = subagent_tool_specs(&roles);
+    let names: Vec<&str> = specs.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["spawn_subagent", "send_subagent_message", "wait_subagent"]);
+    assert!(specs[0].description.contains("- explorer: Answers codebase questions"));
+    assert!(specs[0].description.contains("- worker"));
+    assert_eq!(
+        specs[0].input_schema["required"],
+        serde_json::json!(["role", "message"])
+    );
+
+    let bare = subagent_tool_specs(&BTreeMap::new());
+    assert!(!bare[0].description.contains("Available roles"));
+}
+
+#[test]
+fn agent_roles_from_toml_reads_agents_table() {
+    use crate::config_loader::agent_roles_from_toml;
+
+    let toml = r#"
+[agents]
+max_threads = 4
+
+[agents.explorer]
+description = "Explores"
+
+[agents.worker]
+"#;
+    let roles = agent_roles_from_toml(toml);
+    assert_eq!(roles.len(), 2);
+    assert_eq!(roles["explorer"].as_deref(), Some("Explores"));
+    assert_eq!(roles["worker"], None);
+
+    assert!(agent_roles_from_toml("model = \"gpt-4o\"").is_empty());
+    assert!(agent_roles_from_toml("not toml [").is_empty());
+}
+
+#[test]
+fn subagent_turn_op_carries_message() {
+    use codex_protocol::protocol::{AskForApproval, Op, SandboxPolicy};
+    use crate::tools::subagent_turn_op;
+    use crate::types::extract_message;
+
+    let op = subagent_turn_op(
+        "check the tests".to_string(),
+        "/repo",
+        "gpt-4o",
+        AskForApproval::Never,
+        SandboxPolicy::DangerFullAccess,
+    );
+    match op {
+        Op::UserTurn { items, model, .. } => {
+            assert_eq!(extract_message(&items), "check the tests");
+            assert_eq!(model, "gpt-4o");
+        }
+        other => panic!("expected UserTurn, got {other:?}"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Inter-agent messaging tests
+// ---------------------------------------------------------------------------
+
+fn running_agents(ids: &[(&str, &str)]) -> Vec<crate::types::AgentRecord> {
+    ids.iter()
+        .map(|(id, role)| crate::types::AgentRecord::running(*id, *role))
+        .collect()
+}
+
+#[test]
+fn message_recipients_resolve_ids_roles_and_broadcast() {
+    use crate::types::message_recipients;
+
+    let mut agents = running_agents(&[
+        ("s/main", "default"),
+        ("s/worker-2", "worker"),
+        ("s/worker-3", "worker"),
+        ("s/reviewer-4", "reviewer"),
+    ]);
+    agents[2].finish(Err("boom".to_string()));
+
+    // Full and short IDs.
+    assert_eq!(message_recipients(&agents, "s/reviewer-4", "s/worker-2"), ["s/worker-2"]);
+    assert_eq!(message_recipients(&agents, "s/reviewer-4", "main"), ["s/main"]);
+    // Roles reach running agents only.
+    assert_eq!(message_recipients(&agents, "s/reviewer-4", "worker"), ["s/worker-2"]);
+    // Broadcast skips the sender.
+    assert_eq!(
+        message_recipients(&agents, "s/reviewer-4", "*"),
+        ["s/main", "s/worker-2"]
+    );
+    // Exited agents and unknown names match nobody.
+    assert!(message_recipients(&agents, "s/main", "worker-3").is_empty());
+    assert!(message_recipients(&agents, "s/main", "nobody").is_empty());
+    assert!(message_recipients(&agents, "s/main", "main").is_empty());
+}
+
+#[test]
+fn agent_message_serde_and_display() {
+    use crate::types::AgentMessage;
+
+    let message = AgentMessage {
+        seq: 0,
+        from: "s/reviewer-4".to_string(),
+        to: "worker".to_string(),
+        body: "Add a test for the empty case".to_string(),
+        wake: false,
+        delivered_to: Vec::new(),
+    };
+    let json = serde_json::to_string(&message).unwrap();
+    assert!(!json.contains("wake"));
+    assert!(!json.contains("delivered_to"));
+    let back: AgentMessage = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, message);
+    assert_eq!(message.display(), "[reviewer-4 → worker] Add a test for the empty case");
+
+    let minimal: AgentMessage =
+        serde_json::from_str(r#"{"from":"s/main","to":"*","body":"hi"}"#).unwrap();
+    assert_eq!(minimal.seq, 0);
+    assert!(!minimal.wake);
+}
+
+#[test]
+fn short_agent_id_strips_session_prefix() {
+    use crate::types::short_agent_id;
+
+    assert_eq!(short_agent_id("codex-session-1/worker-2"), "worker-2");
+    assert_eq!(short_agent_id("session"), "session");
+}
+
+#[test]
+fn messaging_tool_specs_cover_post_and_read() {
+    use crate::tools::messaging_tool_specs;
+
+    let specs = messaging_tool_specs();
+    let names: Vec<&str> = specs.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["post_message", "read_messages"]);
+    assert_eq!(specs[0].input_schema["required"], serde_json::json!(["to", "message"]));
+}
+
+// ---------------------------------------------------------------------------
+// Session approval tests
+// ---------------------------------------------------------------------------
+
+use codex_protocol::protocol::EventMsg;
+
+fn exec_approval_event(call_id: &str, command: &[&str]) -> EventMsg {
+    use codex_protocol::protocol::ExecApprovalRequestEvent;
+
+    EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent {
+        call_id: call_id.to_string(),
+        approval_id: Some(call_id.to_string()),
+        turn_id: "turn-1".to_string(),
+        command: command.iter().map(|c| c.to_string()).collect(),
+        cwd: std::path::PathBuf::from("/tmp"),
+        reason: Some("needs network".to_string()),
+        network_approval_context: None,
+        proposed_execpolicy_amendment: None,
+        proposed_network_policy_amendments: None,
+        additional_permissions: None,
+        skill_metadata: None,
+        available_decisions: None,
+        parsed_cmd: Vec::new(),
+    })
+}
+
+#[test]
+fn session_approval_from_exec_and_patch_events() {
+    use codex_protocol::protocol::ApplyPatchApprovalRequestEvent;
+    use crate::types::{ApprovalKind, SessionApproval};
+
+    let exec_event = exec_approval_event("c1", &["cargo", "test"]);
+    let exec = SessionApproval::from_event("s/worker-2", &exec_event).expect("exec approval");
+    assert_eq!(exec.kind, ApprovalKind::Exec);
+    assert_eq!(exec.call_id, "c1");
+    assert_eq!(exec.reason.as_deref(), Some("needs network"));
+    assert_eq!(exec.summary(), "agent worker-2 wants to run `cargo test`");
+
+    let patch_event = EventMsg::ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent {
+        call_id: "c2".to_string(),
+        turn_id: "turn-1".to_string(),
+        changes: std::collections::HashMap::new(),
+        reason: None,
+        grant_root: None,
+    });
+    let patch = SessionApproval::from_event("s/main", &patch_event).expect("patch approval");
+    assert_eq!(patch.kind, ApprovalKind::Patch);
+    assert!(patch.command.is_empty());
+    assert_eq!(patch.summary(), "agent main wants to apply a patch");
+
+    let other = EventMsg::BackgroundEvent(codex_protocol::protocol::BackgroundEventEvent {
+        message: "hi".to_string(),
+    });
+    assert!(SessionApproval::from_event("s/main", &other).is_none());
+}
+
+#[test]
+fn session_approval_serde_roundtrip() {
+    use crate::types::{ApprovalDecisionRequest, SessionApproval};
+
+    let approval =
+        SessionApproval::from_event("s/worker-2", &exec_approval_event("c1", &["ls"])).unwrap();
+    let json = serde_json::to_string(&approval).unwrap();
+    assert!(json.contains(r#""kind":"exec""#));
+    let back: SessionApproval = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, approval);
+
+    // decided_by is omitted for client decisions.
+    let decision: ApprovalDecisionRequest =
+        serde_json::from_str(r#"{"agent_id":"worker-2","call_id":"c1","approved":true}"#).unwrap();
+    assert!(decision.decided_by.is_none());
+    assert!(!serde_json::to_string(&decision).unwrap().contains("decided_by"));
+}
+
+#[test]
+fn approval_supervisor_address_maps_default_to_main() {
+    use crate::types::approval_supervisor_address;
+
+    assert_eq!(approval_supervisor_address("default"), "main");
+    assert_eq!(approval_supervisor_address("lead"), "lead");
+}
+
+#[test]
+fn may_decide_approval_checks_supervisor_role() {
+    use crate::types::{SessionApproval, may_decide_approval};
+
+    let agents = running_agents(&[
+        ("s/main", "default"),
+        ("s/lead-2", "lead"),
+        ("s/worker-3", "worker"),
+    ]);
+    let approval =
+        SessionApproval::from_event("s/worker-3", &exec_approval_event("c1", &["ls"])).unwrap();
+
+    // Clients may always decide.
+    assert!(may_decide_approval(&agents, None, None, &approval));
+    // The supervisor role may decide; other agents may not.
+    assert!(may_decide_approval(&agents, Some("lead"), Some("s/lead-2"), &approval));
+    assert!(!may_decide_approval(&agents, Some("lead"), Some("s/main"), &approval));
+    // No supervisor configured: agents may not decide.
+    assert!(!may_decide_approval(&agents, None, Some("s/lead-2"), &approval));
+    // A supervisor may not approve its own request.
+    let own =
+        SessionApproval::from_event("s/lead-2", &exec_approval_event("c2", &["ls"])).unwrap();
+    assert!(!may_decide_approval(&agents, Some("lead"), Some("s/lead-2"), &own));
+}
+
+#[test]
+fn approval_supervisor_tool_spec_requires_decision_fields() {
+    use crate::tools::approval_supervisor_tool_specs;
+
+    let specs = approval_supervisor_tool_specs();
+    assert_eq!(specs.len(), 1);
+    assert_eq!(specs[0].name, "decide_approval");
+    assert_eq!(
+        specs[0].input_schema["required"],
+        serde_json::json!(["agent_id", "call_id", "approve"])
+    );
+}
+
+#[test]
+fn crew_supervisor_sets_session_approval_supervisor() {
+    use crate::config_loader::{apply_crew_type, validate_crew_type};
+    use crate::types::SessionWorkflowInput;
+
+    let mut crew = pipeline_crew();
+    crew.supervisor = Some("reviewer".to_string());
+    validate_crew_type(&crew).unwrap();
+
+    let mut base = SessionWorkflowInput {
+        user_message: String::new(),
+        model: "gpt-4o".to_string(),
+        instructions: String::new(),
+        approval_policy: codex_protocol::protocol::AskForApproval::OnRequest,
+        web_search_mode: None,
+        reasoning_effort: None,
+        reasoning_summary: codex_protocol::config_types::ReasoningSummary::Auto,
+        personality: None,
+        developer_instructions: None,
+        model_provider: None,
+        crew_agents: BTreeMap::new(),
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
+        continued_state: None,
+        main_agent_state: None,
+        max_iterations: None,
+        output_schema: None,
+    };
+    let inputs = BTreeMap::from([("feature".to_string(), "dark mode".to_string())]);
+    apply_crew_type(&crew, &inputs, &mut base).unwrap();
+    assert_eq!(base.approval_supervisor.as_deref(), Some("reviewer"));
+
+    // The main agent supervises with the "default" role.
+    crew.supervisor = Some(crew.main_agent.clone());
+    apply_crew_type(&crew, &inputs, &mut base).unwrap();
+    assert_eq!(base.approval_supervisor.as_deref(), Some("default"));
+
+    crew.supervisor = Some("nobody".to_string());
+    assert!(validate_crew_type(&crew).unwrap_err().contains("supervisor"));
+}
+
+// ---------------------------------------------------------------------------
+// Session event aggregation tests
+// ---------------------------------------------------------------------------
+
+fn turn_started_event() -> codex_protocol::protocol::Event {
+    codex_protocol::protocol::Event {
+        id: String::new(),
+        msg: EventMsg::TurnStarted(codex_protocol::protocol::TurnStartedEvent {
+            turn_id: "turn-0".to_string(),
+            model_context_window: None,
+            collaboration_mode_kind: Default::default(),
+        }),
+    }
+}
+
+fn turn_complete_event(message: &str) -> codex_protocol::protocol::Event {
+    codex_protocol::protocol::Event {
+        id: String::new(),
+        msg: EventMsg::TurnComplete(codex_protocol::protocol::TurnCompleteEvent {
+            turn_id: "turn-0".to_string(),
+            last_agent_message: Some(message.to_string()),
+        }),
+    }
+}
+
+#[test]
+fn session_event_tag_assigns_per_agent_watermarks() {
+    use crate::types::SessionEvent;
+
+    // Two new events ending at watermark 7 sit at 6 and 7.
+    let tagged = SessionEvent::tag(
+        "s/worker-2",
+        "worker",
+        vec![turn_started_event(), turn_complete_event("done")],
+        7,
+    );
+    let watermarks: Vec<usize> = tagged.iter().map(|e| e.watermark).collect();
+    assert_eq!(watermarks, [6, 7]);
+    assert!(tagged.iter().all(|e| e.agent_id == "s/worker-2" && e.role == "worker"));
+}
+
+#[test]
+fn session_event_timeline_lines_are_agent_tagged() {
+    use crate::types::SessionEvent;
+
+    let tagged = SessionEvent::tag(
+        "s/worker-2",
+        "worker",
+        vec![
+            turn_started_event(),
+            codex_protocol::protocol::Event {
+                id: String::new(),
+                msg: exec_approval_event("c1", &["cargo", "test"]),
+            },
+            turn_complete_event("All tests pass.\nDetails follow."),
+        ],
+        3,
+    );
+    let lines: Vec<String> = tagged.iter().filter_map(|e| e.timeline_line()).collect();
+    assert_eq!(
+        lines,
+        [
+            "[worker-2] turn started",
+            "[worker-2] awaits approval to run: cargo test",
+            "[worker-2] turn complete: All tests pass.",
+        ]
+    );
+}
+
+#[test]
+fn session_timeline_tracks_status_and_ignores_replays() {
+    use crate::types::{AgentActivity, SessionEvent, SessionTimeline};
+
+    let mut timeline = SessionTimeline::new();
+    let started = SessionEvent::tag("s/main", "default", vec![turn_started_event()], 1);
+    assert!(timeline.record(&started[0]));
+    let approval = SessionEvent::tag(
+        "s/worker-2",
+        "worker",
+        vec![codex_protocol::protocol::Event {
+            id: String::new(),
+            msg: exec_approval_event("c1", &["ls"]),
+        }],
+        4,
+    );
+    assert!(timeline.record(&approval[0]));
+
+    let panel = timeline.status_panel();
+    assert_eq!(panel.len(), 2);
+    assert_eq!(panel[0].activity, AgentActivity::Working);
+    assert_eq!(panel[1].activity, AgentActivity::AwaitingApproval);
+    assert_eq!(panel[1].last_update.as_deref(), Some("awaits approval to run: ls"));
+
+    // A replayed event does not move the watermark back or change status.
+    assert!(!timeline.record(&started[0]));
+    assert_eq!(timeline.watermark("s/worker-2"), 4);
+    assert_eq!(timeline.watermark("s/other"), 0);
+    assert_eq!(
+        timeline.watermarks(),
+        BTreeMap::from([("s/main".to_string(), 1), ("s/worker-2".to_string(), 4)])
+    );
+
+    let done = SessionEvent::tag("s/main", "default", vec![turn_complete_event("ok")], 2);
+    timeline.record(&done[0]);
+    assert_eq!(timeline.status_panel()[0].activity, AgentActivity::Idle);
+
+    let shutdown = SessionEvent::tag(
+        "s/main",
+        "default",
+        vec![codex_protocol::protocol::Event {
+            id: String::new(),
+            msg: EventMsg::ShutdownComplete,
+        }],
+        3,
+    );
+    timeline.record(&shutdown[0]);
+    assert!(timeline.has_exited("s/main"));
+
+    let rendered = timeline.render_status_panel();
+    assert!(rendered.starts_with("AGENT"));
+    assert!(rendered.contains("worker-2"));
+    assert!(rendered.contains("awaiting approval"));
+    assert!(rendered.contains("exited"));
+}
+
+#[test]
+fn preview_text_takes_first_line_and_truncates() {
+    use crate::types::preview_text;
+
+    assert_eq!(preview_text("\n  first line  \nsecond"), "first line");
+    let long = "x".repeat(200);
+    let preview = preview_text(&long);
+    assert_eq!(preview.chars().count(), 120);
+    assert!(preview.ends_with('…'));
+}
+
+// ---------------------------------------------------------------------------
+// Crew schedule tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn parse_interval_accepts_units_and_combinations() {
+    use crate::schedules::parse_interval;
+    use std::time::Duration;
+
+    assert_eq!(parse_interval("90s").unwrap(), Duration::from_secs(90));
+    assert_eq!(parse_interval("15m").unwrap(), Duration::from_secs(15 * 60));
+    assert_eq!(parse_interval("1d").unwrap(), Duration::from_secs(24 * 3600));
+    assert_eq!(parse_interval("1h30m").unwrap(), Duration::from_secs(5400));
+
+    for bad in ["", "10", "0m", "5w", "h", "1h30"] {
+        assert!(parse_interval(bad).is_err(), "{bad:?} should be rejected");
+    }
+}
+
+#[test]
+fn parse_interval_rejects_overflowing_intervals() {
+    use crate::schedules::parse_interval;
+
+    // A part, or the sum of parts, that overflows u64 seconds is rejected.
+    let max = u64::MAX;
+    for huge in [format!("{max}d"), format!("{max}s1s"), format!("{}h1d", max / 3600)] {
+        let err = parse_interval(&huge).unwrap_err();
+        assert!(err.contains("invalid interval"), "{huge}: {err}");
+    }
+    assert!(parse_interval(&format!("{max}s")).is_ok());
+}
+
+#[test]
+fn crew_schedule_spec_requires_cron_or_interval() {
+    use crate::schedules::CrewScheduleSpec;
+    use std::time::Duration;
+
+    let spec = CrewScheduleSpec::from_flags(vec!["0 6 * * *".to_string()], &["12h".to_string()])
+        .unwrap();
+    assert_eq!(spec.cron, ["0 6 * * *"]);
+    assert_eq!(spec.every, [Duration::from_secs(12 * 3600)]);
+
+    assert!(CrewScheduleSpec::from_flags(Vec::new(), &[]).is_err());
+    assert!(CrewScheduleSpec::from_flags(Vec::new(), &["soon".to_string()]).is_err());
+}
+
+#[test]
+fn scheduled_session_input_registers_with_harness() {
+    use crate::schedules::{schedule_workflow_id, scheduled_session_input};
+    use crate::types::SessionWorkflowInput;
+
+    let base: SessionWorkflowInput = serde_json::from_str(
+        r#"{"user_message":"audit","model":"gpt-4o","instructions":""}"#,
+    )
+    .unwrap();
+    let input = scheduled_session_input(base, "nightly-audit", "dep-audit", "codex-harness-me");
+    let registration = input.harness_registration.expect("registration");
+    assert_eq!(registration.harness_id, "codex-harness-me");
+    assert_eq!(registration.crew_type.as_deref(), Some("dep-audit"));
+    assert_eq!(registration.schedule_id.as_deref(), Some("nightly-audit"));
+    assert_eq!(input.user_message, "audit");
+
+    assert_eq!(schedule_workflow_id("nightly-audit"), "codex-session-nightly-audit");
+}
+
+#[test]
+fn session_entry_schedule_id_is_optional() {
+    let json = r#"{"session_id":"s1","name":null,"model":"gpt-4o","created_at_millis":0,"status":"Running"}"#;
+    let entry: SessionEntry = serde_json::from_str(json).unwrap();
+    assert!(entry.schedule_id.is_none());
+    assert!(!serde_json::to_string(&entry).unwrap().contains("schedule_id"));
+}
+
+// ---------------------------------------------------------------------------
+// Crew results tests
+// ---------------------------------------------------------------------------
+
+#[test]
+fn crew_output_spec_extracts_prefix_and_json_field() {
+    use crate::types::CrewOutputSpec;
+
+    let whole = CrewOutputSpec::default();
+    assert_eq!(whole.extract("  Done.\n").as_deref(), Some("Done."));
+    assert_eq!(whole.extract("   "), None);
+
+    let verdict = CrewOutputSpec {
+        line_prefix: Some("VERDICT:".to_string()),
+        ..Default::default()
+    };
+    let message = "Reviewed 3 files.\n  VERDICT: approved \nThanks";
+    assert_eq!(verdict.extract(message).as_deref(), Some("approved"));
+    assert_eq!(verdict.extract("no verdict here"), None);
+
+    let summary = CrewOutputSpec {
+        json_pointer: Some("/report/summary".to_string()),
+        ..Default::default()
+    };
+    let fenced = "Result:\n```json\n{\"report\": {\"summary\": \"all good\", \"count\": 2}}\n```";
+    assert_eq!(summary.extract(fenced).as_deref(), Some("all good"));
+    let count = CrewOutputSpec {
+        json_pointer: Some("/report/count".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(count.extract(fenced).as_deref(), Some("2"));
+    assert_eq!(summary.extract("not json"), None);
+}
+
+#[test]
+fn crew_results_spec_reads_agents_and_stages() {
+    use crate::types::{AgentRecord, CrewOutputSpec, CrewResultsSpec, PipelineRun};
+
+    let mut main = AgentRecord::running("s/main", "default");
+    main.last_agent_message = Some("Summary: shipped".to_string());
+    let mut first = AgentRecord::running("s/reviewer-1", "reviewer");
+    first.last_agent_message = Some("VERDICT: rejected".to_string());
+    let mut second = AgentRecord::running("s/reviewer-2", "reviewer");
+    second.last_agent_message = Some("VERDICT: approved".to_string());
+    let agents = vec![main, first, second];
+
+    let mut pipeline = PipelineRun::new(pipeline_crew().stages);
+    pipeline.outputs.insert("stage.plan".to_string(), "1. do it".to_string());
+
+    let spec = CrewResultsSpec {
+        outputs: BTreeMap::from([
+            (
+                "summary".to_string(),
+                CrewOutputSpec {
+                    line_prefix: Some("Summary:".to_string()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "verdict".to_string(),
+                CrewOutputSpec {
+                    agent: Some("reviewer".to_string()),
+                    line_prefix: Some("VERDICT:".to_string()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "plan".to_string(),
+                CrewOutputSpec {
+                    stage: Some("plan".to_string()),
+                    ..Default::default()
+                },
+            ),
+            (
+                "review".to_string(),
+                CrewOutputSpec {
+                    stage: Some("review".to_string()),
+                    ..Default::default()
+                },
+            ),
+        ]),
+        artifacts: Vec::new(),
+    };
+
+    let (outputs, missing) = spec.extract_outputs(&agents, Some(&pipeline));
+    assert_eq!(outputs["summary"], "shipped");
+    // The most recent agent of the role wins.
+    assert_eq!(outputs["verdict"], "approved");
+    assert_eq!(outputs["plan"], "1. do it");
+    assert_eq!(missing, ["review"]);
+}
+
+#[test]
+fn validate_crew_type_checks_outputs_and_artifacts() {
+    use crate::config_loader::validate_crew_type;
+    use crate::types::CrewOutputSpec;
+
+    let mut crew = pipeline_crew();
+    crew.outputs.insert(
+        "verdict".to_string(),
+        CrewOutputSpec {
+            agent: Some("reviewer".to_string()),
+            ..Default::default()
+        },
+    );
+    crew.outputs.insert(
+        "plan".to_string(),
+        CrewOutputSpec {
+            stage: Some("plan".to_string()),
+            ..Default::default()
+        },
+    );
+    crew.artifacts = vec!["report.md".to_string(), "out/".to_string()];
+    validate_crew_type(&crew).expect("valid outputs");
+
+    let mut bad = crew.clone();
+    bad.outputs.get_mut("plan").unwrap().stage = Some("deploy".to_string());
+    assert!(validate_crew_type(&bad).unwrap_err().contains("unknown stage"));
+
+    let mut bad = crew.clone();
+    bad.outputs.get_mut("verdict").unwrap().agent = Some("nobody".to_string());
+    assert!(validate_crew_type(&bad).unwrap_err().contains("unknown agent"));
+
+    let mut bad = crew.clone();
+    bad.outputs.get_mut("verdict").unwrap().stage = Some("plan".to_string());
+    assert!(validate_crew_type(&bad).unwrap_err().contains("both"));
+
+    for path in ["/etc/passwd", "../secret", ""] {
+        let mut bad = crew.clone();
+        bad.artifacts = vec![path.to_string()];
+        assert!(validate_crew_type(&bad).is_err(), "{path:?} should be rejected");
+    }
+}
+
+#[test]
+fn apply_crew_type_sets_crew_results() {
+    use crate::config_loader::apply_crew_type;
+    use crate::types::{CrewOutputSpec, SessionWorkflowInput};
+
+    let mut crew: CrewType = toml::from_str(
+        r#"
+name = "review"
+description = "Review"
+main_agent = "lead"
+artifacts = ["report.md"]
+
+[outputs.summary]
+agent = "lead"
+
+[outputs.verdict]
+agent = "reviewer"
+line_prefix = "VERDICT:"
+"#,
+    )
+    .unwrap();
+    let mut base: SessionWorkflowInput = serde_json::from_str(
+        r#"{"user_message":"","model":"gpt-4o","instructions":""}"#,
+    )
+    .unwrap();
+    apply_crew_type(&crew, &BTreeMap::new(), &mut base).unwrap();
+
+    let results = base.crew_results.expect("crew results");
+    assert_eq!(results.artifacts, ["report.md"]);
+    // Outputs of the main agent carry no agent name.
+    assert_eq!(results.outputs["summary"], CrewOutputSpec::default());
+    assert_eq!(results.outputs["verdict"].agent.as_deref(), Some("reviewer"));
+
+    // Crews without outputs or artifacts report no results.
+    crew.outputs.clear();
+    crew.artifacts.clear();
+    let mut base: SessionWorkflowInput = serde_json::from_str(
+        r#"{"user_message":"","model":"gpt-4o","instructions":""}"#,
+    )
+    .unwrap();
+    apply_crew_type(&crew, &BTreeMap::new(), &mut base).unwrap();
+    assert!(base.crew_results.is_none());
+}
+
+#[test]
+fn collect_artifacts_reads_files_and_directories() {
+    use crate::activities::collect_artifacts_in;
+    use crate::types::MAX_ARTIFACT_BYTES;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::write(root.join("report.md"), "# Report\n").unwrap();
+    std::fs::create_dir_all(root.join("out/nested")).unwrap();
+    std::fs::write(root.join("out/b.txt"), "b").unwrap();
+    std::fs::write(root.join("out/nested/a.txt"), "a").unwrap();
+    std::fs::write(root.join("out/.hidden"), "skip").unwrap();
+    std::fs::write(root.join("out/image.bin"), [0xff, 0xfe, 0x00]).unwrap();
+    std::fs::write(root.join("big.log"), "x".repeat(MAX_ARTIFACT_BYTES + 10)).unwrap();
+
+    let paths = ["report.md", "out", "big.log", "missing.txt", "../escape", "report.md"]
+        .map(String::from);
+    let output = collect_artifacts_in(root, &paths);
+
+    let collected: Vec<&str> = output.artifacts.iter().map(|a| a.path.as_str()).collect();
+    assert_eq!(
+        collected,
+        ["report.md", "out/b.txt", "out/image.bin", "out/nested/a.txt", "big.log"]
+    );
+    assert_eq!(output.artifacts[0].content, "# Report\n");
+    let binary = &output.artifacts[2];
+    assert!(binary.binary && binary.content.is_empty());
+    assert_eq!(binary.size, 3);
+    let big = &output.artifacts[4];
+    assert!(big.truncated);
+    assert_eq!(big.content.len(), MAX_ARTIFACT_BYTES);
+    assert_eq!(big.size, (MAX_ARTIFACT_BYTES + 10) as u64);
+
+    assert_eq!(output.errors.len(), 2, "{:?}", output.errors);
+    assert!(output.errors[0].starts_with("missing.txt"));
+    assert!(output.errors[1].contains("outside the workspace"));
+}
+
+#[cfg(unix)]
+#[test]
+fn collect_artifacts_skips_symbolic_links() {
+    use crate::activities::collect_artifacts_in;
+
+    let outside = tempfile::tempdir().unwrap();
+    std::fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    std::fs::create_dir_all(root.join("out")).unwrap();
+    std::fs::write(root.join("out/a.txt"), "a").unwrap();
+    std::os::unix::fs::symlink(outside.path().join("secret.txt"), root.join("out/leak.txt"))
+        .unwrap();
+    std::os::unix::fs::symlink(root.join("out"), root.join("out/loop")).unwrap();
+    std::os::unix::fs::symlink(outside.path(), root.join("linked")).unwrap();
+
+    let paths = ["out", "linked", "linked/secret.txt"].map(String::from);
+    let output = collect_artifacts_in(root, &paths);
+
+    let collected: Vec<&str> = output.artifacts.iter().map(|a| a.path.as_str()).collect();
+    assert_eq!(collected, ["out/a.txt"]);
+    assert_eq!(output.errors.len(), 2, "{:?}", output.errors);
+    assert!(output.errors.iter().all(|e| e.contains("outside the workspace")));
+}
+
+#[test]
+fn crew_results_summary_drops_artifact_contents() {
+    use crate::types::{CrewArtifact, CrewResults, MAX_HARNESS_OUTPUT_BYTES};
+
+    let results = CrewResults {
+        outputs: BTreeMap::from([
+            ("verdict".to_string(), "approved".to_string()),
+            ("report".to_string(), "é".repeat(MAX_HARNESS_OUTPUT_BYTES)),
+        ]),
+        artifacts: vec![CrewArtifact {
+            path: "report.md".to_string(),
+            size: 9,
+            content: "# Report\n".to_string(),
+            truncated: false,
+            binary: false,
+        }],
+        ..Default::default()
+    };
+    let summary = results.summary();
+    assert_eq!(summary.outputs["verdict"], "approved");
+    assert_eq!(summary.outputs["report"].len(), MAX_HARNESS_OUTPUT_BYTES);
+    assert_eq!(summary.truncated_outputs, ["report"]);
+    assert_eq!(summary.artifacts[0].path, "report.md");
+    assert_eq!(summary.artifacts[0].size, 9);
+    assert!(summary.artifacts[0].content.is_empty());
+}
+
+#[test]
+fn harness_state_results_default_to_empty() {
+    use crate::types::CrewResults;
+
+    let back: HarnessState = serde_json::from_str(r#"{"sessions":[]}"#).unwrap();
+    assert!(back.results.is_empty());
+
+    let results = CrewResults {
+        outputs: BTreeMap::from([("verdict".to_string(), "approved".to_string())]),
+        ..Default::default()
+    };
+    let state = HarnessState {
+        sessions: Vec::new(),
+        credentials_available: None,
+        results: BTreeMap::from([("s1".to_string(), results.clone())]),
+        retention: Default::default(),
+        idle_shutdown_sent: Default::default(),
+    };
+    let back: HarnessState = serde_json::from_str(&serde_json::to_string(&state).unwrap()).unwrap();
+    assert_eq!(back.results["s1"], results);
+}
+
+// ---------------------------------------------------------------------------
+// Session status reporting tests
+// ---------------------------------------------------------------------------
+
+fn reported_entry(session_id: &str, created_at_millis: u64) -> SessionEntry {
+    SessionEntry {
+        session_id: session_id.to_string(),
+        name: None,
+        model: "gpt-4o".to_string(),
+        created_at_millis,
+        status: SessionStatus::Running,
+        crew_type: None,
+        schedule_id: None,
+        last_activity_millis: created_at_millis,
+        last_message_preview: None,
+        pinned: false,
+        archived_at_millis: None,
+        title: None,
+        tags: Default::default(),
+        cwd: None,
+        repository: None,
+        branch: None,
+    }
+}
+
+#[test]
+fn session_entry_activity_fields_default_for_old_entries() {
+    let json = r#"{"session_id":"s1","name":null,"model":"gpt-4o","created_at_millis":500,"status":"Running"}"#;
+    let entry: SessionEntry = serde_json::from_str(json).unwrap();
+    assert_eq!(entry.last_activity_millis, 0);
+    assert!(entry.last_message_preview.is_none());
+    assert_eq!(entry.last_active_millis(), 500);
+    assert!(!serde_json::to_string(&entry).unwrap().contains("last_message_preview"));
+}
+
+#[test]
+fn session_entry_merge_keeps_known_fields() {
+    let mut existing = reported_entry("s1", 1_000);
+    existing.name = Some("[review]".to_string());
+    existing.crew_type = Some("review".to_string());
+    existing.last_activity_millis = 5_000;
+    existing.last_message_preview = Some("Looks good".to_string());
+
+    // A later re-registration without a name must not wipe what is known.
+    let mut update = reported_entry("s1", 2_000);
+    update.status = SessionStatus::Completed;
+    existing.merge(update);
+
+    assert_eq!(existing.name.as_deref(), Some("[review]"));
+    assert_eq!(existing.crew_type.as_deref(), Some("review"));
+    assert_eq!(existing.status, SessionStatus::Completed);
+    assert_eq!(existing.created_at_millis, 1_000);
+    assert_eq!(existing.last_activity_millis, 5_000);
+    assert_eq!(existing.last_message_preview.as_deref(), Some("Looks good"));
+}
+
+#[test]
+fn session_entry_merge_takes_new_values() {
+    let mut existing = reported_entry("s1", 0);
+    let mut update = reported_entry("s1", 3_000);
+    update.name = Some("Fix the build".to_string());
+    update.last_message_preview = Some("Done".to_string());
+    existing.merge(update);
+
+    assert_eq!(existing.name.as_deref(), Some("Fix the build"));
+    assert_eq!(existing.created_at_millis, 3_000, "unknown creation time is replaced");
+    assert_eq!(existing.last_active_millis(), 3_000);
+    assert_eq!(existing.last_message_preview.as_deref(), Some("Done"));
+}
+
+#[test]
+fn session_activity_roundtrips() {
+    use crate::types::SessionActivity;
+
+    let activity = SessionActivity {
+        session_id: "codex-session-1".to_string(),
+        at_millis: 42,
+        preview: Some("All tests pass".to_string()),
+    };
+    let json = serde_json::to_string(&activity).unwrap();
+    let back: SessionActivity = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, activity);
+
+    let bare: SessionActivity =
+        serde_json::from_str(r#"{"session_id":"s","at_millis":1}"#).unwrap();
+    assert!(bare.preview.is_none());
+}
+
+#[test]
+fn harness_workflow_id_names_the_users_harness() {
+    use crate::harness::harness_workflow_id;
+
+    let id = harness_workflow_id();
+    let user = id.strip_prefix("codex-harness-").expect(&id);
+    let expected = std::env::var("USER").unwrap_or_else(|_| "default".to_string());
+    assert_eq!(user, expected);
+}
+
+#[test]
+fn session_agents_query_lists_running_agents_of_a_terminated_session() {
+    use crate::activities::session_agents_query;
+
+    // The agents of `codex-session-1` outlive its termination; the query
+    // finds them, but not those of `codex-session-10`.
+    assert_eq!(
+        session_agents_query("codex-session-1"),
+        "WorkflowId STARTS_WITH \"codex-session-1/\" AND ExecutionStatus = \"Running\""
+    );
+    assert_eq!(
+        session_agents_query("odd\"id"),
+        "WorkflowId STARTS_WITH \"odd\\\"id/\" AND ExecutionStatus = \"Running\""
+    );
+}
+
+#[test]
+fn closed_session_status_maps_workflow_status() {
+    use crate::activities::closed_session_status;
+    use temporalio_common::protos::temporal::api::enums::v1::WorkflowExecutionStatus;
+
+    assert_eq!(closed_session_status(WorkflowExecutionStatus::Running), None);
+    assert_eq!(closed_session_status(WorkflowExecutionStatus::ContinuedAsNew), None);
+    assert_eq!(
+        closed_session_status(WorkflowExecutionStatus::Completed),
+        Some(SessionStatus::Completed)
+    );
+    assert_eq!(
+        closed_session_status(WorkflowExecutionStatus::Terminated),
+        Some(SessionStatus::Terminated)
+    );
+    for status in [
+        WorkflowExecutionStatus::Failed,
+        WorkflowExecutionStatus::Canceled,
+        WorkflowExecutionStatus::TimedOut,
+    ] {
+        assert_eq!(
+            closed_session_status(status),
+            Some(SessionStatus::Failed),
+            "{status:?} should count as failed"
+        );
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Session retention tests
+// ---------------------------------------------------------------------------
 
-    let record = AgentRecord {
-        agent_id: "session/explorer-1".to_string(),
-        workflow_id: "session/explorer-1".to_string(),
-        role: "explorer".to_string(),
-        status: AgentLifecycle::Completed,
-    };
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
 
-    let json = serde_json::to_string(&record).unwrap();
-    let back: AgentRecord = serde_json::from_str(&json).unwrap();
+fn closed_entry(session_id: &str, last_active_millis: u64) -> SessionEntry {
+    let mut entry = reported_entry(session_id, last_active_millis);
+    entry.status = SessionStatus::Completed;
+    entry
+}
 
-    assert_eq!(back.agent_id, "session/explorer-1");
-    assert_eq!(back.role, "explorer");
-    assert_eq!(back.status, AgentLifecycle::Completed);
+#[test]
+fn retention_policy_defaults_only_cap_archived_sessions() {
+    use crate::types::{DEFAULT_MAX_ARCHIVED_SESSIONS, IdleAction, RetentionPolicy};
+
+    let policy: RetentionPolicy = serde_json::from_str("{}").unwrap();
+    assert_eq!(policy, RetentionPolicy::default());
+    assert_eq!(policy.max_archived, Some(DEFAULT_MAX_ARCHIVED_SESSIONS));
+    assert!(policy.keep_pinned);
+    assert!(policy.max_sessions.is_none() && policy.max_age_secs.is_none());
+    assert_eq!(policy.idle_action, IdleAction::Shutdown);
+
+    let mut sessions = vec![closed_entry("s1", 0), reported_entry("s2", 0)];
+    let outcome = policy.apply(&mut sessions, 1000 * DAY_MILLIS);
+    assert_eq!(outcome, Default::default());
+    assert!(sessions.iter().all(|s| !s.is_archived()));
 }
 
 #[test]
-fn agent_summary_roundtrips() {
-    use crate::types::{AgentSummary, AgentLifecycle};
+fn retention_archives_closed_sessions_past_max_age() {
+    use crate::types::RetentionPolicy;
 
-    let summary = AgentSummary {
-        agent_id: "session/main".to_string(),
-        role: "default".to_string(),
-        status: AgentLifecycle::Running,
-        iterations: 5,
-        token_usage: None,
+    let policy = RetentionPolicy {
+        max_age_secs: Some(7 * 24 * 60 * 60),
+        ..Default::default()
     };
-
-    let json = serde_json::to_string(&summary).unwrap();
-    let back: AgentSummary = serde_json::from_str(&json).unwrap();
-
-    assert_eq!(back.agent_id, "session/main");
-    assert_eq!(back.iterations, 5);
-    assert_eq!(back.status, AgentLifecycle::Running);
+    let now = 30 * DAY_MILLIS;
+    let mut pinned = closed_entry("pinned", DAY_MILLIS);
+    pinned.pinned = true;
+    let mut sessions = vec![
+        closed_entry("old", DAY_MILLIS),
+        closed_entry("recent", now - DAY_MILLIS),
+        reported_entry("running", DAY_MILLIS),
+        pinned,
+    ];
+
+    let outcome = policy.apply(&mut sessions, now);
+    assert_eq!(outcome.archived, vec!["old".to_string()]);
+    assert_eq!(sessions[0].archived_at_millis, Some(now));
+    assert!(sessions[1..].iter().all(|s| !s.is_archived()), "running/pinned/recent are kept");
 }
 
 #[test]
-fn resolve_role_config_input_roundtrips() {
-    use crate::types::ResolveRoleConfigInput;
+fn retention_archives_oldest_sessions_beyond_max_sessions() {
+    use crate::types::RetentionPolicy;
 
-    let input = ResolveRoleConfigInput {
-        config_toml: "model = \"gpt-4o\"".to_string(),
-        cwd: "/home/user/project".to_string(),
-        role_name: "explorer".to_string(),
+    let policy = RetentionPolicy {
+        max_sessions: Some(2),
+        ..Default::default()
     };
-
-    let json = serde_json::to_string(&input).unwrap();
-    let back: ResolveRoleConfigInput = serde_json::from_str(&json).unwrap();
-
-    assert_eq!(back.role_name, "explorer");
-    assert_eq!(back.cwd, "/home/user/project");
+    let mut sessions = vec![
+        closed_entry("newest", 300),
+        closed_entry("oldest", 100),
+        reported_entry("running", 50),
+        closed_entry("middle", 200),
+    ];
+
+    let outcome = policy.apply(&mut sessions, 1_000);
+    assert_eq!(outcome.archived, vec!["oldest".to_string(), "middle".to_string()]);
+    let active: Vec<&str> = sessions
+        .iter()
+        .filter(|s| !s.is_archived())
+        .map(|s| s.session_id.as_str())
+        .collect();
+    assert_eq!(active, vec!["newest", "running"]);
 }
 
 #[test]
-fn resolve_role_config_output_roundtrips() {
-    use crate::types::ResolveRoleConfigOutput;
-    use codex_protocol::openai_models::ReasoningEffort;
+fn retention_purges_oldest_archived_sessions() {
+    use crate::types::RetentionPolicy;
 
-    let output = ResolveRoleConfigOutput {
-        config_toml: "model = \"gpt-5\"".to_string(),
-        model: Some("gpt-5".to_string()),
-        instructions: Some("Be an explorer.".to_string()),
-        reasoning_effort: Some(ReasoningEffort::Medium),
-        reasoning_summary: codex_protocol::config_types::ReasoningSummary::Auto,
-        personality: None,
-        developer_instructions: None,
-        model_provider: None,
+    let policy = RetentionPolicy {
+        max_archived: Some(1),
+        ..Default::default()
     };
+    let mut first = closed_entry("first", 0);
+    first.archived_at_millis = Some(100);
+    let mut second = closed_entry("second", 0);
+    second.archived_at_millis = Some(200);
+    let mut sessions = vec![second, first, closed_entry("active", 0)];
+
+    let outcome = policy.apply(&mut sessions, 1_000);
+    assert_eq!(outcome.purged, vec!["first".to_string()]);
+    let ids: Vec<&str> = sessions.iter().map(|s| s.session_id.as_str()).collect();
+    assert_eq!(ids, vec!["second", "active"]);
+}
 
-    let json = serde_json::to_string(&output).unwrap();
-    let back: ResolveRoleConfigOutput = serde_json::from_str(&json).unwrap();
+#[test]
+fn retention_reports_idle_running_sessions() {
+    use crate::types::RetentionPolicy;
 
-    assert_eq!(back.model.as_deref(), Some("gpt-5"));
-    assert_eq!(back.reasoning_effort, Some(ReasoningEffort::Medium));
+    let policy = RetentionPolicy {
+        idle_after_secs: Some(60 * 60),
+        ..Default::default()
+    };
+    let now = DAY_MILLIS;
+    let mut busy = reported_entry("busy", 0);
+    busy.last_activity_millis = now - 60_000;
+    let mut pinned = reported_entry("pinned", 0);
+    pinned.pinned = true;
+    let mut sessions = vec![reported_entry("idle", 0), busy, pinned, closed_entry("done", 0)];
+
+    let outcome = policy.apply(&mut sessions, now);
+    assert_eq!(outcome.idle, vec!["idle".to_string()]);
+
+    let unprotected = RetentionPolicy {
+        keep_pinned: false,
+        ..policy
+    };
+    let outcome = unprotected.apply(&mut sessions, now);
+    assert_eq!(outcome.idle, vec!["idle".to_string(), "pinned".to_string()]);
 }
 
 #[test]
-fn agent_lifecycle_serde() {
-    use crate::types::AgentLifecycle;
-
-    for status in [AgentLifecycle::Running, AgentLifecycle::Completed, AgentLifecycle::Failed] {
-        let json = serde_json::to_string(&status).unwrap();
-        let back: AgentLifecycle = serde_json::from_str(&json).unwrap();
-        assert_eq!(back, status);
-    }
+fn retention_policy_set_option_parses_flags() {
+    use crate::types::{IdleAction, RetentionPolicy};
+
+    let mut policy = RetentionPolicy::default();
+    policy.set_option("max-sessions", "50").unwrap();
+    policy.set_option("max-age", "30d").unwrap();
+    policy.set_option("idle-after", "2h").unwrap();
+    policy.set_option("idle-action", "terminate").unwrap();
+    policy.set_option("keep-pinned", "false").unwrap();
+    policy.set_option("max-archived", "off").unwrap();
+    assert_eq!(policy.max_sessions, Some(50));
+    assert_eq!(policy.max_age_secs, Some(30 * 24 * 60 * 60));
+    assert_eq!(policy.idle_after_secs, Some(2 * 60 * 60));
+    assert_eq!(policy.idle_action, IdleAction::Terminate);
+    assert!(!policy.keep_pinned);
+    assert!(policy.max_archived.is_none());
+
+    assert!(policy.set_option("max-sessions", "many").is_err());
+    assert!(policy.set_option("idle-action", "pause").is_err());
+    assert!(policy.set_option("max-size", "1").is_err());
 }
 
 #[test]
-fn session_workflow_output_roundtrips() {
-    use crate::types::{SessionWorkflowOutput, AgentSummary, AgentLifecycle};
+fn session_entry_merge_keeps_pin_and_unarchives() {
+    let mut existing = closed_entry("s1", 100);
+    existing.pinned = true;
+    existing.archived_at_millis = Some(500);
+
+    existing.merge(reported_entry("s1", 100));
+    assert!(existing.pinned);
+    assert!(!existing.is_archived(), "registering again brings the session back");
+}
 
-    let output = SessionWorkflowOutput {
-        agents: vec![
-            AgentSummary {
-                agent_id: "session/main".to_string(),
-                role: "default".to_string(),
-                status: AgentLifecycle::Completed,
-                iterations: 10,
-                token_usage: None,
-            },
-            AgentSummary {
-                agent_id: "session/explorer-1".to_string(),
-                role: "explorer".to_string(),
-                status: AgentLifecycle::Completed,
-                iterations: 3,
-                token_usage: None,
-            },
-        ],
-    };
+// ---------------------------------------------------------------------------
+// Session search, tag and rename tests
+// ---------------------------------------------------------------------------
 
-    let json = serde_json::to_string(&output).unwrap();
-    let back: SessionWorkflowOutput = serde_json::from_str(&json).unwrap();
+fn tagged_entry(session_id: &str, tags: &[&str]) -> SessionEntry {
+    let mut entry = reported_entry(session_id, 1_000);
+    entry.tags = tags.iter().map(|t| t.to_string()).collect();
+    entry
+}
 
-    assert_eq!(back.agents.len(), 2);
-    assert_eq!(back.agents[0].agent_id, "session/main");
-    assert_eq!(back.agents[1].role, "explorer");
+#[test]
+fn session_edit_renames_tags_and_pins() {
+    use crate::types::SessionEdit;
+
+    let mut entry = tagged_entry("s1", &["old"]);
+    entry.name = Some("fix the flaky test".to_string());
+    entry.apply_edit(&SessionEdit {
+        session_id: "s1".to_string(),
+        title: Some("  Flaky test hunt ".to_string()),
+        add_tags: vec!["ci".to_string(), " ".to_string()],
+        remove_tags: vec!["old".to_string()],
+        pinned: Some(true),
+    });
+    assert_eq!(entry.display_name(), Some("Flaky test hunt"));
+    assert_eq!(entry.tags.iter().collect::<Vec<_>>(), vec!["ci"]);
+    assert!(entry.pinned);
+
+    // An empty title falls back to the registered name.
+    entry.apply_edit(&SessionEdit {
+        session_id: "s1".to_string(),
+        title: Some(String::new()),
+        ..Default::default()
+    });
+    assert_eq!(entry.display_name(), Some("fix the flaky test"));
+    assert!(entry.pinned, "an edit without `pinned` keeps the pin");
 }
 
 #[test]
-fn backward_compat_type_aliases_work() {
-    // Verify the type aliases compile and are equivalent.
-    let _input: CodexWorkflowInput = CodexWorkflowInput {
-        user_message: "test".to_string(),
-        model: "gpt-4o".to_string(),
-        instructions: "test".to_string(),
-        approval_policy: Default::default(),
-        web_search_mode: None,
-        reasoning_effort: None,
-        reasoning_summary: codex_protocol::config_types::ReasoningSummary::Auto,
-        personality: None,
-        developer_instructions: None,
-        model_provider: None,
-        continued_state: None,
-        max_iterations: None,
-        role: "default".to_string(),
-        config_toml: None,
-        project_context: None,
-        mcp_tools: std::collections::HashMap::new(),
-        dynamic_tools: Vec::new(),
-    };
+fn session_entry_merge_keeps_title_tags_and_workspace() {
+    let mut existing = tagged_entry("s1", &["ci"]);
+    existing.title = Some("Renamed".to_string());
+    existing.repository = Some("https://github.com/acme/app".to_string());
+    existing.branch = Some("main".to_string());
+
+    existing.merge(reported_entry("s1", 1_000));
+    assert_eq!(existing.title.as_deref(), Some("Renamed"));
+    assert!(existing.tags.contains("ci"));
+    assert_eq!(existing.branch.as_deref(), Some("main"));
+}
 
-    let _output: CodexWorkflowOutput = CodexWorkflowOutput {
-        last_agent_message: None,
-        iterations: 0,
-        token_usage: None,
-    };
+#[test]
+fn session_entry_takes_workspace_from_project_context() {
+    let mut entry = reported_entry("s1", 0);
+    entry.set_workspace(&ProjectContextOutput {
+        cwd: "/work/app".to_string(),
+        user_instructions: None,
+        git_info: Some(codex_protocol::protocol::GitInfo {
+            commit_hash: None,
+            branch: Some("feature/search".to_string()),
+            repository_url: Some("git@github.com:acme/app.git".to_string()),
+        }),
+    });
+    assert_eq!(entry.cwd.as_deref(), Some("/work/app"));
+    assert_eq!(entry.branch.as_deref(), Some("feature/search"));
+    assert_eq!(entry.repository.as_deref(), Some("git@github.com:acme/app.git"));
 }
 
 #[test]
-fn codex_workflow_input_converts_to_session_workflow_input() {
-    use crate::types::SessionWorkflowInput;
+fn session_filter_matches_text_tags_repo_and_status() {
+    use crate::types::SessionFilter;
+
+    let mut entry = tagged_entry("codex-session-1", &["ci", "release"]);
+    entry.name = Some("Bump the version".to_string());
+    entry.last_message_preview = Some("Opened PR #42".to_string());
+    entry.repository = Some("https://github.com/acme/App".to_string());
+
+    let matches = |filter: SessionFilter| filter.matches(&entry);
+    assert!(matches(SessionFilter::default()));
+    assert!(matches(SessionFilter {
+        text: Some("BUMP".into()),
+        ..Default::default()
+    }));
+    assert!(matches(SessionFilter {
+        text: Some("pr #42".into()),
+        ..Default::default()
+    }));
+    assert!(matches(SessionFilter {
+        text: Some("releas".into()),
+        ..Default::default()
+    }));
+    assert!(!matches(SessionFilter {
+        text: Some("deploy".into()),
+        ..Default::default()
+    }));
+    assert!(matches(SessionFilter {
+        tags: vec!["ci".into(), "release".into()],
+        ..Default::default()
+    }));
+    assert!(!matches(SessionFilter {
+        tags: vec!["rel".into()],
+        ..Default::default()
+    }));
+    assert!(matches(SessionFilter {
+        repo: Some("acme/app".into()),
+        ..Default::default()
+    }));
+    assert!(!matches(SessionFilter {
+        repo: Some("other".into()),
+        ..Default::default()
+    }));
+    assert!(matches(SessionFilter {
+        status: Some(SessionStatus::Running),
+        ..Default::default()
+    }));
+    assert!(!matches(SessionFilter {
+        status: Some(SessionStatus::Failed),
+        ..Default::default()
+    }));
+    assert!(!matches(SessionFilter {
+        archived: true,
+        ..Default::default()
+    }));
+}
 
-    let agent_input = CodexWorkflowInput {
-        user_message: "hello".to_string(),
-        model: "gpt-4o".to_string(),
-        instructions: "be helpful".to_string(),
-        approval_policy: Default::default(),
-        web_search_mode: None,
-        reasoning_effort: None,
-        reasoning_summary: codex_protocol::config_types::ReasoningSummary::Auto,
-        personality: None,
-        developer_instructions: Some("dev note".to_string()),
-        model_provider: None,
-        continued_state: None,
-        max_iterations: None,
-        role: "default".to_string(),
-        config_toml: None,
-        project_context: None,
-        mcp_tools: std::collections::HashMap::new(),
-        dynamic_tools: Vec::new(),
-    };
+#[test]
+fn session_filter_dates_use_last_activity() {
+    use crate::types::SessionFilter;
+
+    let mut filter = SessionFilter::default();
+    let now = 10 * DAY_MILLIS;
+    filter.set_option("since", "2d", now).unwrap();
+    assert_eq!(filter.active_since_millis, Some(8 * DAY_MILLIS));
+
+    let mut recent = reported_entry("recent", 0);
+    recent.last_activity_millis = 9 * DAY_MILLIS;
+    assert!(filter.matches(&recent));
+    assert!(!filter.matches(&reported_entry("stale", DAY_MILLIS)));
+
+    filter.set_option("before", "12h", now).unwrap();
+    assert!(filter.matches(&recent));
+    recent.last_activity_millis = now;
+    assert!(!filter.matches(&recent));
+}
 
-    let session_input: SessionWorkflowInput = agent_input.into();
+#[test]
+fn session_filter_set_option_parses_flags() {
+    use crate::types::SessionFilter;
+
+    let mut filter = SessionFilter::default();
+    filter.set_option("search", "flaky", 0).unwrap();
+    filter.set_option("tag", "ci", 0).unwrap();
+    filter.set_option("tag", "nightly", 0).unwrap();
+    filter.set_option("repo", "acme", 0).unwrap();
+    filter.set_option("status", "Completed", 0).unwrap();
+    assert_eq!(filter.text.as_deref(), Some("flaky"));
+    assert_eq!(filter.tags, vec!["ci".to_string(), "nightly".to_string()]);
+    assert_eq!(filter.repo.as_deref(), Some("acme"));
+    assert_eq!(filter.status, Some(SessionStatus::Completed));
+
+    let mut missing = SessionFilter::default();
+    missing.set_option("status", "missing", 0).unwrap();
+    assert_eq!(missing.status, Some(SessionStatus::Missing));
+    assert!(filter.set_option("status", "paused", 0).is_err());
+    assert!(filter.set_option("since", "yesterday", 0).is_err());
+    assert!(filter.set_option("owner", "me", 0).is_err());
+
+    let json = serde_json::to_string(&filter).unwrap();
+    assert_eq!(serde_json::from_str::<SessionFilter>(&json).unwrap(), filter);
+}
 
-    assert_eq!(session_input.user_message, "hello");
-    assert_eq!(session_input.model, "gpt-4o");
-    assert_eq!(
-        session_input.developer_instructions.as_deref(),
-        Some("dev note")
-    );
-    assert!(session_input.continued_state.is_none());
+#[test]
+fn sort_sessions_for_display_puts_pinned_then_recent_first() {
+    use crate::types::sort_sessions_for_display;
+
+    let mut pinned = reported_entry("pinned", 100);
+    pinned.pinned = true;
+    let mut sessions = vec![
+        reported_entry("old", 200),
+        pinned,
+        reported_entry("new", 300),
+    ];
+    sort_sessions_for_display(&mut sessions);
+    let ids: Vec<&str> = sessions.iter().map(|s| s.session_id.as_str()).collect();
+    assert_eq!(ids, vec!["pinned", "new", "old"]);
 }
 
 // ---------------------------------------------------------------------------
@@ -2600,6 +6064,10 @@ fn apply_crew_type_interpolates_placeholders() {
             m
         },
         approval_policy: None,
+        stages: Vec::new(),
+        supervisor: None,
+        outputs: BTreeMap::new(),
+        artifacts: Vec::new(),
     };
 
     let mut inputs = BTreeMap::new();
@@ -2617,8 +6085,14 @@ fn apply_crew_type_interpolates_placeholders() {
         developer_instructions: None,
         model_provider: None,
         crew_agents: BTreeMap::new(),
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
         continued_state: None,
+        main_agent_state: None,
         max_iterations: None,
+        output_schema: None,
     };
 
     apply_crew_type(&crew, &inputs, &mut base).unwrap();
@@ -2660,6 +6134,10 @@ fn apply_crew_type_rejects_missing_required_input() {
         main_agent: "default".to_string(),
         agents: BTreeMap::new(),
         approval_policy: None,
+        stages: Vec::new(),
+        supervisor: None,
+        outputs: BTreeMap::new(),
+        artifacts: Vec::new(),
     };
 
     let empty_inputs = BTreeMap::new();
@@ -2675,8 +6153,14 @@ fn apply_crew_type_rejects_missing_required_input() {
         developer_instructions: None,
         model_provider: None,
         crew_agents: BTreeMap::new(),
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
         continued_state: None,
+        main_agent_state: None,
         max_iterations: None,
+        output_schema: None,
     };
 
     let err = apply_crew_type(&crew, &empty_inputs, &mut base);
@@ -2710,6 +6194,10 @@ fn apply_crew_type_uses_input_defaults() {
         main_agent: "default".to_string(),
         agents: BTreeMap::new(),
         approval_policy: None,
+        stages: Vec::new(),
+        supervisor: None,
+        outputs: BTreeMap::new(),
+        artifacts: Vec::new(),
     };
 
     let empty_inputs = BTreeMap::new();
@@ -2725,8 +6213,14 @@ fn apply_crew_type_uses_input_defaults() {
         developer_instructions: None,
         model_provider: None,
         crew_agents: BTreeMap::new(),
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
         continued_state: None,
+        main_agent_state: None,
         max_iterations: None,
+        output_schema: None,
     };
 
     apply_crew_type(&crew, &empty_inputs, &mut base).unwrap();
@@ -2808,6 +6302,323 @@ impl Drop for EnvGuard {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Crew pipeline tests
+// ---------------------------------------------------------------------------
+
+const PIPELINE_CREW_TOML: &str = r#"
+name = "feature"
+description = "Plan, implement and review a feature"
+
+[inputs.feature]
+description = "Feature to build"
+required = true
+
+[agents.planner]
+description = "Plans work"
+
+[agents.coder]
+role = "worker"
+
+[agents.reviewer]
+description = "Reviews changes"
+
+[[stages]]
+name = "plan"
+agent = "planner"
+prompt = "Plan: {feature}"
+
+[[stages]]
+name = "implement"
+agent = "coder"
+prompt = "Implement {stage.plan}. Review notes: {stage.review}"
+
+[[stages]]
+name = "review"
+agent = "reviewer"
+prompt = "Review the implementation of {feature}: {stage.implement}"
+repeat = { from = "implement", until = "APPROVED", max_iterations = 2 }
+"#;
+
+fn pipeline_crew() -> CrewType {
+    toml::from_str(PIPELINE_CREW_TOML).expect("parse pipeline crew TOML")
+}
+
+#[test]
+fn crew_type_parses_pipeline_stages() {
+    let crew = pipeline_crew();
+    assert_eq!(crew.stages.len(), 3);
+    let review = &crew.stages[2];
+    assert_eq!(review.agent.as_deref(), Some("reviewer"));
+    let repeat = review.repeat.as_ref().expect("repeat");
+    assert_eq!(repeat.from, "implement");
+    assert_eq!(repeat.until, "APPROVED");
+    assert_eq!(repeat.max_iterations, 2);
+
+    // max_iterations defaults to 3.
+    let repeat: crate::types::CrewStageRepeat =
+        toml::from_str("from = \"a\"\nuntil = \"OK\"").unwrap();
+    assert_eq!(repeat.max_iterations, 3);
+}
+
+#[test]
+fn crew_stage_repeat_matches_the_last_line_only() {
+    let repeat: crate::types::CrewStageRepeat =
+        toml::from_str("from = \"a\"\nuntil = \"APPROVED\"").unwrap();
+    assert!(repeat.is_done("APPROVED"));
+    assert!(repeat.is_done("Looks good.\n  APPROVED  \n\n"));
+    assert!(!repeat.is_done("NOT APPROVED"));
+    assert!(!repeat.is_done("APPROVED, except for the tests.\nPlease add tests."));
+    assert!(!repeat.is_done("APPROVED?"));
+    assert!(!repeat.is_done(""));
+}
+
+#[test]
+fn validate_crew_type_accepts_loop_references() {
+    crate::config_loader::validate_crew_type(&pipeline_crew()).expect("valid pipeline");
+    crate::config_loader::validate_crew_type(&crate::config_loader::built_in_default_crew())
+        .expect("built-in crew has no stages");
+}
+
+#[test]
+fn validate_crew_type_rejects_invalid_stages() {
+    use crate::config_loader::validate_crew_type;
+
+    let mut crew = pipeline_crew();
+    crew.stages[1].agent = Some("nobody".to_string());
+    assert!(validate_crew_type(&crew).unwrap_err().contains("unknown agent"));
+
+    let mut crew = pipeline_crew();
+    crew.stages[1].agent = Some(crew.main_agent.clone());
+    crew.agents.insert(crew.main_agent.clone(), CrewAgentDef {
+        role: None,
+        model: None,
+        instructions: None,
+        description: None,
+    });
+    assert!(validate_crew_type(&crew).unwrap_err().contains("main agent"));
+
+    let mut crew = pipeline_crew();
+    crew.stages[1].name = "plan".to_string();
+    assert!(validate_crew_type(&crew).unwrap_err().contains("duplicate"));
+
+    // Forward reference outside a loop.
+    let mut crew = pipeline_crew();
+    crew.stages[0].prompt = Some("Plan using {stage.review}".to_string());
+    assert!(validate_crew_type(&crew).unwrap_err().contains("has not run yet"));
+
+    let mut crew = pipeline_crew();
+    crew.stages[1].prompt = Some("{stage.missing}".to_string());
+    assert!(validate_crew_type(&crew).unwrap_err().contains("unknown stage"));
+
+    // Without the loop, implement cannot see the review output.
+    let mut crew = pipeline_crew();
+    crew.stages[2].repeat = None;
+    assert!(validate_crew_type(&crew).is_err());
+
+    let mut crew = pipeline_crew();
+    crew.stages[0].repeat = Some(crate::types::CrewStageRepeat {
+        from: "review".to_string(),
+        until: "DONE".to_string(),
+        max_iterations: 3,
+    });
+    assert!(validate_crew_type(&crew).unwrap_err().contains("earlier stage"));
+
+    // A stage needs either agent + prompt or parallel branches.
+    let mut crew = pipeline_crew();
+    crew.stages[0].prompt = None;
+    assert!(validate_crew_type(&crew).unwrap_err().contains("either"));
+}
+
+#[test]
+fn apply_crew_type_sets_pipeline_with_inputs() {
+    use crate::types::SessionWorkflowInput;
+
+    let crew = pipeline_crew();
+    let mut base = SessionWorkflowInput {
+        user_message: String::new(),
+        model: "gpt-4o".to_string(),
+        instructions: String::new(),
+        approval_policy: codex_protocol::protocol::AskForApproval::Never,
+        web_search_mode: None,
+        reasoning_effort: None,
+        reasoning_summary: codex_protocol::config_types::ReasoningSummary::Auto,
+        personality: None,
+        developer_instructions: None,
+        model_provider: None,
+        crew_agents: BTreeMap::new(),
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
+        continued_state: None,
+        main_agent_state: None,
+        max_iterations: None,
+        output_schema: None,
+    };
+    let inputs = BTreeMap::from([("feature".to_string(), "dark mode".to_string())]);
+    crate::config_loader::apply_crew_type(&crew, &inputs, &mut base).unwrap();
+
+    assert_eq!(base.pipeline.len(), 3);
+    assert_eq!(base.pipeline[0].prompt.as_deref(), Some("Plan: dark mode"));
+    // Stage placeholders are left for run time.
+    assert_eq!(
+        base.pipeline[2].prompt.as_deref(),
+        Some("Review the implementation of dark mode: {stage.implement}")
+    );
+}
+
+#[test]
+fn pipeline_run_loops_until_approved() {
+    use crate::types::{PipelineRun, PipelineStatus};
+
+    let mut run = PipelineRun::new(pipeline_crew().stages);
+
+    let tasks = run.stage_tasks();
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].agent, "planner");
+    run.task_started("s/planner-2".to_string(), None);
+    assert!(run.owns("s/planner-2"));
+    assert!(run.task_finished("s/planner-2", Ok("the plan".to_string())));
+
+    // First implement run: no review output yet.
+    let tasks = run.stage_tasks();
+    assert_eq!(tasks[0].prompt, "Implement the plan. Review notes: ");
+    run.task_started("s/coder-3".to_string(), None);
+    run.task_finished("s/coder-3", Ok("v1".to_string()));
+
+    run.task_started("s/reviewer-4".to_string(), None);
+    run.task_finished("s/reviewer-4", Ok("needs tests".to_string()));
+
+    // Rejected: back to implement with the review notes.
+    assert_eq!(run.status, PipelineStatus::Running);
+    let tasks = run.stage_tasks();
+    assert_eq!(tasks[0].stage, "implement");
+    assert_eq!(tasks[0].prompt, "Implement the plan. Review notes: needs tests");
+    run.task_started("s/coder-5".to_string(), None);
+    run.task_finished("s/coder-5", Ok("v2".to_string()));
+
+    run.task_started("s/reviewer-6".to_string(), None);
+    run.task_finished("s/reviewer-6", Ok("APPROVED".to_string()));
+
+    assert_eq!(run.status, PipelineStatus::Completed);
+    assert_eq!(run.final_output(), Some("APPROVED"));
+    let runs: Vec<(&str, u32)> =
+        run.history.iter().map(|r| (r.stage.as_str(), r.iteration)).collect();
+    assert_eq!(
+        runs,
+        vec![("plan", 1), ("implement", 1), ("review", 1), ("implement", 2), ("review", 2)]
+    );
+    assert!(run.stage_tasks().is_empty());
+}
+
+#[test]
+fn pipeline_run_stops_looping_at_max_iterations() {
+    use crate::types::{PipelineRun, PipelineStatus};
+
+    let mut run = PipelineRun::new(pipeline_crew().stages);
+    let mut n = 0;
+    while !run.is_finished() {
+        for task in run.stage_tasks() {
+            n += 1;
+            let id = format!("s/{}-{n}", task.agent);
+            run.task_started(id.clone(), None);
+            run.task_finished(&id, Ok("rejected".to_string()));
+        }
+    }
+    assert_eq!(run.status, PipelineStatus::Completed);
+    // plan + 2 × (implement, review)
+    assert_eq!(run.history.len(), 5);
+}
+
+#[test]
+fn pipeline_run_fans_out_parallel_branches() {
+    use crate::types::{CrewBranch, CrewStage, PipelineRun, PipelineStatus};
+
+    let branch = |name: &str, prompt: &str| CrewBranch {
+        name: name.to_string(),
+        agent: "explorer".to_string(),
+        prompt: prompt.to_string(),
+    };
+    let stages = vec![
+        CrewStage {
+            name: "research".to_string(),
+            agent: None,
+            prompt: None,
+            parallel: vec![branch("api", "Study the API"), branch("ui", "Study the UI")],
+            repeat: None,
+        },
+        CrewStage {
+            name: "summary".to_string(),
+            agent: Some("worker".to_string()),
+            prompt: Some("Summarize {stage.research} (UI: {stage.research.ui})".to_string()),
+            parallel: Vec::new(),
+            repeat: None,
+        },
+    ];
+    let mut run = PipelineRun::new(stages);
+
+    let tasks = run.stage_tasks();
+    assert_eq!(tasks.len(), 2);
+    run.task_started("s/explorer-2".to_string(), tasks[0].branch.clone());
+    run.task_started("s/explorer-3".to_string(), tasks[1].branch.clone());
+
+    // The stage completes only when every branch has finished.
+    assert!(!run.task_finished("s/explorer-3", Ok("ui notes".to_string())));
+    assert!(run.task_finished("s/explorer-2", Ok("api notes".to_string())));
+
+    let tasks = run.stage_tasks();
+    assert_eq!(
+        tasks[0].prompt,
+        "Summarize ## api\napi notes\n\n## ui\nui notes (UI: ui notes)"
+    );
+
+    // A failing agent fails the pipeline.
+    run.task_started("s/worker-4".to_string(), None);
+    assert!(run.task_finished("s/worker-4", Err("boom".to_string())));
+    assert_eq!(run.status, PipelineStatus::Failed);
+    assert!(run.error.as_deref().unwrap().contains("summary"));
+    assert!(!run.task_finished("s/unknown", Ok(String::new())));
+}
+
+#[test]
+fn pipeline_run_waits_for_queued_branches() {
+    use crate::types::{CrewBranch, CrewStage, PipelineRun, PipelineStatus, PipelineTaskRef};
+
+    let branch = |name: &str| CrewBranch {
+        name: name.to_string(),
+        agent: "explorer".to_string(),
+        prompt: format!("Study the {name}"),
+    };
+    let mut run = PipelineRun::new(vec![CrewStage {
+        name: "research".to_string(),
+        agent: None,
+        prompt: None,
+        parallel: vec![branch("api"), branch("ui")],
+        repeat: None,
+    }]);
+    for _ in run.stage_tasks() {
+        run.task_queued();
+    }
+
+    // Only one slot was free: the second branch is still queued when the
+    // first finishes, so the stage is not complete.
+    run.task_started("s/explorer-2".to_string(), Some("api".to_string()));
+    assert!(!run.task_finished("s/explorer-2", Ok("api notes".to_string())));
+    assert_eq!(run.status, PipelineStatus::Running);
+
+    run.task_started("s/explorer-3".to_string(), Some("ui".to_string()));
+    assert!(run.task_finished("s/explorer-3", Ok("ui notes".to_string())));
+    assert_eq!(run.status, PipelineStatus::Completed);
+    assert_eq!(run.queued, 0);
+
+    let task = PipelineTaskRef {
+        stage: "research".to_string(),
+        branch: Some("ui".to_string()),
+    };
+    assert_eq!(task.label(), "research.ui");
+}
+
 // ---------------------------------------------------------------------------
 // Crew subagent scoping tests
 // ---------------------------------------------------------------------------
@@ -2848,8 +6659,14 @@ fn session_workflow_input_crew_agents_roundtrip() {
         developer_instructions: None,
         model_provider: None,
         crew_agents: crew_agents.clone(),
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
         continued_state: None,
+        main_agent_state: None,
         max_iterations: None,
+        output_schema: None,
     };
 
     let json = serde_json::to_string(&input).unwrap();
@@ -2942,6 +6759,10 @@ fn apply_crew_type_populates_crew_agents() {
             m
         },
         approval_policy: None,
+        stages: Vec::new(),
+        supervisor: None,
+        outputs: BTreeMap::new(),
+        artifacts: Vec::new(),
     };
 
     let mut inputs = BTreeMap::new();
@@ -2959,8 +6780,14 @@ fn apply_crew_type_populates_crew_agents() {
         developer_instructions: None,
         model_provider: None,
         crew_agents: BTreeMap::new(),
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
         continued_state: None,
+        main_agent_state: None,
         max_iterations: None,
+        output_schema: None,
     };
 
     apply_crew_type(&crew, &inputs, &mut base).unwrap();
@@ -3061,6 +6888,12 @@ fn session_continue_as_new_state_crew_agents_roundtrip() {
             workflow_id: "session/main".to_string(),
             role: "default".to_string(),
             status: AgentLifecycle::Running,
+            output: None,
+            error: None,
+            parent_agent_id: None,
+            last_agent_message: None,
+            iterations: 0,
+            token_usage: None,
         }],
         config_toml: "model = \"gpt-4o\"".to_string(),
         project_context: ProjectContextOutput {
@@ -3070,6 +6903,13 @@ fn session_continue_as_new_state_crew_agents_roundtrip() {
         },
         mcp_tools: std::collections::HashMap::new(),
         crew_agents: crew_agents.clone(),
+        disabled_mcp_servers: Default::default(),
+        messages: Vec::new(),
+        pipeline: None,
+        max_agents: None,
+        pending_spawns: Vec::new(),
+        approvals: Vec::new(),
+        agent_counter: None,
     };
 
     let json = serde_json::to_string(&state).unwrap();
@@ -4,6 +4,9 @@
 //! returns them.  This replaces the old client-side query + exponential
 //! backoff polling loop.
 //!
+//! [`watch_session`] fans the watchers of all agents in a session into one
+//! stream of agent-tagged [`SessionEvent`]s.
+//!
 //! ## SDK bug workaround: "Update poll returned no outcome"
 //!
 //! The Temporal server uses a ~20-second long-poll interval for update
@@ -23,14 +26,17 @@
 //! `execute_update` with the same `update_id` so the server
 //! deduplicates and we rejoin the in-flight update.
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::time::Duration;
 
 use codex_protocol::protocol::Event;
-use temporalio_client::{Client, WorkflowExecuteUpdateOptions};
+use temporalio_client::{Client, WorkflowExecuteUpdateOptions, WorkflowQueryOptions};
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 use uuid::Uuid;
 
-use crate::types::{StateUpdateRequest, StateUpdateResponse};
+use crate::session_workflow::{SessionWorkflow, SessionWorkflowRun};
+use crate::types::{AgentRecord, SessionEvent, StateUpdateRequest, StateUpdateResponse};
 use crate::workflow::{AgentWorkflow, AgentWorkflowRun};
 
 /// Result of a single watch cycle.
@@ -109,12 +115,38 @@ impl Watcher {
         }
     }
 
+    /// One bounded call for request/response callers.  Waits at most `wait`
+    /// for new events; on timeout returns an empty response at
+    /// `since_index` (the server-side handler completes on its own once
+    /// events arrive).
+    pub async fn poll_once(
+        &self,
+        since_index: usize,
+        wait: Duration,
+    ) -> Result<StateUpdateResponse, String> {
+        let update_id = Uuid::new_v4().to_string();
+        match tokio::time::timeout(wait, self.watch(since_index, &update_id)).await {
+            Ok(result) => result,
+            Err(_) => Ok(StateUpdateResponse {
+                events: Vec::new(),
+                watermark: since_index,
+                completed: false,
+            }),
+        }
+    }
+
     /// Continuous loop.  Sends parsed events on `tx`.  Each iteration blocks
     /// until the workflow has new data (no polling, no backoff for success).
     /// Returns only when the workflow completes or the receiver is dropped.
     /// All errors (RPC failures, local timeouts) retry indefinitely.
     pub async fn run_watching(self, tx: mpsc::Sender<WatcherEvent>) {
-        let mut since_index: usize = 0;
+        self.run_watching_from(0, tx).await;
+    }
+
+    /// [`run_watching`](Self::run_watching), starting after watermark
+    /// `since_index` instead of from the first event.
+    pub async fn run_watching_from(self, since_index: usize, tx: mpsc::Sender<WatcherEvent>) {
+        let mut since_index = since_index;
         // Stable update ID per logical watch call.  Reused on timeout retries
         // so the server deduplicates and we rejoin the in-flight update
         // instead of creating a new handler invocation.
@@ -175,3 +207,98 @@ impl Watcher {
         }
     }
 }
+
+/// How often [`watch_session`] checks the session for newly spawned agents.
+const AGENT_DISCOVERY_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Fan-in of the event streams of every agent in session `session_id`.
+///
+/// Runs one [`Watcher`] per running agent — agents spawned later are picked
+/// up by polling the session's `list_agents` query — and forwards their
+/// events on the returned channel, tagged with agent ID and role.  Each
+/// agent resumes after its entry in `watermarks` (from the start if
+/// absent).  Watching stops — the discovery loop and every agent's watcher
+/// — when `cancel` is cancelled or the receiver is dropped.
+pub fn watch_session(
+    client: Client,
+    session_id: String,
+    watermarks: BTreeMap<String, usize>,
+    cancel: CancellationToken,
+) -> mpsc::Receiver<SessionEvent> {
+    let (tx, rx) = mpsc::channel(256);
+    tokio::spawn(async move {
+        let mut watched: BTreeSet<String> = BTreeSet::new();
+        while !tx.is_closed() && !cancel.is_cancelled() {
+            let handle = client.get_workflow_handle::<SessionWorkflowRun>(&session_id);
+            match handle
+                .query(SessionWorkflow::list_agents, (), WorkflowQueryOptions::default())
+                .await
+            {
+                Ok(json) => {
+                    let agents: Vec<AgentRecord> = serde_json::from_str(&json).unwrap_or_default();
+                    for agent in agents {
+                        // Closed agent workflows no longer answer updates.
+                        if !agent.status.is_live() || !watched.insert(agent.agent_id.clone()) {
+                            continue;
+                        }
+                        let since = watermarks.get(&agent.agent_id).copied().unwrap_or(0);
+                        tokio::spawn(forward_agent_events(
+                            client.clone(),
+                            agent.agent_id,
+                            agent.role,
+                            since,
+                            tx.clone(),
+                            cancel.clone(),
+                        ));
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(session_id = %session_id, error = %e,
+                        "session watcher: list_agents failed, retrying");
+                }
+            }
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = tokio::time::sleep(AGENT_DISCOVERY_INTERVAL) => {}
+            }
+        }
+    });
+    rx
+}
+
+/// Watch one agent and forward its tagged events until it completes, the
+/// session receiver is dropped or `cancel` is cancelled.  The agent's
+/// [`Watcher`] task is aborted on the way out.
+async fn forward_agent_events(
+    client: Client,
+    agent_id: String,
+    role: String,
+    since_index: usize,
+    out: mpsc::Sender<SessionEvent>,
+    cancel: CancellationToken,
+) {
+    let (tx, mut rx) = mpsc::channel(64);
+    let watcher = Watcher::new(client, agent_id.clone());
+    let watching = tokio::spawn(watcher.run_watching_from(since_index, tx));
+
+    'forward: loop {
+        let update = tokio::select! {
+            _ = cancel.cancelled() => break,
+            update = rx.recv() => update,
+        };
+        match update {
+            Some(WatcherEvent::Events(events, watermark)) => {
+                for event in SessionEvent::tag(&agent_id, &role, events, watermark) {
+                    if out.send(event).await.is_err() {
+                        break 'forward;
+                    }
+                }
+            }
+            Some(WatcherEvent::Completed) | None => break,
+            Some(WatcherEvent::Error(e)) => {
+                tracing::debug!(agent_id = %agent_id, error = %e, "session watcher: agent retrying");
+            }
+        }
+    }
+    watching.abort();
+}
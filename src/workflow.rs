@@ -27,7 +27,8 @@ use codex_core::{
 use codex_protocol::config_types::ReasoningSummary;
 use codex_protocol::models::{BaseInstructions, ContentItem, ResponseItem};
 use codex_protocol::protocol::{
-    AgentMessageEvent, AskForApproval, ContextCompactedEvent, Event, EventMsg, Op,
+    AgentMessageEvent, AskForApproval, BackgroundEventEvent, ContextCompactedEvent, Event,
+    EventMsg, Op,
     ReviewDecision, TurnAbortReason, TurnAbortedEvent, TurnCompleteEvent, TurnStartedEvent,
 };
 use codex_protocol::ThreadId;
@@ -41,18 +42,30 @@ use temporalio_sdk::{
 use tokio::sync::Mutex;
 use tokio_util::sync::CancellationToken;
 
-use crate::config_loader::config_from_toml;
+use crate::config_loader::{
+    agent_roles_from_toml, config_from_toml, mcp_approval_from_toml, with_refreshed_mcp_config,
+};
 use crate::entropy::TemporalRandomSource;
+use crate::history::{TranscriptCache, rollout_page};
 use crate::sink::{BufferEventSink, DEFAULT_EVENT_BUFFER_CAPACITY};
 use crate::storage::InMemoryStorage;
 use crate::streamer::TemporalModelStreamer;
-use crate::tools::TemporalToolHandler;
+use crate::tools::{
+    TemporalToolHandler, approval_supervisor_tool_specs, main_agent_session,
+    mcp_prompt_tool_specs, messaging_tool_specs, session_of_agent, subagent_tool_specs,
+};
 use crate::activities::{CodexActivities, activity_opts};
+use crate::startup::signal_workflow;
 use crate::types::{
-    AgentWorkflowInput, AgentWorkflowOutput, ConfigOutput, ContinueAsNewState, PendingApproval,
-    PendingDynamicTool, PendingElicitation,
-    PendingPatchApproval, PendingUserInput, ProjectContextOutput, ResolveModelInfoInput,
-    StateUpdateRequest, StateUpdateResponse, TurnOverrides, UserTurnInput, extract_message,
+    AgentControlAction, AgentFinishedNotice, AgentMessage, AgentTurnNotice, AgentWorkflowInput,
+    AgentWorkflowOutput, ApprovalDecision, ConfigOutput, ContinueAsNewState,
+    ExportRequest, HistoryRequest,
+    McpApprovalMode, McpGetPromptInput, McpPromptRef,
+    McpToolsUpdate,
+    PendingApproval, PendingDynamicTool, PendingElicitation, PendingPatchApproval,
+    PendingUserInput, ProjectContextOutput, ResolveModelInfoInput, StateUpdateRequest,
+    StateUpdateResponse, SubagentNotice, Subagents, TurnOverrides, UserTurnInput,
+    extract_message, parse_mcp_prompt_command, render_mcp_prompt, short_agent_id,
 };
 
 /// Default maximum number of model→tool loop iterations per turn.
@@ -61,7 +74,14 @@ const DEFAULT_MAX_ITERATIONS: u32 = 50;
 #[workflow]
 pub struct AgentWorkflow {
     input: AgentWorkflowInput,
+    /// This agent's workflow ID (e.g. "codex-session-{uuid}/main").
+    pub(crate) workflow_id: String,
     pub(crate) events: Arc<BufferEventSink>,
+    /// Durable rollout of the conversation (seeded from the continue-as-new
+    /// state); the source of the `get_history` transcript.
+    pub(crate) storage: Arc<InMemoryStorage>,
+    /// Transcripts of the paged `get_history` / `export_session` queries.
+    transcripts: TranscriptCache,
     /// Queue of user turns waiting to be processed.
     user_turns: Vec<UserTurnInput>,
     /// Counter for generating turn IDs.
@@ -80,6 +100,19 @@ pub struct AgentWorkflow {
     /// Pending dynamic tool call (set by tool handler, resolved by
     /// `Op::DynamicToolResponse` signal).
     pub(crate) pending_dynamic_tool: Option<PendingDynamicTool>,
+    /// Refreshed MCP tool set from `update_mcp_tools`, applied between turns.
+    pending_mcp_tools: Option<McpToolsUpdate>,
+    /// Subagents spawned through the subagent tools (updated by
+    /// `subagent_notice` signals from the session).
+    pub(crate) subagents: Subagents,
+    /// Unread messages from other agents (drained by `read_messages`).
+    pub(crate) inbox: Vec<AgentMessage>,
+    /// Set by a `Pause` control action: no new turns are started and the
+    /// current turn holds before its next model call.
+    pub(crate) paused: bool,
+    /// ID of the latest turn that stopped at its iteration budget
+    /// (`max_iterations`), for the `turn_budget_exhausted` query.
+    budget_exhausted_turn: Option<String>,
     /// When true the workflow will exit after the current turn completes.
     shutdown_requested: bool,
     /// When true the workflow will run compaction and then continue-as-new.
@@ -167,6 +200,10 @@ fn do_continue_as_new(
     token_usage: Option<codex_protocol::protocol::TokenUsage>,
     mcp_tools: HashMap<String, serde_json::Value>,
     overrides: TurnOverrides,
+    subagents: Subagents,
+    inbox: Vec<AgentMessage>,
+    paused: bool,
+    budget_exhausted_turn: Option<String>,
 ) -> WorkflowResult<AgentWorkflowOutput> {
     let (event_offset, event_snapshot) = events.snapshot();
     let state = ContinueAsNewState {
@@ -179,6 +216,10 @@ fn do_continue_as_new(
         overrides,
         event_offset,
         event_snapshot,
+        subagents,
+        inbox,
+        paused,
+        budget_exhausted_turn,
     };
 
     let mut can_input = input.clone();
@@ -198,10 +239,13 @@ fn do_continue_as_new(
 #[workflow_methods]
 impl AgentWorkflow {
     #[init]
-    pub fn new(_ctx: &WorkflowContextView, input: AgentWorkflowInput) -> Self {
+    pub fn new(ctx: &WorkflowContextView, input: AgentWorkflowInput) -> Self {
+        let workflow_id = ctx.workflow_id.clone();
+
         // If restoring from continue-as-new, use the carried-over state.
         if let Some(ref state) = input.continued_state {
             return Self {
+                workflow_id,
                 user_turns: state.pending_user_turns.clone(),
                 turn_counter: state.cumulative_turn_count,
                 events: Arc::new(BufferEventSink::from_snapshot(
@@ -209,11 +253,18 @@ impl AgentWorkflow {
                     state.event_snapshot.clone(),
                     DEFAULT_EVENT_BUFFER_CAPACITY,
                 )),
+                storage: Arc::new(InMemoryStorage::with_items(state.rollout_items.clone())),
+                transcripts: TranscriptCache::default(),
                 pending_approval: None,
                 pending_user_input: None,
                 pending_patch_approval: None,
                 pending_elicitation: None,
                 pending_dynamic_tool: None,
+                pending_mcp_tools: None,
+                subagents: state.subagents.clone(),
+                inbox: state.inbox.clone(),
+                paused: state.paused,
+                budget_exhausted_turn: state.budget_exhausted_turn.clone(),
                 shutdown_requested: false,
                 compact_requested: false,
                 overrides: state.overrides.clone(),
@@ -236,6 +287,7 @@ impl AgentWorkflow {
                     effort: input.reasoning_effort,
                     summary: input.reasoning_summary,
                     personality: input.personality,
+                    output_schema: input.output_schema.clone(),
                 }],
                 1,
             )
@@ -243,7 +295,10 @@ impl AgentWorkflow {
 
         Self {
             input,
+            workflow_id,
             events: Arc::new(BufferEventSink::new(DEFAULT_EVENT_BUFFER_CAPACITY, 0)),
+            storage: Arc::new(InMemoryStorage::new()),
+            transcripts: TranscriptCache::default(),
             user_turns: initial_turns,
             turn_counter,
             pending_approval: None,
@@ -251,6 +306,11 @@ impl AgentWorkflow {
             pending_patch_approval: None,
             pending_elicitation: None,
             pending_dynamic_tool: None,
+            pending_mcp_tools: None,
+            subagents: Subagents::default(),
+            inbox: Vec::new(),
+            paused: false,
+            budget_exhausted_turn: None,
             shutdown_requested: false,
             compact_requested: false,
             overrides: TurnOverrides::default(),
@@ -293,6 +353,7 @@ impl AgentWorkflow {
                 effort,
                 summary,
                 personality,
+                final_output_json_schema,
                 ..
             } => {
                 let message = extract_message(&items);
@@ -304,6 +365,7 @@ impl AgentWorkflow {
                     effort,
                     summary: summary.unwrap_or_default(),
                     personality,
+                    output_schema: final_output_json_schema,
                 });
                 self.bump_version();
             }
@@ -388,12 +450,14 @@ impl AgentWorkflow {
                 server_name,
                 request_id,
                 decision,
+                content,
                 ..
             } => {
                 if let Some(ref mut pe) = self.pending_elicitation
                     && pe.server_name == server_name && pe.request_id == request_id
                 {
                     pe.response = Some(decision);
+                    pe.content = content;
                     self.bump_version();
                 }
             }
@@ -410,6 +474,152 @@ impl AgentWorkflow {
         }
     }
 
+    /// Replace the MCP tool set after a session-level refresh (sent by
+    /// `SessionWorkflow`).  Applied between turns so an in-flight turn keeps
+    /// a stable tool list; a newer update supersedes an unapplied one.
+    #[signal]
+    pub fn update_mcp_tools(
+        &mut self,
+        _ctx: &mut SyncWorkflowContext<Self>,
+        mut update: McpToolsUpdate,
+    ) {
+        // Keep the config of a superseded update that carried one.
+        if update.config_toml.is_none() {
+            update.config_toml = self.pending_mcp_tools.take().and_then(|u| u.config_toml);
+        }
+        self.pending_mcp_tools = Some(update);
+        self.bump_version();
+    }
+
+    /// News about a subagent spawned by this agent's subagent tools (sent by
+    /// `SessionWorkflow`).
+    #[signal]
+    pub fn subagent_notice(
+        &mut self,
+        _ctx: &mut SyncWorkflowContext<Self>,
+        notice: SubagentNotice,
+    ) {
+        if let SubagentNotice::SpawnQueued {
+            position,
+            max_agents,
+            ..
+        } = &notice
+        {
+            self.events.emit_event_sync(Event {
+                id: String::new(),
+                msg: EventMsg::BackgroundEvent(BackgroundEventEvent {
+                    message: format!(
+                        "Subagent spawn queued at position {position}: \
+                         the session is at its max_agents limit ({max_agents})."
+                    ),
+                }),
+            });
+        }
+        self.subagents.apply(notice);
+        self.bump_version();
+    }
+
+    /// A session-level notice (e.g. about a spawn request) shown as a
+    /// background event.
+    #[signal]
+    pub fn session_event(&mut self, _ctx: &mut SyncWorkflowContext<Self>, message: String) {
+        self.events.emit_event_sync(Event {
+            id: String::new(),
+            msg: EventMsg::BackgroundEvent(BackgroundEventEvent { message }),
+        });
+        self.bump_version();
+    }
+
+    /// Per-agent lifecycle control (sent by `SessionWorkflow::control_agent`).
+    #[signal]
+    pub fn control(&mut self, _ctx: &mut SyncWorkflowContext<Self>, action: AgentControlAction) {
+        let message = match action {
+            AgentControlAction::Pause => {
+                self.paused = true;
+                "Agent paused; it will hold before its next model call."
+            }
+            AgentControlAction::Resume => {
+                self.paused = false;
+                "Agent resumed."
+            }
+            AgentControlAction::Cancel => {
+                self.paused = false;
+                self.shutdown_requested = true;
+                "Agent cancelled; shutting down after the current turn."
+            }
+            AgentControlAction::Terminate => {
+                self.paused = false;
+                self.user_turns.clear();
+                self.shutdown_requested = true;
+                self.interrupt_requested = true;
+                if let Some(ref token) = self.current_turn_cancellation {
+                    token.cancel();
+                }
+                "Agent terminated."
+            }
+        };
+        self.events.emit_event_sync(Event {
+            id: String::new(),
+            msg: EventMsg::BackgroundEvent(BackgroundEventEvent {
+                message: message.to_string(),
+            }),
+        });
+        self.bump_version();
+    }
+
+    /// A decision on this agent's pending exec/patch approval made at the
+    /// session level (by a client or the session's approval supervisor).
+    #[signal]
+    pub fn resolve_approval(
+        &mut self,
+        _ctx: &mut SyncWorkflowContext<Self>,
+        decision: ApprovalDecision,
+    ) {
+        if let Some(ref mut pa) = self.pending_approval
+            && pa.call_id == decision.call_id
+        {
+            pa.decision = Some(decision.approved);
+            self.bump_version();
+        } else if let Some(ref mut pa) = self.pending_patch_approval
+            && pa.call_id == decision.call_id
+        {
+            pa.decision = Some(decision.approved);
+            self.bump_version();
+        }
+    }
+
+    /// A message from another agent, routed by `SessionWorkflow`.  It is
+    /// shown as a background event and either queued as a new turn (`wake`)
+    /// or left in the mailbox for `read_messages`.
+    #[signal]
+    pub fn deliver_message(&mut self, _ctx: &mut SyncWorkflowContext<Self>, message: AgentMessage) {
+        self.events.emit_event_sync(Event {
+            id: String::new(),
+            msg: EventMsg::BackgroundEvent(BackgroundEventEvent {
+                message: message.display(),
+            }),
+        });
+        if message.wake {
+            let turn_id = format!("turn-{}", self.turn_counter);
+            self.turn_counter += 1;
+            self.user_turns.push(UserTurnInput {
+                turn_id,
+                message: format!(
+                    "Message from agent {}: {}",
+                    short_agent_id(&message.from),
+                    message.body
+                ),
+                effort: self.input.reasoning_effort,
+                summary: self.input.reasoning_summary,
+                personality: self.input.personality,
+                output_schema: None,
+            });
+        } else {
+            self.inbox.push(message);
+        }
+        self.bump_version();
+    }
+
     // ----- updates -----
 
     /// Blocking update handler: returns new events when the workflow state
@@ -448,10 +658,85 @@ impl AgentWorkflow {
         })
     }
 
+    // ----- queries -----
+
+    /// Return one page of the agent's transcript as a JSON `HistoryPage`.
+    ///
+    /// The transcript is rebuilt from the durable rollout items, so unlike
+    /// `get_state_update` it covers the whole conversation, not just what is
+    /// still in the event buffer.  It is rebuilt for the first page only;
+    /// the following pages reuse it while the rollout is unchanged.
+    #[query]
+    pub fn get_history(&self, _ctx: &WorkflowContextView, req: HistoryRequest) -> String {
+        let cwd = self
+            .input
+            .project_context
+            .as_ref()
+            .map(|pc| pc.cwd.clone())
+            .unwrap_or_default();
+        let page = self.transcripts.history_page(&self.storage, &cwd, &req);
+        serde_json::to_string(&page).unwrap_or_default()
+    }
+
+    /// Return one page of the agent's raw rollout items as a JSON
+    /// `RolloutPage`, e.g. to restore the conversation in a new workflow.
+    #[query]
+    pub fn get_rollout(&self, _ctx: &WorkflowContextView, req: HistoryRequest) -> String {
+        let page = self.storage.read(|items| rollout_page(items, &req));
+        serde_json::to_string(&page).unwrap_or_default()
+    }
+
+    /// Return one page of the agent's conversation exported as codex
+    /// rollout JSONL or a Markdown transcript, as a JSON `ExportPage`.  Like
+    /// `get_history`, the export is built for the first page only.
+    #[query]
+    pub fn export_session(&self, _ctx: &WorkflowContextView, req: ExportRequest) -> String {
+        let cwd = self
+            .input
+            .project_context
+            .as_ref()
+            .map(|pc| pc.cwd.clone())
+            .unwrap_or_default();
+        let page = self.transcripts.export_page(&self.storage, &cwd, &req);
+        serde_json::to_string(&page).unwrap_or_default()
+    }
+
+    /// Whether turn `turn_id` stopped at its iteration budget
+    /// (`max_iterations`); only the latest such turn is remembered.
+    #[query]
+    pub fn turn_budget_exhausted(&self, _ctx: &WorkflowContextView, turn_id: String) -> bool {
+        self.budget_exhausted_turn.as_deref() == Some(turn_id.as_str())
+    }
+
     // ----- run -----
 
+    /// Runs the agent and, for a session agent, reports its outcome to the
+    /// session (which may have continued as new since starting it).
     #[run]
     pub async fn run(ctx: &mut WorkflowContext<Self>) -> WorkflowResult<AgentWorkflowOutput> {
+        let result = Self::run_agent(ctx).await;
+        let outcome = match &result {
+            Ok(output) => Ok(output.clone()),
+            Err(WorkflowTermination::ContinueAsNew(_)) => return result,
+            Err(e) => Err(format!("agent failed: {e}")),
+        };
+        let agent_id = ctx.state(|s| s.workflow_id.clone());
+        if let Some(session_id) = session_of_agent(&agent_id) {
+            let notice = AgentFinishedNotice {
+                agent_id: agent_id.clone(),
+                outcome,
+            };
+            if let Err(e) = signal_workflow!(ctx, session_id, "agent_finished", &notice) {
+                tracing::warn!(session_id = %session_id, error = %e,
+                    "failed to notify session of agent finish");
+            }
+        }
+        result
+    }
+
+    /// The agent's main body: startup, then one turn per queued user input
+    /// until shutdown or continue-as-new.
+    async fn run_agent(ctx: &mut WorkflowContext<Self>) -> WorkflowResult<AgentWorkflowOutput> {
         let input = ctx.state(|s| s.input.clone());
         let events = ctx.state(|s| s.events.clone());
 
@@ -466,12 +751,17 @@ impl AgentWorkflow {
             .scope(entropy, async {
                 loop {
                     ctx.wait_condition(|s| {
-                        !s.user_turns.is_empty()
+                        (!s.user_turns.is_empty() && !s.paused)
                             || s.shutdown_requested
                             || s.compact_requested
+                            || s.pending_mcp_tools.is_some()
                     })
                     .await;
 
+                    if let Some(update) = ctx.state_mut(|s| s.pending_mcp_tools.take()) {
+                        rt.apply_mcp_tools_update(ctx, update);
+                    }
+
                     if ctx.state(|s| s.compact_requested) {
                         break Some(rt.handle_compact(ctx));
                     }
@@ -480,6 +770,10 @@ impl AgentWorkflow {
                         break None;
                     }
 
+                    if ctx.state(|s| s.user_turns.is_empty() || s.paused) {
+                        continue;
+                    }
+
                     let turn = ctx.state_mut(|s| s.user_turns.remove(0));
                     let overrides = ctx.state(|s| s.overrides.clone());
 
@@ -529,6 +823,7 @@ enum TurnOutcome {
 /// impl so it is unconstrained by the Temporal macro's signature rules.
 struct WorkflowRuntime {
     input: AgentWorkflowInput,
+    workflow_id: String,
     events: Arc<BufferEventSink>,
     config: Arc<codex_core::config::Config>,
     config_toml: String,
@@ -541,6 +836,9 @@ struct WorkflowRuntime {
     conversation_id: ThreadId,
     mcp_tools: HashMap<String, serde_json::Value>,
     mcp_tool_names: HashSet<String>,
+    /// MCP tools whose calls need user approval, with the mode gating them
+    /// (per `[mcp_tool_approval]`).
+    mcp_approval_tools: HashMap<String, McpApprovalMode>,
     dynamic_tool_names: HashSet<String>,
     max_iterations: u32,
     total_iterations: u32,
@@ -564,7 +862,12 @@ impl WorkflowRuntime {
                     config_toml: input.config_toml.clone().unwrap(),
                 };
                 let project_context = input.project_context.clone().unwrap();
-                let mcp_tools = input.mcp_tools.clone();
+                // After a continue-as-new the carried tools reflect any
+                // mid-session refresh; the parent's input may be stale.
+                let mcp_tools = input
+                    .continued_state
+                    .as_ref()
+                    .map_or_else(|| input.mcp_tools.clone(), |s| s.mcp_tools.clone());
                 tracing::debug!("using pre-resolved config/context from parent workflow");
                 (config_output, project_context, mcp_tools)
             } else {
@@ -630,7 +933,7 @@ impl WorkflowRuntime {
         // --- session ---
         let conversation_id = ThreadId::new();
         let event_sink: Arc<dyn EventSink> = events.clone();
-        let storage = Arc::new(InMemoryStorage::new());
+        let storage = ctx.state(|s| Arc::clone(&s.storage));
         let storage_backend: Arc<dyn StorageBackend> = Arc::clone(&storage) as _;
 
         let sess = Session::new_minimal(
@@ -642,9 +945,8 @@ impl WorkflowRuntime {
         .await;
 
         // --- restore state from continue-as-new (if any) ---
+        // (The carried-over rollout items are already in `storage`.)
         if let Some(ref state) = input.continued_state {
-            storage.save(&state.rollout_items).await;
-
             let mut history_items: Vec<ResponseItem> = Vec::new();
             for item in &state.rollout_items {
                 match item {
@@ -665,24 +967,67 @@ impl WorkflowRuntime {
             sess.replace_history(history_items, None).await;
         }
 
+        let dynamic_tool_names: HashSet<String> = input
+            .dynamic_tools
+            .iter()
+            .map(|dt| dt.name.clone())
+            .collect();
+
+        let total_iterations = input
+            .continued_state
+            .as_ref()
+            .map_or(0, |s| s.cumulative_iterations);
+
+        let mut rt = Self {
+            input: input.clone(),
+            workflow_id: ctx.state(|s| s.workflow_id.clone()),
+            events: Arc::clone(events),
+            config,
+            config_toml: config_output.config_toml,
+            sess,
+            storage,
+            tools: Vec::new(),
+            base_instructions: BaseInstructions {
+                text: input.instructions.clone(),
+            },
+            context_items,
+            model_info,
+            conversation_id,
+            mcp_tools,
+            mcp_tool_names: HashSet::new(),
+            mcp_approval_tools: HashMap::new(),
+            dynamic_tool_names,
+            max_iterations,
+            total_iterations,
+            last_agent_message: None,
+        };
+
         // --- tools ---
+        rt.rebuild_tools();
+        Ok(rt)
+    }
+
+    /// Build `tools`, `mcp_tool_names` and `mcp_approval_tools` from the
+    /// current config, model info and MCP tool set.
+    fn rebuild_tools(&mut self) {
+        let config = &self.config;
         let sandbox_policy = config.permissions.sandbox_policy.get();
         let tools_config = ToolsConfig::new(&ToolsConfigParams {
-            model_info: &model_info,
+            model_info: &self.model_info,
             available_models: &vec![],
             features: &config.features,
-            web_search_mode: input.web_search_mode,
+            web_search_mode: self.input.web_search_mode,
             session_source: codex_protocol::protocol::SessionSource::Exec,
             sandbox_policy,
             windows_sandbox_level: codex_protocol::config_types::WindowsSandboxLevel::Disabled,
         })
         .with_agent_roles(config.agent_roles.clone());
 
-        let rmcp_tools: Option<HashMap<String, rmcp::model::Tool>> = if mcp_tools.is_empty() {
+        let rmcp_tools: Option<HashMap<String, rmcp::model::Tool>> = if self.mcp_tools.is_empty() {
             None
         } else {
             let mut map = HashMap::new();
-            for (name, value) in &mcp_tools {
+            for (name, value) in &self.mcp_tools {
                 match serde_json::from_value::<rmcp::model::Tool>(value.clone()) {
                     Ok(tool) => {
                         map.insert(name.clone(), tool);
@@ -700,42 +1045,82 @@ impl WorkflowRuntime {
             .map(|m| m.keys().cloned().collect())
             .unwrap_or_default();
 
-        let dynamic_tool_names: HashSet<String> = input
-            .dynamic_tools
+        let mcp_approval = mcp_approval_from_toml(&self.config_toml);
+        self.mcp_approval_tools = self
+            .mcp_tools
             .iter()
-            .map(|dt| dt.name.clone())
+            .filter(|(name, _)| mcp_tool_names.contains(*name))
+            .filter_map(|(name, tool)| {
+                Some((name.clone(), mcp_approval.approval_mode(name, tool)?))
+            })
             .collect();
+        self.mcp_tool_names = mcp_tool_names;
 
-        let builder = build_specs(&tools_config, rmcp_tools, None, &input.dynamic_tools);
+        // MCP prompt tools ride along with the client's dynamic tools but are
+        // handled by `TemporalToolHandler` (not routed back to the client).
+        let mut tool_specs_dynamic = self.input.dynamic_tools.clone();
+        if rmcp_tools.is_some() {
+            tool_specs_dynamic.extend(mcp_prompt_tool_specs());
+        }
+        // Likewise the subagent tools, offered to a session's main agent,
+        // and the message bus, offered to every agent in a session.
+        if main_agent_session(&self.workflow_id).is_some() {
+            tool_specs_dynamic.extend(subagent_tool_specs(&agent_roles_from_toml(
+                &self.config_toml,
+            )));
+        }
+        if session_of_agent(&self.workflow_id).is_some() {
+            tool_specs_dynamic.extend(messaging_tool_specs());
+            if self.input.approval_supervisor {
+                tool_specs_dynamic.extend(approval_supervisor_tool_specs());
+            }
+        }
+        let builder = build_specs(&tools_config, rmcp_tools, None, &tool_specs_dynamic);
         let (configured_specs, _registry) = builder.build();
-        let tools: Vec<ToolSpec> = configured_specs.into_iter().map(|cs| cs.spec).collect();
+        self.tools = configured_specs.into_iter().map(|cs| cs.spec).collect();
+    }
 
-        let total_iterations = input
-            .continued_state
-            .as_ref()
-            .map_or(0, |s| s.cumulative_iterations);
+    /// Swap in a refreshed MCP tool set (and the session's refreshed MCP
+    /// settings, which drive approval gating and server launches) and
+    /// rebuild the tool specs.  The diff is recomputed against this agent's
+    /// own tools so superseded updates still yield an accurate summary.
+    fn apply_mcp_tools_update(
+        &mut self,
+        ctx: &WorkflowContext<AgentWorkflow>,
+        update: McpToolsUpdate,
+    ) {
+        let config_changed = update.config_toml.is_some_and(|refreshed| {
+            let config_toml = with_refreshed_mcp_config(&self.config_toml, &refreshed);
+            let changed = config_toml != self.config_toml;
+            self.config_toml = config_toml;
+            changed
+        });
+        let update = McpToolsUpdate::diff(&self.mcp_tools, update.mcp_tools);
+        if update.is_empty() && !config_changed {
+            tracing::debug!("MCP tool refresh: no changes");
+            return;
+        }
 
-        Ok(Self {
-            input: input.clone(),
-            events: Arc::clone(events),
-            config,
-            config_toml: config_output.config_toml,
-            sess,
-            storage,
-            tools,
-            base_instructions: BaseInstructions {
-                text: input.instructions.clone(),
-            },
-            context_items,
-            model_info,
-            conversation_id,
-            mcp_tools,
-            mcp_tool_names,
-            dynamic_tool_names,
-            max_iterations,
-            total_iterations,
-            last_agent_message: None,
-        })
+        let summary = if update.is_empty() {
+            "configuration updated".to_string()
+        } else {
+            update.summary()
+        };
+        tracing::info!(
+            added = update.added.len(),
+            removed = update.removed.len(),
+            changed = update.changed.len(),
+            "applying MCP tool refresh"
+        );
+        self.mcp_tools = update.mcp_tools;
+        self.rebuild_tools();
+
+        AgentWorkflow::emit_and_bump(ctx, &self.events, Event {
+            id: String::new(),
+            msg: EventMsg::BackgroundEvent(BackgroundEventEvent {
+                message: format!("MCP tools refreshed: {summary}"),
+            }),
+        });
     }
 
     /// Handle a compact request: emit event and trigger continue-as-new.
@@ -763,6 +1148,10 @@ impl WorkflowRuntime {
         let pending = ctx.state(|s| s.user_turns.clone());
         let turn_count = ctx.state(|s| s.turn_counter);
         let overrides = ctx.state(|s| s.overrides.clone());
+        let subagents = ctx.state(|s| s.subagents.clone());
+        let inbox = ctx.state(|s| s.inbox.clone());
+        let paused = ctx.state(|s| s.paused);
+        let budget_exhausted_turn = ctx.state(|s| s.budget_exhausted_turn.clone());
 
         do_continue_as_new(
             &self.input,
@@ -774,6 +1163,10 @@ impl WorkflowRuntime {
             self.events.latest_token_usage(),
             self.mcp_tools.clone(),
             overrides,
+            subagents,
+            inbox,
+            paused,
+            budget_exhausted_turn,
         )
     }
 
@@ -782,7 +1175,7 @@ impl WorkflowRuntime {
     async fn process_turn(
         &mut self,
         ctx: &mut WorkflowContext<AgentWorkflow>,
-        turn: UserTurnInput,
+        mut turn: UserTurnInput,
         overrides: &TurnOverrides,
     ) -> TurnOutcome {
         let turn_id = turn.turn_id.clone();
@@ -797,6 +1190,22 @@ impl WorkflowRuntime {
             }),
         });
 
+        // `/mcp-prompt <server> <prompt> [key=value ...]` pulls an MCP prompt
+        // into the turn in place of the command text.
+        if let Some(prompt) = parse_mcp_prompt_command(&turn.message) {
+            match self.expand_mcp_prompt(ctx, prompt).await {
+                Ok(text) => turn.message = text,
+                Err(message) => {
+                    let err = codex_core::error::CodexErr::Fatal(format!(
+                        "failed to load MCP prompt: {message}"
+                    ));
+                    self.emit_turn_end_events(ctx, &turn_id, false, Some(&err));
+                    self.notify_session_of_turn(ctx).await;
+                    return TurnOutcome::Completed;
+                }
+            }
+        }
+
         // Record the user message in session history.
         let user_item = ResponseItem::Message {
             id: None,
@@ -817,12 +1226,20 @@ impl WorkflowRuntime {
         self.sess.record_items(&turn_context, &[user_item]).await;
 
         // Run the agentic iteration loop.
-        let (turn_aborted, turn_error) =
-            self.run_agentic_loop(ctx, &turn_id, &turn_config, &turn_context, overrides)
-                .await;
+        let (turn_aborted, turn_error) = self
+            .run_agentic_loop(
+                ctx,
+                &turn_id,
+                &turn_config,
+                &turn_context,
+                overrides,
+                turn.output_schema.take(),
+            )
+            .await;
 
         // Emit turn-end events.
         self.emit_turn_end_events(ctx, &turn_id, turn_aborted, turn_error.as_ref());
+        self.notify_session_of_turn(ctx).await;
 
         // Check if server suggests continue-as-new.
         if ctx.continue_as_new_suggested() {
@@ -837,6 +1254,51 @@ impl WorkflowRuntime {
         TurnOutcome::Completed
     }
 
+    /// Tell the parent `SessionWorkflow` (if any) that this agent finished a
+    /// turn, so an agent waiting on it via `wait_subagent` can resume.
+    async fn notify_session_of_turn(&self, ctx: &mut WorkflowContext<AgentWorkflow>) {
+        let Some(session_id) = session_of_agent(&self.workflow_id) else {
+            return;
+        };
+        let notice = AgentTurnNotice {
+            agent_id: self.workflow_id.clone(),
+            last_agent_message: self.last_agent_message.clone(),
+            iterations: self.total_iterations,
+            token_usage: self.events.latest_token_usage(),
+        };
+        if let Err(e) = signal_workflow!(ctx, session_id, "agent_turn_complete", &notice) {
+            tracing::warn!(session_id = %session_id, error = %e, "failed to notify session of turn");
+        }
+    }
+
+    /// Fetch an MCP prompt via the `mcp_get_prompt` activity and render it
+    /// as user-message text.
+    async fn expand_mcp_prompt(
+        &self,
+        ctx: &mut WorkflowContext<AgentWorkflow>,
+        prompt: McpPromptRef,
+    ) -> Result<String, String> {
+        let arguments = (!prompt.arguments.is_empty())
+            .then(|| serde_json::to_value(&prompt.arguments).unwrap_or_default());
+        let input = McpGetPromptInput {
+            server: prompt.server,
+            name: prompt.name,
+            arguments,
+            config_toml: Some(self.config_toml.clone()),
+            cwd: Some(self.config.cwd.to_string_lossy().to_string()),
+        };
+        let output = ctx
+            .start_activity(CodexActivities::mcp_get_prompt, input, activity_opts(120))
+            .await
+            .map_err(|e| format!("mcp_get_prompt activity failed: {e}"))?;
+
+        let text = render_mcp_prompt(&output.result?);
+        if text.is_empty() {
+            return Err("prompt has no text content".to_string());
+        }
+        Ok(text)
+    }
+
     /// Build a per-turn `Config`, merging per-turn overrides (from
     /// `UserTurnInput`) with persistent overrides (from
     /// `Op::OverrideTurnContext`).  Per-turn values take priority.
@@ -877,6 +1339,7 @@ impl WorkflowRuntime {
         &self,
         history: Vec<ResponseItem>,
         turn_config: &codex_core::config::Config,
+        output_schema: Option<serde_json::Value>,
     ) -> Prompt {
         let mut input_items = self.context_items.clone();
 
@@ -900,7 +1363,7 @@ impl WorkflowRuntime {
             parallel_tool_calls: false,
             base_instructions: self.base_instructions.clone(),
             personality: turn_config.personality,
-            output_schema: None,
+            output_schema,
         }
     }
 
@@ -912,6 +1375,7 @@ impl WorkflowRuntime {
         turn_config: &Arc<codex_core::config::Config>,
         turn_context: &Arc<TurnContext>,
         overrides: &TurnOverrides,
+        output_schema: Option<serde_json::Value>,
     ) -> (bool, Option<codex_core::error::CodexErr>) {
         let cancellation_token = CancellationToken::new();
         ctx.state_mut(|s| {
@@ -940,11 +1404,19 @@ impl WorkflowRuntime {
                 break;
             }
 
+            // Hold here while paused; an interrupt (or terminate) aborts.
+            if ctx.state(|s| s.paused) {
+                tracing::info!("agent paused, holding before next model call");
+                ctx.wait_condition(|s| !s.paused || s.interrupt_requested).await;
+                continue;
+            }
+
             if iterations >= self.max_iterations {
                 tracing::warn!(
                     "max iterations reached ({}), stopping turn",
                     self.max_iterations
                 );
+                ctx.state_mut(|s| s.budget_exhausted_turn = Some(turn_id.to_string()));
                 AgentWorkflow::emit_and_bump(ctx, &self.events, Event {
                     id: turn_id.to_string(),
                     msg: EventMsg::AgentMessage(AgentMessageEvent {
@@ -984,12 +1456,13 @@ impl WorkflowRuntime {
                 self.config.cwd.to_string_lossy().to_string(),
                 Some(self.config_toml.clone()),
                 self.mcp_tool_names.clone(),
+                self.mcp_approval_tools.clone(),
                 self.dynamic_tool_names.clone(),
                 self.config.permissions.sandbox_policy.get().clone(),
             );
 
             let history = self.sess.history_items().await;
-            let prompt = self.build_prompt(history, turn_config);
+            let prompt = self.build_prompt(history, turn_config, output_schema.clone());
 
             let mut server_model_warning_emitted = false;
             let result = try_run_sampling_request(
@@ -126,11 +126,13 @@ fn new_session_with_policy(
         model_provider: None,
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
     TemporalAgentSession::new(client.clone(), workflow_id, base_input)
 }
@@ -404,11 +406,13 @@ fn new_session_with_web_search(client: &Client, model: &str) -> TemporalAgentSes
         model_provider: None,
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
     TemporalAgentSession::new(client.clone(), workflow_id, base_input)
 }
@@ -433,11 +437,13 @@ fn new_session_with_effort(
         model_provider: None,
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
     TemporalAgentSession::new(client.clone(), workflow_id, base_input)
 }
@@ -512,11 +518,13 @@ fn new_session_for_caching(client: &Client) -> TemporalAgentSession {
         model_provider: None,
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
     TemporalAgentSession::new(client.clone(), workflow_id, base_input)
 }
@@ -799,6 +807,16 @@ async fn session_resume(client: &Client) {
         created_at_millis: now_millis,
         status: SessionStatus::Running,
         crew_type: None,
+        schedule_id: None,
+        last_activity_millis: 0,
+        last_message_preview: None,
+        pinned: false,
+        archived_at_millis: None,
+        title: None,
+        tags: Default::default(),
+        cwd: None,
+        repository: None,
+        branch: None,
     };
     let harness_handle = client.get_workflow_handle::<CodexHarnessRun>(&harness_id);
     harness_handle
@@ -857,11 +875,13 @@ async fn session_resume(client: &Client) {
         model_provider: None,
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
     let resumed = TemporalAgentSession::resume(client.clone(), session_id.clone(), base_input);
 
@@ -1117,11 +1137,13 @@ shell_tool = true
         model_provider: None,
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
     let session = TemporalAgentSession::new(client.clone(), workflow_id, base_input);
 
@@ -1212,11 +1234,13 @@ sandbox_mode = "read-only"
         model_provider: None,
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
     let session = TemporalAgentSession::new(client.clone(), workflow_id, base_input);
 
@@ -1329,6 +1353,16 @@ async fn session_picker_conversion(client: &Client) {
         created_at_millis: now_millis,
         status: SessionStatus::Running,
         crew_type: None,
+        schedule_id: None,
+        last_activity_millis: 0,
+        last_message_preview: None,
+        pinned: false,
+        archived_at_millis: None,
+        title: None,
+        tags: Default::default(),
+        cwd: None,
+        repository: None,
+        branch: None,
     };
     let entry2 = SessionEntry {
         session_id: session_id_2.clone(),
@@ -1337,6 +1371,16 @@ async fn session_picker_conversion(client: &Client) {
         created_at_millis: now_millis + 1000,
         status: SessionStatus::Running,
         crew_type: None,
+        schedule_id: None,
+        last_activity_millis: 0,
+        last_message_preview: None,
+        pinned: false,
+        archived_at_millis: None,
+        title: None,
+        tags: Default::default(),
+        cwd: None,
+        repository: None,
+        branch: None,
     };
 
     harness_handle
@@ -2055,11 +2099,13 @@ async fn patch_approval_flow(client: &Client) {
         model_provider: None,
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
     let session = TemporalAgentSession::new(client.clone(), workflow_id, base_input);
 
@@ -2170,7 +2216,9 @@ async fn dynamic_tool_flow(client: &Client) {
             }),
             defer_loading: false,
         }],
+        approval_supervisor: false,
         max_iterations: None,
+        output_schema: None,
     };
     let session = TemporalAgentSession::new(client.clone(), workflow_id, base_input);
 
@@ -2260,8 +2308,14 @@ async fn session_spawns_main_agent(client: &Client) {
         developer_instructions: None,
         model_provider: None,
         crew_agents: std::collections::BTreeMap::new(),
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
         continued_state: None,
+        main_agent_state: None,
         max_iterations: None,
+        output_schema: None,
     };
     let session = TemporalAgentSession::new(client.clone(), workflow_id, base_input);
 
@@ -2326,8 +2380,14 @@ async fn session_spawn_additional_agent(client: &Client) {
         developer_instructions: None,
         model_provider: None,
         crew_agents: std::collections::BTreeMap::new(),
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
         continued_state: None,
+        main_agent_state: None,
         max_iterations: None,
+        output_schema: None,
     };
     let session = TemporalAgentSession::new(client.clone(), session_id.clone(), base_input);
 
@@ -2354,6 +2414,8 @@ async fn session_spawn_additional_agent(client: &Client) {
         .spawn_agent(SpawnAgentInput {
             role: "default".to_string(),
             message: "Say goodbye.".to_string(),
+            requested_by: None,
+            request_id: None,
         })
         .await
         .expect("spawn_agent failed");
@@ -2444,8 +2506,14 @@ description = "Main agent"
         developer_instructions: None,
         model_provider: None,
         crew_agents: std::collections::BTreeMap::new(),
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
         continued_state: None,
+        main_agent_state: None,
         max_iterations: None,
+        output_schema: None,
     };
 
     let mut inputs = std::collections::BTreeMap::new();
@@ -2536,8 +2604,14 @@ description = "Helper agent for sub-tasks"
         developer_instructions: None,
         model_provider: None,
         crew_agents: std::collections::BTreeMap::new(),
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
         continued_state: None,
+        main_agent_state: None,
         max_iterations: None,
+        output_schema: None,
     };
 
     let inputs = std::collections::BTreeMap::new();
@@ -2584,6 +2658,8 @@ description = "Helper agent for sub-tasks"
         .spawn_agent(SpawnAgentInput {
             role: "helper".to_string(),
             message: "Say the word 'banana' and nothing else.".to_string(),
+            requested_by: None,
+            request_id: None,
         })
         .await
         .expect("spawn_agent failed");
@@ -2665,8 +2741,14 @@ async fn default_crew_agents_available(client: &Client) {
         developer_instructions: None,
         model_provider: None,
         crew_agents,
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
         continued_state: None,
+        main_agent_state: None,
         max_iterations: None,
+        output_schema: None,
     };
     let session = TemporalAgentSession::new(client.clone(), session_id.clone(), base_input);
 
@@ -2693,6 +2775,8 @@ async fn default_crew_agents_available(client: &Client) {
         .spawn_agent(SpawnAgentInput {
             role: "explorer".to_string(),
             message: "What files exist in the current directory?".to_string(),
+            requested_by: None,
+            request_id: None,
         })
         .await
         .expect("spawn_agent explorer failed");
@@ -3080,11 +3164,13 @@ async fn session_switch_via_browser(client: &Client) {
         model_provider: None,
         continued_state: None,
         max_iterations: None,
+        output_schema: None,
         role: "default".to_string(),
         config_toml: None,
         project_context: None,
         mcp_tools: std::collections::HashMap::new(),
         dynamic_tools: Vec::new(),
+        approval_supervisor: false,
     };
     let base_input_b = base_input_a.clone();
 
@@ -3114,6 +3200,16 @@ async fn session_switch_via_browser(client: &Client) {
                 created_at_millis: now_millis,
                 status: SessionStatus::Running,
                 crew_type: None,
+                schedule_id: None,
+                last_activity_millis: 0,
+                last_message_preview: None,
+                pinned: false,
+                archived_at_millis: None,
+                title: None,
+                tags: Default::default(),
+                cwd: None,
+                repository: None,
+                branch: None,
             },
             WorkflowSignalOptions::default(),
         )
@@ -3131,6 +3227,16 @@ async fn session_switch_via_browser(client: &Client) {
                 created_at_millis: now_millis + 1000,
                 status: SessionStatus::Running,
                 crew_type: None,
+                schedule_id: None,
+                last_activity_millis: 0,
+                last_message_preview: None,
+                pinned: false,
+                archived_at_millis: None,
+                title: None,
+                tags: Default::default(),
+                cwd: None,
+                repository: None,
+                branch: None,
             },
             WorkflowSignalOptions::default(),
         )
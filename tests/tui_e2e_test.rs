@@ -107,8 +107,14 @@ fn new_session(client: &Client, model: &str) -> TemporalAgentSession {
         developer_instructions: None,
         model_provider: None,
         crew_agents: Default::default(),
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
         continued_state: None,
+        main_agent_state: None,
         max_iterations: None,
+        output_schema: None,
     };
     TemporalAgentSession::new(client.clone(), workflow_id, base_input)
 }
@@ -494,8 +500,14 @@ async fn tui_max_iterations_warning_and_recovery_inner() {
         developer_instructions: None,
         model_provider: None,
         crew_agents: Default::default(),
+        pipeline: Vec::new(),
+        approval_supervisor: None,
+        harness_registration: None,
+        crew_results: None,
         continued_state: None,
+        main_agent_state: None,
         max_iterations: Some(1),
+        output_schema: None,
     };
     let session = TemporalAgentSession::new(client.clone(), workflow_id, base_input);
 